@@ -0,0 +1,56 @@
+//! Benchmarks for swap key derivation.
+//!
+//! Compares deriving keys through a fresh [`HdWallet`] for every swap, which recomputes the seed
+//! each time, with reusing one wallet and with batch derivation as done during recovery.
+//!
+//! Run with `cargo bench -p lendaswap-core`.
+
+use bitcoin::Network;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use lendaswap_core::HdWallet;
+use std::hint::black_box;
+
+const MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+fn bench_derivation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("derive_swap_params");
+
+    for count in [10u32, 100] {
+        group.bench_with_input(
+            BenchmarkId::new("fresh_wallet", count),
+            &count,
+            |b, &count| {
+                b.iter(|| {
+                    for index in 0..count {
+                        let wallet = HdWallet::from_mnemonic(MNEMONIC, Network::Bitcoin).unwrap();
+                        black_box(wallet.derive_swap_params(index).unwrap());
+                    }
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("cached_wallet", count),
+            &count,
+            |b, &count| {
+                let wallet = HdWallet::from_mnemonic(MNEMONIC, Network::Bitcoin).unwrap();
+                b.iter(|| {
+                    for index in 0..count {
+                        black_box(wallet.derive_swap_params(index).unwrap());
+                    }
+                })
+            },
+        );
+
+        group.bench_with_input(BenchmarkId::new("range", count), &count, |b, &count| {
+            let wallet = HdWallet::from_mnemonic(MNEMONIC, Network::Bitcoin).unwrap();
+            b.iter(|| black_box(wallet.derive_swap_params_range(0..count).unwrap()))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_derivation);
+criterion_main!(benches);
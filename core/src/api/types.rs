@@ -27,6 +27,21 @@ impl TokenId {
             TokenId::Coin(s) => s,
         }
     }
+
+    /// The chain the token lives on, going by the suffix of EVM token IDs (e.g. `usdc_pol`).
+    ///
+    /// Returns `None` for EVM tokens with an unknown suffix.
+    pub fn chain(&self) -> Option<Chain> {
+        match self {
+            TokenId::BtcLightning => Some(Chain::Lightning),
+            TokenId::BtcArkade => Some(Chain::Arkade),
+            TokenId::Coin(coin) => match coin.rsplit_once('_')?.1 {
+                "pol" => Some(Chain::Polygon),
+                "eth" => Some(Chain::Ethereum),
+                _ => None,
+            },
+        }
+    }
 }
 
 impl std::fmt::Display for TokenId {
@@ -240,6 +255,35 @@ pub enum SwapStatus {
     ClientRedeemedAndClientRefunded,
 }
 
+impl SwapStatus {
+    /// How far along the state machine this status is.
+    ///
+    /// Statuses only ever move to a higher progress, so of two snapshots of the same swap the one
+    /// with the higher progress is the newer one. All terminal states share the highest value.
+    pub fn progress(&self) -> u8 {
+        match self {
+            SwapStatus::Pending => 0,
+            SwapStatus::ClientFunded
+            | SwapStatus::ClientInvalidFunded
+            | SwapStatus::ClientFundedTooLate => 1,
+            SwapStatus::ServerFunded => 2,
+            SwapStatus::ClientRedeeming => 3,
+            SwapStatus::ClientRedeemed | SwapStatus::ClientRefundedServerFunded => 4,
+            SwapStatus::ClientRefunded
+            | SwapStatus::ServerRedeemed
+            | SwapStatus::ClientFundedServerRefunded
+            | SwapStatus::ClientRefundedServerRefunded
+            | SwapStatus::Expired
+            | SwapStatus::ClientRedeemedAndClientRefunded => 5,
+        }
+    }
+
+    /// Whether no further transitions are possible.
+    pub fn is_terminal(&self) -> bool {
+        self.progress() == 5
+    }
+}
+
 /// Request to create an Arkade to EVM swap (BTC → Token).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SwapRequest {
@@ -449,6 +493,13 @@ impl GetSwapResponse {
         self.common().status
     }
 
+    /// Time after which the client can refund the swap's VHTLC, see
+    /// [`SwapCommonFields::refund_locktime`].
+    pub fn refund_deadline(&self) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(self.common().refund_locktime.into())
+            .expect("u32 timestamps are in range")
+    }
+
     /// Get the direction of the swap.
     pub fn direction(&self) -> SwapDirection {
         match self {
@@ -456,6 +507,14 @@ impl GetSwapResponse {
             GetSwapResponse::EvmToBtc(_) => SwapDirection::EvmToBtc,
         }
     }
+
+    /// Get the source and target token of the swap.
+    pub fn tokens(&self) -> (&TokenId, &TokenId) {
+        match self {
+            GetSwapResponse::BtcToEvm(r) => (&r.source_token, &r.target_token),
+            GetSwapResponse::EvmToBtc(r) => (&r.source_token, &r.target_token),
+        }
+    }
 }
 
 /// Gelato relay submit request.
@@ -0,0 +1,310 @@
+//! Encrypted full backups of a wallet and its swaps.
+//!
+//! A backup is a single JSON document holding the mnemonic, the key indices and all stored swaps,
+//! including VTXO swaps, encrypted and authenticated with a password (see [`crate::crypto`]). It can be restored into
+//! any pair of wallet and swap storage, e.g. to move a user from the browser to a native app.
+//!
+//! The BIP39 passphrase is never part of a backup. Only its fingerprint is, so a restored wallet
+//! still requires the passphrase to be unlocked.
+
+use crate::client::{ExtendedSwapStorageData, ExtendedVtxoSwapStorageData};
+use crate::crypto::{DerivedKey, EncryptedEnvelope, KdfParams};
+use crate::error::{Error, Result};
+use crate::types::{DerivationScheme, Network, PublicSwapParams};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use time::OffsetDateTime;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Identifies Lendaswap backup files.
+const BACKUP_FORMAT: &str = "lendaswap-backup";
+/// Current backup format version. Version 2 added VTXO swaps.
+const BACKUP_VERSION: u32 = 2;
+
+/// A backup as written to a file: the encrypted [`Backup`] and the format it uses.
+#[derive(Serialize, Deserialize)]
+struct BackupFile {
+    format: String,
+    version: u32,
+    envelope: EncryptedEnvelope,
+}
+
+/// Information about a backup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupMetadata {
+    /// Network of the wallet. A backup can only be restored on the same network.
+    pub network: Network,
+    /// When the backup was created.
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    /// Account of the client that created the backup.
+    pub account: u32,
+    /// Number of swaps in the backup.
+    pub swap_count: usize,
+    /// Number of VTXO swaps in the backup.
+    #[serde(default)]
+    pub vtxo_swap_count: usize,
+}
+
+/// The decrypted contents of a backup.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Backup {
+    pub metadata: BackupMetadata,
+    pub wallet: WalletBackup,
+    pub swaps: Vec<ExtendedSwapStorageData>,
+    #[serde(default)]
+    pub vtxo_swaps: Vec<ExtendedVtxoSwapStorageData>,
+}
+
+/// The wallet data of a backup.
+///
+/// The mnemonic is wiped from memory when dropped.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct WalletBackup {
+    pub mnemonic: String,
+    /// See [`crate::WalletStorage::get_passphrase_fingerprint`].
+    pub passphrase_fingerprint: Option<String>,
+    pub derivation_scheme: DerivationScheme,
+    /// Key index by account.
+    pub key_indices: BTreeMap<u32, u32>,
+}
+
+impl WalletBackup {
+    /// Raise the key index of each account past the highest one used by `swap_params`, so that
+    /// a restored wallet doesn't reuse the keys of the restored swaps.
+    pub(crate) fn raise_key_indices<'a>(
+        &mut self,
+        swap_params: impl IntoIterator<Item = &'a PublicSwapParams>,
+    ) {
+        for params in swap_params {
+            let index = self.key_indices.entry(params.account).or_default();
+            *index = (*index).max(params.key_index.saturating_add(1));
+        }
+    }
+}
+
+impl Drop for WalletBackup {
+    fn drop(&mut self) {
+        self.mnemonic.zeroize();
+    }
+}
+
+impl Backup {
+    /// Encrypt the backup with a password.
+    pub(crate) fn encrypt(&self, password: &str, kdf_params: KdfParams) -> Result<String> {
+        let plaintext = Zeroizing::new(serde_json::to_vec(self)?);
+        let key = DerivedKey::derive(password, kdf_params)?;
+
+        let file = BackupFile {
+            format: BACKUP_FORMAT.to_string(),
+            version: BACKUP_VERSION,
+            envelope: key.encrypt(&plaintext)?,
+        };
+        Ok(serde_json::to_string(&file)?)
+    }
+
+    /// Decrypt a backup created with [`Backup::encrypt`] and check that it is complete.
+    ///
+    /// Returns [`Error::InvalidPassword`] if the password is wrong,
+    /// [`Error::CorruptedCiphertext`] if the encrypted data was modified and
+    /// [`Error::InvalidBackup`] if the file is not a backup this version can read.
+    pub(crate) fn decrypt(backup: &str, password: &str) -> Result<Self> {
+        let file: BackupFile = serde_json::from_str(backup)
+            .map_err(|e| Error::InvalidBackup(format!("not a backup file: {}", e)))?;
+        if file.format != BACKUP_FORMAT {
+            return Err(Error::InvalidBackup(format!(
+                "unknown format {}",
+                file.format
+            )));
+        }
+        if file.version == 0 || file.version > BACKUP_VERSION {
+            return Err(Error::InvalidBackup(format!(
+                "unsupported version {}",
+                file.version
+            )));
+        }
+
+        let key = DerivedKey::for_envelope(password, &file.envelope)?;
+        let plaintext = key.decrypt(&file.envelope)?;
+        let backup: Self = serde_json::from_slice(&plaintext)
+            .map_err(|e| Error::InvalidBackup(format!("invalid contents: {}", e)))?;
+
+        if backup.swaps.len() != backup.metadata.swap_count {
+            return Err(Error::InvalidBackup(format!(
+                "expected {} swaps, found {}",
+                backup.metadata.swap_count,
+                backup.swaps.len()
+            )));
+        }
+        if backup.vtxo_swaps.len() != backup.metadata.vtxo_swap_count {
+            return Err(Error::InvalidBackup(format!(
+                "expected {} VTXO swaps, found {}",
+                backup.metadata.vtxo_swap_count,
+                backup.vtxo_swaps.len()
+            )));
+        }
+
+        Ok(backup)
+    }
+
+    /// Check that the backup can be restored on `network`.
+    pub(crate) fn check_network(&self, network: Network) -> Result<()> {
+        if self.metadata.network != network {
+            return Err(Error::InvalidBackup(format!(
+                "backup is for {}, but the client uses {}",
+                self.metadata.network, network
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::SwapStatus;
+    use crate::storage::conformance::sample_swap;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    /// Cheap parameters so tests don't spend time in the KDF.
+    fn fast_kdf() -> KdfParams {
+        KdfParams {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+        }
+    }
+
+    fn backup() -> Backup {
+        Backup {
+            metadata: BackupMetadata {
+                network: Network::Regtest,
+                created_at: OffsetDateTime::UNIX_EPOCH,
+                account: 0,
+                swap_count: 0,
+                vtxo_swap_count: 0,
+            },
+            wallet: WalletBackup {
+                mnemonic: PHRASE.to_string(),
+                passphrase_fingerprint: None,
+                derivation_scheme: DerivationScheme::V1,
+                key_indices: BTreeMap::from([(0, 7)]),
+            },
+            swaps: Vec::new(),
+            vtxo_swaps: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_backup_roundtrip_and_validation() {
+        let encrypted = backup().encrypt("hunter2", fast_kdf()).unwrap();
+        assert!(!encrypted.contains("abandon"));
+
+        let decrypted = Backup::decrypt(&encrypted, "hunter2").unwrap();
+        assert_eq!(decrypted.metadata, backup().metadata);
+        assert_eq!(decrypted.wallet.mnemonic, backup().wallet.mnemonic);
+        assert_eq!(decrypted.wallet.key_indices, backup().wallet.key_indices);
+        decrypted.check_network(Network::Regtest).unwrap();
+        assert!(matches!(
+            decrypted.check_network(Network::Bitcoin),
+            Err(Error::InvalidBackup(_))
+        ));
+
+        assert!(matches!(
+            Backup::decrypt(&encrypted, "hunter3"),
+            Err(Error::InvalidPassword)
+        ));
+
+        // Modify the first byte of the ciphertext
+        let mut file: serde_json::Value = serde_json::from_str(&encrypted).unwrap();
+        let ciphertext = file["envelope"]["ciphertext"].as_str().unwrap();
+        let tampered = format!(
+            "{}{}",
+            if ciphertext.starts_with('0') {
+                "1"
+            } else {
+                "0"
+            },
+            &ciphertext[1..]
+        );
+        file["envelope"]["ciphertext"] = tampered.into();
+        assert!(matches!(
+            Backup::decrypt(&file.to_string(), "hunter2"),
+            Err(Error::CorruptedCiphertext(_))
+        ));
+
+        file["version"] = (BACKUP_VERSION + 1).into();
+        assert!(matches!(
+            Backup::decrypt(&file.to_string(), "hunter2"),
+            Err(Error::InvalidBackup(_))
+        ));
+        assert!(matches!(
+            Backup::decrypt("{}", "hunter2"),
+            Err(Error::InvalidBackup(_))
+        ));
+    }
+
+    #[test]
+    fn test_raise_key_indices() {
+        let mut wallet = backup().wallet;
+        let swap_params = |key_index, account| PublicSwapParams {
+            key_index,
+            account,
+            ..sample_swap("a", SwapStatus::Pending).swap_params
+        };
+
+        wallet.raise_key_indices(&[swap_params(3, 0), swap_params(4, 2), swap_params(1, 2)]);
+        assert_eq!(wallet.key_indices, BTreeMap::from([(0, 7), (2, 5)]));
+
+        wallet.raise_key_indices(&[swap_params(9, 0), swap_params(u32::MAX, 1)]);
+        assert_eq!(
+            wallet.key_indices,
+            BTreeMap::from([(0, 10), (1, u32::MAX), (2, 5)])
+        );
+    }
+
+    #[test]
+    fn test_backup_with_missing_swaps_is_rejected() {
+        let mut incomplete = backup();
+        incomplete.metadata.swap_count = 1;
+        let encrypted = incomplete.encrypt("hunter2", fast_kdf()).unwrap();
+
+        assert!(matches!(
+            Backup::decrypt(&encrypted, "hunter2"),
+            Err(Error::InvalidBackup(_))
+        ));
+
+        let mut incomplete = backup();
+        incomplete.metadata.vtxo_swap_count = 1;
+        let encrypted = incomplete.encrypt("hunter2", fast_kdf()).unwrap();
+        assert!(matches!(
+            Backup::decrypt(&encrypted, "hunter2"),
+            Err(Error::InvalidBackup(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_version_1_backup() {
+        // Version 1 had no VTXO swaps
+        let key = DerivedKey::derive("hunter2", fast_kdf()).unwrap();
+        let mut contents = serde_json::to_value(backup()).unwrap();
+        contents.as_object_mut().unwrap().remove("vtxo_swaps");
+        contents["metadata"]
+            .as_object_mut()
+            .unwrap()
+            .remove("vtxo_swap_count");
+        let file = BackupFile {
+            format: BACKUP_FORMAT.to_string(),
+            version: 1,
+            envelope: key
+                .encrypt(&serde_json::to_vec(&contents).unwrap())
+                .unwrap(),
+        };
+
+        let decrypted = Backup::decrypt(&serde_json::to_string(&file).unwrap(), "hunter2").unwrap();
+        assert_eq!(decrypted.metadata, backup().metadata);
+        assert!(decrypted.vtxo_swaps.is_empty());
+    }
+}
@@ -1,36 +1,59 @@
 use crate::api::{
     AssetPair, BtcToEvmSwapResponse, CreateVtxoSwapRequest, EstimateVtxoSwapResponse, EvmChain,
     EvmToArkadeSwapRequest, EvmToBtcSwapResponse, EvmToLightningSwapRequest, GetSwapResponse,
-    QuoteRequest, QuoteResponse, SwapRequest, TokenId, TokenInfo, Version, VtxoSwapResponse,
+    QuoteRequest, QuoteResponse, RecoveredSwap, SwapRequest, TokenId, TokenInfo, Version,
+    VtxoSwapResponse,
+};
+use crate::backup::{Backup, BackupMetadata};
+use crate::history::{SwapEvent, SwapEventKind, SwapHistoryExport};
+use crate::journal::{PendingCreation, PendingCreationKind, ReconciledCreations};
+use crate::message::{
+    MessageSignature, OwnershipKey, OwnershipProof, message_hash, ownership_message,
+    verify_swap_ownership,
+};
+use crate::nostr_backup::{self, NostrRelay};
+use crate::recovery::{self, RecoveryReport};
+use crate::signer::{SchemeSigner, SwapSigner};
+use crate::storage::{
+    KdfParams, QuarantinedRecord, SwapPage, SwapQuery, SwapStorage, WalletStorage,
+    WatchOnlyWalletStorage,
+};
+use crate::types::{DerivationScheme, SwapData, SwapParams};
+use crate::{
+    ApiClient, MnemonicOptions, Network, PublicSwapParams, VhtlcAmounts, Wallet, vhtlc, vtxo_swap,
 };
-use crate::storage::{SwapStorage, WalletStorage};
-use crate::types::SwapData;
-use crate::{ApiClient, Network, SwapParams, VhtlcAmounts, Wallet, vhtlc, vtxo_swap};
 use ark_rs::core::ArkAddress;
+use bitcoin::bip32::Xpub;
 use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::str::FromStr;
+use time::OffsetDateTime;
 
 /// Extended swap data that combines the API response with client-side swap parameters.
 ///
 /// This is the data structure stored for each swap, containing both the server response
-/// and the cryptographic parameters derived by the client.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// and the cryptographic parameters derived by the client. It is serialized with a schema
+/// version, and older records are migrated when deserialized (see [`crate::storage::schema`]).
+#[derive(Debug, Clone)]
 pub struct ExtendedSwapStorageData {
     /// The swap response from the API.
     pub response: GetSwapResponse,
-    /// Client-side swap parameters (keys, preimage, etc.).
+    /// Public client-side swap parameters (public key, preimage hash, key index).
     /// Sometimes not relevant, e.g. for evm-to-lightning swaps.
-    pub swap_params: SwapParams,
+    ///
+    /// Secrets are not stored: they are re-derived from the key index when needed.
+    pub swap_params: PublicSwapParams,
 }
 
 /// Extended VTXO swap data that combines the API response with client-side swap parameters.
-#[derive(Serialize, Deserialize, Debug, Clone)]
+///
+/// Versioned like [`ExtendedSwapStorageData`].
+#[derive(Debug, Clone)]
 pub struct ExtendedVtxoSwapStorageData {
     /// The VTXO swap response from the API.
     pub response: VtxoSwapResponse,
-    /// Client-side swap parameters (keys, preimage, etc.).
-    pub swap_params: SwapParams,
+    /// Public client-side swap parameters (public key, preimage hash, key index).
+    pub swap_params: PublicSwapParams,
 }
 
 /// The main client for interacting with Lendaswap.
@@ -43,6 +66,47 @@ pub struct Client<S: WalletStorage, SS: SwapStorage> {
     wallet: Wallet<S>,
     swap_storage: SS,
     arkade_url: String,
+    /// External signer. If `None`, the wallet derives keys from the mnemonic.
+    signer: Option<Box<dyn SwapSigner>>,
+    /// User-ID Xpub of a watch-only client, which has no keys. See [`Client::watch_only`].
+    watch_only: Option<Xpub>,
+}
+
+impl<SS: SwapStorage> Client<WatchOnlyWalletStorage, SS> {
+    /// Create a watch-only client from a user-ID Xpub (see [`Client::get_user_id_xpub`]).
+    ///
+    /// A watch-only client can recover, list and refresh the wallet's swaps and query their
+    /// VHTLC amounts, but has no keys: creating, claiming and refunding swaps fails with
+    /// [`crate::Error::WatchOnly`]. Only public swap data is stored, the wallet storage is a
+    /// [`WatchOnlyWalletStorage`]. Use
+    /// [`Client::with_account`] if the Xpub belongs to an account other than 0.
+    ///
+    /// # Arguments
+    /// * `url` - The Lendaswap API URL
+    /// * `user_id_xpub` - The wallet's user-ID Xpub
+    /// * `swap_storage` - Storage for swap data
+    /// * `network` - The Bitcoin network to use
+    /// * `arkade_url` - The Arkade server URL
+    pub fn watch_only(
+        url: impl Into<String>,
+        user_id_xpub: &str,
+        swap_storage: SS,
+        network: Network,
+        arkade_url: String,
+    ) -> crate::Result<Self> {
+        let xpub = Xpub::from_str(user_id_xpub)
+            .map_err(|e| crate::Error::Parse(format!("Invalid user ID xpub: {}", e)))?;
+
+        let mut client = Self::new(
+            url,
+            WatchOnlyWalletStorage,
+            swap_storage,
+            network,
+            arkade_url,
+        );
+        client.watch_only = Some(xpub);
+        Ok(client)
+    }
 }
 
 impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
@@ -69,6 +133,45 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
             wallet,
             swap_storage,
             arkade_url,
+            signer: None,
+            watch_only: None,
+        }
+    }
+
+    /// Use an external [`SwapSigner`] for claims and refunds instead of the mnemonic.
+    ///
+    /// The signer must use the same key indices as the wallet, which still derives the public
+    /// swap parameters when creating swaps.
+    pub fn with_signer(mut self, signer: impl SwapSigner + 'static) -> Self {
+        self.signer = Some(Box::new(signer));
+        self
+    }
+
+    /// Use the given account of the mnemonic, see [`Wallet::with_account`].
+    ///
+    /// Swaps of other accounts are ignored when listing and recovering swaps, and cannot be
+    /// claimed or refunded by this client.
+    pub fn with_account(mut self, account: u32) -> Self {
+        self.wallet = self.wallet.with_account(account);
+        self
+    }
+
+    /// Get the signer used for claims and refunds.
+    pub fn signer(&self) -> &dyn SwapSigner {
+        match &self.signer {
+            Some(signer) => signer.as_ref(),
+            None => &self.wallet,
+        }
+    }
+
+    /// Get the signer for a stored swap.
+    ///
+    /// Without an external signer, the swap's keys are derived with the scheme it was created
+    /// with rather than the wallet's current one.
+    fn swap_signer(&self, params: &PublicSwapParams) -> Box<dyn SwapSigner + '_> {
+        match &self.signer {
+            Some(signer) => Box::new(signer.as_ref()),
+            None => Box::new(SchemeSigner::new(&self.wallet, params.scheme)),
         }
     }
 
@@ -77,15 +180,290 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
         &self.swap_storage
     }
 
+    /// Whether this client was created with [`Client::watch_only`].
+    pub fn is_watch_only(&self) -> bool {
+        self.watch_only.is_some()
+    }
+
+    fn ensure_not_watch_only(&self) -> crate::Result<()> {
+        match self.watch_only {
+            Some(_) => Err(crate::Error::WatchOnly),
+            None => Ok(()),
+        }
+    }
+
+    /// Derive the swap parameters for a new swap.
+    async fn derive_swap_params(&self) -> crate::Result<SwapParams> {
+        self.ensure_not_watch_only()?;
+        self.wallet.derive_swap_params().await
+    }
+
+    /// Load the stored mnemonic, or import `mnemonic` or generate a new one if none is stored.
+    ///
+    /// Swap records stored in an older format are rewritten afterwards, so that secrets stored by
+    /// older versions are removed, and swap creations that were interrupted are reconciled, see
+    /// [`Client::reconcile_pending_creations`]. Failures are logged and retried on the next
+    /// initialization.
     pub async fn init(&self, mnemonic: Option<String>) -> crate::Result<()> {
+        self.ensure_not_watch_only()?;
         if let Some(mnemonic) = mnemonic {
             self.wallet.import_mnemonic(mnemonic.as_str()).await?;
         } else {
             self.wallet.generate_or_get_mnemonic().await?;
         }
+        self.finish_init().await;
         Ok(())
     }
 
+    /// Like [`Client::init`], but creates a new mnemonic according to `options`.
+    ///
+    /// `options` are ignored if a mnemonic is imported or one is already stored.
+    pub async fn init_with_options(
+        &self,
+        mnemonic: Option<String>,
+        options: &MnemonicOptions,
+    ) -> crate::Result<()> {
+        self.ensure_not_watch_only()?;
+        if let Some(mnemonic) = mnemonic {
+            self.wallet.import_mnemonic(mnemonic.as_str()).await?;
+        } else {
+            self.wallet
+                .generate_or_get_mnemonic_with_options(options)
+                .await?;
+        }
+        self.finish_init().await;
+        Ok(())
+    }
+
+    /// Like [`Client::init`], but for a wallet protected by a BIP39 passphrase.
+    ///
+    /// If an existing wallet is loaded, the passphrase is verified against it.
+    pub async fn init_with_passphrase(
+        &self,
+        mnemonic: Option<String>,
+        passphrase: &str,
+    ) -> crate::Result<()> {
+        self.ensure_not_watch_only()?;
+        if let Some(mnemonic) = mnemonic {
+            self.wallet
+                .import_mnemonic_with_passphrase(mnemonic.as_str(), passphrase)
+                .await?;
+        } else {
+            self.wallet
+                .generate_or_get_mnemonic_with_passphrase(&MnemonicOptions::default(), passphrase)
+                .await?;
+        }
+        self.finish_init().await;
+        Ok(())
+    }
+
+    /// Rewrite swap records stored in an older format and reconcile interrupted swap creations,
+    /// logging failures instead of returning them.
+    async fn finish_init(&self) {
+        if let Err(e) = self.migrate_outdated_swap_records().await {
+            log::warn!("Failed to rewrite outdated swap records: {e:#}");
+        }
+        if let Err(e) = self.reconcile_pending_creations().await {
+            log::warn!("Failed to reconcile interrupted swap creations: {e:#}");
+        }
+    }
+
+    /// Derive the parameters of a new swap and journal its creation, see [`crate::journal`].
+    ///
+    /// The swap isn't created if it can't be journaled.
+    async fn begin_swap_creation(&self) -> crate::Result<SwapParams> {
+        let (swap_params, _) = self.begin_creation(PendingCreationKind::Swap).await?;
+        Ok(swap_params)
+    }
+
+    /// Derive the parameters of a new swap of the given kind and journal its creation. Returns
+    /// the parameters and the journal entry.
+    async fn begin_creation(
+        &self,
+        kind: PendingCreationKind,
+    ) -> crate::Result<(SwapParams, PendingCreation)> {
+        let swap_params = self.derive_swap_params().await?;
+        let pending = PendingCreation::now_with_kind(swap_params.to_public(), kind);
+        self.swap_storage
+            .store_pending_creation(&pending)
+            .await
+            .map_err(|e| {
+                crate::Error::Storage(format!(
+                    "Failed to journal swap creation with key index {}: {:#}",
+                    swap_params.key_index, e
+                ))
+            })?;
+
+        Ok((swap_params, pending))
+    }
+
+    /// Store a created swap and finalize its creation.
+    async fn finish_swap_creation(
+        &self,
+        swap_id: &str,
+        data: &ExtendedSwapStorageData,
+    ) -> crate::Result<()> {
+        self.swap_storage.store(swap_id, data).await?;
+        self.finalize_creation(swap_id, &data.swap_params).await;
+        Ok(())
+    }
+
+    /// Store a created VTXO swap and finalize its creation.
+    async fn finish_vtxo_swap_creation(
+        &self,
+        swap_id: &str,
+        data: &ExtendedVtxoSwapStorageData,
+    ) -> crate::Result<()> {
+        // The key index is all that's needed to re-derive the preimage, so name it in case the
+        // swap can't be stored.
+        self.swap_storage
+            .store_vtxo_swap(swap_id, data)
+            .await
+            .map_err(|e| {
+                crate::Error::Storage(format!(
+                    "Failed to store VTXO swap {} with key index {}: {:#}",
+                    swap_id, data.swap_params.key_index, e
+                ))
+            })?;
+        self.finalize_creation(swap_id, &data.swap_params).await;
+        Ok(())
+    }
+
+    /// Delete the journal entry of a stored swap.
+    async fn finalize_creation(&self, swap_id: &str, swap_params: &PublicSwapParams) {
+        // A leftover entry only costs a lookup on the next initialization
+        let user_id = hex::encode(swap_params.user_id.serialize());
+        if let Err(e) = self.swap_storage.delete_pending_creation(&user_id).await {
+            log::warn!("Failed to finalize creation of swap {swap_id}: {e:#}");
+        }
+    }
+
+    /// Resolve the swap creations of the client's account that were started but not finalized,
+    /// e.g. because the app crashed during the API call. See [`crate::journal`].
+    ///
+    /// The swaps the server created for them are looked up by key index, VTXO swaps by the
+    /// journaled ID, and stored unless a record that is at least as far along is stored already.
+    /// The entries of stored swaps are deleted. Entries without a swap are kept for
+    /// [`PENDING_CREATION_GRACE_PERIOD`](crate::journal::PENDING_CREATION_GRACE_PERIOD), as
+    /// another client on the same storage may still be creating it. Returns the stored swaps.
+    pub async fn reconcile_pending_creations(&self) -> crate::Result<ReconciledCreations> {
+        let account = self.wallet.account();
+        let (vtxo_swaps, swaps): (Vec<PendingCreation>, Vec<PendingCreation>) = self
+            .swap_storage
+            .get_pending_creations()
+            .await?
+            .into_iter()
+            .filter(|pending| pending.swap_params.account == account)
+            .partition(|pending| pending.kind == PendingCreationKind::VtxoSwap);
+
+        let now = OffsetDateTime::now_utc();
+        Ok(ReconciledCreations {
+            swaps: self.reconcile_swap_creations(&swaps, now).await?,
+            vtxo_swaps: self.reconcile_vtxo_swap_creations(&vtxo_swaps, now).await?,
+        })
+    }
+
+    /// Reconcile the given swap creations, looking the swaps up by key index.
+    async fn reconcile_swap_creations(
+        &self,
+        pending: &[PendingCreation],
+        now: OffsetDateTime,
+    ) -> crate::Result<Vec<ExtendedSwapStorageData>> {
+        if pending.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let xpub = self
+            .wallet
+            .get_user_id_xpub()
+            .await?
+            .ok_or(crate::Error::NoMnemonic)?;
+        let recovered = self.api_client.recover_swaps(xpub.as_str()).await?;
+        let mut candidates = Vec::new();
+        let mut resolved = Vec::new();
+        for recovered_swap in recovered.swaps {
+            let Some(pending) = pending
+                .iter()
+                .find(|pending| pending.swap_params.key_index == recovered_swap.index)
+            else {
+                continue;
+            };
+            resolved.push(pending.user_id());
+            candidates.push(ExtendedSwapStorageData {
+                response: recovered_swap.swap,
+                swap_params: pending.swap_params.clone(),
+            });
+        }
+
+        let (to_store, _) = recovery::merge_swaps(self.list_all().await?, candidates);
+        for data in &to_store {
+            let swap_id = data.response.id();
+            log::info!("Recovered swap {swap_id} of an interrupted creation");
+            self.swap_storage.store(&swap_id, data).await?;
+        }
+        for pending in pending {
+            let user_id = pending.user_id();
+            if resolved.contains(&user_id) || pending.is_abandoned(now) {
+                self.swap_storage.delete_pending_creation(&user_id).await?;
+            }
+        }
+
+        Ok(to_store)
+    }
+
+    /// Reconcile the given VTXO swap creations, looking the swaps up by their journaled IDs.
+    async fn reconcile_vtxo_swap_creations(
+        &self,
+        pending: &[PendingCreation],
+        now: OffsetDateTime,
+    ) -> crate::Result<Vec<ExtendedVtxoSwapStorageData>> {
+        if !pending.is_empty() && !self.swap_storage.supports_vtxo_swaps() {
+            // Keep the journaled IDs for a storage that can store the swaps
+            log::warn!(
+                "Not recovering {} VTXO swap creations: the storage doesn't support VTXO swaps",
+                pending.len()
+            );
+            return Ok(Vec::new());
+        }
+
+        let mut restored = Vec::new();
+        for pending in pending {
+            let Some(swap_id) = &pending.swap_id else {
+                // Without an ID the swap was never returned to the caller, so it wasn't funded
+                if pending.is_abandoned(now) {
+                    self.swap_storage
+                        .delete_pending_creation(&pending.user_id())
+                        .await?;
+                }
+                continue;
+            };
+
+            if self.swap_storage.get_vtxo_swap(swap_id).await?.is_none() {
+                let response = self.api_client.get_vtxo_swap(swap_id).await?;
+                if hex::encode(pending.swap_params.public_key.serialize()) != response.client_pk {
+                    log::warn!(
+                        "Not recovering VTXO swap {swap_id}: it doesn't use the key with index {}",
+                        pending.swap_params.key_index
+                    );
+                    continue;
+                }
+
+                log::info!("Recovered VTXO swap {swap_id} of an interrupted creation");
+                let data = ExtendedVtxoSwapStorageData {
+                    response,
+                    swap_params: pending.swap_params.clone(),
+                };
+                self.swap_storage.store_vtxo_swap(swap_id, &data).await?;
+                restored.push(data);
+            }
+            self.swap_storage
+                .delete_pending_creation(&pending.user_id())
+                .await?;
+        }
+
+        Ok(restored)
+    }
+
     pub fn api_client(&self) -> &ApiClient {
         &self.api_client
     }
@@ -102,7 +480,7 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
         target_chain: EvmChain,
         referral_code: Option<String>,
     ) -> crate::Result<BtcToEvmSwapResponse> {
-        let swap_params = self.wallet.derive_swap_params().await?;
+        let swap_params = self.begin_swap_creation().await?;
 
         let request = SwapRequest {
             target_address,
@@ -120,12 +498,14 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
             .await?;
 
         let swap_id = response.common.id.to_string();
+        self.record_event(&swap_id, SwapEventKind::created(&response.common.status))
+            .await;
         let swap_data = ExtendedSwapStorageData {
             response: GetSwapResponse::BtcToEvm(response.clone()),
-            swap_params,
+            swap_params: swap_params.to_public(),
         };
 
-        self.swap_storage.store(&swap_id, &swap_data).await?;
+        self.finish_swap_creation(&swap_id, &swap_data).await?;
 
         Ok(response)
     }
@@ -139,7 +519,7 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
         source_chain: EvmChain,
         referral_code: Option<String>,
     ) -> crate::Result<EvmToBtcSwapResponse> {
-        let swap_params = self.wallet.derive_swap_params().await?;
+        let swap_params = self.begin_swap_creation().await?;
 
         let request = EvmToArkadeSwapRequest {
             target_address,
@@ -157,12 +537,14 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
             .create_evm_to_arkade_swap(&request, source_chain)
             .await?;
         let swap_id = response.common.id.to_string();
+        self.record_event(&swap_id, SwapEventKind::created(&response.common.status))
+            .await;
         let swap_data = ExtendedSwapStorageData {
             response: GetSwapResponse::EvmToBtc(response.clone()),
-            swap_params,
+            swap_params: swap_params.to_public(),
         };
 
-        self.swap_storage.store(&swap_id, &swap_data).await?;
+        self.finish_swap_creation(&swap_id, &swap_data).await?;
 
         Ok(response)
     }
@@ -175,7 +557,7 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
         source_chain: EvmChain,
         referral_code: Option<String>,
     ) -> crate::Result<EvmToBtcSwapResponse> {
-        let swap_params = self.wallet.derive_swap_params().await?;
+        let swap_params = self.begin_swap_creation().await?;
 
         let request = EvmToLightningSwapRequest {
             bolt11_invoice,
@@ -190,12 +572,14 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
             .create_evm_to_lightning_swap(&request, source_chain)
             .await?;
         let swap_id = response.common.id.to_string();
+        self.record_event(&swap_id, SwapEventKind::created(&response.common.status))
+            .await;
         let swap_data = ExtendedSwapStorageData {
             response: GetSwapResponse::EvmToBtc(response.clone()),
-            swap_params,
+            swap_params: swap_params.to_public(),
         };
 
-        self.swap_storage.store(&swap_id, &swap_data).await?;
+        self.finish_swap_creation(&swap_id, &swap_data).await?;
 
         Ok(response)
     }
@@ -212,7 +596,8 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
 
     /// Get swap details by ID.
     ///
-    /// This fetches the latest swap status from the API and updates the local storage.
+    /// This fetches the latest swap status from the API and updates the local storage. A status
+    /// change is recorded in the swap's history.
     pub async fn get_swap(&self, id: &str) -> crate::Result<ExtendedSwapStorageData> {
         let maybe_data = self.swap_storage.get(id).await?;
 
@@ -221,7 +606,12 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
                 "Swap id not found {id}"
             ))),
             Some(known) => {
-                let swap_response = self.api_client.get_swap(id).await?;
+                let result = self.api_client.get_swap(id).await;
+                let swap_response = self
+                    .record_outcome(id, "get_swap", result, |response| {
+                        SwapEventKind::status_changed(&known.response.status(), &response.status())
+                    })
+                    .await?;
                 let new_extended_swap_data = ExtendedSwapStorageData {
                     response: swap_response,
                     swap_params: known.swap_params,
@@ -242,6 +632,20 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
         &self,
         swap_id: &str,
         maybe_secret: Option<String>,
+    ) -> crate::Result<()> {
+        let result = self.request_gelato_claim(swap_id, maybe_secret).await;
+        self.record_outcome(swap_id, "claim_gelato", result, |_| {
+            Some(SwapEventKind::ApiCall {
+                endpoint: "claim_gelato".to_string(),
+            })
+        })
+        .await
+    }
+
+    async fn request_gelato_claim(
+        &self,
+        swap_id: &str,
+        maybe_secret: Option<String>,
     ) -> crate::Result<()> {
         if let Some(secret) = maybe_secret {
             self.api_client.claim_gelato(swap_id, &secret).await?;
@@ -249,12 +653,24 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
         }
 
         let swap_data = self.load_swap_data_from_storage(swap_id).await?;
-        let preimage = swap_data.swap_params.preimage;
+        self.check_signer_key(&swap_data.swap_params).await?;
+        let preimage = self
+            .swap_signer(&swap_data.swap_params)
+            .preimage(swap_data.swap_params.key_index)
+            .await?;
         let preimage = hex::encode(preimage);
         self.api_client.claim_gelato(swap_id, &preimage).await
     }
 
     pub async fn claim_vhtlc(&self, swap_id: &str) -> crate::Result<String> {
+        let result = self.broadcast_vhtlc_claim(swap_id).await;
+        self.record_outcome(swap_id, "claim_vhtlc", result, |txid| {
+            Some(SwapEventKind::Claimed { txid: txid.clone() })
+        })
+        .await
+    }
+
+    async fn broadcast_vhtlc_claim(&self, swap_id: &str) -> crate::Result<String> {
         let swap_data = self.load_swap_data_from_storage(swap_id).await?;
         if let GetSwapResponse::EvmToBtc(data) = &swap_data.response {
             match &data.user_address_arkade {
@@ -265,6 +681,7 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
                     let address = ArkAddress::from_str(arkade_address)
                         .map_err(|e| crate::Error::Parse(format!("Invalid ark address {e})")))?;
 
+                    self.check_signer_key(&swap_data.swap_params).await?;
                     let common_swap_data = swap_data.response.common();
                     let txid = vhtlc::claim(
                         &self.arkade_url,
@@ -281,7 +698,7 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
                             network: common_swap_data.network.parse()?,
                             vhtlc_address: data.htlc_address_arkade.clone(),
                         },
-                        swap_data.swap_params,
+                        self.swap_signer(&swap_data.swap_params).as_ref(),
                         self.wallet.network(),
                     )
                     .await?;
@@ -334,11 +751,24 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
     /// does not apply to swaps funded with Lightning, since the user's Lightning wallet is
     /// responsible for refunding the Lightning HTLC.
     pub async fn refund_vhtlc(&self, swap_id: &str, refund_address: &str) -> crate::Result<String> {
+        let result = self.broadcast_vhtlc_refund(swap_id, refund_address).await;
+        self.record_outcome(swap_id, "refund_vhtlc", result, |txid| {
+            Some(SwapEventKind::Refunded { txid: txid.clone() })
+        })
+        .await
+    }
+
+    async fn broadcast_vhtlc_refund(
+        &self,
+        swap_id: &str,
+        refund_address: &str,
+    ) -> crate::Result<String> {
         let swap_data = self.load_swap_data_from_storage(swap_id).await?;
         if let GetSwapResponse::BtcToEvm(data) = &swap_data.response {
             let refund_address = ArkAddress::from_str(refund_address)
                 .map_err(|e| crate::Error::Parse(format!("Invalid refund ark address {e})")))?;
 
+            self.check_signer_key(&swap_data.swap_params).await?;
             let common_swap_data = swap_data.response.common();
             let txid = vhtlc::refund(
                 &self.arkade_url,
@@ -355,7 +785,8 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
                     network: common_swap_data.network.parse()?,
                     vhtlc_address: data.htlc_address_arkade.clone(),
                 },
-                swap_data.swap_params,
+                swap_data.swap_params.preimage_hash,
+                self.swap_signer(&swap_data.swap_params).as_ref(),
                 self.wallet.network(),
             )
             .await?;
@@ -379,50 +810,449 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
             .ok_or_else(|| crate::Error::SwapNotFound(format!("Swap id not found {swap_id}")))
     }
 
-    /// Load swap data from storage without fetching from the API.
-    pub async fn list_all(&self) -> crate::Result<Vec<ExtendedSwapStorageData>> {
+    /// Prove that this client created the swap `swap_id`, e.g. for a support dispute, in answer
+    /// to the verifier's `challenge`.
+    ///
+    /// With [`OwnershipKey::SwapKey`] the proof is signed by the swap's signer and can be checked
+    /// against the swap with [`crate::verify_swap_ownership`]. With [`OwnershipKey::UserId`] it is
+    /// signed with the user ID derived from the mnemonic.
+    pub async fn prove_swap_ownership(
+        &self,
+        swap_id: &str,
+        challenge: &str,
+        key: OwnershipKey,
+    ) -> crate::Result<OwnershipProof> {
+        let swap_data = self.load_swap_data_from_storage(swap_id).await?;
+        let params = &swap_data.swap_params;
+        let message = ownership_message(swap_id, key, challenge);
+
+        let signature = match key {
+            OwnershipKey::SwapKey => {
+                self.check_signer_key(params).await?;
+                let signature = self
+                    .swap_signer(params)
+                    .sign_schnorr(params.key_index, message_hash(message.as_bytes()))
+                    .await?;
+                MessageSignature {
+                    public_key: params.public_key,
+                    signature,
+                }
+            }
+            OwnershipKey::UserId => {
+                self.ensure_not_watch_only()?;
+                let signature = self
+                    .wallet
+                    .sign_message_with_user_id(params.key_index, message.as_bytes())
+                    .await?;
+                if signature.public_key != params.user_id {
+                    return Err(crate::Error::KeyDerivation(format!(
+                        "User ID derived at index {} does not match the stored swap",
+                        params.key_index
+                    )));
+                }
+                signature
+            }
+        };
+
+        let proof = OwnershipProof::new(swap_id, key, challenge, signature);
+        if key == OwnershipKey::SwapKey {
+            verify_swap_ownership(&swap_data.response, challenge, &proof)?;
+        }
+        Ok(proof)
+    }
+
+    /// Check that the signer's key at the stored index matches the stored public key.
+    ///
+    /// Fails e.g. if the swap was created with a different mnemonic or passphrase.
+    async fn check_signer_key(&self, stored: &PublicSwapParams) -> crate::Result<()> {
+        self.ensure_not_watch_only()?;
+        if stored.account != self.wallet.account() {
+            return Err(crate::Error::KeyDerivation(format!(
+                "Swap belongs to account {}, but the client uses account {}",
+                stored.account,
+                self.wallet.account()
+            )));
+        }
+
+        let public_key = self
+            .swap_signer(stored)
+            .public_key(stored.key_index)
+            .await?;
+
+        if public_key != stored.public_key {
+            return Err(crate::Error::KeyDerivation(format!(
+                "Key derived at index {} does not match the stored swap",
+                stored.key_index
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Rewrite all stored swaps in the current storage format.
+    ///
+    /// Loading a record migrates it to the current schema version (see
+    /// [`crate::storage::schema`]), e.g. older versions persisted the secret key and preimage of
+    /// each swap and loading such a record drops these fields. Storing it again writes the
+    /// migrated record to the backend. Quarantined records are left alone. Records in an older
+    /// format are rewritten by [`Client::init`] already.
+    ///
+    /// Returns the number of swaps rewritten.
+    pub async fn migrate_swap_storage(&self) -> crate::Result<usize> {
+        // Unlike `get`, `get_all` skips quarantined records rather than failing on them.
         let swaps = self.swap_storage.get_all().await?;
+        let mut migrated = 0;
+        for data in swaps {
+            self.swap_storage
+                .store(data.response.id().as_str(), &data)
+                .await?;
+            migrated += 1;
+        }
+
+        log::info!("Rewrote {} stored swaps without secrets", migrated);
+        Ok(migrated)
+    }
+
+    /// Rewrite the swap records stored in an older format, see [`SwapStorage::list_outdated`].
+    async fn migrate_outdated_swap_records(&self) -> crate::Result<usize> {
+        let outdated = self.swap_storage.list_outdated().await?;
+        if outdated.is_empty() {
+            return Ok(0);
+        }
+
+        let mut migrated = 0;
+        for data in self.swap_storage.get_all().await? {
+            let swap_id = data.response.id();
+            if outdated.contains(&swap_id) {
+                self.swap_storage.store(&swap_id, &data).await?;
+                migrated += 1;
+            }
+        }
+        log::info!("Rewrote {} swaps stored in an older format", migrated);
+        Ok(migrated)
+    }
+
+    /// List the stored swap and VTXO swap records that can't be read and are therefore skipped,
+    /// e.g. records written by a newer version of the SDK.
+    pub async fn list_quarantined_swaps(&self) -> crate::Result<Vec<QuarantinedRecord>> {
+        let records = self.swap_storage.get_quarantined().await?;
+        Ok(records)
+    }
+
+    /// Load the swaps of the client's account from storage without fetching from the API.
+    pub async fn list_all(&self) -> crate::Result<Vec<ExtendedSwapStorageData>> {
+        let account = self.wallet.account();
+        let swaps = self
+            .swap_storage
+            .get_all()
+            .await?
+            .into_iter()
+            .filter(|data| data.swap_params.account == account)
+            .collect();
 
         Ok(swaps)
     }
 
+    /// Query the swaps of the client's account in storage without fetching from the API.
+    ///
+    /// The query's account is set to the client's account. Pass the returned
+    /// [`SwapPage::next_cursor`] in the next query to get the following page.
+    pub async fn list_swaps(&self, query: SwapQuery) -> crate::Result<SwapPage> {
+        let query = SwapQuery {
+            account: Some(self.wallet.account()),
+            ..query
+        };
+        let page = self.swap_storage.query(&query).await?;
+        Ok(page)
+    }
+
     pub async fn get_version(&self) -> crate::Result<Version> {
         let version = self.api_client.get_version().await?;
         Ok(version)
     }
 
+    /// Recover swaps from the server and merge them into local storage.
+    ///
+    /// Returns all stored swaps of the account afterwards. See
+    /// [`Client::recover_swaps_with_report`].
     pub async fn recover_swaps(&self) -> crate::Result<Vec<ExtendedSwapStorageData>> {
-        self.clear_swap_storage().await?;
+        self.recover_swaps_with_report().await?;
 
-        let xpub = self
-            .wallet
-            .get_user_id_xpub()
-            .await
-            .map_err(|e| crate::Error::Other(format!("Could not retrieve user xpub {e:#}")))?
-            .ok_or(crate::Error::NoMnemonic)?;
+        self.list_all().await
+    }
+
+    /// Recover swaps from the server and merge them into local storage.
+    ///
+    /// Local data is never wiped: swaps the server doesn't know about are kept, recovered swaps
+    /// only replace a stored record if they are at least as far along, and records whose key
+    /// disagrees with the recovered swap are left alone and reported as conflicts. The key index
+    /// is never lowered.
+    ///
+    /// Only the client's account is recovered; swaps of other accounts are left untouched.
+    pub async fn recover_swaps_with_report(&self) -> crate::Result<RecoveryReport> {
+        let xpub = match &self.watch_only {
+            Some(xpub) => xpub.to_string(),
+            None => self
+                .wallet
+                .get_user_id_xpub()
+                .await
+                .map_err(|e| crate::Error::Other(format!("Could not retrieve user xpub {e:#}")))?
+                .ok_or(crate::Error::NoMnemonic)?,
+        };
         let recovered = self.api_client.recover_swaps(xpub.as_str()).await?;
+        let highest_index = recovered.highest_index;
 
-        for recovered_swap in recovered.swaps {
-            let swap_params = self
+        let candidates = match &self.watch_only {
+            Some(xpub) => self.watch_only_candidates(xpub, recovered.swaps).await?,
+            None => self.derive_candidates(recovered.swaps).await?,
+        };
+
+        let local = self.list_all().await?;
+        let (to_store, mut report) = recovery::merge_swaps(local, candidates);
+        for data in &to_store {
+            self.swap_storage
+                .store(data.response.id().as_str(), data)
+                .await?;
+        }
+
+        let key_index = self.wallet.get_key_index().await?.max(highest_index);
+        if !self.is_watch_only() {
+            self.wallet.set_key_index(key_index).await?;
+        }
+        report.key_index = key_index;
+
+        log::info!(
+            "Recovered swaps: {} added, {} updated, {} conflicts, {} local only",
+            report.added.len(),
+            report.updated.len(),
+            report.conflicts.len(),
+            report.local_only.len()
+        );
+        Ok(report)
+    }
+
+    /// Pair recovered swaps with the swap parameters derived from the mnemonic.
+    ///
+    /// Every known derivation scheme is tried, since the swap was possibly created with another
+    /// scheme than the wallet's current one. Swaps no scheme matches, e.g. those paying a
+    /// Lightning invoice, get the parameters of the current scheme. Swaps whose key index is out
+    /// of range are skipped.
+    async fn derive_candidates(
+        &self,
+        swaps: Vec<RecoveredSwap>,
+    ) -> crate::Result<Vec<ExtendedSwapStorageData>> {
+        // Derive the keys of all recovered swaps in one go rather than one by one
+        let indices = recovery::recovered_key_indices(&swaps);
+        let mut swap_params = Vec::with_capacity(DerivationScheme::ALL.len());
+        for scheme in DerivationScheme::ALL {
+            let params = self
                 .wallet
-                .derive_swap_params_at_index(recovered_swap.index)
+                .derive_swap_params_batch_with_scheme(scheme, &indices)
                 .await?;
-            let swap_id = recovered_swap.swap.id();
-            let data = ExtendedSwapStorageData {
-                response: recovered_swap.swap,
-                swap_params,
-            };
+            swap_params.push(params);
+        }
+        let current_scheme = self.wallet.derivation_scheme().await?;
+
+        let candidates = swaps
+            .into_iter()
+            .filter_map(|recovered_swap| {
+                // Swaps with out of range indices were left out
+                let offset = indices.binary_search(&recovered_swap.index).ok()?;
+                let params = swap_params
+                    .iter()
+                    .map(|params| params[offset].to_public())
+                    .find(|params| recovery::swap_matches_params(&recovered_swap.swap, params))
+                    .unwrap_or_else(|| swap_params[current_scheme.position()][offset].to_public());
+                Some(ExtendedSwapStorageData {
+                    response: recovered_swap.swap,
+                    swap_params: params,
+                })
+            })
+            .collect();
+        Ok(candidates)
+    }
+
+    /// Pair recovered swaps with swap parameters reconstructed from the swaps and the Xpub.
+    ///
+    /// The derivation scheme can't be told from public data, so swaps are assumed to use the
+    /// wallet's scheme. Swaps whose parameters can't be reconstructed are skipped.
+    async fn watch_only_candidates(
+        &self,
+        xpub: &Xpub,
+        swaps: Vec<RecoveredSwap>,
+    ) -> crate::Result<Vec<ExtendedSwapStorageData>> {
+        let scheme = self.wallet.derivation_scheme().await?;
+        let candidates = swaps
+            .into_iter()
+            .filter_map(|recovered_swap| {
+                let swap_params = recovery::watch_only_swap_params(
+                    &recovered_swap.swap,
+                    xpub,
+                    recovered_swap.index,
+                    self.wallet.account(),
+                    scheme,
+                );
+                match swap_params {
+                    Ok(swap_params) => Some(ExtendedSwapStorageData {
+                        response: recovered_swap.swap,
+                        swap_params,
+                    }),
+                    Err(e) => {
+                        log::warn!("Skipping recovered swap {}: {e}", recovered_swap.swap.id());
+                        None
+                    }
+                }
+            })
+            .collect();
+        Ok(candidates)
+    }
+
+    /// Export the wallet and all stored swaps as an encrypted backup.
+    ///
+    /// The backup contains the mnemonic, the key indices, the passphrase fingerprint and the
+    /// swaps and VTXO swaps of all accounts, encrypted with `password`. The BIP39 passphrase itself is not part
+    /// of the backup. Restore it with [`Client::import_backup`].
+    pub async fn export_backup(&self, password: &str) -> crate::Result<String> {
+        self.export_backup_with_kdf_params(password, KdfParams::default())
+            .await
+    }
+
+    /// Like [`Client::export_backup`], but with custom key derivation parameters.
+    pub async fn export_backup_with_kdf_params(
+        &self,
+        password: &str,
+        kdf_params: KdfParams,
+    ) -> crate::Result<String> {
+        self.ensure_not_watch_only()?;
+        let swaps = self.swap_storage.get_all().await?;
+        let vtxo_swaps = self.swap_storage.get_all_vtxo_swaps().await?;
+        let accounts = swaps
+            .iter()
+            .map(|data| data.swap_params.account)
+            .chain(vtxo_swaps.iter().map(|data| data.swap_params.account));
+        let wallet = self.wallet.backup(accounts).await?;
+
+        let backup = Backup {
+            metadata: BackupMetadata {
+                network: self.wallet.network(),
+                created_at: OffsetDateTime::now_utc(),
+                account: self.wallet.account(),
+                swap_count: swaps.len(),
+                vtxo_swap_count: vtxo_swaps.len(),
+            },
+            wallet,
+            swaps,
+            vtxo_swaps,
+        };
+        backup.encrypt(password, kdf_params)
+    }
 
-            self.swap_storage.store(swap_id.as_str(), &data).await?;
+    /// Restore a backup created with [`Client::export_backup`].
+    ///
+    /// The backup must be for the client's network. The wallet storage must be empty or hold the
+    /// same mnemonic. Swaps are merged into the swap storage like recovered swaps (see
+    /// [`Client::recover_swaps_with_report`]), so newer local records are kept. VTXO swaps are
+    /// only added if they aren't stored yet. Key indices are
+    /// never lowered and are raised past those of the imported swaps in every account.
+    ///
+    /// Returns the metadata of the backup and a report of the merged swaps.
+    pub async fn import_backup(
+        &self,
+        backup: &str,
+        password: &str,
+    ) -> crate::Result<(BackupMetadata, RecoveryReport)> {
+        self.ensure_not_watch_only()?;
+        let mut backup = Backup::decrypt(backup, password)?;
+        backup.check_network(self.wallet.network())?;
+
+        let swap_params = backup
+            .swaps
+            .iter()
+            .map(|data| &data.swap_params)
+            .chain(backup.vtxo_swaps.iter().map(|data| &data.swap_params));
+        backup.wallet.raise_key_indices(swap_params);
+        self.wallet.restore_backup(&backup.wallet).await?;
+
+        let local = self.swap_storage.get_all().await?;
+        let (to_store, mut report) = recovery::merge_swaps(local, backup.swaps);
+        for data in &to_store {
+            self.swap_storage
+                .store(data.response.id().as_str(), data)
+                .await?;
+        }
+        let local = self.swap_storage.get_all_vtxo_swaps().await?;
+        let to_store = recovery::merge_vtxo_swaps(local, backup.vtxo_swaps, &mut report);
+        for data in &to_store {
+            self.swap_storage
+                .store_vtxo_swap(&data.response.id.to_string(), data)
+                .await?;
         }
+        report.key_index = self.wallet.get_key_index().await?;
+
+        log::info!(
+            "Imported backup: {} added, {} updated, {} conflicts",
+            report.added.len(),
+            report.updated.len(),
+            report.conflicts.len()
+        );
+        Ok((backup.metadata, report))
+    }
+
+    /// Publish a backup of each stored swap of the account to Nostr relays.
+    ///
+    /// See [`crate::nostr_backup`]. Returns the number of swaps published.
+    pub async fn publish_swap_backups<R: NostrRelay + ?Sized>(
+        &self,
+        relay: &R,
+    ) -> crate::Result<usize> {
+        self.ensure_not_watch_only()?;
+        let keys = self.wallet.derive_nostr_keys().await?;
+        let swaps = self.list_all().await?;
+        nostr_backup::publish_swap_backups(relay, &keys, &swaps).await?;
+
+        Ok(swaps.len())
+    }
 
-        self.wallet.set_key_index(recovered.highest_index).await?;
+    /// Restore the account's swaps from their Nostr backups and merge them into local storage.
+    ///
+    /// Swaps are merged like in [`Client::recover_swaps_with_report`] and the key index is raised
+    /// past the highest restored one.
+    pub async fn restore_swap_backups<R: NostrRelay + ?Sized>(
+        &self,
+        relay: &R,
+    ) -> crate::Result<RecoveryReport> {
+        self.ensure_not_watch_only()?;
+        let keys = self.wallet.derive_nostr_keys().await?;
+        let restored = nostr_backup::fetch_swap_backups(relay, &keys).await?;
+        let next_index = restored
+            .iter()
+            .map(|data| data.swap_params.key_index.saturating_add(1))
+            .max()
+            .unwrap_or(0);
+
+        let local = self.list_all().await?;
+        let (to_store, mut report) = recovery::merge_swaps(local, restored);
+        for data in &to_store {
+            self.swap_storage
+                .store(data.response.id().as_str(), data)
+                .await?;
+        }
 
-        let all_swaps = self.swap_storage.get_all().await?;
-        Ok(all_swaps)
+        let key_index = self.wallet.get_key_index().await?.max(next_index);
+        self.wallet.set_key_index(key_index).await?;
+        report.key_index = key_index;
+
+        log::info!(
+            "Restored swaps from Nostr: {} added, {} updated, {} conflicts",
+            report.added.len(),
+            report.updated.len(),
+            report.conflicts.len()
+        );
+        Ok(report)
     }
 
     pub async fn get_mnemonic(&self) -> crate::Result<String> {
+        self.ensure_not_watch_only()?;
         let mnemonic = self
             .wallet
             .get_mnemonic()
@@ -433,6 +1263,10 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
     }
 
     pub async fn get_user_id_xpub(&self) -> crate::Result<String> {
+        if let Some(xpub) = &self.watch_only {
+            return Ok(xpub.to_string());
+        }
+
         let xpub = self
             .wallet
             .get_user_id_xpub()
@@ -446,6 +1280,11 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
         for id in swap_ids {
             self.swap_storage.delete(&id).await?;
         }
+        for data in self.swap_storage.get_all_vtxo_swaps().await? {
+            self.swap_storage
+                .delete_vtxo_swap(&data.response.id.to_string())
+                .await?;
+        }
         Ok(())
     }
     pub async fn delete_swap(&self, id: String) -> crate::Result<()> {
@@ -475,13 +1314,22 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
     /// then the server funds their VHTLC, and the client claims the server's
     /// VHTLC to complete the swap.
     ///
+    /// The swap is stored before it is returned, see [`SwapStorage::store_vtxo_swap`], so it can
+    /// later be claimed or refunded by ID. Its creation is journaled like that of other swaps,
+    /// see [`crate::journal`]. Fails without creating a swap if the storage doesn't support VTXO
+    /// swaps, see [`SwapStorage::supports_vtxo_swaps`].
+    ///
     /// # Arguments
     /// * `vtxos` - List of VTXO outpoints to refresh ("txid:vout" format)
     pub async fn create_vtxo_swap(
         &self,
         vtxos: Vec<String>,
-    ) -> crate::Result<(VtxoSwapResponse, SwapParams)> {
-        let swap_params = self.wallet.derive_swap_params().await?;
+    ) -> crate::Result<(VtxoSwapResponse, PublicSwapParams)> {
+        // Fail before the server creates a swap that couldn't be stored
+        if !self.swap_storage.supports_vtxo_swaps() {
+            return Err(crate::storage::vtxo_swaps_unsupported());
+        }
+        let (swap_params, mut pending) = self.begin_creation(PendingCreationKind::VtxoSwap).await?;
 
         let request = CreateVtxoSwapRequest {
             vtxos,
@@ -494,8 +1342,22 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
 
         let swap_id = response.id.to_string();
         log::info!("Created VTXO swap {}", swap_id);
+        self.record_event(&swap_id, SwapEventKind::created(&response.status))
+            .await;
 
-        Ok((response, swap_params))
+        // VTXO swaps can't be looked up by key index, so journal the ID before storing the swap
+        pending.swap_id = Some(swap_id.clone());
+        if let Err(e) = self.swap_storage.store_pending_creation(&pending).await {
+            log::warn!("Failed to journal the ID of VTXO swap {swap_id}: {e:#}");
+        }
+
+        let data = ExtendedVtxoSwapStorageData {
+            response,
+            swap_params: swap_params.to_public(),
+        };
+        self.finish_vtxo_swap_creation(&swap_id, &data).await?;
+
+        Ok((data.response, data.swap_params))
     }
 
     /// Get VTXO swap details by ID.
@@ -504,6 +1366,89 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
         Ok(response)
     }
 
+    /// Fetch the latest status of a stored VTXO swap from the API and update the local storage.
+    /// A status change is recorded in the swap's history.
+    pub async fn refresh_vtxo_swap(&self, id: &str) -> crate::Result<ExtendedVtxoSwapStorageData> {
+        let known = self.load_vtxo_swap_from_storage(id).await?;
+        let result = self.api_client.get_vtxo_swap(id).await;
+        let response = self
+            .record_outcome(id, "get_vtxo_swap", result, |response| {
+                SwapEventKind::status_changed(&known.response.status, &response.status)
+            })
+            .await?;
+        let data = ExtendedVtxoSwapStorageData {
+            response,
+            swap_params: known.swap_params,
+        };
+
+        self.swap_storage.store_vtxo_swap(id, &data).await?;
+        Ok(data)
+    }
+
+    /// Load VTXO swap data from storage without fetching from the API.
+    pub async fn load_vtxo_swap_from_storage(
+        &self,
+        swap_id: &str,
+    ) -> crate::Result<ExtendedVtxoSwapStorageData> {
+        self.swap_storage
+            .get_vtxo_swap(swap_id)
+            .await?
+            .ok_or_else(|| crate::Error::SwapNotFound(format!("VTXO swap id not found {swap_id}")))
+    }
+
+    /// Load the VTXO swaps of the client's account from storage without fetching from the API.
+    pub async fn list_vtxo_swaps(&self) -> crate::Result<Vec<ExtendedVtxoSwapStorageData>> {
+        let account = self.wallet.account();
+        let swaps = self
+            .swap_storage
+            .get_all_vtxo_swaps()
+            .await?
+            .into_iter()
+            .filter(|data| data.swap_params.account == account)
+            .collect();
+
+        Ok(swaps)
+    }
+
+    pub async fn delete_vtxo_swap(&self, id: &str) -> crate::Result<()> {
+        self.swap_storage.delete_vtxo_swap(id).await?;
+        Ok(())
+    }
+
+    /// Claim the server's VHTLC of a stored VTXO swap, see [`Client::claim_vtxo_swap`].
+    ///
+    /// The swap's status is refreshed first, so the claim uses the latest server data.
+    pub async fn claim_vtxo_swap_by_id(
+        &self,
+        swap_id: &str,
+        claim_address: &str,
+    ) -> crate::Result<String> {
+        let data = self.refresh_vtxo_swap(swap_id).await?;
+        let result = self
+            .broadcast_vtxo_swap_claim(&data.response, &data.swap_params, claim_address)
+            .await;
+        self.record_outcome(swap_id, "claim_vtxo_swap", result, |txid| {
+            Some(SwapEventKind::Claimed { txid: txid.clone() })
+        })
+        .await
+    }
+
+    /// Refund the client's VHTLC of a stored VTXO swap, see [`Client::refund_vtxo_swap`].
+    pub async fn refund_vtxo_swap_by_id(
+        &self,
+        swap_id: &str,
+        refund_address: &str,
+    ) -> crate::Result<String> {
+        let data = self.load_vtxo_swap_from_storage(swap_id).await?;
+        let result = self
+            .broadcast_vtxo_swap_refund(&data.response, &data.swap_params, refund_address)
+            .await;
+        self.record_outcome(swap_id, "refund_vtxo_swap", result, |txid| {
+            Some(SwapEventKind::Refunded { txid: txid.clone() })
+        })
+        .await
+    }
+
     /// Claim the server's VHTLC in a VTXO swap.
     ///
     /// This should be called after the server has funded their VHTLC.
@@ -511,22 +1456,42 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
     ///
     /// # Arguments
     /// * `swap` - The VTXO swap response
-    /// * `swap_params` - The client's swap parameters (containing preimage)
+    /// * `key_index` - The key index of the swap's parameters (the preimage is re-derived)
     /// * `claim_address` - The Arkade address to receive the claimed funds
     pub async fn claim_vtxo_swap(
         &self,
         swap: &VtxoSwapResponse,
-        swap_params: SwapParams,
+        key_index: u32,
+        claim_address: &str,
+    ) -> crate::Result<String> {
+        let result = async {
+            let params = self.vtxo_swap_params(swap, key_index).await?;
+            self.broadcast_vtxo_swap_claim(swap, &params, claim_address)
+                .await
+        }
+        .await;
+        self.record_outcome(&swap.id.to_string(), "claim_vtxo_swap", result, |txid| {
+            Some(SwapEventKind::Claimed { txid: txid.clone() })
+        })
+        .await
+    }
+
+    async fn broadcast_vtxo_swap_claim(
+        &self,
+        swap: &VtxoSwapResponse,
+        params: &PublicSwapParams,
         claim_address: &str,
     ) -> crate::Result<String> {
         let claim_ark_address = ArkAddress::from_str(claim_address)
             .map_err(|e| crate::Error::Parse(format!("Invalid claim ark address: {}", e)))?;
+        self.check_signer_key(params).await?;
 
         let txid = vtxo_swap::claim_server_vhtlc(
             &self.arkade_url,
             claim_ark_address,
             swap,
-            swap_params,
+            params.key_index,
+            self.swap_signer(params).as_ref(),
             self.wallet.network(),
         )
         .await?;
@@ -541,26 +1506,162 @@ impl<S: WalletStorage, SS: SwapStorage> Client<S, SS> {
     ///
     /// # Arguments
     /// * `swap` - The VTXO swap response
-    /// * `swap_params` - The client's swap parameters
+    /// * `key_index` - The key index of the swap's parameters (the key is re-derived)
     /// * `refund_address` - The Arkade address to receive the refunded funds
     pub async fn refund_vtxo_swap(
         &self,
         swap: &VtxoSwapResponse,
-        swap_params: SwapParams,
+        key_index: u32,
+        refund_address: &str,
+    ) -> crate::Result<String> {
+        let result = async {
+            let params = self.vtxo_swap_params(swap, key_index).await?;
+            self.broadcast_vtxo_swap_refund(swap, &params, refund_address)
+                .await
+        }
+        .await;
+        self.record_outcome(&swap.id.to_string(), "refund_vtxo_swap", result, |txid| {
+            Some(SwapEventKind::Refunded { txid: txid.clone() })
+        })
+        .await
+    }
+
+    async fn broadcast_vtxo_swap_refund(
+        &self,
+        swap: &VtxoSwapResponse,
+        params: &PublicSwapParams,
         refund_address: &str,
     ) -> crate::Result<String> {
         let refund_ark_address = ArkAddress::from_str(refund_address)
             .map_err(|e| crate::Error::Parse(format!("Invalid refund ark address: {}", e)))?;
+        self.check_signer_key(params).await?;
 
         let txid = vtxo_swap::refund_client_vhtlc(
             &self.arkade_url,
             refund_ark_address,
             swap,
-            swap_params,
+            params.key_index,
+            self.swap_signer(params).as_ref(),
             self.wallet.network(),
         )
         .await?;
 
         Ok(txid.to_string())
     }
+
+    // =========================================================================
+    // Swap History
+    // =========================================================================
+
+    /// The recorded events of a swap or VTXO swap, oldest first. See [`crate::history`].
+    pub async fn swap_history(&self, id: &str) -> crate::Result<Vec<SwapEvent>> {
+        self.swap_storage.get_swap_events(id).await
+    }
+
+    /// Export the histories of the swaps and VTXO swaps of the client's account, including
+    /// deleted ones, oldest event first.
+    ///
+    /// Events don't record an account, so the histories of deleted swaps are included whichever
+    /// account they belonged to. Only the histories of swaps stored for other accounts are left
+    /// out.
+    pub async fn export_swap_history(&self) -> crate::Result<SwapHistoryExport> {
+        let account = self.wallet.account();
+        let swaps = self.swap_storage.get_all().await?.into_iter().map(|data| {
+            let id = data.response.id();
+            (id, data.swap_params.account)
+        });
+        let vtxo_swaps = self
+            .swap_storage
+            .get_all_vtxo_swaps()
+            .await?
+            .into_iter()
+            .map(|data| (data.response.id.to_string(), data.swap_params.account));
+        let other_accounts: HashSet<String> = swaps
+            .chain(vtxo_swaps)
+            .filter(|(_, swap_account)| *swap_account != account)
+            .map(|(id, _)| id)
+            .collect();
+
+        let mut events: Vec<SwapEvent> = self
+            .swap_storage
+            .get_all_swap_events()
+            .await?
+            .into_iter()
+            .filter(|event| !other_accounts.contains(&event.swap_id))
+            .collect();
+        events.sort_by_key(|event| event.timestamp);
+
+        Ok(SwapHistoryExport {
+            exported_at: OffsetDateTime::now_utc(),
+            account,
+            events,
+        })
+    }
+
+    /// Append an event to the history of `swap_id`. Failures are logged and otherwise ignored.
+    async fn record_event(&self, swap_id: &str, kind: SwapEventKind) {
+        let event = SwapEvent::now(swap_id, kind);
+        if let Err(e) = self.swap_storage.append_swap_event(&event).await {
+            log::warn!("Failed to record event of swap {swap_id}: {e:#}");
+        }
+    }
+
+    /// Record the outcome of `operation` on `swap_id`: the event returned by `on_success`, if
+    /// any, or the error. The result is passed through.
+    async fn record_outcome<T>(
+        &self,
+        swap_id: &str,
+        operation: &str,
+        result: crate::Result<T>,
+        on_success: impl FnOnce(&T) -> Option<SwapEventKind>,
+    ) -> crate::Result<T> {
+        let kind = match &result {
+            Ok(value) => on_success(value),
+            Err(e) => Some(SwapEventKind::Error {
+                operation: operation.to_string(),
+                message: format!("{e:#}"),
+            }),
+        };
+        if let Some(kind) = kind {
+            self.record_event(swap_id, kind).await;
+        }
+
+        result
+    }
+
+    /// The parameters to claim or refund `swap` with: the stored ones if the swap is stored,
+    /// otherwise those at `key_index` with the wallet's current account and scheme, which must
+    /// match the swap's client key.
+    async fn vtxo_swap_params(
+        &self,
+        swap: &VtxoSwapResponse,
+        key_index: u32,
+    ) -> crate::Result<PublicSwapParams> {
+        match self
+            .swap_storage
+            .get_vtxo_swap(&swap.id.to_string())
+            .await?
+        {
+            Some(data) if data.swap_params.key_index == key_index => Ok(data.swap_params),
+            Some(data) => Err(crate::Error::KeyDerivation(format!(
+                "VTXO swap {} is stored with key index {}, not {}",
+                swap.id, data.swap_params.key_index, key_index
+            ))),
+            None => {
+                self.ensure_not_watch_only()?;
+                let params = self
+                    .wallet
+                    .derive_swap_params_at_index(key_index)
+                    .await?
+                    .to_public();
+                if hex::encode(params.public_key.serialize()) != swap.client_pk {
+                    return Err(crate::Error::KeyDerivation(format!(
+                        "Key derived at index {} does not match the client key of VTXO swap {}",
+                        key_index, swap.id
+                    )));
+                }
+                Ok(params)
+            }
+        }
+    }
 }
@@ -0,0 +1,246 @@
+//! Password-based encryption for secrets at rest.
+//!
+//! Secrets are encrypted with XChaCha20-Poly1305 under a key derived from a password with
+//! Argon2id. The result is a self-describing [`EncryptedEnvelope`] carrying the KDF parameters,
+//! salt and nonce, so it can be decrypted again with nothing but the password.
+//!
+//! Argon2id produces 64 bytes: the first half is the encryption key, the hash of the second half
+//! is stored as a key check value. This lets us tell a wrong password
+//! ([`Error::InvalidPassword`]) apart from a damaged ciphertext ([`Error::CorruptedCiphertext`]),
+//! which an AEAD failure alone cannot.
+
+use crate::error::{Error, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+/// Current envelope format version.
+const ENVELOPE_VERSION: u8 = 1;
+/// Associated data binding ciphertexts to this format.
+const ENVELOPE_AAD: &[u8] = b"lendaswap/encrypted-envelope/v1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+/// Upper bounds of the KDF parameters, so that a tampered envelope can't make unlocking allocate
+/// gigabytes of memory or run for hours.
+const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_ITERATIONS: u32 = 64;
+const MAX_PARALLELISM: u32 = 16;
+
+/// Argon2id parameters used to derive an encryption key from a password.
+///
+/// The defaults follow the OWASP recommendation for Argon2id (19 MiB, 2 iterations, 1 lane).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Memory cost in KiB.
+    pub memory_kib: u32,
+    /// Number of iterations.
+    pub iterations: u32,
+    /// Degree of parallelism.
+    pub parallelism: u32,
+}
+
+impl KdfParams {
+    /// Check the parameters against the upper bounds this SDK derives keys with.
+    fn check_limits(&self) -> std::result::Result<(), String> {
+        if self.memory_kib > MAX_MEMORY_KIB {
+            return Err(format!(
+                "memory cost {} KiB exceeds {} KiB",
+                self.memory_kib, MAX_MEMORY_KIB
+            ));
+        }
+        if self.iterations > MAX_ITERATIONS {
+            return Err(format!(
+                "{} iterations exceed {}",
+                self.iterations, MAX_ITERATIONS
+            ));
+        }
+        if self.parallelism > MAX_PARALLELISM {
+            return Err(format!(
+                "parallelism {} exceeds {}",
+                self.parallelism, MAX_PARALLELISM
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// An encrypted secret, serialized as JSON when persisted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct EncryptedEnvelope {
+    version: u8,
+    kdf: KdfParams,
+    /// Hex-encoded Argon2id salt.
+    salt: String,
+    /// Hex-encoded SHA256 of the key check half of the Argon2id output.
+    key_check: String,
+    /// Hex-encoded XChaCha20-Poly1305 nonce.
+    nonce: String,
+    /// Hex-encoded ciphertext including the authentication tag.
+    ciphertext: String,
+}
+
+impl EncryptedEnvelope {
+    /// Parse a persisted envelope.
+    pub(crate) fn parse(value: &str) -> Result<Self> {
+        let envelope: Self = serde_json::from_str(value)
+            .map_err(|e| Error::CorruptedCiphertext(format!("invalid envelope: {}", e)))?;
+
+        if envelope.version != ENVELOPE_VERSION {
+            return Err(Error::CorruptedCiphertext(format!(
+                "unsupported envelope version {}",
+                envelope.version
+            )));
+        }
+
+        Ok(envelope)
+    }
+
+    /// Serialize the envelope for persistence.
+    pub(crate) fn encode(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// A key derived from a password, bound to a salt and KDF parameters.
+#[derive(Clone)]
+pub(crate) struct DerivedKey {
+    key: Zeroizing<[u8; 32]>,
+    key_check: [u8; 32],
+    salt: [u8; SALT_LEN],
+    params: KdfParams,
+}
+
+impl DerivedKey {
+    /// Derive a key from a password with a fresh random salt.
+    pub(crate) fn derive(password: &str, params: KdfParams) -> Result<Self> {
+        params
+            .check_limits()
+            .map_err(|e| Error::Other(format!("Invalid KDF parameters: {}", e)))?;
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::derive_with_salt(password, salt, params)
+    }
+
+    /// Re-derive the key an envelope was encrypted with.
+    ///
+    /// Returns [`Error::InvalidPassword`] if the password does not match and
+    /// [`Error::CorruptedCiphertext`] if the envelope's KDF parameters exceed the limits.
+    pub(crate) fn for_envelope(password: &str, envelope: &EncryptedEnvelope) -> Result<Self> {
+        envelope
+            .kdf
+            .check_limits()
+            .map_err(|e| Error::CorruptedCiphertext(format!("invalid KDF parameters: {}", e)))?;
+        let salt = decode_hex::<SALT_LEN>(&envelope.salt, "salt")?;
+        let key = Self::derive_with_salt(password, salt, envelope.kdf)?;
+
+        if hex::encode(key.key_check) != envelope.key_check {
+            return Err(Error::InvalidPassword);
+        }
+
+        Ok(key)
+    }
+
+    fn derive_with_salt(password: &str, salt: [u8; SALT_LEN], params: KdfParams) -> Result<Self> {
+        let argon2_params = Params::new(
+            params.memory_kib,
+            params.iterations,
+            params.parallelism,
+            Some(64),
+        )
+        .map_err(|e| Error::Other(format!("Invalid KDF parameters: {}", e)))?;
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, argon2_params);
+
+        let mut output = Zeroizing::new([0u8; 64]);
+        argon2
+            .hash_password_into(password.as_bytes(), &salt, output.as_mut())
+            .map_err(|e| Error::Other(format!("Key derivation failed: {}", e)))?;
+
+        let mut key = Zeroizing::new([0u8; 32]);
+        key.copy_from_slice(&output[..32]);
+        let key_check = Sha256::digest(&output[32..]).into();
+
+        Ok(Self {
+            key,
+            key_check,
+            salt,
+            params,
+        })
+    }
+
+    /// Whether this key was derived for the given envelope's salt and parameters.
+    pub(crate) fn matches(&self, envelope: &EncryptedEnvelope) -> bool {
+        envelope.salt == hex::encode(self.salt)
+            && envelope.kdf == self.params
+            && envelope.key_check == hex::encode(self.key_check)
+    }
+
+    /// Encrypt a secret with a fresh random nonce.
+    pub(crate) fn encrypt(&self, plaintext: &[u8]) -> Result<EncryptedEnvelope> {
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(self.key.as_ref()));
+        let ciphertext = cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: ENVELOPE_AAD,
+                },
+            )
+            .map_err(|e| Error::Other(format!("Encryption failed: {}", e)))?;
+
+        Ok(EncryptedEnvelope {
+            version: ENVELOPE_VERSION,
+            kdf: self.params,
+            salt: hex::encode(self.salt),
+            key_check: hex::encode(self.key_check),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    /// Decrypt an envelope.
+    ///
+    /// Returns [`Error::CorruptedCiphertext`] if authentication fails.
+    pub(crate) fn decrypt(&self, envelope: &EncryptedEnvelope) -> Result<Zeroizing<Vec<u8>>> {
+        let nonce = decode_hex::<NONCE_LEN>(&envelope.nonce, "nonce")?;
+        let ciphertext = hex::decode(&envelope.ciphertext)
+            .map_err(|e| Error::CorruptedCiphertext(format!("invalid ciphertext: {}", e)))?;
+
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(self.key.as_ref()));
+        let plaintext = cipher
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: ENVELOPE_AAD,
+                },
+            )
+            .map_err(|_| Error::CorruptedCiphertext("authentication tag mismatch".to_string()))?;
+
+        Ok(Zeroizing::new(plaintext))
+    }
+}
+
+fn decode_hex<const N: usize>(value: &str, field: &str) -> Result<[u8; N]> {
+    hex::decode(value)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| Error::CorruptedCiphertext(format!("invalid {}", field)))
+}
@@ -16,6 +16,14 @@ pub enum Error {
     #[error("Invalid mnemonic phrase: {0}")]
     InvalidMnemonic(String),
 
+    /// The stored mnemonic is protected by a BIP39 passphrase that has not been provided.
+    #[error("This wallet is protected by a passphrase. Provide it before deriving keys.")]
+    PassphraseRequired,
+
+    /// The provided BIP39 passphrase does not match the one the wallet was set up with.
+    #[error("Invalid passphrase: it does not match the wallet's passphrase fingerprint")]
+    InvalidPassphrase,
+
     /// Swap not found in storage.
     #[error("Swap not found: {0}")]
     SwapNotFound(String),
@@ -52,6 +60,34 @@ pub enum Error {
     #[error("Arkade error: {0}")]
     Arkade(String),
 
+    /// The encrypted wallet storage must be unlocked with its password first.
+    #[error("Wallet is locked. Unlock it with its password first.")]
+    WalletLocked,
+
+    /// The password does not match the one the data was encrypted with.
+    #[error("Invalid password")]
+    InvalidPassword,
+
+    /// Encrypted data could not be decrypted although the password is correct.
+    #[error("Corrupted ciphertext: {0}")]
+    CorruptedCiphertext(String),
+
+    /// A backup could not be read or does not match the client.
+    #[error("Invalid backup: {0}")]
+    InvalidBackup(String),
+
+    /// A signature or ownership proof is invalid.
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    /// A Nostr event could not be created or read.
+    #[error("Nostr error: {0}")]
+    Nostr(String),
+
+    /// The operation needs the wallet's keys, but the client is watch-only.
+    #[error("Not available in watch-only mode: this client has no keys")]
+    WatchOnly,
+
     /// Generic error with context.
     #[error("{0}")]
     Other(String),
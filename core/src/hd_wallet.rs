@@ -3,27 +3,70 @@
 //! This module provides BIP39/BIP32 key derivation for Lendaswap swaps.
 
 use crate::error::{Error, Result};
-use crate::types::SwapParams;
+use crate::message::{self, MessageSignature};
+use crate::slip39::{self, Slip39Options};
+use crate::types::{
+    DerivationScheme, MnemonicOptions, Preimage, SwapParams, SwapSecretKey, entropy_len,
+};
 use anyhow::Context;
-use bitcoin::bip32::{DerivationPath, Xpriv, Xpub};
+use bitcoin::bip32::{ChildNumber, DerivationPath, Fingerprint, Xpriv, Xpub};
 use bitcoin::key::Secp256k1;
-use bitcoin::secp256k1::PublicKey;
+use bitcoin::secp256k1::{All, PublicKey};
+use secrecy::{ExposeSecret, SecretBox};
 use sha2::{Digest, Sha256};
+use std::fmt;
+use std::ops::Range;
 use std::str::FromStr;
+use std::sync::OnceLock;
+use unicode_normalization::UnicodeNormalization;
+use zeroize::{Zeroize, Zeroizing};
 
-/// BIP-85 prefix for signing keys.
+/// BIP-85 prefix for signing keys (scheme V1).
 const SIGNING_PREFIX: u32 = 83696968;
+/// BIP-44 coin type of Nostr keys (NIP-06).
+const NOSTR_COIN_TYPE: u32 = 1237;
 /// Prefix for identity key derivation.
 const ID_PREFIX: u32 = 9419;
 /// Lendaswap identifier ("LSW" encoded).
 const LSW_IDENTIFIER: u32 = 121923;
-/// Tag for BIP340-style tagged hash preimage generation.
+/// Hardened child reserved for the swap keys of accounts other than 0.
+///
+/// Account 0 uses the original path `m/{SIGNING_PREFIX}'/{LSW_IDENTIFIER}'/{index}'`, which
+/// takes up every other child, so the highest one is set aside for the remaining accounts.
+const ACCOUNTS_BRANCH: u32 = (1 << 31) - 1;
+/// The highest key index swap parameters can be derived at.
+pub const MAX_KEY_INDEX: u32 = ACCOUNTS_BRANCH - 1;
+/// Tag for BIP340-style tagged hash preimage generation (scheme V1).
 const PREIMAGE_TAG: &str = "lendaswap/preimage";
 
 /// HD Wallet for Lendaswap key derivation.
+///
+/// The mnemonic, passphrase and derived keys are wiped from memory when the wallet is dropped,
+/// and are left out of its `Debug` output.
 pub struct HdWallet {
     mnemonic: bip39::Mnemonic,
+    /// Optional BIP39 passphrase (the "25th word"). Empty if unused.
+    passphrase: Zeroizing<String>,
+    /// Account that keys are derived for.
+    account: u32,
     network: bitcoin::Network,
+    /// Derivation scheme used for new swaps.
+    scheme: DerivationScheme,
+    /// Keys derived from the seed on first use, per derivation scheme. Reset when the passphrase
+    /// or account changes.
+    keys: [OnceLock<DerivedKeys>; DerivationScheme::ALL.len()],
+}
+
+impl fmt::Debug for HdWallet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HdWallet")
+            .field("mnemonic", &"[REDACTED]")
+            .field("passphrase", &"[REDACTED]")
+            .field("account", &self.account)
+            .field("network", &self.network)
+            .field("scheme", &self.scheme)
+            .finish_non_exhaustive()
+    }
 }
 
 impl HdWallet {
@@ -33,67 +76,259 @@ impl HdWallet {
     /// * `network` - Bitcoin network to use
     /// * `word_count` - Number of words (12, 15, 18, 21, or 24)
     pub fn generate(network: bitcoin::Network, word_count: usize) -> Result<Self> {
-        use bip39::{Language, Mnemonic};
-        use rand::rngs::OsRng;
+        Self::generate_with_options(network, &MnemonicOptions::with_word_count(word_count))
+    }
 
-        let mnemonic = Mnemonic::generate_in_with(&mut OsRng, Language::English, word_count)
-            .map_err(|e| Error::KeyDerivation(format!("Failed to generate mnemonic: {}", e)))?;
+    /// Generate a new HD wallet according to the given options.
+    ///
+    /// If the options carry entropy, the mnemonic encodes exactly that entropy, otherwise it is
+    /// drawn from the OS random number generator.
+    pub fn generate_with_options(
+        network: bitcoin::Network,
+        options: &MnemonicOptions,
+    ) -> Result<Self> {
+        use bip39::Mnemonic;
+        use rand::rngs::OsRng;
 
-        Ok(Self { mnemonic, network })
+        let entropy_len = entropy_len(options.word_count)?;
+        let language = options.language.to_bip39_language();
+        let mnemonic = match &options.entropy {
+            Some(entropy) => {
+                if entropy.len() != entropy_len {
+                    return Err(Error::InvalidMnemonic(format!(
+                        "{} words require {} bytes of entropy, got {}",
+                        options.word_count,
+                        entropy_len,
+                        entropy.len()
+                    )));
+                }
+                Mnemonic::from_entropy_in(language, entropy)
+            }
+            None => Mnemonic::generate_in_with(&mut OsRng, language, options.word_count),
+        }
+        .map_err(|e| Error::KeyDerivation(format!("Failed to generate mnemonic: {}", e)))?;
+
+        Ok(Self {
+            mnemonic,
+            passphrase: Zeroizing::new(String::new()),
+            account: 0,
+            network,
+            scheme: DerivationScheme::LATEST,
+            keys: Default::default(),
+        })
     }
 
     /// Create an HD wallet from an existing mnemonic phrase.
     pub fn from_mnemonic(phrase: &str, network: bitcoin::Network) -> Result<Self> {
-        use bip39::Mnemonic;
-        use std::str::FromStr;
+        Self::from_mnemonic_with_passphrase(phrase, "", network)
+    }
 
-        let mnemonic =
-            Mnemonic::from_str(phrase).map_err(|e| Error::InvalidMnemonic(format!("{}", e)))?;
+    /// Create an HD wallet from an existing mnemonic phrase and a BIP39 passphrase.
+    ///
+    /// The passphrase is mixed into the seed, so the same mnemonic with a different passphrase
+    /// yields an entirely different set of keys. Like the mnemonic, it is NFKD-normalized, as
+    /// BIP39 requires. An empty passphrase is equivalent to [`HdWallet::from_mnemonic`].
+    pub fn from_mnemonic_with_passphrase(
+        phrase: &str,
+        passphrase: &str,
+        network: bitcoin::Network,
+    ) -> Result<Self> {
+        let mnemonic = parse_mnemonic(phrase)?;
+
+        Ok(Self {
+            mnemonic,
+            passphrase: Zeroizing::new(passphrase.to_string()),
+            account: 0,
+            network,
+            scheme: DerivationScheme::LATEST,
+            keys: Default::default(),
+        })
+    }
 
-        Ok(Self { mnemonic, network })
+    /// Restore an HD wallet from SLIP-39 shares created with [`HdWallet::to_slip39_shares`].
+    ///
+    /// `slip39_passphrase` is the passphrase the shares were created with. A wrong passphrase
+    /// cannot be detected and restores a different wallet.
+    pub fn from_slip39_shares<S: AsRef<str>>(
+        shares: &[S],
+        slip39_passphrase: &str,
+        network: bitcoin::Network,
+    ) -> Result<Self> {
+        let entropy = slip39::combine_shares(shares, slip39_passphrase)?;
+        let mnemonic = bip39::Mnemonic::from_entropy(&entropy)
+            .map_err(|e| Error::InvalidMnemonic(format!("{}", e)))?;
+
+        Ok(Self {
+            mnemonic,
+            passphrase: Zeroizing::new(String::new()),
+            account: 0,
+            network,
+            scheme: DerivationScheme::LATEST,
+            keys: Default::default(),
+        })
+    }
+
+    /// Split the mnemonic into SLIP-39 shares.
+    ///
+    /// The shares encode the mnemonic's entropy, so restoring with
+    /// [`HdWallet::from_slip39_shares`] yields the same mnemonic and the same keys. The BIP39
+    /// passphrase is not part of the shares and has to be provided again after restoring.
+    ///
+    /// Only English mnemonics are supported, since the wordlist is not part of the shares.
+    pub fn to_slip39_shares(&self, options: &Slip39Options) -> Result<Vec<Vec<String>>> {
+        if self.mnemonic.language() != bip39::Language::English {
+            return Err(Error::InvalidMnemonic(
+                "SLIP-39 backups are only supported for English mnemonics".to_string(),
+            ));
+        }
+
+        let entropy = Zeroizing::new(self.mnemonic.to_entropy());
+        slip39::split_secret(&entropy, options)
+    }
+
+    /// Use the given BIP39 passphrase for all subsequent derivations.
+    pub fn with_passphrase(mut self, passphrase: &str) -> Self {
+        self.passphrase = Zeroizing::new(passphrase.to_string());
+        self.keys = Default::default();
+        self
+    }
+
+    /// Derive keys for the given account.
+    ///
+    /// Each account has its own swap keys and user-ID Xpub, so the swaps of different accounts
+    /// cannot be linked by the server. Account 0 is the default and uses the paths of wallets
+    /// created before accounts were introduced.
+    pub fn with_account(mut self, account: u32) -> Self {
+        if account != self.account {
+            self.account = account;
+            self.keys = Default::default();
+        }
+        self
+    }
+
+    /// The account keys are derived for.
+    pub fn account(&self) -> u32 {
+        self.account
+    }
+
+    /// Use the given derivation scheme for [`HdWallet::derive_swap_params`].
+    ///
+    /// Wallets use [`DerivationScheme::LATEST`] by default. Swaps derived with another scheme can
+    /// still be derived with [`HdWallet::derive_swap_params_with_scheme`].
+    pub fn with_scheme(mut self, scheme: DerivationScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// The derivation scheme used for new swaps.
+    pub fn scheme(&self) -> DerivationScheme {
+        self.scheme
     }
 
     /// Get the mnemonic phrase as a string.
+    ///
+    /// The phrase is in normalized form (NFKD, single spaces).
     pub fn mnemonic_phrase(&self) -> String {
         self.mnemonic.to_string()
     }
 
-    /// Derive swap parameters at the given index.
-    ///
-    /// Derivation path: `m/{SIGNING_PREFIX}'/{LSW_IDENTIFIER}'/{index}'`
+    /// Derive swap parameters at the given index with the wallet's derivation scheme.
     pub fn derive_swap_params(&self, index: u32) -> Result<SwapParams> {
-        use bitcoin::bip32::{DerivationPath, Xpriv};
-        use bitcoin::secp256k1::Secp256k1;
-        use sha2::{Digest, Sha256};
+        self.derive_swap_params_with_scheme(self.scheme, index)
+    }
 
+    /// Derive swap parameters at the given index with the given derivation scheme.
+    ///
+    /// In scheme V1 the derivation path is `m/{SIGNING_PREFIX}'/{LSW_IDENTIFIER}'/{index}'` for
+    /// account 0 and `m/{SIGNING_PREFIX}'/{LSW_IDENTIFIER}'/{ACCOUNTS_BRANCH}'/{account}'/{index}'`
+    /// otherwise.
+    pub fn derive_swap_params_with_scheme(
+        &self,
+        scheme: DerivationScheme,
+        index: u32,
+    ) -> Result<SwapParams> {
         let secp = Secp256k1::new();
-        let seed = self.mnemonic.to_seed("");
-        let master = Xpriv::new_master(self.network, &seed)
-            .map_err(|e| Error::KeyDerivation(format!("Failed to derive master key: {}", e)))?;
-
-        // Derive signing key: m/{SIGNING_PREFIX}'/{LSW_IDENTIFIER}'/{index}'
-        let path_str = format!("m/{}'/{}'/{}'", SIGNING_PREFIX, LSW_IDENTIFIER, index);
-        let path: DerivationPath = path_str
-            .parse()
-            .map_err(|e| Error::KeyDerivation(format!("Invalid derivation path: {}", e)))?;
+        self.derive_swap_params_with(&secp, self.keys(scheme)?, scheme, index)
+    }
 
-        let derived = master
-            .derive_priv(&secp, &path)
+    /// Derive swap parameters for every index in `indices` with the wallet's derivation scheme.
+    ///
+    /// Equivalent to calling [`HdWallet::derive_swap_params`] for each index, but shares the
+    /// secp256k1 context between derivations.
+    pub fn derive_swap_params_range(&self, indices: Range<u32>) -> Result<Vec<SwapParams>> {
+        self.derive_swap_params_range_with_scheme(self.scheme, indices)
+    }
+
+    /// Derive swap parameters for every index in `indices` with the given derivation scheme.
+    pub fn derive_swap_params_range_with_scheme(
+        &self,
+        scheme: DerivationScheme,
+        indices: Range<u32>,
+    ) -> Result<Vec<SwapParams>> {
+        let secp = Secp256k1::new();
+        let keys = self.keys(scheme)?;
+        indices
+            .map(|index| self.derive_swap_params_with(&secp, keys, scheme, index))
+            .collect()
+    }
+
+    /// Derive swap parameters for each of `indices` with the given derivation scheme, e.g. for
+    /// the swaps found by a recovery.
+    pub fn derive_swap_params_batch_with_scheme(
+        &self,
+        scheme: DerivationScheme,
+        indices: &[u32],
+    ) -> Result<Vec<SwapParams>> {
+        let secp = Secp256k1::new();
+        let keys = self.keys(scheme)?;
+        indices
+            .iter()
+            .map(|&index| self.derive_swap_params_with(&secp, keys, scheme, index))
+            .collect()
+    }
+
+    fn derive_swap_params_with(
+        &self,
+        secp: &Secp256k1<All>,
+        keys: &DerivedKeys,
+        scheme: DerivationScheme,
+        index: u32,
+    ) -> Result<SwapParams> {
+        if index > MAX_KEY_INDEX {
+            return Err(Error::KeyDerivation(format!(
+                "Key index {} is out of range",
+                index
+            )));
+        }
+
+        // Derive signing key
+        let child = ChildNumber::from_hardened_idx(index)
+            .map_err(|e| Error::KeyDerivation(format!("Invalid derivation path: {}", e)))?;
+        let mut derived = keys
+            .swap_xpriv
+            .derive_priv(secp, &[child])
             .map_err(|e| Error::KeyDerivation(format!("Key derivation failed: {}", e)))?;
 
-        let secret_key = derived.private_key;
-        let public_key = secret_key.public_key(&secp);
+        let secret_key = SecretBox::new(Box::new(SwapSecretKey::from(derived.private_key)));
+        wipe_xpriv(&mut derived);
+        let public_key = secret_key.expose_secret().public_key(secp);
 
-        // Generate preimage using tagged hash (BIP340-style)
-        // preimage = sha256(sha256(tag) || sha256(tag) || secret_key)
-        let preimage = tagged_hash(PREIMAGE_TAG, &secret_key.secret_bytes());
+        let preimage = match scheme {
+            // Generate preimage using tagged hash (BIP340-style)
+            // preimage = sha256(sha256(tag) || sha256(tag) || secret_key)
+            DerivationScheme::V1 => {
+                let secret_bytes = Zeroizing::new(secret_key.expose_secret().secret_bytes());
+                tagged_hash(PREIMAGE_TAG, secret_bytes.as_slice())
+            }
+        };
+        let preimage = SecretBox::new(Box::new(Preimage::from(preimage)));
 
         // preimage_hash = sha256(preimage)
-        let preimage_hash = Sha256::digest(preimage).into();
+        let preimage_hash = Sha256::digest(**preimage.expose_secret()).into();
 
         // Derive user ID
-        let user_id = self
-            .derive_user_id(index)
+        let user_id = keys
+            .derive_user_id(secp, index)
             .context("failed to derive user_id")?;
 
         Ok(SwapParams {
@@ -103,37 +338,132 @@ impl HdWallet {
             preimage_hash,
             user_id,
             key_index: index,
+            account: self.account,
+            scheme,
         })
     }
 
-    /// Derive a `user_id` at the specified index. The `user_id` is actually just a public key.
+    /// Sign `msg` with the swap key at `index`, see [`crate::message`].
+    pub fn sign_message(&self, index: u32, msg: &[u8]) -> Result<MessageSignature> {
+        let params = self.derive_swap_params(index)?;
+        Ok(message::sign_message_with_key(
+            params.secret_key.expose_secret(),
+            msg,
+        ))
+    }
+
+    /// Sign `msg` with the private key of the user ID at `index`, see [`crate::message`].
     ///
-    /// User IDs are derived using a non-hardened path, so that the corresponding Xpub can be shared
-    /// with the server for efficient recovery of swap data.
-    fn derive_user_id(&self, index: u32) -> Result<PublicKey> {
+    /// Derivation path: `m/{ID_PREFIX}'/{LSW_IDENTIFIER}'/{account}'/{ID_PREFIX}/{LSW_IDENTIFIER}/{index}`
+    pub fn sign_message_with_user_id(&self, index: u32, msg: &[u8]) -> Result<MessageSignature> {
         let secp = Secp256k1::new();
-        let xpub = self
-            .derive_user_id_xpub()
-            .context("could not derive user ID Xpub")?;
+        let path = format!(
+            "m/{ID_PREFIX}'/{LSW_IDENTIFIER}'/{}'/{ID_PREFIX}/{LSW_IDENTIFIER}/{index}",
+            self.account
+        );
+        let path = DerivationPath::from_str(&path)
+            .map_err(|e| Error::KeyDerivation(format!("Invalid derivation path: {}", e)))?;
+        let mut derived = self
+            .keys(self.scheme)?
+            .master
+            .derive_priv(&secp, &path)
+            .map_err(|e| Error::KeyDerivation(format!("Failed to derive user_id: {}", e)))?;
 
-        // Build non-hardened derivation path.
-        let path_str = format!("m/{ID_PREFIX}/{LSW_IDENTIFIER}/{index}");
-        let path = DerivationPath::from_str(&path_str).context("Invalid derivation path")?;
+        let signature = message::sign_message_with_key(&derived.private_key, msg);
+        wipe_xpriv(&mut derived);
+        Ok(signature)
+    }
+
+    /// Derive the account's Nostr keys as specified by NIP-06.
+    ///
+    /// Derivation path: `m/44'/1237'/{account}'/0/0`
+    pub fn derive_nostr_keys(&self) -> Result<nostr::Keys> {
+        let secp = Secp256k1::new();
+        let path = format!("m/44'/{NOSTR_COIN_TYPE}'/{}'/0/0", self.account);
+        let path = DerivationPath::from_str(&path)
+            .map_err(|e| Error::KeyDerivation(format!("Invalid derivation path: {}", e)))?;
+        let mut derived = self
+            .keys(self.scheme)?
+            .master
+            .derive_priv(&secp, &path)
+            .map_err(|e| Error::KeyDerivation(format!("Failed to derive Nostr key: {}", e)))?;
+
+        let secret_bytes = Zeroizing::new(derived.private_key.secret_bytes());
+        let secret_key = nostr::SecretKey::from_slice(secret_bytes.as_slice())
+            .map_err(|e| Error::KeyDerivation(format!("Invalid Nostr key: {}", e)));
+        wipe_xpriv(&mut derived);
+        Ok(nostr::Keys::new(secret_key?))
+    }
+
+    /// Get the keys of `scheme` derived from the seed, deriving them on first use.
+    fn keys(&self, scheme: DerivationScheme) -> Result<&DerivedKeys> {
+        let cached = &self.keys[scheme.position()];
+        if let Some(keys) = cached.get() {
+            return Ok(keys);
+        }
+
+        let keys = self
+            .derive_keys(scheme)
+            .map_err(|e| Error::KeyDerivation(format!("Failed to derive master key: {e:#}")))?;
+        // If another thread was faster, its keys are identical and ours are dropped (and wiped).
+        Ok(cached.get_or_init(|| keys))
+    }
+
+    fn derive_keys(&self, scheme: DerivationScheme) -> anyhow::Result<DerivedKeys> {
+        let secp = Secp256k1::new();
+        let passphrase = Zeroizing::new(self.passphrase.nfkd().collect::<String>());
+        let seed = Zeroizing::new(self.mnemonic.to_seed_normalized(passphrase.as_str()));
+        let master =
+            Xpriv::new_master(self.network, seed.as_slice()).context("Failed to derive Xpriv")?;
+
+        let swap_path = match (scheme, self.account) {
+            (DerivationScheme::V1, 0) => format!("m/{SIGNING_PREFIX}'/{LSW_IDENTIFIER}'"),
+            (DerivationScheme::V1, account) => {
+                format!("m/{SIGNING_PREFIX}'/{LSW_IDENTIFIER}'/{ACCOUNTS_BRANCH}'/{account}'")
+            }
+        };
+        let swap_path = DerivationPath::from_str(&swap_path).context("Invalid derivation path")?;
+        let swap_xpriv = master
+            .derive_priv(&secp, &swap_path)
+            .context("Failed to derive swap Xpriv")?;
 
-        let derived_xpub = xpub
-            .derive_pub(&secp, &path)
+        // Build hardened derivation path.
+        let user_id_path = format!("m/{ID_PREFIX}'/{LSW_IDENTIFIER}'/{}'", self.account);
+        let user_id_path =
+            DerivationPath::from_str(&user_id_path).context("Invalid derivation path")?;
+        let mut user_id_xpriv = master
+            .derive_priv(&secp, &user_id_path)
+            .context("Failed to derive user_id Xpriv")?;
+
+        let user_id_xpub = Xpub::from_priv(&secp, &user_id_xpriv);
+        wipe_xpriv(&mut user_id_xpriv);
+
+        // User IDs are derived from the Xpub along a non-hardened path.
+        let user_id_path = format!("m/{ID_PREFIX}/{LSW_IDENTIFIER}");
+        let user_id_path =
+            DerivationPath::from_str(&user_id_path).context("Invalid derivation path")?;
+        let user_id_parent = user_id_xpub
+            .derive_pub(&secp, &user_id_path)
             .context("Failed to derive user_id")?;
 
-        Ok(derived_xpub.public_key)
+        Ok(DerivedKeys {
+            master,
+            swap_xpriv,
+            user_id_xpub,
+            user_id_parent,
+        })
     }
 
-    /// Derive the master extended private key from the mnemonic
-    fn master_xpriv(&self) -> anyhow::Result<Xpriv> {
-        // No passphrase.
-        let seed = self.mnemonic.to_seed("");
-        let xpriv = Xpriv::new_master(self.network, &seed).context("Failed to derive Xpriv")?;
+    /// Fingerprint of the master key.
+    ///
+    /// Since the passphrase is part of the seed, this can be persisted to check whether a
+    /// passphrase entered later is the one the wallet was set up with, without storing the
+    /// passphrase itself.
+    pub fn master_fingerprint(&self) -> anyhow::Result<Fingerprint> {
+        let secp = Secp256k1::new();
+        let keys = self.keys(self.scheme)?;
 
-        Ok(xpriv)
+        Ok(keys.master.fingerprint(&secp))
     }
 
     /// Derive an Xpub used to derive user IDs.
@@ -142,19 +472,94 @@ impl HdWallet {
     /// from it are leaked, the parent Xpriv is safe.
     ///
     /// This Xpub is used for wallet recovery: the server derives individual `user_id`s from it.
+    ///
+    /// Derivation path: `m/{ID_PREFIX}'/{LSW_IDENTIFIER}'/{account}'`
     pub fn derive_user_id_xpub(&self) -> anyhow::Result<Xpub> {
-        let secp = Secp256k1::new();
-        let master = self.master_xpriv()?;
+        Ok(self.keys(self.scheme)?.user_id_xpub)
+    }
+}
 
-        // Build hardened derivation path.
-        let path_str = format!("m/{ID_PREFIX}'/{LSW_IDENTIFIER}'/0'");
-        let path = DerivationPath::from_str(&path_str).context("Invalid derivation path")?;
+/// Keys derived from the seed, kept so that the seed is only computed once per wallet.
+///
+/// The extended private keys are wiped when dropped.
+struct DerivedKeys {
+    master: Xpriv,
+    /// Parent of the account's swap keys.
+    swap_xpriv: Xpriv,
+    /// The account's user-ID Xpub, see [`HdWallet::derive_user_id_xpub`].
+    user_id_xpub: Xpub,
+    /// `user_id_xpub/{ID_PREFIX}/{LSW_IDENTIFIER}`, the parent of the user IDs.
+    user_id_parent: Xpub,
+}
 
-        let derived_xpriv = master
-            .derive_priv(&secp, &path)
-            .context("Failed to derive user_id Xpriv")?;
+impl DerivedKeys {
+    /// Derive a `user_id` at the specified index. The `user_id` is actually just a public key.
+    ///
+    /// User IDs are derived using a non-hardened path, so that the corresponding Xpub can be shared
+    /// with the server for efficient recovery of swap data.
+    fn derive_user_id(&self, secp: &Secp256k1<All>, index: u32) -> anyhow::Result<PublicKey> {
+        let child = ChildNumber::from_normal_idx(index).context("Invalid derivation path")?;
+        let derived_xpub = self
+            .user_id_parent
+            .derive_pub(secp, &[child])
+            .context("Failed to derive user_id")?;
 
-        Ok(Xpub::from_priv(&secp, &derived_xpriv))
+        Ok(derived_xpub.public_key)
+    }
+}
+
+/// Derive the `user_id` at `index` from a user-ID Xpub, see [`HdWallet::derive_user_id_xpub`].
+///
+/// Lets watch-only clients, which only know the Xpub, compute the user IDs of their swaps.
+pub(crate) fn derive_user_id_from_xpub(xpub: &Xpub, index: u32) -> Result<PublicKey> {
+    let secp = Secp256k1::new();
+    let path = format!("m/{ID_PREFIX}/{LSW_IDENTIFIER}/{index}");
+    let path = DerivationPath::from_str(&path)
+        .map_err(|e| Error::KeyDerivation(format!("Invalid derivation path: {}", e)))?;
+    let derived_xpub = xpub
+        .derive_pub(&secp, &path)
+        .map_err(|e| Error::KeyDerivation(format!("Failed to derive user_id: {}", e)))?;
+
+    Ok(derived_xpub.public_key)
+}
+
+impl Drop for DerivedKeys {
+    fn drop(&mut self) {
+        wipe_xpriv(&mut self.master);
+        wipe_xpriv(&mut self.swap_xpriv);
+    }
+}
+
+/// Overwrite the secret parts of an extended private key.
+fn wipe_xpriv(xpriv: &mut Xpriv) {
+    xpriv.private_key.non_secure_erase();
+    AsMut::<[u8]>::as_mut(&mut xpriv.chain_code).zeroize();
+}
+
+/// Parse a mnemonic phrase in any supported language.
+///
+/// The phrase is lowercased, Unicode-normalized (NFKD) and split on any whitespace, including
+/// the ideographic space used by Japanese mnemonics, so that differently typed versions of the
+/// same phrase yield the same seed.
+fn parse_mnemonic(phrase: &str) -> Result<bip39::Mnemonic> {
+    use bip39::Mnemonic;
+
+    let normalized = phrase
+        .nfkd()
+        .collect::<String>()
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    match Mnemonic::parse(normalized.as_str()) {
+        Ok(mnemonic) => Ok(mnemonic),
+        // Every word appears in several wordlists: use the one whose checksum matches.
+        Err(bip39::Error::AmbiguousLanguages(languages)) => languages
+            .iter()
+            .find_map(|language| Mnemonic::parse_in(language, normalized.as_str()).ok())
+            .ok_or_else(|| Error::InvalidMnemonic("invalid checksum".to_string())),
+        Err(e) => Err(Error::InvalidMnemonic(format!("{}", e))),
     }
 }
 
@@ -173,6 +578,7 @@ fn tagged_hash(tag: &str, data: &[u8]) -> [u8; 32] {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::MnemonicLanguage;
     use bitcoin::Network;
 
     #[test]
@@ -201,10 +607,13 @@ mod tests {
         } = wallet.derive_swap_params(1).unwrap();
 
         // Different indices should produce different keys
-        assert_ne!(sk1.secret_bytes(), sk2.secret_bytes());
+        assert_ne!(
+            sk1.expose_secret().secret_bytes(),
+            sk2.expose_secret().secret_bytes()
+        );
         assert_ne!(pk1, pk2);
 
-        assert_ne!(preimage1, preimage2);
+        assert_ne!(preimage1.expose_secret(), preimage2.expose_secret());
         assert_ne!(preimage_hash1, preimage_hash2);
 
         // Same index should produce same keys
@@ -216,10 +625,13 @@ mod tests {
             ..
         } = wallet.derive_swap_params(0).unwrap();
 
-        assert_eq!(sk1.secret_bytes(), sk1_again.secret_bytes());
+        assert_eq!(
+            sk1.expose_secret().secret_bytes(),
+            sk1_again.expose_secret().secret_bytes()
+        );
         assert_eq!(pk1, pk1_again);
 
-        assert_eq!(preimage1, preimage1_again);
+        assert_eq!(preimage1.expose_secret(), preimage1_again.expose_secret());
         assert_eq!(preimage_hash1, preimage_hash1_again);
     }
 
@@ -242,7 +654,324 @@ mod tests {
             ..
         } = wallet2.derive_swap_params(0).unwrap();
 
-        assert_eq!(sk1.secret_bytes(), sk2.secret_bytes());
-        assert_eq!(preimage1, preimage2);
+        assert_eq!(
+            sk1.expose_secret().secret_bytes(),
+            sk2.expose_secret().secret_bytes()
+        );
+        assert_eq!(preimage1.expose_secret(), preimage2.expose_secret());
+    }
+
+    #[test]
+    fn test_passphrase_normalization() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+        // Precomposed and decomposed forms of the same passphrase yield the same seed
+        let precomposed =
+            HdWallet::from_mnemonic_with_passphrase(phrase, "caf\u{e9}", Network::Bitcoin).unwrap();
+        let decomposed =
+            HdWallet::from_mnemonic_with_passphrase(phrase, "cafe\u{301}", Network::Bitcoin)
+                .unwrap();
+        assert_eq!(
+            precomposed.master_fingerprint().unwrap(),
+            decomposed.master_fingerprint().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_passphrase_changes_keys() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+        let plain = HdWallet::from_mnemonic(phrase, Network::Bitcoin).unwrap();
+        let empty = HdWallet::from_mnemonic_with_passphrase(phrase, "", Network::Bitcoin).unwrap();
+        let protected =
+            HdWallet::from_mnemonic_with_passphrase(phrase, "TREZOR", Network::Bitcoin).unwrap();
+
+        // An empty passphrase must be identical to no passphrase
+        assert_eq!(
+            plain.derive_swap_params(0).unwrap().public_key,
+            empty.derive_swap_params(0).unwrap().public_key
+        );
+        assert_eq!(
+            plain.master_fingerprint().unwrap(),
+            empty.master_fingerprint().unwrap()
+        );
+
+        // A passphrase must change swap keys, user IDs and the recovery Xpub
+        let plain_params = plain.derive_swap_params(0).unwrap();
+        let protected_params = protected.derive_swap_params(0).unwrap();
+        assert_ne!(plain_params.public_key, protected_params.public_key);
+        assert_ne!(
+            plain_params.preimage.expose_secret(),
+            protected_params.preimage.expose_secret()
+        );
+        assert_ne!(plain_params.user_id, protected_params.user_id);
+        assert_ne!(
+            plain.derive_user_id_xpub().unwrap(),
+            protected.derive_user_id_xpub().unwrap()
+        );
+        assert_ne!(
+            plain.master_fingerprint().unwrap(),
+            protected.master_fingerprint().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_generate_with_options() {
+        let options = MnemonicOptions::from_entropy(vec![0; 16]);
+        let wallet = HdWallet::generate_with_options(Network::Bitcoin, &options).unwrap();
+        assert_eq!(
+            wallet.mnemonic_phrase(),
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"
+        );
+
+        let options = MnemonicOptions::from_entropy(vec![0xff; 32]);
+        let wallet = HdWallet::generate_with_options(Network::Bitcoin, &options).unwrap();
+        assert_eq!(
+            wallet.mnemonic_phrase(),
+            format!("{}vote", "zoo ".repeat(23))
+        );
+
+        for word_count in [12, 15, 18, 21, 24] {
+            let wallet = HdWallet::generate(Network::Bitcoin, word_count).unwrap();
+            assert_eq!(wallet.mnemonic_phrase().split(' ').count(), word_count);
+        }
+        assert!(HdWallet::generate(Network::Bitcoin, 13).is_err());
+
+        let mismatched = MnemonicOptions {
+            entropy: Some(Zeroizing::new(vec![0; 16])),
+            ..MnemonicOptions::with_word_count(24)
+        };
+        assert!(matches!(
+            HdWallet::generate_with_options(Network::Bitcoin, &mismatched),
+            Err(Error::InvalidMnemonic(_))
+        ));
+
+        // Dice rolls: deterministic, range-checked and with a minimum count
+        let rolls: Vec<u8> = (0..50).map(|i| i % 6 + 1).collect();
+        let options = MnemonicOptions::from_dice_rolls(&rolls, 12).unwrap();
+        assert_eq!(options.entropy.as_ref().unwrap().len(), 16);
+        assert_eq!(
+            options,
+            MnemonicOptions::from_dice_rolls(&rolls, 12).unwrap()
+        );
+        assert!(MnemonicOptions::from_dice_rolls(&rolls[..49], 12).is_err());
+        assert!(MnemonicOptions::from_dice_rolls(&rolls, 24).is_err());
+        assert!(MnemonicOptions::from_dice_rolls(&[7; 50], 12).is_err());
+    }
+
+    #[test]
+    fn test_non_english_mnemonic_normalization() {
+        let options =
+            MnemonicOptions::from_entropy(vec![0; 16]).language(MnemonicLanguage::Spanish);
+        let original = HdWallet::generate_with_options(Network::Bitcoin, &options).unwrap();
+
+        // Precomposed accents, upper case and irregular whitespace
+        let typed = "ÁBACO  ábaco ábaco ábaco ábaco ábaco ábaco ábaco ábaco ábaco ábaco\tabierto";
+        let imported = HdWallet::from_mnemonic(typed, Network::Bitcoin).unwrap();
+        assert_eq!(imported.mnemonic_phrase(), original.mnemonic_phrase());
+        assert_eq!(
+            imported.derive_swap_params(0).unwrap().public_key,
+            original.derive_swap_params(0).unwrap().public_key
+        );
+
+        // Japanese phrases are separated by ideographic spaces
+        let options =
+            MnemonicOptions::from_entropy(vec![0x7f; 16]).language(MnemonicLanguage::Japanese);
+        let original = HdWallet::generate_with_options(Network::Bitcoin, &options).unwrap();
+        let typed = original.mnemonic_phrase().replace(' ', "\u{3000}");
+        let imported = HdWallet::from_mnemonic(&typed, Network::Bitcoin).unwrap();
+        assert_eq!(
+            imported.master_fingerprint().unwrap(),
+            original.master_fingerprint().unwrap()
+        );
+
+        // The wordlist is part of the seed: the same entropy in English is a different wallet
+        let english = HdWallet::generate_with_options(
+            Network::Bitcoin,
+            &MnemonicOptions::from_entropy(vec![0x7f; 16]),
+        )
+        .unwrap();
+        assert_ne!(
+            english.master_fingerprint().unwrap(),
+            original.master_fingerprint().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_slip39_restore_keeps_keys() {
+        let original = HdWallet::generate(Network::Bitcoin, 24).unwrap();
+        let shares = original
+            .to_slip39_shares(&Slip39Options::single_group(2, 3).passphrase("TREZOR"))
+            .unwrap();
+
+        let restored = HdWallet::from_slip39_shares(
+            &[&shares[0][2], &shares[0][0]],
+            "TREZOR",
+            Network::Bitcoin,
+        )
+        .unwrap();
+        assert_eq!(restored.mnemonic_phrase(), original.mnemonic_phrase());
+        assert_eq!(
+            restored.derive_swap_params(7).unwrap().public_key,
+            original.derive_swap_params(7).unwrap().public_key
+        );
+    }
+
+    #[test]
+    fn test_derive_swap_params_range() {
+        let wallet = HdWallet::generate(Network::Bitcoin, 12).unwrap();
+        let fresh = HdWallet::from_mnemonic(&wallet.mnemonic_phrase(), Network::Bitcoin).unwrap();
+
+        let params = wallet.derive_swap_params_range(3..8).unwrap();
+        assert_eq!(params.len(), 5);
+        for (index, params) in (3..8).zip(params) {
+            assert_eq!(params.key_index, index);
+            assert_eq!(
+                params.public_key,
+                fresh.derive_swap_params(index).unwrap().public_key
+            );
+            assert_eq!(
+                params.user_id,
+                fresh.derive_swap_params(index).unwrap().user_id
+            );
+        }
+
+        assert!(wallet.derive_swap_params_range(5..5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_derive_swap_params_batch() {
+        let wallet = HdWallet::generate(Network::Bitcoin, 12).unwrap();
+        let scheme = DerivationScheme::LATEST;
+
+        let params = wallet
+            .derive_swap_params_batch_with_scheme(scheme, &[2, 2_000_000, MAX_KEY_INDEX])
+            .unwrap();
+        assert_eq!(params.len(), 3);
+        for (index, params) in [2, 2_000_000, MAX_KEY_INDEX].into_iter().zip(params) {
+            assert_eq!(params.key_index, index);
+            assert_eq!(
+                params.to_public(),
+                wallet
+                    .derive_swap_params_with_scheme(scheme, index)
+                    .unwrap()
+                    .to_public()
+            );
+        }
+
+        assert!(
+            wallet
+                .derive_swap_params_batch_with_scheme(scheme, &[0, MAX_KEY_INDEX + 1])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_derivation_schemes() {
+        let wallet = HdWallet::generate(Network::Bitcoin, 12).unwrap();
+        assert_eq!(wallet.scheme(), DerivationScheme::LATEST);
+
+        for scheme in DerivationScheme::ALL {
+            let params = wallet.derive_swap_params_with_scheme(scheme, 4).unwrap();
+            assert_eq!(params.scheme, scheme);
+            assert_eq!(params.key_index, 4);
+
+            let range = wallet
+                .derive_swap_params_range_with_scheme(scheme, 3..5)
+                .unwrap();
+            assert_eq!(range[1].to_public(), params.to_public());
+
+            let pinned = HdWallet::from_mnemonic(&wallet.mnemonic_phrase(), Network::Bitcoin)
+                .unwrap()
+                .with_scheme(scheme);
+            assert_eq!(
+                pinned.derive_swap_params(4).unwrap().to_public(),
+                params.to_public()
+            );
+        }
+
+        // Scheme V1 must keep deriving the keys of swaps created before schemes were recorded
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let wallet = HdWallet::from_mnemonic(phrase, Network::Bitcoin).unwrap();
+        let params = wallet
+            .derive_swap_params_with_scheme(DerivationScheme::V1, 0)
+            .unwrap();
+        let secp = Secp256k1::new();
+        let expected = Xpriv::new_master(Network::Bitcoin, &wallet.mnemonic.to_seed(""))
+            .unwrap()
+            .derive_priv(
+                &secp,
+                &DerivationPath::from_str(&format!("m/{SIGNING_PREFIX}'/{LSW_IDENTIFIER}'/0'"))
+                    .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(params.public_key, expected.private_key.public_key(&secp));
+        assert_eq!(
+            **params.preimage.expose_secret(),
+            tagged_hash(PREIMAGE_TAG, &expected.private_key.secret_bytes())
+        );
+    }
+
+    #[test]
+    fn test_accounts_are_separate() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let default = HdWallet::from_mnemonic(phrase, Network::Bitcoin).unwrap();
+        let account0 = HdWallet::from_mnemonic(phrase, Network::Bitcoin)
+            .unwrap()
+            .with_account(0);
+        let account1 = HdWallet::from_mnemonic(phrase, Network::Bitcoin)
+            .unwrap()
+            .with_account(1);
+
+        // Existing wallets are account 0
+        assert_eq!(
+            default.derive_swap_params(0).unwrap().public_key,
+            account0.derive_swap_params(0).unwrap().public_key
+        );
+        assert_eq!(
+            default.derive_user_id_xpub().unwrap(),
+            account0.derive_user_id_xpub().unwrap()
+        );
+
+        let params0 = account0.derive_swap_params(1).unwrap();
+        let params1 = account1.derive_swap_params(1).unwrap();
+        assert_eq!(params1.account, 1);
+        assert_ne!(params0.public_key, params1.public_key);
+        assert_ne!(
+            params0.preimage.expose_secret(),
+            params1.preimage.expose_secret()
+        );
+        assert_ne!(params0.user_id, params1.user_id);
+        assert_ne!(
+            account0.derive_user_id_xpub().unwrap(),
+            account1.derive_user_id_xpub().unwrap()
+        );
+
+        // The reserved branch can't be used as a key index
+        assert!(account0.derive_swap_params(ACCOUNTS_BRANCH).is_err());
+    }
+
+    #[test]
+    fn test_nostr_keys_nip06_vector() {
+        // Test vector from NIP-06
+        let wallet = HdWallet::from_mnemonic(
+            "leader monkey parrot ring guide accident before fence cannon height naive bean",
+            Network::Bitcoin,
+        )
+        .unwrap();
+        let keys = wallet.derive_nostr_keys().unwrap();
+
+        assert_eq!(
+            keys.secret_key().to_secret_hex(),
+            "7f7ff03d123792d6ac594bfa67bf6d0c0ab55b6b1fdb6249303fe861f1ccba9a"
+        );
+        assert_eq!(
+            keys.public_key().to_string(),
+            "17162c921dc4d2518f9a101db33695df1afb56ab82f5ff3e5da6eec3ca5cd917"
+        );
+
+        // Each account has its own keys
+        let account1 = wallet.with_account(1).derive_nostr_keys().unwrap();
+        assert_ne!(account1.public_key(), keys.public_key());
     }
 }
@@ -0,0 +1,131 @@
+//! Per-swap event history.
+//!
+//! Refreshing a swap overwrites its stored response, so the stored record only tells where a swap
+//! is, not how it got there. The client therefore appends a [`SwapEvent`] to the swap's history
+//! whenever it creates a swap, sees its status change, makes an API call on its behalf, broadcasts
+//! a claim or refund, or fails to do so. Histories are append-only and kept even when the swap is
+//! deleted.
+//!
+//! Recording is best effort: an event that can't be stored is logged and otherwise ignored, so a
+//! failing history never fails the operation itself.
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// An event in the history of a swap.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwapEvent {
+    /// The swap the event belongs to.
+    pub swap_id: String,
+    /// When the SDK recorded the event.
+    #[serde(with = "time::serde::rfc3339")]
+    pub timestamp: OffsetDateTime,
+    /// What happened.
+    #[serde(flatten)]
+    pub kind: SwapEventKind,
+}
+
+impl SwapEvent {
+    /// An event of `swap_id` that happened now.
+    pub fn now(swap_id: &str, kind: SwapEventKind) -> Self {
+        Self {
+            swap_id: swap_id.to_string(),
+            timestamp: OffsetDateTime::now_utc(),
+            kind,
+        }
+    }
+}
+
+/// What happened to a swap.
+///
+/// Statuses are named as the API serializes them, e.g. `serverfunded`, so that the history covers
+/// swaps and VTXO swaps alike.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SwapEventKind {
+    /// The swap was created with the API.
+    Created { status: String },
+    /// A refresh from the API returned a different status than the stored one.
+    StatusChanged { from: String, to: String },
+    /// An API call was made for the swap, e.g. a gasless claim.
+    ApiCall { endpoint: String },
+    /// A claim transaction was broadcast.
+    Claimed { txid: String },
+    /// A refund transaction was broadcast.
+    Refunded { txid: String },
+    /// An operation on the swap failed.
+    Error { operation: String, message: String },
+}
+
+impl SwapEventKind {
+    /// A [`SwapEventKind::Created`] event with the given status.
+    pub(crate) fn created<T: Serialize>(status: &T) -> Self {
+        SwapEventKind::Created {
+            status: status_name(status),
+        }
+    }
+
+    /// A [`SwapEventKind::StatusChanged`] event, or `None` if the status didn't change.
+    pub(crate) fn status_changed<T: Serialize + PartialEq>(from: &T, to: &T) -> Option<Self> {
+        (from != to).then(|| SwapEventKind::StatusChanged {
+            from: status_name(from),
+            to: status_name(to),
+        })
+    }
+}
+
+/// The history of all swaps of an account, e.g. for compliance records.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwapHistoryExport {
+    /// When the history was exported.
+    #[serde(with = "time::serde::rfc3339")]
+    pub exported_at: OffsetDateTime,
+    /// The account whose swaps are included.
+    pub account: u32,
+    /// The events of the account's swaps and of deleted swaps, oldest first.
+    pub events: Vec<SwapEvent>,
+}
+
+/// The name a status is serialized as.
+fn status_name<T: Serialize>(status: &T) -> String {
+    match serde_json::to_value(status) {
+        Ok(serde_json::Value::String(name)) => name,
+        Ok(other) => other.to_string(),
+        Err(e) => format!("<{}>", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{SwapStatus, VtxoSwapStatus};
+    use time::macros::datetime;
+
+    #[test]
+    fn test_event_json() {
+        let event = SwapEvent {
+            swap_id: "a".to_string(),
+            timestamp: datetime!(2025-01-01 0:00 UTC),
+            kind: SwapEventKind::status_changed(&SwapStatus::Pending, &SwapStatus::ClientFunded)
+                .unwrap(),
+        };
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "swap_id": "a",
+                "timestamp": "2025-01-01T00:00:00Z",
+                "type": "status_changed",
+                "from": "pending",
+                "to": "clientfunded",
+            })
+        );
+        assert_eq!(serde_json::from_value::<SwapEvent>(json).unwrap(), event);
+
+        assert_eq!(
+            SwapEventKind::status_changed(&VtxoSwapStatus::Pending, &VtxoSwapStatus::Pending),
+            None
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Write-ahead journal of swap creations.
+//!
+//! Creating a swap reserves a key index, calls the API and then stores the swap. If the process
+//! crashes or the storage fails after the API call succeeded, the server knows a swap the client
+//! has no record of, and its preimage could only be found by a full recovery.
+//!
+//! The client therefore stores a [`PendingCreation`] with the swap's parameters before calling
+//! the API, and deletes it once the swap is stored. Entries left behind are reconciled when the
+//! client is initialized, see [`Client::reconcile_pending_creations`]: the swaps the server
+//! created for them are looked up by key index and stored. VTXO swaps can't be looked up by key
+//! index, so their entries are updated with the swap ID as soon as the API returns it.
+//!
+//! Another client on the same storage, e.g. in a second browser tab, may still be creating the
+//! swap of an entry. Entries are therefore only deleted once their swap is stored, or when they
+//! are older than [`PENDING_CREATION_GRACE_PERIOD`].
+//!
+//! [`Client::reconcile_pending_creations`]: crate::Client::reconcile_pending_creations
+
+use crate::client::{ExtendedSwapStorageData, ExtendedVtxoSwapStorageData};
+use crate::types::PublicSwapParams;
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+
+/// How long an unresolved entry is kept, so that a creation still in progress isn't discarded.
+pub const PENDING_CREATION_GRACE_PERIOD: Duration = Duration::minutes(10);
+
+/// The kind of swap a [`PendingCreation`] creates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PendingCreationKind {
+    /// A swap between Bitcoin and an EVM token.
+    #[default]
+    Swap,
+    /// A VTXO swap, see [`crate::Client::create_vtxo_swap`].
+    VtxoSwap,
+}
+
+/// A swap creation that was started but not finalized.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingCreation {
+    /// The parameters the swap is created with.
+    pub swap_params: PublicSwapParams,
+    /// The kind of swap. Entries written before VTXO swaps were journaled are swaps.
+    #[serde(default)]
+    pub kind: PendingCreationKind,
+    /// The ID the server assigned, once known. Only recorded for VTXO swaps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swap_id: Option<String>,
+    /// When the creation was started.
+    #[serde(with = "time::serde::rfc3339")]
+    pub started_at: OffsetDateTime,
+}
+
+impl PendingCreation {
+    /// A creation of a swap with the given parameters, started now.
+    pub fn now(swap_params: PublicSwapParams) -> Self {
+        Self::now_with_kind(swap_params, PendingCreationKind::Swap)
+    }
+
+    /// A creation of a swap of the given kind with the given parameters, started now.
+    pub fn now_with_kind(swap_params: PublicSwapParams, kind: PendingCreationKind) -> Self {
+        Self {
+            swap_params,
+            kind,
+            swap_id: None,
+            started_at: OffsetDateTime::now_utc(),
+        }
+    }
+
+    /// Whether the creation was started more than [`PENDING_CREATION_GRACE_PERIOD`] before `now`,
+    /// so that no client is still working on it.
+    pub fn is_abandoned(&self, now: OffsetDateTime) -> bool {
+        now - self.started_at > PENDING_CREATION_GRACE_PERIOD
+    }
+
+    /// The hex-encoded user ID sent to the API, which identifies the entry in storage.
+    pub fn user_id(&self) -> String {
+        hex::encode(self.swap_params.user_id.serialize())
+    }
+}
+
+/// The swaps stored by [`crate::Client::reconcile_pending_creations`].
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReconciledCreations {
+    /// The restored swaps.
+    pub swaps: Vec<ExtendedSwapStorageData>,
+    /// The restored VTXO swaps.
+    pub vtxo_swaps: Vec<ExtendedVtxoSwapStorageData>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::SwapStatus;
+    use crate::storage::conformance::sample_swap;
+    use time::macros::datetime;
+
+    #[test]
+    fn test_pending_creation_json() {
+        let swap_params = sample_swap("a", SwapStatus::Pending).swap_params;
+        let pending = PendingCreation {
+            swap_params: swap_params.clone(),
+            kind: PendingCreationKind::Swap,
+            swap_id: None,
+            started_at: datetime!(2025-01-01 0:00 UTC),
+        };
+
+        // Written before VTXO swaps were journaled
+        let json = serde_json::json!({
+            "swap_params": swap_params,
+            "started_at": "2025-01-01T00:00:00Z",
+        });
+        assert_eq!(
+            serde_json::from_value::<PendingCreation>(json).unwrap(),
+            pending
+        );
+
+        let vtxo_swap = PendingCreation {
+            kind: PendingCreationKind::VtxoSwap,
+            swap_id: Some("b".to_string()),
+            ..pending
+        };
+        let json = serde_json::to_value(&vtxo_swap).unwrap();
+        assert_eq!(json["kind"], "vtxo_swap");
+        assert_eq!(json["swap_id"], "b");
+        assert_eq!(
+            serde_json::from_value::<PendingCreation>(json).unwrap(),
+            vtxo_swap
+        );
+    }
+
+    #[test]
+    fn test_is_abandoned() {
+        let mut pending = PendingCreation::now(sample_swap("a", SwapStatus::Pending).swap_params);
+        pending.started_at = datetime!(2025-01-01 0:00 UTC);
+
+        assert!(!pending.is_abandoned(pending.started_at + PENDING_CREATION_GRACE_PERIOD));
+        assert!(pending.is_abandoned(
+            pending.started_at + PENDING_CREATION_GRACE_PERIOD + Duration::seconds(1)
+        ));
+    }
+}
@@ -22,9 +22,18 @@
 //! ```
 
 pub mod api;
+pub mod backup;
 pub mod client;
+mod crypto;
 pub mod error;
 pub mod hd_wallet;
+pub mod history;
+pub mod journal;
+pub mod message;
+pub mod nostr_backup;
+pub mod recovery;
+pub mod signer;
+pub mod slip39;
 pub mod storage;
 pub mod types;
 pub mod vhtlc;
@@ -32,9 +41,29 @@ pub mod vtxo_swap;
 pub mod wallet;
 
 pub use api::ApiClient;
+pub use backup::BackupMetadata;
 pub use client::{Client, ExtendedSwapStorageData, ExtendedVtxoSwapStorageData};
 pub use error::{Error, Result};
 pub use hd_wallet::HdWallet;
-pub use storage::{StorageFuture, SwapStorage, WalletStorage, WalletStorageExt};
-pub use types::{Network, SwapParams, VhtlcAmounts};
+pub use history::{SwapEvent, SwapEventKind, SwapHistoryExport};
+pub use journal::{PendingCreation, PendingCreationKind, ReconciledCreations};
+pub use message::{
+    MessageSignature, OwnershipKey, OwnershipProof, verify_swap_ownership, verify_user_id_ownership,
+};
+pub use nostr_backup::NostrRelay;
+pub use recovery::{RecoveryConflict, RecoveryConflictKind, RecoveryReport};
+pub use signer::{SchemeSigner, SignerFuture, SwapSigner};
+pub use slip39::{Slip39Group, Slip39Options};
+#[cfg(feature = "fs")]
+pub use storage::FsStorage;
+#[cfg(feature = "sqlite")]
+pub use storage::SqliteStorage;
+pub use storage::{
+    EncryptedWalletStorage, KeyValueStore, KvStorage, QuarantinedRecord, SortOrder, StorageFuture,
+    SwapPage, SwapQuery, SwapStorage, WalletStorage, WalletStorageExt, WatchOnlyWalletStorage,
+};
+pub use types::{
+    DerivationScheme, MnemonicLanguage, MnemonicOptions, Network, PublicSwapParams, SwapParams,
+    VhtlcAmounts,
+};
 pub use wallet::Wallet;
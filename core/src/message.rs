@@ -0,0 +1,322 @@
+//! Signed messages and proofs of swap ownership.
+//!
+//! Messages are signed with BIP340 Schnorr signatures over a tagged hash of the message, as in
+//! BIP322's simple signatures but without the virtual transactions. The tag keeps these
+//! signatures from being valid for anything else the keys sign, e.g. VHTLC spends.
+//!
+//! An [`OwnershipProof`] is a signature over a fixed message naming the swap, made with either the
+//! swap's client key (`refund_pk` or `claim_pk`) or the swap's user ID. Support uses it to check
+//! that whoever opens a dispute actually created the swap. The message includes a challenge
+//! chosen by the verifier, e.g. a random nonce, so that a proof shown once can't be replayed.
+
+use crate::api::GetSwapResponse;
+use crate::error::{Error, Result};
+use bitcoin::key::{Keypair, Secp256k1};
+use bitcoin::secp256k1::{Message, PublicKey, SecretKey, schnorr};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+/// Tag of the hash that signed messages commit to.
+const MESSAGE_TAG: &str = "lendaswap/signed-message";
+
+/// A BIP340 Schnorr signature over a message, together with the signing key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageSignature {
+    pub public_key: PublicKey,
+    pub signature: schnorr::Signature,
+}
+
+impl MessageSignature {
+    /// Check that this is a valid signature of `message` by `public_key`.
+    pub fn verify(&self, message: &[u8]) -> Result<()> {
+        let (public_key, _) = self.public_key.x_only_public_key();
+        Secp256k1::verification_only()
+            .verify_schnorr(&self.signature, &message_hash(message), &public_key)
+            .map_err(|e| Error::InvalidSignature(format!("{}", e)))
+    }
+}
+
+/// The hash that is signed for `message`.
+///
+/// Computes: sha256(sha256(tag) || sha256(tag) || message) with tag `lendaswap/signed-message`.
+pub fn message_hash(message: &[u8]) -> Message {
+    let tag_hash = Sha256::digest(MESSAGE_TAG.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(tag_hash);
+    hasher.update(tag_hash);
+    hasher.update(message);
+    Message::from_digest(hasher.finalize().into())
+}
+
+/// Sign `message` with `secret_key`, wiping the keypair afterwards.
+pub(crate) fn sign_message_with_key(secret_key: &SecretKey, message: &[u8]) -> MessageSignature {
+    let secp = Secp256k1::new();
+    let mut keypair = Keypair::from_secret_key(&secp, secret_key);
+    let signature = secp.sign_schnorr_no_aux_rand(&message_hash(message), &keypair);
+    let public_key = keypair.public_key();
+    keypair.non_secure_erase();
+
+    MessageSignature {
+        public_key,
+        signature,
+    }
+}
+
+/// The key an [`OwnershipProof`] is made with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OwnershipKey {
+    /// The client's key of the swap, i.e. its `refund_pk` or `claim_pk`.
+    SwapKey,
+    /// The swap's user ID.
+    UserId,
+}
+
+impl fmt::Display for OwnershipKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OwnershipKey::SwapKey => write!(f, "swap_key"),
+            OwnershipKey::UserId => write!(f, "user_id"),
+        }
+    }
+}
+
+impl FromStr for OwnershipKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "swap_key" => Ok(OwnershipKey::SwapKey),
+            "user_id" => Ok(OwnershipKey::UserId),
+            _ => Err(Error::Parse(format!("Unknown ownership key: {}", s))),
+        }
+    }
+}
+
+/// Proof that the holder of a key created a swap.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnershipProof {
+    pub swap_id: String,
+    pub key: OwnershipKey,
+    /// The verifier's challenge the proof answers.
+    pub challenge: String,
+    pub public_key: PublicKey,
+    /// Signature of [`ownership_message`] for `swap_id`, `key` and `challenge`.
+    pub signature: schnorr::Signature,
+}
+
+impl OwnershipProof {
+    /// Create a proof from a signature of [`ownership_message`].
+    pub fn new(
+        swap_id: &str,
+        key: OwnershipKey,
+        challenge: &str,
+        signature: MessageSignature,
+    ) -> Self {
+        Self {
+            swap_id: swap_id.to_string(),
+            key,
+            challenge: challenge.to_string(),
+            public_key: signature.public_key,
+            signature: signature.signature,
+        }
+    }
+
+    /// Check that the proof answers `challenge` and the signature, without checking that the key
+    /// belongs to the swap.
+    fn verify_signature(&self, challenge: &str) -> Result<()> {
+        if self.challenge != challenge {
+            return Err(Error::InvalidSignature(
+                "proof answers a different challenge".to_string(),
+            ));
+        }
+
+        MessageSignature {
+            public_key: self.public_key,
+            signature: self.signature,
+        }
+        .verify(ownership_message(&self.swap_id, self.key, &self.challenge).as_bytes())
+    }
+}
+
+/// The message signed by an [`OwnershipProof`] answering the verifier's `challenge`.
+pub fn ownership_message(swap_id: &str, key: OwnershipKey, challenge: &str) -> String {
+    format!(
+        "Lendaswap swap ownership\nswap: {}\nkey: {}\nchallenge: {}",
+        swap_id, key, challenge
+    )
+}
+
+/// Verify a proof made with the client key of `swap` in answer to `challenge`.
+///
+/// The response doesn't carry the user ID, so proofs made with it are rejected here: check them
+/// with [`verify_user_id_ownership`] against the user ID the swap was created with.
+pub fn verify_swap_ownership(
+    swap: &GetSwapResponse,
+    challenge: &str,
+    proof: &OwnershipProof,
+) -> Result<()> {
+    if proof.key != OwnershipKey::SwapKey {
+        return Err(Error::InvalidSignature(
+            "user ID proofs must be checked against the swap's user ID".to_string(),
+        ));
+    }
+
+    let swap_id = swap.id();
+    if proof.swap_id != swap_id {
+        return Err(Error::InvalidSignature(format!(
+            "proof is for swap {}, not {}",
+            proof.swap_id, swap_id
+        )));
+    }
+
+    let sender_pk = PublicKey::from_str(&swap.common().sender_pk)
+        .map_err(|e| Error::Parse(format!("Invalid client public key: {}", e)))?;
+    if proof.public_key != sender_pk {
+        return Err(Error::InvalidSignature(
+            "proof is not signed with the swap's client key".to_string(),
+        ));
+    }
+
+    proof.verify_signature(challenge)
+}
+
+/// Verify a proof made with the user ID `user_id` of the swap `swap_id` in answer to `challenge`.
+pub fn verify_user_id_ownership(
+    swap_id: &str,
+    user_id: &PublicKey,
+    challenge: &str,
+    proof: &OwnershipProof,
+) -> Result<()> {
+    if proof.key != OwnershipKey::UserId {
+        return Err(Error::InvalidSignature(
+            "proof is not made with a user ID".to_string(),
+        ));
+    }
+    if proof.swap_id != swap_id {
+        return Err(Error::InvalidSignature(format!(
+            "proof is for swap {}, not {}",
+            proof.swap_id, swap_id
+        )));
+    }
+    if proof.public_key != *user_id {
+        return Err(Error::InvalidSignature(
+            "proof is not signed with the swap's user ID".to_string(),
+        ));
+    }
+
+    proof.verify_signature(challenge)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HdWallet;
+    use crate::api::SwapStatus;
+    use crate::storage::conformance::sample_swap;
+    use secrecy::ExposeSecret;
+
+    const SWAP_ID: &str = "00000000-0000-0000-0000-00000000000a";
+    const CHALLENGE: &str = "support ticket 42, nonce 5f1c";
+
+    fn swap(sender_pk: &PublicKey) -> GetSwapResponse {
+        let swap = sample_swap(SWAP_ID, SwapStatus::Pending).response;
+        let mut swap = serde_json::to_value(swap).unwrap();
+        swap["sender_pk"] = sender_pk.to_string().into();
+        serde_json::from_value(swap).unwrap()
+    }
+
+    #[test]
+    fn test_sign_and_verify_message() {
+        let wallet = HdWallet::generate(bitcoin::Network::Bitcoin, 12).unwrap();
+        let signature = wallet.sign_message(3, b"hello").unwrap();
+
+        assert_eq!(
+            signature.public_key,
+            wallet.derive_swap_params(3).unwrap().public_key
+        );
+        signature.verify(b"hello").unwrap();
+        assert!(matches!(
+            signature.verify(b"hello!"),
+            Err(Error::InvalidSignature(_))
+        ));
+
+        let serialized = serde_json::to_string(&signature).unwrap();
+        let deserialized: MessageSignature = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, signature);
+
+        // A signature of the bare SHA256 hash, e.g. a transaction sighash, is not a valid message
+        // signature.
+        let digest = Message::from_digest(Sha256::digest(b"hello").into());
+        let params = wallet.derive_swap_params(3).unwrap();
+        let keypair =
+            Keypair::from_secret_key(&Secp256k1::new(), params.secret_key.expose_secret());
+        let untagged = MessageSignature {
+            public_key: params.public_key,
+            signature: Secp256k1::new().sign_schnorr_no_aux_rand(&digest, &keypair),
+        };
+        assert!(untagged.verify(b"hello").is_err());
+    }
+
+    #[test]
+    fn test_verify_swap_ownership() {
+        let wallet = HdWallet::generate(bitcoin::Network::Bitcoin, 12).unwrap();
+        let params = wallet.derive_swap_params(5).unwrap();
+        let swap = swap(&params.public_key);
+
+        let message = ownership_message(SWAP_ID, OwnershipKey::SwapKey, CHALLENGE);
+        let signature = wallet.sign_message(5, message.as_bytes()).unwrap();
+        let proof = OwnershipProof::new(SWAP_ID, OwnershipKey::SwapKey, CHALLENGE, signature);
+        verify_swap_ownership(&swap, CHALLENGE, &proof).unwrap();
+
+        // Replayed to a verifier with another challenge
+        assert!(verify_swap_ownership(&swap, "other challenge", &proof).is_err());
+        let rechallenged = OwnershipProof {
+            challenge: "other challenge".to_string(),
+            ..proof.clone()
+        };
+        assert!(verify_swap_ownership(&swap, "other challenge", &rechallenged).is_err());
+
+        // Signed with the key of another swap
+        let signature = wallet.sign_message(6, message.as_bytes()).unwrap();
+        let other_key = OwnershipProof::new(SWAP_ID, OwnershipKey::SwapKey, CHALLENGE, signature);
+        assert!(verify_swap_ownership(&swap, CHALLENGE, &other_key).is_err());
+
+        // Valid proof for another swap
+        let other_id = "00000000-0000-0000-0000-00000000000b";
+        let message = ownership_message(other_id, OwnershipKey::SwapKey, CHALLENGE);
+        let signature = wallet.sign_message(5, message.as_bytes()).unwrap();
+        let other_swap = OwnershipProof::new(other_id, OwnershipKey::SwapKey, CHALLENGE, signature);
+        assert!(verify_swap_ownership(&swap, CHALLENGE, &other_swap).is_err());
+
+        // Claims to be for this swap
+        let relabeled = OwnershipProof {
+            swap_id: SWAP_ID.to_string(),
+            ..other_swap
+        };
+        assert!(verify_swap_ownership(&swap, CHALLENGE, &relabeled).is_err());
+
+        let message = ownership_message(SWAP_ID, OwnershipKey::UserId, CHALLENGE);
+        let signature = wallet
+            .sign_message_with_user_id(5, message.as_bytes())
+            .unwrap();
+        assert_eq!(signature.public_key, params.user_id);
+        let proof = OwnershipProof::new(SWAP_ID, OwnershipKey::UserId, CHALLENGE, signature);
+        verify_user_id_ownership(SWAP_ID, &params.user_id, CHALLENGE, &proof).unwrap();
+        assert!(
+            verify_user_id_ownership(SWAP_ID, &params.user_id, "other challenge", &proof).is_err()
+        );
+        assert!(verify_swap_ownership(&swap, CHALLENGE, &proof).is_err());
+        assert!(
+            verify_user_id_ownership(
+                SWAP_ID,
+                &wallet.derive_swap_params(6).unwrap().user_id,
+                CHALLENGE,
+                &proof
+            )
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Swap backups on Nostr relays.
+//!
+//! Each stored swap is published as an addressable NIP-78 application data event, signed with the
+//! wallet's NIP-06 Nostr keys (see [`crate::HdWallet::derive_nostr_keys`]) and encrypted to
+//! itself with NIP-44. Relays keep only the latest event per swap, so republishing a swap after a
+//! status change replaces its backup. Since the keys are derived from the mnemonic, a wallet can
+//! restore its swaps from relays without Lendaswap's recover endpoint.
+//!
+//! The SDK doesn't talk to relays itself: [`NostrRelay`] is implemented by the application, e.g.
+//! on top of a WebSocket client or a relay pool.
+
+use crate::client::ExtendedSwapStorageData;
+use crate::error::{Error, Result};
+use crate::storage::StorageFuture;
+use nostr::nips::nip44;
+use nostr::{Event, EventBuilder, Filter, Keys, Kind, Tag};
+use std::collections::HashMap;
+
+/// Prefix of the `d` tag of swap backup events, followed by the swap ID.
+const SWAP_BACKUP_PREFIX: &str = "lendaswap/swap/";
+
+/// A Nostr relay, or a pool of them.
+///
+/// Relays are expected to replace addressable events (NIP-01), i.e. to only keep the latest
+/// event per author, kind and `d` tag. Older events that are still returned are ignored.
+#[cfg(target_arch = "wasm32")]
+pub trait NostrRelay {
+    /// Publish a signed event.
+    fn publish(&self, event: Event) -> StorageFuture<'_, ()>;
+
+    /// Fetch the stored events matching `filter`.
+    fn fetch(&self, filter: Filter) -> StorageFuture<'_, Vec<Event>>;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub trait NostrRelay: Send + Sync {
+    /// Publish a signed event.
+    fn publish(&self, event: Event) -> StorageFuture<'_, ()>;
+
+    /// Fetch the stored events matching `filter`.
+    fn fetch(&self, filter: Filter) -> StorageFuture<'_, Vec<Event>>;
+}
+
+/// Create the backup event of a swap.
+///
+/// The swap is stored without secrets, as it is in swap storage.
+pub fn swap_backup_event(keys: &Keys, swap: &ExtendedSwapStorageData) -> Result<Event> {
+    let plaintext = serde_json::to_string(swap)?;
+    let content = nip44::encrypt(
+        keys.secret_key(),
+        &keys.public_key(),
+        plaintext,
+        nip44::Version::V2,
+    )
+    .map_err(|e| Error::Nostr(format!("Failed to encrypt swap backup: {}", e)))?;
+
+    EventBuilder::new(Kind::ApplicationSpecificData, content)
+        .tag(Tag::identifier(format!(
+            "{SWAP_BACKUP_PREFIX}{}",
+            swap.response.id()
+        )))
+        .sign_with_keys(keys)
+        .map_err(|e| Error::Nostr(format!("Failed to sign swap backup: {}", e)))
+}
+
+/// Read a swap from a backup event created with [`swap_backup_event`].
+pub fn read_swap_backup_event(keys: &Keys, event: &Event) -> Result<ExtendedSwapStorageData> {
+    if event.pubkey != keys.public_key() || event.kind != Kind::ApplicationSpecificData {
+        return Err(Error::Nostr(format!(
+            "Event {} is not a swap backup of this wallet",
+            event.id
+        )));
+    }
+    event
+        .verify()
+        .map_err(|e| Error::Nostr(format!("Invalid event {}: {}", event.id, e)))?;
+
+    let swap_id = swap_backup_id(event)
+        .ok_or_else(|| Error::Nostr(format!("Event {} is not a swap backup", event.id)))?;
+    let plaintext = nip44::decrypt(keys.secret_key(), &keys.public_key(), &event.content)
+        .map_err(|e| Error::Nostr(format!("Failed to decrypt swap backup: {}", e)))?;
+    let swap: ExtendedSwapStorageData = serde_json::from_str(&plaintext)?;
+
+    // The ID is authenticated by the signature, the content only by the encryption key
+    if swap.response.id() != swap_id {
+        return Err(Error::Nostr(format!(
+            "Event {} contains swap {} instead of {}",
+            event.id,
+            swap.response.id(),
+            swap_id
+        )));
+    }
+
+    Ok(swap)
+}
+
+/// Publish a backup event for each swap.
+pub async fn publish_swap_backups<R: NostrRelay + ?Sized>(
+    relay: &R,
+    keys: &Keys,
+    swaps: &[ExtendedSwapStorageData],
+) -> Result<()> {
+    for swap in swaps {
+        relay.publish(swap_backup_event(keys, swap)?).await?;
+    }
+
+    Ok(())
+}
+
+/// Fetch the swaps backed up with `keys`.
+///
+/// If a relay returns several events for a swap, the latest one is used. Events that can't be
+/// read are skipped.
+pub async fn fetch_swap_backups<R: NostrRelay + ?Sized>(
+    relay: &R,
+    keys: &Keys,
+) -> Result<Vec<ExtendedSwapStorageData>> {
+    let filter = Filter::new()
+        .author(keys.public_key())
+        .kind(Kind::ApplicationSpecificData);
+    let events = relay.fetch(filter).await?;
+
+    let mut latest: HashMap<&str, &Event> = HashMap::new();
+    for event in &events {
+        let Some(swap_id) = swap_backup_id(event) else {
+            continue;
+        };
+        match latest.get(swap_id) {
+            Some(known) if known.created_at >= event.created_at => {}
+            _ => {
+                latest.insert(swap_id, event);
+            }
+        }
+    }
+
+    let mut swaps = Vec::new();
+    for event in latest.into_values() {
+        match read_swap_backup_event(keys, event) {
+            Ok(swap) => swaps.push(swap),
+            Err(e) => log::warn!("Skipping swap backup: {e:#}"),
+        }
+    }
+    swaps.sort_by_key(|swap| swap.swap_params.key_index);
+
+    Ok(swaps)
+}
+
+/// The swap ID of a backup event, taken from its `d` tag.
+fn swap_backup_id(event: &Event) -> Option<&str> {
+    event.tags.identifier()?.strip_prefix(SWAP_BACKUP_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::SwapStatus;
+    use crate::storage::conformance::sample_swap;
+    use std::sync::Mutex;
+
+    /// In-process stand-in for a relay, replacing addressable events like a real one.
+    #[derive(Default)]
+    struct MemoryRelay {
+        events: Mutex<Vec<Event>>,
+    }
+
+    impl NostrRelay for MemoryRelay {
+        fn publish(&self, event: Event) -> StorageFuture<'_, ()> {
+            Box::pin(async move {
+                let mut events = self.events.lock().unwrap();
+                events.retain(|stored| {
+                    stored.pubkey != event.pubkey
+                        || stored.kind != event.kind
+                        || stored.tags.identifier() != event.tags.identifier()
+                });
+                events.push(event);
+                Ok(())
+            })
+        }
+
+        fn fetch(&self, filter: Filter) -> StorageFuture<'_, Vec<Event>> {
+            Box::pin(async move {
+                let events = self.events.lock().unwrap();
+                Ok(events
+                    .iter()
+                    .filter(|event| filter.match_event(event))
+                    .cloned()
+                    .collect())
+            })
+        }
+    }
+
+    fn swap(id: &str, status: SwapStatus, key_index: u32) -> ExtendedSwapStorageData {
+        let mut data = sample_swap(id, status);
+        data.swap_params.key_index = key_index;
+        data
+    }
+
+    const SWAP_A: &str = "00000000-0000-0000-0000-00000000000a";
+    const SWAP_B: &str = "00000000-0000-0000-0000-00000000000b";
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_publish_and_fetch_swap_backups() {
+        let wallet = crate::HdWallet::generate(bitcoin::Network::Bitcoin, 12).unwrap();
+        let keys = wallet.derive_nostr_keys().unwrap();
+        let relay = MemoryRelay::default();
+
+        let swaps = vec![
+            swap(SWAP_A, SwapStatus::Pending, 0),
+            swap(SWAP_B, SwapStatus::Pending, 1),
+        ];
+        publish_swap_backups(&relay, &keys, &swaps).await.unwrap();
+
+        let event = relay.events.lock().unwrap()[0].clone();
+        assert!(!event.content.contains(SWAP_A));
+        let restored = read_swap_backup_event(&keys, &event).unwrap();
+        assert_eq!(restored.response.id(), SWAP_A);
+        assert_eq!(restored.swap_params, swaps[0].swap_params);
+
+        // A status update replaces the backup
+        let updated = swap(SWAP_A, SwapStatus::ServerFunded, 0);
+        publish_swap_backups(&relay, &keys, &[updated])
+            .await
+            .unwrap();
+        assert_eq!(relay.events.lock().unwrap().len(), 2);
+
+        let fetched: Vec<_> = fetch_swap_backups(&relay, &keys)
+            .await
+            .unwrap()
+            .iter()
+            .map(|data| (data.response.id(), data.response.status()))
+            .collect();
+        assert_eq!(
+            fetched,
+            vec![
+                (SWAP_A.to_string(), SwapStatus::ServerFunded),
+                (SWAP_B.to_string(), SwapStatus::Pending),
+            ]
+        );
+
+        // Another wallet neither sees nor reads the backups
+        let other = crate::HdWallet::generate(bitcoin::Network::Bitcoin, 12)
+            .unwrap()
+            .derive_nostr_keys()
+            .unwrap();
+        assert!(fetch_swap_backups(&relay, &other).await.unwrap().is_empty());
+        assert!(read_swap_backup_event(&other, &event).is_err());
+    }
+}
@@ -0,0 +1,408 @@
+//! Merging swaps recovered from the server into local storage.
+//!
+//! Recovery used to wipe local storage and replace it with whatever the server returned. That
+//! loses swaps the server doesn't report (e.g. ones created since the last sync) and can roll back
+//! local status updates. Instead, each recovered swap is compared with the stored record and the
+//! newer one wins.
+
+use crate::PublicSwapParams;
+use crate::api::{GetSwapResponse, RecoveredSwap};
+use crate::client::{ExtendedSwapStorageData, ExtendedVtxoSwapStorageData};
+use crate::error::{Error, Result};
+use crate::hd_wallet::{MAX_KEY_INDEX, derive_user_id_from_xpub};
+use crate::types::DerivationScheme;
+use bitcoin::bip32::Xpub;
+use bitcoin::secp256k1::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Outcome of merging recovered swaps into local storage.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryReport {
+    /// Swaps that were not stored locally and have been added.
+    pub added: Vec<String>,
+    /// Swaps whose stored status was behind the server and have been updated.
+    pub updated: Vec<String>,
+    /// Swaps that were already up to date.
+    pub unchanged: Vec<String>,
+    /// Swaps where the local record was kept because it disagrees with the server.
+    pub conflicts: Vec<RecoveryConflict>,
+    /// Swaps that are stored locally but were not reported by the server.
+    pub local_only: Vec<String>,
+    /// The key index after recovery.
+    pub key_index: u32,
+}
+
+/// A recovered swap that could not be merged automatically.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryConflict {
+    pub swap_id: String,
+    pub kind: RecoveryConflictKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryConflictKind {
+    /// The stored key index or public key differs from the one derived for the recovered swap.
+    KeyMismatch,
+    /// The stored status is further along than the one reported by the server.
+    LocalStatusAhead,
+}
+
+/// Merge recovered swaps into the locally stored ones.
+///
+/// Returns the records that need to be written and a report. The report's `key_index` is left at
+/// zero for the caller to fill in.
+pub(crate) fn merge_swaps(
+    local: Vec<ExtendedSwapStorageData>,
+    recovered: Vec<ExtendedSwapStorageData>,
+) -> (Vec<ExtendedSwapStorageData>, RecoveryReport) {
+    let mut local: HashMap<String, ExtendedSwapStorageData> = local
+        .into_iter()
+        .map(|data| (data.response.id(), data))
+        .collect();
+
+    let mut to_store = Vec::new();
+    let mut report = RecoveryReport::default();
+
+    for candidate in recovered {
+        let swap_id = candidate.response.id();
+
+        let Some(stored) = local.remove(&swap_id) else {
+            report.added.push(swap_id);
+            to_store.push(candidate);
+            continue;
+        };
+
+        if stored.swap_params != candidate.swap_params {
+            log::warn!("Keeping local swap {swap_id}: key does not match recovered swap");
+            report.conflicts.push(RecoveryConflict {
+                swap_id,
+                kind: RecoveryConflictKind::KeyMismatch,
+            });
+            continue;
+        }
+
+        let stored_status = stored.response.status();
+        let recovered_status = candidate.response.status();
+        if stored_status.progress() > recovered_status.progress() {
+            log::warn!(
+                "Keeping local swap {swap_id}: local status {stored_status:?} is ahead of {recovered_status:?}"
+            );
+            report.conflicts.push(RecoveryConflict {
+                swap_id,
+                kind: RecoveryConflictKind::LocalStatusAhead,
+            });
+            continue;
+        }
+
+        // Same progress or behind: the server's view is authoritative.
+        if stored_status == recovered_status {
+            report.unchanged.push(swap_id);
+        } else {
+            report.updated.push(swap_id);
+        }
+        to_store.push(candidate);
+    }
+
+    report.local_only = local.into_keys().collect();
+    report.local_only.sort();
+
+    (to_store, report)
+}
+
+/// Merge backed up VTXO swaps into the locally stored ones, adding them to `report`.
+///
+/// VTXO swap statuses don't tell which of two records is newer, so stored records are always
+/// kept and only missing swaps are added. Returns the records that need to be written.
+pub(crate) fn merge_vtxo_swaps(
+    local: Vec<ExtendedVtxoSwapStorageData>,
+    imported: Vec<ExtendedVtxoSwapStorageData>,
+    report: &mut RecoveryReport,
+) -> Vec<ExtendedVtxoSwapStorageData> {
+    let local: HashMap<String, ExtendedVtxoSwapStorageData> = local
+        .into_iter()
+        .map(|data| (data.response.id.to_string(), data))
+        .collect();
+
+    let mut to_store = Vec::new();
+    for candidate in imported {
+        let swap_id = candidate.response.id.to_string();
+        match local.get(&swap_id) {
+            None => {
+                report.added.push(swap_id);
+                to_store.push(candidate);
+            }
+            Some(stored) if stored.swap_params != candidate.swap_params => {
+                log::warn!("Keeping local VTXO swap {swap_id}: key does not match imported swap");
+                report.conflicts.push(RecoveryConflict {
+                    swap_id,
+                    kind: RecoveryConflictKind::KeyMismatch,
+                });
+            }
+            Some(_) => report.unchanged.push(swap_id),
+        }
+    }
+
+    to_store
+}
+
+/// The distinct key indices of recovered swaps in ascending order, e.g. to derive their keys in
+/// one go. Indices no keys can be derived at are logged and left out.
+pub(crate) fn recovered_key_indices(swaps: &[RecoveredSwap]) -> Vec<u32> {
+    let mut indices: Vec<u32> = swaps
+        .iter()
+        .filter_map(|recovered_swap| {
+            if recovered_swap.index > MAX_KEY_INDEX {
+                log::warn!(
+                    "Skipping recovered swap {}: key index {} is out of range",
+                    recovered_swap.swap.id(),
+                    recovered_swap.index
+                );
+                return None;
+            }
+            Some(recovered_swap.index)
+        })
+        .collect();
+    indices.sort_unstable();
+    indices.dedup();
+    indices
+}
+
+/// Reconstruct the public swap parameters of a recovered swap without the wallet's keys.
+///
+/// The client's public key and the preimage hash are taken from the swap itself, the user ID is
+/// derived from the user-ID Xpub. For swaps created by this wallet the result is the same as
+/// deriving the parameters from the mnemonic.
+pub(crate) fn watch_only_swap_params(
+    swap: &GetSwapResponse,
+    user_id_xpub: &Xpub,
+    key_index: u32,
+    account: u32,
+    scheme: DerivationScheme,
+) -> Result<PublicSwapParams> {
+    let common = swap.common();
+    let public_key = PublicKey::from_str(&common.sender_pk)
+        .map_err(|e| Error::Parse(format!("Invalid client public key: {}", e)))?;
+
+    let hash_lock = common.hash_lock.trim_start_matches("0x");
+    let preimage_hash = hex::decode(hash_lock)
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| Error::Parse(format!("Invalid hash lock: {}", common.hash_lock)))?;
+
+    Ok(PublicSwapParams {
+        public_key,
+        preimage_hash,
+        user_id: derive_user_id_from_xpub(user_id_xpub, key_index)?,
+        key_index,
+        account,
+        scheme,
+    })
+}
+
+/// Whether a recovered swap was created with `params`.
+///
+/// Compares the preimage hash with the swap's hash lock and the public key with the swap's
+/// client key. The receiver key is Lendaswap's, so it never identifies the client's parameters.
+/// Used to tell which derivation scheme a swap was created with.
+pub(crate) fn swap_matches_params(swap: &GetSwapResponse, params: &PublicSwapParams) -> bool {
+    let common = swap.common();
+
+    common
+        .hash_lock
+        .trim_start_matches("0x")
+        .eq_ignore_ascii_case(&hex::encode(params.preimage_hash))
+        || common.sender_pk == params.public_key.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PublicSwapParams;
+    use crate::api::{GetSwapResponse, SwapStatus, VtxoSwapStatus};
+    use crate::storage::conformance::sample_vtxo_swap;
+    use bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+    fn swap(id: &str, status: &str, key_index: u32) -> ExtendedSwapStorageData {
+        let response: GetSwapResponse = serde_json::from_value(serde_json::json!({
+            "direction": "btc_to_evm",
+            "id": id,
+            "status": status,
+            "hash_lock": format!("0x{}", "00".repeat(32)),
+            "fee_sats": 100,
+            "asset_amount": 10.0,
+            "sender_pk": "",
+            "receiver_pk": "",
+            "server_pk": "",
+            "refund_locktime": 0,
+            "unilateral_claim_delay": 0,
+            "unilateral_refund_delay": 0,
+            "unilateral_refund_without_receiver_delay": 0,
+            "network": "signet",
+            "created_at": "2025-01-01T00:00:00Z",
+            "htlc_address_evm": "",
+            "htlc_address_arkade": "",
+            "user_address_evm": "",
+            "ln_invoice": "",
+            "sats_receive": 1000,
+            "source_token": "btc_arkade",
+            "target_token": "usdc_pol",
+            "bitcoin_htlc_claim_txid": null,
+            "bitcoin_htlc_fund_txid": null,
+            "evm_htlc_claim_txid": null,
+            "evm_htlc_fund_txid": null,
+        }))
+        .unwrap();
+
+        let secret_key = SecretKey::from_slice(&[key_index as u8 + 1; 32]).unwrap();
+        ExtendedSwapStorageData {
+            response,
+            swap_params: PublicSwapParams {
+                public_key: PublicKey::from_secret_key(&Secp256k1::new(), &secret_key),
+                preimage_hash: [key_index as u8; 32],
+                user_id: PublicKey::from_secret_key(&Secp256k1::new(), &secret_key),
+                key_index,
+                account: 0,
+                scheme: DerivationScheme::V1,
+            },
+        }
+    }
+
+    const SWAP_A: &str = "00000000-0000-0000-0000-00000000000a";
+    const SWAP_B: &str = "00000000-0000-0000-0000-00000000000b";
+    const SWAP_C: &str = "00000000-0000-0000-0000-00000000000c";
+    const SWAP_D: &str = "00000000-0000-0000-0000-00000000000d";
+    const SWAP_E: &str = "00000000-0000-0000-0000-00000000000e";
+
+    #[test]
+    fn test_merge_keeps_newer_records() {
+        let local = vec![
+            swap(SWAP_A, "pending", 0),
+            swap(SWAP_B, "clientredeemed", 1),
+            swap(SWAP_C, "serverfunded", 2),
+            swap(SWAP_D, "pending", 3),
+        ];
+        let recovered = vec![
+            swap(SWAP_A, "serverfunded", 0),
+            swap(SWAP_B, "serverfunded", 1),
+            swap(SWAP_C, "serverfunded", 5),
+            swap(SWAP_E, "pending", 4),
+        ];
+
+        let (to_store, report) = merge_swaps(local, recovered);
+
+        assert_eq!(report.added, vec![SWAP_E]);
+        assert_eq!(report.updated, vec![SWAP_A]);
+        assert!(report.unchanged.is_empty());
+        assert_eq!(
+            report.conflicts,
+            vec![
+                RecoveryConflict {
+                    swap_id: SWAP_B.to_string(),
+                    kind: RecoveryConflictKind::LocalStatusAhead,
+                },
+                RecoveryConflict {
+                    swap_id: SWAP_C.to_string(),
+                    kind: RecoveryConflictKind::KeyMismatch,
+                },
+            ]
+        );
+        assert_eq!(report.local_only, vec![SWAP_D]);
+
+        let stored: Vec<_> = to_store
+            .iter()
+            .map(|data| (data.response.id(), data.response.status()))
+            .collect();
+        assert_eq!(
+            stored,
+            vec![
+                (SWAP_A.to_string(), SwapStatus::ServerFunded),
+                (SWAP_E.to_string(), SwapStatus::Pending),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_vtxo_swaps_keeps_stored_records() {
+        let mut stored_b = sample_vtxo_swap(SWAP_B, VtxoSwapStatus::ServerFunded);
+        let mut mismatch = sample_vtxo_swap(SWAP_C, VtxoSwapStatus::Pending);
+        mismatch.swap_params.key_index = 1;
+        let local = vec![
+            stored_b.clone(),
+            sample_vtxo_swap(SWAP_C, VtxoSwapStatus::Pending),
+        ];
+        stored_b.response.status = VtxoSwapStatus::Pending;
+        let imported = vec![
+            sample_vtxo_swap(SWAP_A, VtxoSwapStatus::Pending),
+            stored_b,
+            mismatch,
+        ];
+
+        let mut report = RecoveryReport::default();
+        let to_store = merge_vtxo_swaps(local, imported, &mut report);
+
+        assert_eq!(report.added, vec![SWAP_A]);
+        assert_eq!(report.unchanged, vec![SWAP_B]);
+        assert_eq!(
+            report.conflicts,
+            vec![RecoveryConflict {
+                swap_id: SWAP_C.to_string(),
+                kind: RecoveryConflictKind::KeyMismatch,
+            }]
+        );
+        assert_eq!(to_store.len(), 1);
+        assert_eq!(to_store[0].response.id.to_string(), SWAP_A);
+    }
+
+    #[test]
+    fn test_recovered_key_indices() {
+        let recovered = |index| RecoveredSwap {
+            swap: swap(SWAP_A, "pending", 0).response,
+            index,
+        };
+        let swaps = [
+            recovered(2_000_000),
+            recovered(0),
+            recovered(2_000_000),
+            recovered(MAX_KEY_INDEX + 1),
+            recovered(u32::MAX),
+            recovered(MAX_KEY_INDEX),
+        ];
+
+        assert_eq!(
+            recovered_key_indices(&swaps),
+            vec![0, 2_000_000, MAX_KEY_INDEX]
+        );
+        assert!(recovered_key_indices(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_watch_only_params_match_derived() {
+        let wallet = crate::HdWallet::generate(bitcoin::Network::Bitcoin, 12).unwrap();
+        let xpub = wallet.derive_user_id_xpub().unwrap();
+        let derived = wallet.derive_swap_params(7).unwrap();
+
+        let mut data = swap(SWAP_A, "serverfunded", 7);
+        let GetSwapResponse::BtcToEvm(response) = &mut data.response else {
+            unreachable!()
+        };
+        response.common.sender_pk = derived.public_key.to_string();
+        response.common.hash_lock = format!("0x{}", hex::encode(derived.preimage_hash));
+
+        let params =
+            watch_only_swap_params(&data.response, &xpub, 7, 0, DerivationScheme::V1).unwrap();
+        assert_eq!(params, derived.to_public());
+        assert!(swap_matches_params(&data.response, &params));
+        let other = wallet.derive_swap_params(8).unwrap().to_public();
+        assert!(!swap_matches_params(&data.response, &other));
+
+        // The receiver key is Lendaswap's, even if it happens to be one of ours
+        let GetSwapResponse::BtcToEvm(response) = &mut data.response else {
+            unreachable!()
+        };
+        response.common.receiver_pk = other.public_key.to_string();
+        assert!(!swap_matches_params(&data.response, &other));
+    }
+}
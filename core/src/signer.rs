@@ -0,0 +1,330 @@
+//! Pluggable signing for VHTLC claims and refunds.
+//!
+//! The [`SwapSigner`] trait abstracts over who holds the swap keys. The SDK only asks it for
+//! public keys, Schnorr signatures and preimages by key index, so the keys themselves may live
+//! in a remote signing service, an HSM or a browser extension.
+//!
+//! [`HdWallet`] and [`Wallet`] implement the trait by deriving keys from the mnemonic with the
+//! wallet's derivation scheme. [`SchemeSigner`] derives them with a given one instead.
+
+use crate::error::{Error, Result};
+use crate::hd_wallet::HdWallet;
+use crate::storage::WalletStorage;
+use crate::types::{DerivationScheme, SwapParams};
+use crate::wallet::Wallet;
+use ark_rs::core::VTXO_CONDITION_KEY;
+use bitcoin::consensus::Encodable;
+use bitcoin::key::{Keypair, Secp256k1};
+use bitcoin::secp256k1::{Message, PublicKey, schnorr};
+use bitcoin::{Psbt, VarInt, XOnlyPublicKey, psbt};
+use secrecy::ExposeSecret;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+/// Type alias for signer futures.
+///
+/// On WASM targets, futures don't need to be `Send` since JavaScript is single-threaded.
+/// On native targets, futures should be `Send` to allow use with multi-threaded runtimes.
+#[cfg(target_arch = "wasm32")]
+pub type SignerFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + 'a>>;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub type SignerFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// Signer for the client side of swaps.
+///
+/// Keys are addressed by the swap's key index, i.e. the same index used for
+/// [`HdWallet::derive_swap_params`].
+#[cfg(target_arch = "wasm32")]
+pub trait SwapSigner {
+    /// Get the public key used for the swap at `key_index`.
+    fn public_key(&self, key_index: u32) -> SignerFuture<'_, PublicKey>;
+
+    /// Produce a BIP340 Schnorr signature over `msg` with the key at `key_index`.
+    fn sign_schnorr(&self, key_index: u32, msg: Message) -> SignerFuture<'_, schnorr::Signature>;
+
+    /// Release the preimage for the swap at `key_index`.
+    ///
+    /// Only called when claiming, i.e. when the preimage is about to be revealed anyway.
+    fn preimage(&self, key_index: u32) -> SignerFuture<'_, [u8; 32]>;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub trait SwapSigner: Send + Sync {
+    /// Get the public key used for the swap at `key_index`.
+    fn public_key(&self, key_index: u32) -> SignerFuture<'_, PublicKey>;
+
+    /// Produce a BIP340 Schnorr signature over `msg` with the key at `key_index`.
+    fn sign_schnorr(&self, key_index: u32, msg: Message) -> SignerFuture<'_, schnorr::Signature>;
+
+    /// Release the preimage for the swap at `key_index`.
+    ///
+    /// Only called when claiming, i.e. when the preimage is about to be revealed anyway.
+    fn preimage(&self, key_index: u32) -> SignerFuture<'_, [u8; 32]>;
+}
+
+impl SwapSigner for HdWallet {
+    fn public_key(&self, key_index: u32) -> SignerFuture<'_, PublicKey> {
+        Box::pin(async move { Ok(self.derive_swap_params(key_index)?.public_key) })
+    }
+
+    fn sign_schnorr(&self, key_index: u32, msg: Message) -> SignerFuture<'_, schnorr::Signature> {
+        Box::pin(async move {
+            let params = self.derive_swap_params(key_index)?;
+            Ok(sign_with_params(&params, msg))
+        })
+    }
+
+    fn preimage(&self, key_index: u32) -> SignerFuture<'_, [u8; 32]> {
+        Box::pin(async move { Ok(**self.derive_swap_params(key_index)?.preimage.expose_secret()) })
+    }
+}
+
+impl<S: WalletStorage> SwapSigner for Wallet<S> {
+    fn public_key(&self, key_index: u32) -> SignerFuture<'_, PublicKey> {
+        Box::pin(async move {
+            let params = self.derive_swap_params_at_index(key_index).await?;
+            Ok(params.public_key)
+        })
+    }
+
+    fn sign_schnorr(&self, key_index: u32, msg: Message) -> SignerFuture<'_, schnorr::Signature> {
+        Box::pin(async move {
+            let params = self.derive_swap_params_at_index(key_index).await?;
+            Ok(sign_with_params(&params, msg))
+        })
+    }
+
+    fn preimage(&self, key_index: u32) -> SignerFuture<'_, [u8; 32]> {
+        Box::pin(async move {
+            let params = self.derive_swap_params_at_index(key_index).await?;
+            Ok(**params.preimage.expose_secret())
+        })
+    }
+}
+
+impl<T: SwapSigner + ?Sized> SwapSigner for &T {
+    fn public_key(&self, key_index: u32) -> SignerFuture<'_, PublicKey> {
+        (**self).public_key(key_index)
+    }
+
+    fn sign_schnorr(&self, key_index: u32, msg: Message) -> SignerFuture<'_, schnorr::Signature> {
+        (**self).sign_schnorr(key_index, msg)
+    }
+
+    fn preimage(&self, key_index: u32) -> SignerFuture<'_, [u8; 32]> {
+        (**self).preimage(key_index)
+    }
+}
+
+/// Signer deriving keys from a [`Wallet`] with a given [`DerivationScheme`].
+///
+/// Used for swaps created with another scheme than the one the wallet uses for new swaps.
+pub struct SchemeSigner<'a, S: WalletStorage> {
+    wallet: &'a Wallet<S>,
+    scheme: DerivationScheme,
+}
+
+impl<'a, S: WalletStorage> SchemeSigner<'a, S> {
+    pub fn new(wallet: &'a Wallet<S>, scheme: DerivationScheme) -> Self {
+        Self { wallet, scheme }
+    }
+}
+
+impl<S: WalletStorage> SwapSigner for SchemeSigner<'_, S> {
+    fn public_key(&self, key_index: u32) -> SignerFuture<'_, PublicKey> {
+        Box::pin(async move {
+            let params = self
+                .wallet
+                .derive_swap_params_with_scheme(self.scheme, key_index)
+                .await?;
+            Ok(params.public_key)
+        })
+    }
+
+    fn sign_schnorr(&self, key_index: u32, msg: Message) -> SignerFuture<'_, schnorr::Signature> {
+        Box::pin(async move {
+            let params = self
+                .wallet
+                .derive_swap_params_with_scheme(self.scheme, key_index)
+                .await?;
+            Ok(sign_with_params(&params, msg))
+        })
+    }
+
+    fn preimage(&self, key_index: u32) -> SignerFuture<'_, [u8; 32]> {
+        Box::pin(async move {
+            let params = self
+                .wallet
+                .derive_swap_params_with_scheme(self.scheme, key_index)
+                .await?;
+            Ok(**params.preimage.expose_secret())
+        })
+    }
+}
+
+/// Sign `msg` with the secret key of `params`, wiping the keypair afterwards.
+fn sign_with_params(params: &SwapParams, msg: Message) -> schnorr::Signature {
+    let secp = Secp256k1::new();
+    let mut keypair = Keypair::from_secret_key(&secp, params.secret_key.expose_secret());
+    let signature = secp.sign_schnorr_no_aux_rand(&msg, &keypair);
+    keypair.non_secure_erase();
+    signature
+}
+
+/// Which ark-rs signing routine to drive.
+#[derive(Clone, Copy)]
+pub(crate) enum PsbtKind {
+    /// The Ark transaction, signing the given input.
+    Ark { input_index: usize },
+    /// A checkpoint transaction.
+    Checkpoint,
+}
+
+/// Sign a PSBT with an async [`SwapSigner`] through ark-rs's synchronous signing callbacks.
+///
+/// ark-rs computes the sighash and hands it to a closure that must return signatures right
+/// away. We therefore run it twice: a dry run on a copy of the PSBT records the messages to
+/// sign, then, once the signer produced the signatures, the real run looks them up.
+///
+/// If `preimage` is set, it is added to the input as the VTXO condition witness.
+pub(crate) async fn sign_psbt(
+    signer: &dyn SwapSigner,
+    key_index: u32,
+    preimage: Option<&[u8; 32]>,
+    psbt: &mut Psbt,
+    kind: PsbtKind,
+) -> Result<()> {
+    let (public_key, _) = signer.public_key(key_index).await?.x_only_public_key();
+    let signatures = SignatureCache::default();
+
+    // Dry run to learn which messages need signing
+    run_ark_signing(&signatures, public_key, preimage, &mut psbt.clone(), kind)?;
+
+    let secp = Secp256k1::verification_only();
+    for msg in signatures.take_requested() {
+        let signature = signer.sign_schnorr(key_index, msg).await?;
+        secp.verify_schnorr(&signature, &msg, &public_key)
+            .map_err(|e| Error::Vhtlc(format!("Signer returned an invalid signature: {}", e)))?;
+        signatures.insert(msg, signature);
+    }
+
+    run_ark_signing(&signatures, public_key, preimage, psbt, kind)?;
+
+    if !signatures.take_requested().is_empty() {
+        return Err(Error::Vhtlc(
+            "Transaction requested a signature that was not prepared".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn run_ark_signing(
+    signatures: &SignatureCache,
+    public_key: XOnlyPublicKey,
+    preimage: Option<&[u8; 32]>,
+    psbt: &mut Psbt,
+    kind: PsbtKind,
+) -> Result<()> {
+    let sign_fn = |input: &mut psbt::Input,
+                   msg: Message|
+     -> std::result::Result<
+        Vec<(schnorr::Signature, XOnlyPublicKey)>,
+        ark_rs::core::Error,
+    > {
+        if let Some(preimage) = preimage {
+            insert_preimage(input, preimage);
+        }
+
+        Ok(vec![(signatures.get_or_request(msg), public_key)])
+    };
+
+    match kind {
+        PsbtKind::Ark { input_index } => {
+            ark_rs::core::send::sign_ark_transaction(sign_fn, psbt, input_index)
+                .map_err(|e| Error::Vhtlc(format!("Failed to sign ark transaction: {}", e)))
+        }
+        PsbtKind::Checkpoint => ark_rs::core::send::sign_checkpoint_transaction(sign_fn, psbt)
+            .map_err(|e| Error::Vhtlc(format!("Failed to sign checkpoint TX: {}", e))),
+    }
+}
+
+/// Add the preimage to a PSBT input as the VTXO condition witness.
+fn insert_preimage(input: &mut psbt::Input, preimage: &[u8; 32]) {
+    let mut bytes = vec![1]; // One witness element
+    let length = VarInt::from(preimage.len() as u64);
+    length
+        .consensus_encode(&mut bytes)
+        .expect("valid length encoding");
+    bytes.extend_from_slice(preimage);
+
+    input.unknown.insert(
+        psbt::raw::Key {
+            type_value: 222,
+            key: VTXO_CONDITION_KEY.to_vec(),
+        },
+        bytes,
+    );
+}
+
+/// Signatures prepared by the signer, and messages ark-rs asked for but which have none yet.
+#[derive(Default)]
+struct SignatureCache {
+    signed: Mutex<Vec<(Message, schnorr::Signature)>>,
+    requested: Mutex<Vec<Message>>,
+}
+
+impl SignatureCache {
+    /// Look up the signature for `msg`, or record the request and return a placeholder.
+    fn get_or_request(&self, msg: Message) -> schnorr::Signature {
+        let signed = self.signed.lock().expect("signature lock poisoned");
+        if let Some((_, signature)) = signed.iter().find(|(m, _)| *m == msg) {
+            return *signature;
+        }
+
+        self.requested
+            .lock()
+            .expect("signature lock poisoned")
+            .push(msg);
+        schnorr::Signature::from_slice(&[0; 64]).expect("64 bytes")
+    }
+
+    fn take_requested(&self) -> Vec<Message> {
+        std::mem::take(&mut *self.requested.lock().expect("signature lock poisoned"))
+    }
+
+    fn insert(&self, msg: Message, signature: schnorr::Signature) {
+        self.signed
+            .lock()
+            .expect("signature lock poisoned")
+            .push((msg, signature));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bitcoin::Network;
+    use bitcoin::hashes::{Hash, sha256};
+
+    #[tokio::test]
+    async fn test_hd_wallet_signer() {
+        let wallet = HdWallet::generate(Network::Bitcoin, 12).unwrap();
+        let params = wallet.derive_swap_params(5).unwrap();
+
+        assert_eq!(wallet.public_key(5).await.unwrap(), params.public_key);
+        assert_eq!(
+            wallet.preimage(5).await.unwrap(),
+            **params.preimage.expose_secret()
+        );
+
+        let msg = Message::from_digest(sha256::Hash::hash(b"vhtlc").to_byte_array());
+        let signature = wallet.sign_schnorr(5, msg).await.unwrap();
+        let (public_key, _) = params.public_key.x_only_public_key();
+        Secp256k1::verification_only()
+            .verify_schnorr(&signature, &msg, &public_key)
+            .unwrap();
+    }
+}
@@ -0,0 +1,799 @@
+//! SLIP-39 Shamir backups.
+//!
+//! Splits a master secret into mnemonic shares according to
+//! [SLIP-39](https://github.com/satoshilabs/slips/blob/master/slip-0039.md): the secret is
+//! encrypted with an optional passphrase, split into groups, and each group secret is split into
+//! member shares. Any `group_threshold` groups, each with `member_threshold` of its shares, are
+//! enough to recover the secret.
+//!
+//! The wallet uses the BIP39 entropy as the master secret (see
+//! [`HdWallet::to_slip39_shares`](crate::HdWallet::to_slip39_shares)), so restoring from shares
+//! yields the original mnemonic and therefore the same keys.
+
+mod wordlist;
+
+use crate::error::{Error, Result};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt;
+use wordlist::WORDLIST;
+use zeroize::Zeroizing;
+
+/// Customization string for the checksum of non-extendable shares and the encryption salt.
+const CUSTOMIZATION_STRING: &[u8] = b"shamir";
+/// Customization string for the checksum of extendable shares.
+const CUSTOMIZATION_STRING_EXTENDABLE: &[u8] = b"shamir_extendable";
+/// Number of words in the checksum.
+const CHECKSUM_WORDS: usize = 3;
+/// Number of words before the share value (identifier, flags and indices).
+const METADATA_WORDS: usize = 4;
+/// Minimum master secret length in bytes.
+const MIN_SECRET_LEN: usize = 16;
+/// Minimum number of words in a share mnemonic.
+const MIN_MNEMONIC_WORDS: usize =
+    METADATA_WORDS + (MIN_SECRET_LEN * 8).div_ceil(10) + CHECKSUM_WORDS;
+/// Maximum number of groups and of shares per group.
+const MAX_SHARE_COUNT: u8 = 16;
+/// x-coordinate of the digest share.
+const DIGEST_INDEX: u8 = 254;
+/// x-coordinate of the shared secret.
+const SECRET_INDEX: u8 = 255;
+const DIGEST_LEN: usize = 4;
+/// Iterations of the encryption's PBKDF2 round function, before applying the exponent.
+const BASE_ITERATION_COUNT: u32 = 10000;
+/// Number of rounds of the Feistel network used for encryption.
+const ROUND_COUNT: u8 = 4;
+
+/// Member shares of one group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Slip39Group {
+    /// Number of member shares required to reconstruct the group.
+    pub member_threshold: u8,
+    /// Number of member shares to create.
+    pub member_count: u8,
+}
+
+/// How to split a secret into SLIP-39 shares.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Slip39Options {
+    /// Number of groups required to recover the secret.
+    pub group_threshold: u8,
+    /// The groups to create.
+    pub groups: Vec<Slip39Group>,
+    /// Passphrase the secret is encrypted with. Must be printable ASCII.
+    ///
+    /// Any passphrase "works" when recovering, but a wrong one yields a different secret.
+    pub passphrase: Zeroizing<String>,
+    /// Exponent for the PBKDF2 iteration count of the encryption (`10000 << exponent`).
+    pub iteration_exponent: u8,
+    /// Whether further share sets for the same secret may be created later.
+    pub extendable: bool,
+}
+
+impl Slip39Options {
+    /// A single group of shares, `threshold` of which are required to recover the secret.
+    pub fn single_group(threshold: u8, count: u8) -> Self {
+        Self::with_groups(
+            1,
+            vec![Slip39Group {
+                member_threshold: threshold,
+                member_count: count,
+            }],
+        )
+    }
+
+    /// Several groups, `group_threshold` of which are required to recover the secret.
+    pub fn with_groups(group_threshold: u8, groups: Vec<Slip39Group>) -> Self {
+        Self {
+            group_threshold,
+            groups,
+            passphrase: Zeroizing::new(String::new()),
+            iteration_exponent: 1,
+            extendable: true,
+        }
+    }
+
+    /// Encrypt the secret with the given passphrase.
+    pub fn passphrase(mut self, passphrase: &str) -> Self {
+        self.passphrase = Zeroizing::new(passphrase.to_string());
+        self
+    }
+}
+
+impl fmt::Debug for Slip39Options {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Slip39Options")
+            .field("group_threshold", &self.group_threshold)
+            .field("groups", &self.groups)
+            .field("passphrase", &"[REDACTED]")
+            .field("iteration_exponent", &self.iteration_exponent)
+            .field("extendable", &self.extendable)
+            .finish()
+    }
+}
+
+/// Split a master secret into SLIP-39 share mnemonics.
+///
+/// Returns one list of mnemonics per group, in the order of [`Slip39Options::groups`].
+pub fn split_secret(master_secret: &[u8], options: &Slip39Options) -> Result<Vec<Vec<String>>> {
+    if master_secret.len() < MIN_SECRET_LEN || !master_secret.len().is_multiple_of(2) {
+        return Err(Error::InvalidMnemonic(format!(
+            "SLIP-39 master secret must be an even number of at least {} bytes, got {}",
+            MIN_SECRET_LEN,
+            master_secret.len()
+        )));
+    }
+    validate_passphrase(&options.passphrase)?;
+
+    let group_count = options.groups.len();
+    if options.group_threshold == 0
+        || options.group_threshold as usize > group_count
+        || group_count > MAX_SHARE_COUNT as usize
+    {
+        return Err(Error::InvalidMnemonic(format!(
+            "Invalid group threshold {} for {} groups",
+            options.group_threshold, group_count
+        )));
+    }
+    for group in &options.groups {
+        if group.member_threshold == 0
+            || group.member_threshold > group.member_count
+            || group.member_count > MAX_SHARE_COUNT
+        {
+            return Err(Error::InvalidMnemonic(format!(
+                "Invalid member threshold {} for {} shares",
+                group.member_threshold, group.member_count
+            )));
+        }
+        if group.member_threshold == 1 && group.member_count > 1 {
+            return Err(Error::InvalidMnemonic(
+                "A group with member threshold 1 must consist of a single share".to_string(),
+            ));
+        }
+    }
+    if options.iteration_exponent > 15 {
+        return Err(Error::InvalidMnemonic(
+            "Iteration exponent must be at most 15".to_string(),
+        ));
+    }
+
+    let identifier = (OsRng.next_u32() & 0x7fff) as u16;
+    let encrypted_secret = encrypt(
+        master_secret,
+        options.passphrase.as_bytes(),
+        options.iteration_exponent,
+        identifier,
+        options.extendable,
+    );
+
+    let group_secrets = split_shamir(
+        options.group_threshold,
+        group_count as u8,
+        &encrypted_secret,
+    );
+
+    let mut groups = Vec::with_capacity(group_count);
+    for ((group_index, group_secret), group) in group_secrets.iter().zip(&options.groups) {
+        let member_secrets = split_shamir(group.member_threshold, group.member_count, group_secret);
+        let mnemonics = member_secrets
+            .into_iter()
+            .map(|(member_index, value)| {
+                Share {
+                    identifier,
+                    extendable: options.extendable,
+                    iteration_exponent: options.iteration_exponent,
+                    group_index: *group_index,
+                    group_threshold: options.group_threshold,
+                    group_count: group_count as u8,
+                    member_index,
+                    member_threshold: group.member_threshold,
+                    value,
+                }
+                .to_mnemonic()
+            })
+            .collect();
+        groups.push(mnemonics);
+    }
+
+    Ok(groups)
+}
+
+/// Recover the master secret from SLIP-39 share mnemonics.
+///
+/// The shares may come from any groups, in any order; surplus shares are ignored. Returns
+/// [`Error::InvalidMnemonic`] if a share is malformed, the shares don't belong together or there
+/// are not enough of them.
+pub fn combine_shares<S: AsRef<str>>(
+    mnemonics: &[S],
+    passphrase: &str,
+) -> Result<Zeroizing<Vec<u8>>> {
+    validate_passphrase(passphrase)?;
+
+    let shares = mnemonics
+        .iter()
+        .map(|mnemonic| Share::parse(mnemonic.as_ref()))
+        .collect::<Result<Vec<_>>>()?;
+    let first = shares
+        .first()
+        .ok_or_else(|| Error::InvalidMnemonic("No shares provided".to_string()))?;
+
+    if shares.iter().any(|share| !first.is_compatible(share)) {
+        return Err(Error::InvalidMnemonic(
+            "Shares do not belong to the same secret".to_string(),
+        ));
+    }
+
+    // Collect the member shares per group
+    let mut groups: Vec<(u8, u8, Vec<IndexedValue>)> = Vec::new();
+    for share in &shares {
+        let position = groups
+            .iter()
+            .position(|(group_index, _, _)| *group_index == share.group_index);
+        let (_, member_threshold, members) = match position {
+            Some(position) => &mut groups[position],
+            None => {
+                groups.push((share.group_index, share.member_threshold, Vec::new()));
+                groups.last_mut().expect("just pushed")
+            }
+        };
+
+        if *member_threshold != share.member_threshold {
+            return Err(Error::InvalidMnemonic(format!(
+                "Shares of group {} have different member thresholds",
+                share.group_index
+            )));
+        }
+        match members
+            .iter()
+            .find(|(index, _)| *index == share.member_index)
+        {
+            Some((_, value)) if *value != share.value => {
+                return Err(Error::InvalidMnemonic(format!(
+                    "Conflicting shares for member {} of group {}",
+                    share.member_index, share.group_index
+                )));
+            }
+            Some(_) => {}
+            None => members.push((share.member_index, share.value.clone())),
+        }
+    }
+
+    let mut group_secrets = Vec::new();
+    for (group_index, member_threshold, members) in &groups {
+        if members.len() < *member_threshold as usize {
+            continue;
+        }
+        let secret = recover_shamir(*member_threshold, &members[..*member_threshold as usize])?;
+        group_secrets.push((*group_index, secret));
+    }
+
+    if group_secrets.len() < first.group_threshold as usize {
+        return Err(Error::InvalidMnemonic(format!(
+            "Insufficient shares: {} of {} required groups are complete",
+            group_secrets.len(),
+            first.group_threshold
+        )));
+    }
+
+    let encrypted_secret = recover_shamir(
+        first.group_threshold,
+        &group_secrets[..first.group_threshold as usize],
+    )?;
+    Ok(decrypt(
+        &encrypted_secret,
+        passphrase.as_bytes(),
+        first.iteration_exponent,
+        first.identifier,
+        first.extendable,
+    ))
+}
+
+/// A share value with its x-coordinate.
+type IndexedValue = (u8, Zeroizing<Vec<u8>>);
+
+/// A single decoded share.
+struct Share {
+    identifier: u16,
+    extendable: bool,
+    iteration_exponent: u8,
+    group_index: u8,
+    group_threshold: u8,
+    group_count: u8,
+    member_index: u8,
+    member_threshold: u8,
+    value: Zeroizing<Vec<u8>>,
+}
+
+impl Share {
+    fn parse(mnemonic: &str) -> Result<Self> {
+        let words = mnemonic
+            .split_whitespace()
+            .map(word_index)
+            .collect::<Result<Vec<_>>>()?;
+
+        if words.len() < MIN_MNEMONIC_WORDS {
+            return Err(Error::InvalidMnemonic(format!(
+                "Share must have at least {} words, got {}",
+                MIN_MNEMONIC_WORDS,
+                words.len()
+            )));
+        }
+
+        let value_words = words.len() - METADATA_WORDS - CHECKSUM_WORDS;
+        let padding = (value_words * 10) % 16;
+        if padding > 8 {
+            return Err(Error::InvalidMnemonic("Invalid share length".to_string()));
+        }
+
+        let id_exp = (words[0] as u32) << 10 | words[1] as u32;
+        let extendable = (id_exp >> 4) & 1 == 1;
+        if rs1024_polymod(customization_string(extendable), &words) != 1 {
+            return Err(Error::InvalidMnemonic("Invalid share checksum".to_string()));
+        }
+
+        let indices = (words[2] as u32) << 10 | words[3] as u32;
+        let group_threshold = ((indices >> 12) & 0xf) as u8 + 1;
+        let group_count = ((indices >> 8) & 0xf) as u8 + 1;
+        if group_threshold > group_count {
+            return Err(Error::InvalidMnemonic(
+                "Group threshold exceeds group count".to_string(),
+            ));
+        }
+
+        let value_len = (value_words * 10 - padding) / 8;
+        let value = words_to_bytes(
+            &words[METADATA_WORDS..words.len() - CHECKSUM_WORDS],
+            value_len,
+        )?;
+
+        Ok(Self {
+            identifier: (id_exp >> 5) as u16,
+            extendable,
+            iteration_exponent: (id_exp & 0xf) as u8,
+            group_index: (indices >> 16) as u8,
+            group_threshold,
+            group_count,
+            member_index: ((indices >> 4) & 0xf) as u8,
+            member_threshold: (indices & 0xf) as u8 + 1,
+            value,
+        })
+    }
+
+    fn to_mnemonic(&self) -> String {
+        let id_exp = (self.identifier as u32) << 5
+            | (self.extendable as u32) << 4
+            | self.iteration_exponent as u32;
+        let indices = (self.group_index as u32) << 16
+            | ((self.group_threshold - 1) as u32) << 12
+            | ((self.group_count - 1) as u32) << 8
+            | (self.member_index as u32) << 4
+            | (self.member_threshold - 1) as u32;
+
+        let mut words = vec![
+            (id_exp >> 10) as u16,
+            (id_exp & 0x3ff) as u16,
+            (indices >> 10) as u16,
+            (indices & 0x3ff) as u16,
+        ];
+        words.extend(bytes_to_words(&self.value));
+
+        let checksum = rs1024_checksum(customization_string(self.extendable), &words);
+        words.extend(checksum);
+
+        words
+            .iter()
+            .map(|&index| WORDLIST[index as usize])
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Whether two shares can be combined.
+    fn is_compatible(&self, other: &Share) -> bool {
+        self.identifier == other.identifier
+            && self.extendable == other.extendable
+            && self.iteration_exponent == other.iteration_exponent
+            && self.group_threshold == other.group_threshold
+            && self.group_count == other.group_count
+            && self.value.len() == other.value.len()
+    }
+}
+
+fn word_index(word: &str) -> Result<u16> {
+    let word = word.to_lowercase();
+    WORDLIST
+        .binary_search(&word.as_str())
+        .map(|index| index as u16)
+        .map_err(|_| Error::InvalidMnemonic(format!("Unknown SLIP-39 word: {}", word)))
+}
+
+fn validate_passphrase(passphrase: &str) -> Result<()> {
+    if passphrase.bytes().all(|b| (32..=126).contains(&b)) {
+        Ok(())
+    } else {
+        Err(Error::InvalidMnemonic(
+            "SLIP-39 passphrase must consist of printable ASCII characters".to_string(),
+        ))
+    }
+}
+
+/// Encode bytes as 10-bit words, padding with zero bits at the front.
+fn bytes_to_words(bytes: &[u8]) -> Vec<u16> {
+    let word_count = (bytes.len() * 8).div_ceil(10);
+    let mut words = Vec::with_capacity(word_count);
+    let mut acc: u32 = 0;
+    let mut bits = word_count * 10 - bytes.len() * 8;
+
+    for &byte in bytes {
+        acc = (acc << 8) | byte as u32;
+        bits += 8;
+        while bits >= 10 {
+            bits -= 10;
+            words.push(((acc >> bits) & 0x3ff) as u16);
+        }
+        acc &= (1 << bits) - 1;
+    }
+
+    words
+}
+
+/// Decode 10-bit words into `len` bytes, rejecting non-zero padding.
+fn words_to_bytes(words: &[u16], len: usize) -> Result<Zeroizing<Vec<u8>>> {
+    let mut padding = words.len() * 10 - len * 8;
+    let mut bytes = Zeroizing::new(Vec::with_capacity(len));
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+
+    for &word in words {
+        acc = (acc << 10) | word as u32;
+        bits += 10;
+        if padding > 0 {
+            let drop = padding.min(bits);
+            bits -= drop;
+            padding -= drop;
+            if acc >> bits != 0 {
+                return Err(Error::InvalidMnemonic("Invalid share padding".to_string()));
+            }
+        }
+        while bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+        acc &= (1 << bits) - 1;
+    }
+
+    Ok(bytes)
+}
+
+fn customization_string(extendable: bool) -> &'static [u8] {
+    if extendable {
+        CUSTOMIZATION_STRING_EXTENDABLE
+    } else {
+        CUSTOMIZATION_STRING
+    }
+}
+
+/// Reed-Solomon code over GF(1024) used for the share checksum.
+fn rs1024_polymod(customization: &[u8], words: &[u16]) -> u32 {
+    const GEN: [u32; 10] = [
+        0xe0e040, 0x1c1c080, 0x3838100, 0x7070200, 0xe0e0009, 0x1c0c2412, 0x38086c24, 0x3090fc48,
+        0x21b1f890, 0x3f3f120,
+    ];
+
+    let values = customization
+        .iter()
+        .map(|&b| b as u32)
+        .chain(words.iter().map(|&w| w as u32));
+
+    let mut chk: u32 = 1;
+    for value in values {
+        let b = chk >> 20;
+        chk = ((chk & 0xfffff) << 10) ^ value;
+        for (i, generator) in GEN.iter().enumerate() {
+            if (b >> i) & 1 == 1 {
+                chk ^= generator;
+            }
+        }
+    }
+    chk
+}
+
+fn rs1024_checksum(customization: &[u8], words: &[u16]) -> [u16; CHECKSUM_WORDS] {
+    let mut values = words.to_vec();
+    values.extend([0; CHECKSUM_WORDS]);
+    let polymod = rs1024_polymod(customization, &values) ^ 1;
+    [
+        ((polymod >> 20) & 0x3ff) as u16,
+        ((polymod >> 10) & 0x3ff) as u16,
+        (polymod & 0x3ff) as u16,
+    ]
+}
+
+/// Encrypt the master secret with a 4-round Feistel network keyed by the passphrase.
+fn encrypt(
+    secret: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+) -> Zeroizing<Vec<u8>> {
+    feistel(
+        secret,
+        passphrase,
+        iteration_exponent,
+        identifier,
+        extendable,
+        0..ROUND_COUNT,
+    )
+}
+
+fn decrypt(
+    encrypted: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+) -> Zeroizing<Vec<u8>> {
+    feistel(
+        encrypted,
+        passphrase,
+        iteration_exponent,
+        identifier,
+        extendable,
+        (0..ROUND_COUNT).rev(),
+    )
+}
+
+fn feistel(
+    input: &[u8],
+    passphrase: &[u8],
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+    rounds: impl Iterator<Item = u8>,
+) -> Zeroizing<Vec<u8>> {
+    let half = input.len() / 2;
+    let mut left = Zeroizing::new(input[..half].to_vec());
+    let mut right = Zeroizing::new(input[half..].to_vec());
+
+    let mut salt = Vec::new();
+    if !extendable {
+        salt.extend_from_slice(CUSTOMIZATION_STRING);
+        salt.extend_from_slice(&identifier.to_be_bytes());
+    }
+    let iterations = (BASE_ITERATION_COUNT << iteration_exponent) / ROUND_COUNT as u32;
+
+    for round in rounds {
+        let mut password = Zeroizing::new(vec![round]);
+        password.extend_from_slice(passphrase);
+        let mut round_salt = salt.clone();
+        round_salt.extend_from_slice(&right);
+
+        let mut f = Zeroizing::new(vec![0u8; half]);
+        pbkdf2::pbkdf2_hmac::<Sha256>(&password, &round_salt, iterations, &mut f);
+
+        for (l, f) in left.iter_mut().zip(f.iter()) {
+            *l ^= f;
+        }
+        std::mem::swap(&mut left, &mut right);
+    }
+
+    let mut output = Zeroizing::new(right.to_vec());
+    output.extend_from_slice(&left);
+    output
+}
+
+/// Split a secret into `count` shares, `threshold` of which recover it.
+fn split_shamir(threshold: u8, count: u8, secret: &[u8]) -> Vec<IndexedValue> {
+    if threshold == 1 {
+        return (0..count)
+            .map(|index| (index, Zeroizing::new(secret.to_vec())))
+            .collect();
+    }
+
+    let random_share_count = threshold - 2;
+    let mut shares: Vec<IndexedValue> = (0..random_share_count)
+        .map(|index| {
+            let mut value = Zeroizing::new(vec![0u8; secret.len()]);
+            OsRng.fill_bytes(&mut value);
+            (index, value)
+        })
+        .collect();
+
+    let mut digest_share = Zeroizing::new(vec![0u8; secret.len()]);
+    OsRng.fill_bytes(&mut digest_share[DIGEST_LEN..]);
+    let digest = share_digest(&digest_share[DIGEST_LEN..], secret);
+    digest_share[..DIGEST_LEN].copy_from_slice(&digest);
+
+    let mut base_shares = shares.clone();
+    base_shares.push((DIGEST_INDEX, digest_share));
+    base_shares.push((SECRET_INDEX, Zeroizing::new(secret.to_vec())));
+
+    for index in random_share_count..count {
+        shares.push((index, interpolate(&base_shares, index)));
+    }
+
+    shares
+}
+
+/// Recover a secret from exactly `threshold` shares, verifying the digest.
+fn recover_shamir(threshold: u8, shares: &[IndexedValue]) -> Result<Zeroizing<Vec<u8>>> {
+    if threshold == 1 {
+        return Ok(shares[0].1.clone());
+    }
+
+    let secret = interpolate(shares, SECRET_INDEX);
+    let digest_share = interpolate(shares, DIGEST_INDEX);
+    if digest_share[..DIGEST_LEN] != share_digest(&digest_share[DIGEST_LEN..], &secret) {
+        return Err(Error::InvalidMnemonic(
+            "Share digest mismatch: the shares do not belong together".to_string(),
+        ));
+    }
+
+    Ok(secret)
+}
+
+fn share_digest(random_part: &[u8], secret: &[u8]) -> [u8; DIGEST_LEN] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(random_part).expect("HMAC accepts any key length");
+    mac.update(secret);
+    let digest = mac.finalize().into_bytes();
+    digest[..DIGEST_LEN].try_into().expect("4 bytes")
+}
+
+/// Lagrange interpolation over GF(256) of the shares, evaluated at `x`.
+fn interpolate(shares: &[IndexedValue], x: u8) -> Zeroizing<Vec<u8>> {
+    if let Some((_, value)) = shares.iter().find(|(index, _)| *index == x) {
+        return value.clone();
+    }
+
+    let (exp, log) = gf256_tables();
+    let log_product: u32 = shares
+        .iter()
+        .map(|(index, _)| log[(index ^ x) as usize] as u32)
+        .sum();
+
+    let mut result = Zeroizing::new(vec![0u8; shares[0].1.len()]);
+    for (index, value) in shares {
+        let log_denominator: u32 = shares
+            .iter()
+            .filter(|(other, _)| other != index)
+            .map(|(other, _)| log[(index ^ other) as usize] as u32)
+            .sum();
+        let log_basis = (log_product + 255 * 2 * shares.len() as u32
+            - log[(index ^ x) as usize] as u32
+            - log_denominator)
+            % 255;
+
+        for (r, &v) in result.iter_mut().zip(value.iter()) {
+            if v != 0 {
+                *r ^= exp[((log[v as usize] as u32 + log_basis) % 255) as usize];
+            }
+        }
+    }
+
+    result
+}
+
+/// Exponent and logarithm tables of GF(256) with the Rijndael polynomial and generator 3.
+const fn gf256_tables() -> ([u8; 255], [u8; 256]) {
+    let mut exp = [0u8; 255];
+    let mut log = [0u8; 256];
+    let mut poly: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = poly as u8;
+        log[poly as usize] = i as u8;
+        poly = (poly << 1) ^ poly;
+        if poly & 0x100 != 0 {
+            poly ^= 0x11b;
+        }
+        i += 1;
+    }
+    (exp, log)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wordlist() {
+        assert!(WORDLIST.windows(2).all(|w| w[0] < w[1]));
+        let mut prefixes: Vec<_> = WORDLIST.iter().map(|w| &w[..4]).collect();
+        prefixes.dedup();
+        assert_eq!(prefixes.len(), 1024);
+    }
+
+    /// Test vectors in the format of the official SLIP-0039 `vectors.json`:
+    /// `[description, mnemonics, master secret hex, BIP32 master xprv]`, where an empty master
+    /// secret marks shares that must be rejected.
+    const VECTORS: &str = include_str!("slip39/vectors.json");
+
+    #[test]
+    fn test_vectors() {
+        let vectors: Vec<(String, Vec<String>, String, String)> =
+            serde_json::from_str(VECTORS).unwrap();
+        assert!(!vectors.is_empty());
+
+        for (description, mnemonics, secret, xprv) in vectors {
+            let result = combine_shares(&mnemonics, "TREZOR");
+            if secret.is_empty() {
+                assert!(result.is_err(), "{description}: accepted invalid shares");
+                continue;
+            }
+
+            let recovered = result.unwrap_or_else(|e| panic!("{description}: {e}"));
+            assert_eq!(hex::encode(&*recovered), secret, "{description}");
+            if !xprv.is_empty() {
+                let master =
+                    bitcoin::bip32::Xpriv::new_master(bitcoin::Network::Bitcoin, &recovered)
+                        .unwrap();
+                assert_eq!(master.to_string(), xprv, "{description}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_and_combine_groups() {
+        let secret = [0x42u8; 32];
+        let options = Slip39Options::with_groups(
+            2,
+            vec![
+                Slip39Group {
+                    member_threshold: 1,
+                    member_count: 1,
+                },
+                Slip39Group {
+                    member_threshold: 2,
+                    member_count: 3,
+                },
+                Slip39Group {
+                    member_threshold: 3,
+                    member_count: 5,
+                },
+            ],
+        )
+        .passphrase("correct horse");
+        assert!(!format!("{options:?}").contains("correct horse"));
+
+        let groups = split_secret(&secret, &options).unwrap();
+        assert_eq!(groups.iter().map(Vec::len).collect::<Vec<_>>(), [1, 3, 5]);
+        assert_eq!(groups[0][0].split(' ').count(), 33);
+
+        // Second and third group
+        let shares = [
+            &groups[1][2],
+            &groups[1][0],
+            &groups[2][4],
+            &groups[2][1],
+            &groups[2][3],
+        ];
+        let recovered = combine_shares(&shares, "correct horse").unwrap();
+        assert_eq!(&*recovered, &secret);
+
+        // A wrong passphrase silently yields a different secret
+        let recovered = combine_shares(&shares, "wrong").unwrap();
+        assert_ne!(&*recovered, &secret);
+
+        // One share short in the third group
+        assert!(combine_shares(&shares[..4], "correct horse").is_err());
+
+        // Shares from different splits don't combine
+        let other = split_secret(&secret, &options).unwrap();
+        assert!(combine_shares(&[&groups[0][0], &other[1][0], &other[1][1]], "").is_err());
+
+        // Non-extendable shares use a different encryption salt
+        let legacy = Slip39Options {
+            extendable: false,
+            ..Slip39Options::single_group(2, 3)
+        };
+        let shares = &split_secret(&secret, &legacy).unwrap()[0];
+        assert_eq!(&*combine_shares(&shares[1..], "").unwrap(), &secret);
+
+        assert!(split_secret(&secret, &Slip39Options::single_group(1, 2)).is_err());
+        assert!(split_secret(&secret, &Slip39Options::single_group(4, 3)).is_err());
+        assert!(split_secret(&secret[..15], &Slip39Options::single_group(2, 3)).is_err());
+    }
+}
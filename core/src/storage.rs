@@ -5,13 +5,54 @@
 //!
 //! There are two separate storage concerns:
 //! - `WalletStorage`: Typed storage for wallet data (mnemonic, key index)
-//! - `SwapStorage`: Typed storage specifically for swap data
+//! - `SwapStorage`: Typed storage specifically for swap data, which can be queried with a
+//!   [`SwapQuery`]
+//!
+//! [`EncryptedWalletStorage`] can wrap any `WalletStorage` to keep the mnemonic encrypted at rest.
+//! [`WatchOnlyWalletStorage`] is the keyless storage of watch-only clients.
+//!
+//! With the `sqlite` feature, [`SqliteStorage`] implements both traits on a SQLite database. With
+//! the `fs` feature, [`FsStorage`] implements them on a directory of JSON files. [`KvStorage`]
+//! implements them on any [`KeyValueStore`].
+//!
+//! With the `test-utils` feature, [`memory`] provides in-memory implementations and
+//! [`conformance`] test suites for checking other implementations against the traits' contracts.
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod conformance;
+mod encrypted;
+#[cfg(feature = "fs")]
+mod fs;
+mod kv;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod memory;
+mod query;
+pub mod schema;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use crate::crypto::KdfParams;
+pub use encrypted::EncryptedWalletStorage;
+#[cfg(feature = "fs")]
+pub use fs::FsStorage;
+pub use kv::{KeyValueStore, KvStorage};
+pub use query::{SortOrder, SwapCursor, SwapPage, SwapQuery};
+pub use schema::QuarantinedRecord;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStorage;
 
-use crate::client::ExtendedSwapStorageData;
-use crate::error::Result;
+use crate::client::{ExtendedSwapStorageData, ExtendedVtxoSwapStorageData};
+use crate::error::{Error, Result};
+use crate::history::SwapEvent;
+use crate::journal::PendingCreation;
+use crate::types::DerivationScheme;
+use futures::lock::Mutex;
 use std::future::Future;
 use std::pin::Pin;
 
+/// Serializes key index reservations of storages that can't reserve atomically themselves.
+static KEY_INDEX_LOCK: Mutex<()> = Mutex::new(());
+
 /// Type alias for storage futures.
 ///
 /// On WASM targets, futures don't need to be `Send` since JavaScript is single-threaded.
@@ -58,6 +99,96 @@ pub trait WalletStorage {
 
     /// Set the key derivation index.
     fn set_key_index(&self, index: u32) -> StorageFuture<'_, ()>;
+
+    /// Atomically reserve the current key index and advance the stored index by one.
+    ///
+    /// Returns the reserved index. No two calls may ever return the same index, otherwise two
+    /// swaps would share a key and preimage. The default implementation serializes reservations
+    /// with an in-process lock (see [`reserve_key_index_in_process`]); backends shared between
+    /// processes should override this with a compare-and-swap or a transaction.
+    fn reserve_key_index(&self) -> StorageFuture<'_, u32> {
+        Box::pin(reserve_key_index_in_process(self))
+    }
+
+    /// Get the key derivation index of an account.
+    ///
+    /// Account 0 is the wallet's default account, whose index is [`WalletStorage::get_key_index`].
+    /// The default implementation only supports account 0.
+    fn get_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        match account {
+            0 => self.get_key_index(),
+            _ => Box::pin(async { Err(accounts_unsupported()) }),
+        }
+    }
+
+    /// Set the key derivation index of an account.
+    ///
+    /// The default implementation only supports account 0.
+    fn set_account_key_index(&self, account: u32, index: u32) -> StorageFuture<'_, ()> {
+        match account {
+            0 => self.set_key_index(index),
+            _ => Box::pin(async { Err(accounts_unsupported()) }),
+        }
+    }
+
+    /// Atomically reserve the current key index of an account, see
+    /// [`WalletStorage::reserve_key_index`].
+    fn reserve_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        match account {
+            0 => self.reserve_key_index(),
+            _ => Box::pin(reserve_account_key_index_in_process(self, account)),
+        }
+    }
+
+    /// Get the master key fingerprint of a passphrase-protected wallet.
+    ///
+    /// Returns `Ok(None)` if the wallet does not use a BIP39 passphrase. The default
+    /// implementation always returns `Ok(None)`.
+    fn get_passphrase_fingerprint(&self) -> StorageFuture<'_, Option<String>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Store (or clear, with `None`) the master key fingerprint of a passphrase-protected wallet.
+    ///
+    /// Only the fingerprint is stored, never the passphrase itself. The default implementation
+    /// rejects storing a fingerprint, so storages must opt in to passphrase support.
+    fn set_passphrase_fingerprint(&self, fingerprint: Option<&str>) -> StorageFuture<'_, ()> {
+        let supported = fingerprint.is_none();
+        Box::pin(async move {
+            if supported {
+                Ok(())
+            } else {
+                Err(Error::Storage(
+                    "this storage does not support passphrase-protected wallets".to_string(),
+                ))
+            }
+        })
+    }
+
+    /// Get the derivation scheme the wallet uses for new swaps.
+    ///
+    /// Returns `Ok(None)` if none has been stored, i.e. for wallets created before derivation
+    /// schemes were introduced. The default implementation always returns `Ok(None)`.
+    fn get_derivation_scheme(&self) -> StorageFuture<'_, Option<DerivationScheme>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Store the derivation scheme the wallet uses for new swaps.
+    ///
+    /// The default implementation only accepts [`DerivationScheme::V1`], which is what
+    /// [`WalletStorage::get_derivation_scheme`] implies by returning `None`.
+    fn set_derivation_scheme(&self, scheme: DerivationScheme) -> StorageFuture<'_, ()> {
+        Box::pin(async move {
+            if scheme == DerivationScheme::V1 {
+                Ok(())
+            } else {
+                Err(Error::Storage(format!(
+                    "this storage does not support derivation scheme {}",
+                    scheme
+                )))
+            }
+        })
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -79,6 +210,134 @@ pub trait WalletStorage: Send + Sync {
 
     /// Set the key derivation index.
     fn set_key_index(&self, index: u32) -> StorageFuture<'_, ()>;
+
+    /// Atomically reserve the current key index and advance the stored index by one.
+    ///
+    /// Returns the reserved index. No two calls may ever return the same index, otherwise two
+    /// swaps would share a key and preimage. The default implementation serializes reservations
+    /// with an in-process lock (see [`reserve_key_index_in_process`]); backends shared between
+    /// processes should override this with a compare-and-swap or a transaction.
+    fn reserve_key_index(&self) -> StorageFuture<'_, u32> {
+        Box::pin(reserve_key_index_in_process(self))
+    }
+
+    /// Get the key derivation index of an account.
+    ///
+    /// Account 0 is the wallet's default account, whose index is [`WalletStorage::get_key_index`].
+    /// The default implementation only supports account 0.
+    fn get_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        match account {
+            0 => self.get_key_index(),
+            _ => Box::pin(async { Err(accounts_unsupported()) }),
+        }
+    }
+
+    /// Set the key derivation index of an account.
+    ///
+    /// The default implementation only supports account 0.
+    fn set_account_key_index(&self, account: u32, index: u32) -> StorageFuture<'_, ()> {
+        match account {
+            0 => self.set_key_index(index),
+            _ => Box::pin(async { Err(accounts_unsupported()) }),
+        }
+    }
+
+    /// Atomically reserve the current key index of an account, see
+    /// [`WalletStorage::reserve_key_index`].
+    fn reserve_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        match account {
+            0 => self.reserve_key_index(),
+            _ => Box::pin(reserve_account_key_index_in_process(self, account)),
+        }
+    }
+
+    /// Get the master key fingerprint of a passphrase-protected wallet.
+    ///
+    /// Returns `Ok(None)` if the wallet does not use a BIP39 passphrase. The default
+    /// implementation always returns `Ok(None)`.
+    fn get_passphrase_fingerprint(&self) -> StorageFuture<'_, Option<String>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Store (or clear, with `None`) the master key fingerprint of a passphrase-protected wallet.
+    ///
+    /// Only the fingerprint is stored, never the passphrase itself. The default implementation
+    /// rejects storing a fingerprint, so storages must opt in to passphrase support.
+    fn set_passphrase_fingerprint(&self, fingerprint: Option<&str>) -> StorageFuture<'_, ()> {
+        let supported = fingerprint.is_none();
+        Box::pin(async move {
+            if supported {
+                Ok(())
+            } else {
+                Err(Error::Storage(
+                    "this storage does not support passphrase-protected wallets".to_string(),
+                ))
+            }
+        })
+    }
+
+    /// Get the derivation scheme the wallet uses for new swaps.
+    ///
+    /// Returns `Ok(None)` if none has been stored, i.e. for wallets created before derivation
+    /// schemes were introduced. The default implementation always returns `Ok(None)`.
+    fn get_derivation_scheme(&self) -> StorageFuture<'_, Option<DerivationScheme>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Store the derivation scheme the wallet uses for new swaps.
+    ///
+    /// The default implementation only accepts [`DerivationScheme::V1`], which is what
+    /// [`WalletStorage::get_derivation_scheme`] implies by returning `None`.
+    fn set_derivation_scheme(&self, scheme: DerivationScheme) -> StorageFuture<'_, ()> {
+        Box::pin(async move {
+            if scheme == DerivationScheme::V1 {
+                Ok(())
+            } else {
+                Err(Error::Storage(format!(
+                    "this storage does not support derivation scheme {}",
+                    scheme
+                )))
+            }
+        })
+    }
+}
+
+/// Reserve a key index by reading and then writing it while holding a process-wide lock.
+///
+/// This makes the read-modify-write atomic with respect to all other callers in this process,
+/// but not with respect to other processes (or browser tabs) sharing the same backend.
+pub async fn reserve_key_index_in_process<S: WalletStorage + ?Sized>(storage: &S) -> Result<u32> {
+    let _guard = KEY_INDEX_LOCK.lock().await;
+
+    let current = storage.get_key_index().await?;
+    let next = current
+        .checked_add(1)
+        .ok_or_else(|| Error::Storage("key index exhausted".to_string()))?;
+    storage.set_key_index(next).await?;
+    Ok(current)
+}
+
+/// Like [`reserve_key_index_in_process`], for the key index of the given account.
+pub async fn reserve_account_key_index_in_process<S: WalletStorage + ?Sized>(
+    storage: &S,
+    account: u32,
+) -> Result<u32> {
+    let _guard = KEY_INDEX_LOCK.lock().await;
+
+    let current = storage.get_account_key_index(account).await?;
+    let next = current
+        .checked_add(1)
+        .ok_or_else(|| Error::Storage("key index exhausted".to_string()))?;
+    storage.set_account_key_index(account, next).await?;
+    Ok(current)
+}
+
+fn accounts_unsupported() -> Error {
+    Error::Storage("this storage does not support multiple accounts".to_string())
+}
+
+pub(crate) fn vtxo_swaps_unsupported() -> Error {
+    Error::Storage("this storage does not support VTXO swaps".to_string())
 }
 
 /// Extension trait for wallet storage operations.
@@ -88,14 +347,10 @@ pub trait WalletStorage: Send + Sync {
 pub trait WalletStorageExt: WalletStorage {
     /// Increment and return the current key index (for auto-derivation).
     ///
-    /// Returns the index to use (before incrementing).
+    /// Returns the index to use (before incrementing). This is
+    /// [`WalletStorage::reserve_key_index`], so concurrent callers never get the same index.
     fn increment_key_index(&self) -> StorageFuture<'_, u32> {
-        Box::pin(async move {
-            let current = self.get_key_index().await?;
-            let next = current + 1;
-            self.set_key_index(next).await?;
-            Ok(current)
-        })
+        self.reserve_key_index()
     }
 }
 
@@ -140,7 +395,127 @@ pub trait SwapStorage {
     fn list(&self) -> StorageFuture<'_, Vec<String>>;
 
     /// Get all stored swaps.
+    ///
+    /// Records that can't be read should be skipped and returned by
+    /// [`SwapStorage::get_quarantined`] instead, see [`schema::read_swap_records`].
     fn get_all(&self) -> StorageFuture<'_, Vec<ExtendedSwapStorageData>>;
+
+    /// Get the stored swap and VTXO swap records that can't be read and are skipped by
+    /// [`SwapStorage::get_all`] and [`SwapStorage::get_all_vtxo_swaps`].
+    ///
+    /// The default implementation returns no records.
+    fn get_quarantined(&self) -> StorageFuture<'_, Vec<QuarantinedRecord>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    /// List the IDs of the swap records stored in an older format, which are rewritten when the
+    /// client is initialized, see [`schema::outdated_swap_records`].
+    ///
+    /// The default implementation can't tell the stored format and lists all swaps.
+    fn list_outdated(&self) -> StorageFuture<'_, Vec<String>> {
+        self.list()
+    }
+
+    /// Append an event to the history of a swap.
+    ///
+    /// Histories are append-only: events are never changed or removed, not even when the swap is
+    /// deleted. The default implementation discards events, so storages must opt in to keeping
+    /// histories.
+    fn append_swap_event(&self, _event: &SwapEvent) -> StorageFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Get the history of a swap, oldest event first.
+    ///
+    /// The default implementation returns no events.
+    fn get_swap_events(&self, _swap_id: &str) -> StorageFuture<'_, Vec<SwapEvent>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    /// Get the histories of all swaps, including deleted ones, each oldest event first.
+    ///
+    /// The default implementation returns no events.
+    fn get_all_swap_events(&self) -> StorageFuture<'_, Vec<SwapEvent>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    /// Record that a swap is about to be created with the API, see [`crate::journal`].
+    ///
+    /// Overwrites any entry with the same user ID. The default implementation discards the entry,
+    /// so storages must opt in to journaling swap creations.
+    fn store_pending_creation(&self, _pending: &PendingCreation) -> StorageFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Delete the pending creation with the given user ID. Deleting a missing entry is not an
+    /// error.
+    fn delete_pending_creation(&self, _user_id: &str) -> StorageFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Get all pending creations.
+    ///
+    /// The default implementation returns no entries.
+    fn get_pending_creations(&self) -> StorageFuture<'_, Vec<PendingCreation>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    /// Get the stored swaps matching a query, in its order and one page at a time.
+    ///
+    /// The default implementation loads all swaps with [`SwapStorage::get_all`] and applies the
+    /// query with [`SwapQuery::apply`]. Backends that can filter and sort by themselves should
+    /// override it, returning the same results.
+    fn query(&self, query: &SwapQuery) -> StorageFuture<'_, SwapPage> {
+        let query = query.clone();
+        Box::pin(async move { query.apply(self.get_all().await?) })
+    }
+
+    /// Whether the storage stores VTXO swaps.
+    ///
+    /// The client doesn't create VTXO swaps otherwise, as it couldn't store them. The default
+    /// implementation returns `false`; storages that implement the VTXO swap methods return
+    /// `true`.
+    fn supports_vtxo_swaps(&self) -> bool {
+        false
+    }
+
+    /// Get VTXO swap data by swap ID.
+    ///
+    /// Returns `Ok(None)` if the swap doesn't exist. The default implementation always returns
+    /// `Ok(None)`.
+    fn get_vtxo_swap(
+        &self,
+        _swap_id: &str,
+    ) -> StorageFuture<'_, Option<ExtendedVtxoSwapStorageData>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Store VTXO swap data.
+    ///
+    /// Overwrites any existing VTXO swap with the same ID. The default implementation rejects
+    /// VTXO swaps, so storages must opt in to storing them, see [`Self::supports_vtxo_swaps`].
+    fn store_vtxo_swap(
+        &self,
+        _swap_id: &str,
+        _data: &ExtendedVtxoSwapStorageData,
+    ) -> StorageFuture<'_, ()> {
+        Box::pin(async { Err(vtxo_swaps_unsupported()) })
+    }
+
+    /// Delete VTXO swap data by swap ID.
+    ///
+    /// Does nothing if the swap doesn't exist.
+    fn delete_vtxo_swap(&self, _swap_id: &str) -> StorageFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Get all stored VTXO swaps.
+    ///
+    /// Records that can't be read should be skipped and returned by
+    /// [`SwapStorage::get_quarantined`] instead, see [`schema::read_vtxo_swap_records`].
+    fn get_all_vtxo_swaps(&self) -> StorageFuture<'_, Vec<ExtendedVtxoSwapStorageData>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -164,67 +539,342 @@ pub trait SwapStorage: Send + Sync {
     fn list(&self) -> StorageFuture<'_, Vec<String>>;
 
     /// Get all stored swaps.
+    ///
+    /// Records that can't be read should be skipped and returned by
+    /// [`SwapStorage::get_quarantined`] instead, see [`schema::read_swap_records`].
     fn get_all(&self) -> StorageFuture<'_, Vec<ExtendedSwapStorageData>>;
+
+    /// Get the stored swap and VTXO swap records that can't be read and are skipped by
+    /// [`SwapStorage::get_all`] and [`SwapStorage::get_all_vtxo_swaps`].
+    ///
+    /// The default implementation returns no records.
+    fn get_quarantined(&self) -> StorageFuture<'_, Vec<QuarantinedRecord>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    /// List the IDs of the swap records stored in an older format, which are rewritten when the
+    /// client is initialized, see [`schema::outdated_swap_records`].
+    ///
+    /// The default implementation can't tell the stored format and lists all swaps.
+    fn list_outdated(&self) -> StorageFuture<'_, Vec<String>> {
+        self.list()
+    }
+
+    /// Append an event to the history of a swap.
+    ///
+    /// Histories are append-only: events are never changed or removed, not even when the swap is
+    /// deleted. The default implementation discards events, so storages must opt in to keeping
+    /// histories.
+    fn append_swap_event(&self, _event: &SwapEvent) -> StorageFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Get the history of a swap, oldest event first.
+    ///
+    /// The default implementation returns no events.
+    fn get_swap_events(&self, _swap_id: &str) -> StorageFuture<'_, Vec<SwapEvent>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    /// Get the histories of all swaps, including deleted ones, each oldest event first.
+    ///
+    /// The default implementation returns no events.
+    fn get_all_swap_events(&self) -> StorageFuture<'_, Vec<SwapEvent>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    /// Record that a swap is about to be created with the API, see [`crate::journal`].
+    ///
+    /// Overwrites any entry with the same user ID. The default implementation discards the entry,
+    /// so storages must opt in to journaling swap creations.
+    fn store_pending_creation(&self, _pending: &PendingCreation) -> StorageFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Delete the pending creation with the given user ID. Deleting a missing entry is not an
+    /// error.
+    fn delete_pending_creation(&self, _user_id: &str) -> StorageFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Get all pending creations.
+    ///
+    /// The default implementation returns no entries.
+    fn get_pending_creations(&self) -> StorageFuture<'_, Vec<PendingCreation>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    /// Get the stored swaps matching a query, in its order and one page at a time.
+    ///
+    /// The default implementation loads all swaps with [`SwapStorage::get_all`] and applies the
+    /// query with [`SwapQuery::apply`]. Backends that can filter and sort by themselves should
+    /// override it, returning the same results.
+    fn query(&self, query: &SwapQuery) -> StorageFuture<'_, SwapPage> {
+        let query = query.clone();
+        Box::pin(async move { query.apply(self.get_all().await?) })
+    }
+
+    /// Whether the storage stores VTXO swaps.
+    ///
+    /// The client doesn't create VTXO swaps otherwise, as it couldn't store them. The default
+    /// implementation returns `false`; storages that implement the VTXO swap methods return
+    /// `true`.
+    fn supports_vtxo_swaps(&self) -> bool {
+        false
+    }
+
+    /// Get VTXO swap data by swap ID.
+    ///
+    /// Returns `Ok(None)` if the swap doesn't exist. The default implementation always returns
+    /// `Ok(None)`.
+    fn get_vtxo_swap(
+        &self,
+        _swap_id: &str,
+    ) -> StorageFuture<'_, Option<ExtendedVtxoSwapStorageData>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Store VTXO swap data.
+    ///
+    /// Overwrites any existing VTXO swap with the same ID. The default implementation rejects
+    /// VTXO swaps, so storages must opt in to storing them, see [`Self::supports_vtxo_swaps`].
+    fn store_vtxo_swap(
+        &self,
+        _swap_id: &str,
+        _data: &ExtendedVtxoSwapStorageData,
+    ) -> StorageFuture<'_, ()> {
+        Box::pin(async { Err(vtxo_swaps_unsupported()) })
+    }
+
+    /// Delete VTXO swap data by swap ID.
+    ///
+    /// Does nothing if the swap doesn't exist.
+    fn delete_vtxo_swap(&self, _swap_id: &str) -> StorageFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Get all stored VTXO swaps.
+    ///
+    /// Records that can't be read should be skipped and returned by
+    /// [`SwapStorage::get_quarantined`] instead, see [`schema::read_vtxo_swap_records`].
+    fn get_all_vtxo_swaps(&self) -> StorageFuture<'_, Vec<ExtendedVtxoSwapStorageData>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
 }
 
-/// In-memory wallet storage implementation for testing.
-#[cfg(test)]
-pub mod memory {
-    use super::*;
-    use std::sync::RwLock;
+/// Wallet storage of a watch-only client, see [`crate::Client::watch_only`].
+///
+/// It never holds a mnemonic: writes fail with [`Error::WatchOnly`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WatchOnlyWalletStorage;
 
-    /// Simple in-memory wallet storage for testing purposes.
-    pub struct MemoryWalletStorage {
-        mnemonic: RwLock<Option<String>>,
-        key_index: RwLock<u32>,
+impl WalletStorage for WatchOnlyWalletStorage {
+    fn get_mnemonic(&self) -> StorageFuture<'_, Option<String>> {
+        Box::pin(async { Ok(None) })
     }
 
-    impl MemoryWalletStorage {
-        /// Create a new empty memory wallet storage.
-        pub fn new() -> Self {
-            Self {
-                mnemonic: RwLock::new(None),
-                key_index: RwLock::new(0),
-            }
-        }
+    fn set_mnemonic(&self, _mnemonic: &str) -> StorageFuture<'_, ()> {
+        Box::pin(async { Err(Error::WatchOnly) })
     }
 
-    impl Default for MemoryWalletStorage {
-        fn default() -> Self {
-            Self::new()
-        }
+    fn get_key_index(&self) -> StorageFuture<'_, u32> {
+        Box::pin(async { Ok(0) })
+    }
+
+    fn set_key_index(&self, _index: u32) -> StorageFuture<'_, ()> {
+        Box::pin(async { Err(Error::WatchOnly) })
+    }
+
+    fn get_account_key_index(&self, _account: u32) -> StorageFuture<'_, u32> {
+        Box::pin(async { Ok(0) })
+    }
+
+    fn set_account_key_index(&self, _account: u32, _index: u32) -> StorageFuture<'_, ()> {
+        Box::pin(async { Err(Error::WatchOnly) })
+    }
+}
+
+/// Forwards every method, so that storages chosen at runtime can be used as
+/// `Box<dyn WalletStorage>`.
+impl<T: WalletStorage + ?Sized> WalletStorage for Box<T> {
+    fn get_mnemonic(&self) -> StorageFuture<'_, Option<String>> {
+        (**self).get_mnemonic()
+    }
+
+    fn set_mnemonic(&self, mnemonic: &str) -> StorageFuture<'_, ()> {
+        (**self).set_mnemonic(mnemonic)
+    }
+
+    fn get_key_index(&self) -> StorageFuture<'_, u32> {
+        (**self).get_key_index()
+    }
+
+    fn set_key_index(&self, index: u32) -> StorageFuture<'_, ()> {
+        (**self).set_key_index(index)
+    }
+
+    fn reserve_key_index(&self) -> StorageFuture<'_, u32> {
+        (**self).reserve_key_index()
+    }
+
+    fn get_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        (**self).get_account_key_index(account)
+    }
+
+    fn set_account_key_index(&self, account: u32, index: u32) -> StorageFuture<'_, ()> {
+        (**self).set_account_key_index(account, index)
     }
 
-    impl WalletStorage for MemoryWalletStorage {
+    fn reserve_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        (**self).reserve_account_key_index(account)
+    }
+
+    fn get_passphrase_fingerprint(&self) -> StorageFuture<'_, Option<String>> {
+        (**self).get_passphrase_fingerprint()
+    }
+
+    fn set_passphrase_fingerprint(&self, fingerprint: Option<&str>) -> StorageFuture<'_, ()> {
+        (**self).set_passphrase_fingerprint(fingerprint)
+    }
+
+    fn get_derivation_scheme(&self) -> StorageFuture<'_, Option<DerivationScheme>> {
+        (**self).get_derivation_scheme()
+    }
+
+    fn set_derivation_scheme(&self, scheme: DerivationScheme) -> StorageFuture<'_, ()> {
+        (**self).set_derivation_scheme(scheme)
+    }
+}
+
+/// Forwards every method, so that storages chosen at runtime can be used as
+/// `Box<dyn SwapStorage>`.
+impl<T: SwapStorage + ?Sized> SwapStorage for Box<T> {
+    fn get(&self, swap_id: &str) -> StorageFuture<'_, Option<ExtendedSwapStorageData>> {
+        (**self).get(swap_id)
+    }
+
+    fn store(&self, swap_id: &str, data: &ExtendedSwapStorageData) -> StorageFuture<'_, ()> {
+        (**self).store(swap_id, data)
+    }
+
+    fn delete(&self, swap_id: &str) -> StorageFuture<'_, ()> {
+        (**self).delete(swap_id)
+    }
+
+    fn list(&self) -> StorageFuture<'_, Vec<String>> {
+        (**self).list()
+    }
+
+    fn get_all(&self) -> StorageFuture<'_, Vec<ExtendedSwapStorageData>> {
+        (**self).get_all()
+    }
+
+    fn get_quarantined(&self) -> StorageFuture<'_, Vec<QuarantinedRecord>> {
+        (**self).get_quarantined()
+    }
+
+    fn list_outdated(&self) -> StorageFuture<'_, Vec<String>> {
+        (**self).list_outdated()
+    }
+
+    fn append_swap_event(&self, event: &SwapEvent) -> StorageFuture<'_, ()> {
+        (**self).append_swap_event(event)
+    }
+
+    fn get_swap_events(&self, swap_id: &str) -> StorageFuture<'_, Vec<SwapEvent>> {
+        (**self).get_swap_events(swap_id)
+    }
+
+    fn get_all_swap_events(&self) -> StorageFuture<'_, Vec<SwapEvent>> {
+        (**self).get_all_swap_events()
+    }
+
+    fn store_pending_creation(&self, pending: &PendingCreation) -> StorageFuture<'_, ()> {
+        (**self).store_pending_creation(pending)
+    }
+
+    fn delete_pending_creation(&self, user_id: &str) -> StorageFuture<'_, ()> {
+        (**self).delete_pending_creation(user_id)
+    }
+
+    fn get_pending_creations(&self) -> StorageFuture<'_, Vec<PendingCreation>> {
+        (**self).get_pending_creations()
+    }
+
+    fn query(&self, query: &SwapQuery) -> StorageFuture<'_, SwapPage> {
+        (**self).query(query)
+    }
+
+    fn supports_vtxo_swaps(&self) -> bool {
+        (**self).supports_vtxo_swaps()
+    }
+
+    fn get_vtxo_swap(
+        &self,
+        swap_id: &str,
+    ) -> StorageFuture<'_, Option<ExtendedVtxoSwapStorageData>> {
+        (**self).get_vtxo_swap(swap_id)
+    }
+
+    fn store_vtxo_swap(
+        &self,
+        swap_id: &str,
+        data: &ExtendedVtxoSwapStorageData,
+    ) -> StorageFuture<'_, ()> {
+        (**self).store_vtxo_swap(swap_id, data)
+    }
+
+    fn delete_vtxo_swap(&self, swap_id: &str) -> StorageFuture<'_, ()> {
+        (**self).delete_vtxo_swap(swap_id)
+    }
+
+    fn get_all_vtxo_swaps(&self) -> StorageFuture<'_, Vec<ExtendedVtxoSwapStorageData>> {
+        (**self).get_all_vtxo_swaps()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::memory::MemoryWalletStorage;
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Storage that yields between reading and writing the key index, and doesn't override
+    /// [`WalletStorage::reserve_key_index`].
+    struct YieldingWalletStorage(MemoryWalletStorage);
+
+    impl WalletStorage for YieldingWalletStorage {
         fn get_mnemonic(&self) -> StorageFuture<'_, Option<String>> {
-            Box::pin(async move {
-                let mnemonic = self.mnemonic.read().unwrap();
-                Ok(mnemonic.clone())
-            })
+            self.0.get_mnemonic()
         }
 
         fn set_mnemonic(&self, mnemonic: &str) -> StorageFuture<'_, ()> {
-            let mnemonic = mnemonic.to_string();
-            Box::pin(async move {
-                let mut stored = self.mnemonic.write().unwrap();
-                *stored = Some(mnemonic);
-                Ok(())
-            })
+            self.0.set_mnemonic(mnemonic)
         }
 
         fn get_key_index(&self) -> StorageFuture<'_, u32> {
             Box::pin(async move {
-                let index = self.key_index.read().unwrap();
-                Ok(*index)
+                let index = self.0.get_key_index().await?;
+                tokio::task::yield_now().await;
+                Ok(index)
             })
         }
 
         fn set_key_index(&self, index: u32) -> StorageFuture<'_, ()> {
-            Box::pin(async move {
-                let mut stored = self.key_index.write().unwrap();
-                *stored = index;
-                Ok(())
-            })
+            self.0.set_key_index(index)
         }
     }
+
+    #[tokio::test]
+    async fn test_default_reserve_key_index_is_atomic() {
+        let storage = YieldingWalletStorage(MemoryWalletStorage::new());
+
+        let indices = futures::future::try_join_all((0..50).map(|_| storage.increment_key_index()))
+            .await
+            .unwrap();
+
+        let unique: HashSet<_> = indices.iter().collect();
+        assert_eq!(unique.len(), 50);
+        assert_eq!(storage.get_key_index().await.unwrap(), 50);
+    }
 }
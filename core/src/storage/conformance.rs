@@ -0,0 +1,457 @@
+//! Conformance tests for storage implementations.
+//!
+//! The suites check the contracts documented on [`WalletStorage`] and [`SwapStorage`], so that
+//! every backend behaves the same towards the [`crate::Client`]. Run them against a fresh, empty
+//! storage from a test of the implementing crate; they panic on the first violation.
+//!
+//! ```rust,ignore
+//! #[tokio::test]
+//! async fn my_storage_conforms() {
+//!     conformance::run_wallet_storage_suite(MyStorage::new()).await;
+//!     conformance::run_swap_storage_suite(MyStorage::new()).await;
+//! }
+//! ```
+
+use super::{SortOrder, SwapPage, SwapQuery, SwapStorage, WalletStorage};
+use crate::api::{
+    Chain, GetSwapResponse, SwapDirection, SwapStatus, TokenId, VtxoSwapResponse, VtxoSwapStatus,
+};
+use crate::client::{ExtendedSwapStorageData, ExtendedVtxoSwapStorageData};
+use crate::history::{SwapEvent, SwapEventKind};
+use crate::journal::PendingCreation;
+use crate::types::{DerivationScheme, PublicSwapParams};
+use bitcoin::secp256k1::{PublicKey, Secp256k1, SecretKey};
+use std::collections::HashSet;
+
+/// Number of concurrent reservations made to check that key indices are never handed out twice.
+const CONCURRENT_RESERVATIONS: u32 = 16;
+
+/// Check a [`WalletStorage`] implementation. `storage` must be empty.
+///
+/// Optional features (accounts, passphrase fingerprints, derivation schemes) are only checked if
+/// the storage supports them, i.e. doesn't reject them with an error.
+pub async fn run_wallet_storage_suite<S: WalletStorage>(storage: S) {
+    // Mnemonic
+    assert_eq!(
+        storage.get_mnemonic().await.unwrap(),
+        None,
+        "a new storage has no mnemonic"
+    );
+    storage.set_mnemonic("first").await.unwrap();
+    storage.set_mnemonic("second").await.unwrap();
+    assert_eq!(
+        storage.get_mnemonic().await.unwrap().as_deref(),
+        Some("second"),
+        "set_mnemonic overwrites"
+    );
+
+    // Key index
+    assert_eq!(
+        storage.get_key_index().await.unwrap(),
+        0,
+        "the key index starts at 0"
+    );
+    storage.set_key_index(5).await.unwrap();
+    assert_eq!(storage.get_key_index().await.unwrap(), 5);
+    assert_eq!(
+        storage.reserve_key_index().await.unwrap(),
+        5,
+        "reserve_key_index returns the current index"
+    );
+    assert_eq!(
+        storage.get_key_index().await.unwrap(),
+        6,
+        "reserve_key_index advances the index"
+    );
+
+    let reserved = futures::future::join_all(
+        (0..CONCURRENT_RESERVATIONS).map(|_| storage.reserve_key_index()),
+    )
+    .await
+    .into_iter()
+    .collect::<crate::Result<HashSet<u32>>>()
+    .unwrap();
+    assert_eq!(
+        reserved,
+        (6..6 + CONCURRENT_RESERVATIONS).collect(),
+        "concurrent reservations return distinct indices"
+    );
+
+    // Account 0 is the default key index
+    assert_eq!(
+        storage.get_account_key_index(0).await.unwrap(),
+        6 + CONCURRENT_RESERVATIONS
+    );
+
+    if storage.get_account_key_index(1).await.is_ok() {
+        assert_eq!(
+            storage.get_account_key_index(1).await.unwrap(),
+            0,
+            "account key indices start at 0"
+        );
+        storage.set_account_key_index(1, 3).await.unwrap();
+        assert_eq!(storage.reserve_account_key_index(1).await.unwrap(), 3);
+        assert_eq!(storage.get_account_key_index(1).await.unwrap(), 4);
+        assert_eq!(
+            storage.get_key_index().await.unwrap(),
+            6 + CONCURRENT_RESERVATIONS,
+            "accounts have separate key indices"
+        );
+    }
+
+    // Passphrase fingerprint
+    if storage
+        .set_passphrase_fingerprint(Some("deadbeef"))
+        .await
+        .is_ok()
+    {
+        assert_eq!(
+            storage
+                .get_passphrase_fingerprint()
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("deadbeef")
+        );
+    }
+    storage.set_passphrase_fingerprint(None).await.unwrap();
+    assert_eq!(
+        storage.get_passphrase_fingerprint().await.unwrap(),
+        None,
+        "set_passphrase_fingerprint(None) clears the fingerprint"
+    );
+
+    // Derivation scheme
+    storage
+        .set_derivation_scheme(DerivationScheme::V1)
+        .await
+        .unwrap();
+    assert!(matches!(
+        storage.get_derivation_scheme().await.unwrap(),
+        None | Some(DerivationScheme::V1)
+    ));
+}
+
+/// Check a [`SwapStorage`] implementation. `storage` must be empty.
+///
+/// Swap histories, pending creations and VTXO swaps are only checked if the storage supports
+/// them.
+pub async fn run_swap_storage_suite<S: SwapStorage>(storage: S) {
+    const SWAP_A: &str = "00000000-0000-0000-0000-00000000000a";
+    const SWAP_B: &str = "00000000-0000-0000-0000-00000000000b";
+
+    assert!(storage.list().await.unwrap().is_empty());
+    assert!(
+        storage.get(SWAP_A).await.unwrap().is_none(),
+        "get returns None for a missing swap"
+    );
+
+    storage
+        .store(SWAP_A, &sample_swap(SWAP_A, SwapStatus::Pending))
+        .await
+        .unwrap();
+    storage
+        .store(SWAP_B, &sample_swap(SWAP_B, SwapStatus::Pending))
+        .await
+        .unwrap();
+    storage
+        .store(SWAP_A, &sample_swap(SWAP_A, SwapStatus::ServerFunded))
+        .await
+        .unwrap();
+
+    let stored = storage.get(SWAP_A).await.unwrap().expect("stored swap");
+    assert_eq!(stored.response.id(), SWAP_A);
+    assert_eq!(
+        stored.response.status(),
+        SwapStatus::ServerFunded,
+        "store overwrites"
+    );
+    assert_eq!(
+        stored.swap_params,
+        sample_swap(SWAP_A, SwapStatus::Pending).swap_params
+    );
+
+    assert_list_matches_get_all(&storage, &[SWAP_A, SWAP_B]).await;
+    assert_query_matches(&storage, SWAP_A, SWAP_B).await;
+
+    storage.delete(SWAP_A).await.unwrap();
+    assert!(storage.get(SWAP_A).await.unwrap().is_none());
+    storage.delete(SWAP_A).await.unwrap();
+    assert_list_matches_get_all(&storage, &[SWAP_B]).await;
+
+    storage.delete(SWAP_B).await.unwrap();
+    assert_list_matches_get_all(&storage, &[]).await;
+
+    assert_swap_events(&storage, SWAP_A, SWAP_B).await;
+    assert_pending_creations(&storage).await;
+
+    // VTXO swaps
+    assert!(storage.get_vtxo_swap(SWAP_A).await.unwrap().is_none());
+    let vtxo_swap = sample_vtxo_swap(SWAP_A, VtxoSwapStatus::Pending);
+    if storage.store_vtxo_swap(SWAP_A, &vtxo_swap).await.is_err() {
+        assert!(storage.get_all_vtxo_swaps().await.unwrap().is_empty());
+        return;
+    }
+    storage
+        .store_vtxo_swap(
+            SWAP_A,
+            &sample_vtxo_swap(SWAP_A, VtxoSwapStatus::ServerFunded),
+        )
+        .await
+        .unwrap();
+
+    let stored = storage
+        .get_vtxo_swap(SWAP_A)
+        .await
+        .unwrap()
+        .expect("stored VTXO swap");
+    assert_eq!(
+        stored.response.status,
+        VtxoSwapStatus::ServerFunded,
+        "store_vtxo_swap overwrites"
+    );
+    assert_eq!(stored.swap_params, vtxo_swap.swap_params);
+    assert_eq!(storage.get_all_vtxo_swaps().await.unwrap().len(), 1);
+    assert_list_matches_get_all(&storage, &[]).await;
+
+    storage.delete_vtxo_swap(SWAP_A).await.unwrap();
+    assert!(storage.get_vtxo_swap(SWAP_A).await.unwrap().is_none());
+    storage.delete_vtxo_swap(SWAP_A).await.unwrap();
+    assert!(storage.get_all_vtxo_swaps().await.unwrap().is_empty());
+}
+
+async fn assert_list_matches_get_all<S: SwapStorage>(storage: &S, expected: &[&str]) {
+    let expected: HashSet<String> = expected.iter().map(|id| id.to_string()).collect();
+
+    let listed = storage.list().await.unwrap();
+    assert_eq!(listed.len(), expected.len(), "list returns each swap once");
+    assert_eq!(listed.into_iter().collect::<HashSet<_>>(), expected);
+
+    let all: HashSet<String> = storage
+        .get_all()
+        .await
+        .unwrap()
+        .iter()
+        .map(|data| data.response.id())
+        .collect();
+    assert_eq!(all, expected, "get_all returns the listed swaps");
+}
+
+/// Check [`SwapStorage::query`] on the swaps `a` (server funded) and `b` (pending), which were
+/// created at the same time and `a < b`.
+async fn assert_query_matches<S: SwapStorage>(storage: &S, a: &str, b: &str) {
+    let ids = |page: &SwapPage| -> Vec<String> {
+        page.swaps.iter().map(|data| data.response.id()).collect()
+    };
+
+    let page = storage.query(&SwapQuery::default()).await.unwrap();
+    assert_eq!(ids(&page), [b, a], "queries return the newest swaps first");
+    assert_eq!(page.next_cursor, None);
+
+    let query = SwapQuery {
+        statuses: vec![SwapStatus::Pending],
+        ..SwapQuery::default()
+    };
+    assert_eq!(ids(&storage.query(&query).await.unwrap()), [b]);
+
+    let query = SwapQuery {
+        order: SortOrder::Ascending,
+        limit: Some(1),
+        ..SwapQuery::default()
+    };
+    let page = storage.query(&query).await.unwrap();
+    assert_eq!(ids(&page), [a]);
+    assert!(page.next_cursor.is_some(), "a full page has a next cursor");
+    let query = SwapQuery {
+        cursor: page.next_cursor,
+        ..query
+    };
+    let page = storage.query(&query).await.unwrap();
+    assert_eq!(ids(&page), [b], "the next page starts after the cursor");
+    assert_eq!(page.next_cursor, None, "the last page has no next cursor");
+    let query = SwapQuery {
+        limit: Some(0),
+        ..SwapQuery::default()
+    };
+    assert!(
+        storage.query(&query).await.is_err(),
+        "a limit of 0 is rejected"
+    );
+
+    let query = SwapQuery {
+        token: Some(TokenId::Coin("usdc_pol".to_string())),
+        chain: Some(Chain::Polygon),
+        direction: Some(SwapDirection::BtcToEvm),
+        account: Some(0),
+        ..SwapQuery::default()
+    };
+    assert_eq!(storage.query(&query).await.unwrap().swaps.len(), 2);
+
+    for query in [
+        SwapQuery {
+            chain: Some(Chain::Ethereum),
+            ..SwapQuery::default()
+        },
+        SwapQuery {
+            account: Some(1),
+            ..SwapQuery::default()
+        },
+        SwapQuery {
+            direction: Some(SwapDirection::EvmToBtc),
+            ..SwapQuery::default()
+        },
+    ] {
+        assert!(
+            storage.query(&query).await.unwrap().swaps.is_empty(),
+            "{:?} matches no swap",
+            query
+        );
+    }
+}
+
+/// Check the history of the deleted swap `a`, if the storage keeps histories. Swap `b` has no
+/// events.
+async fn assert_swap_events<S: SwapStorage>(storage: &S, a: &str, b: &str) {
+    let created = SwapEvent::now(a, SwapEventKind::created(&SwapStatus::Pending));
+    let changed =
+        SwapEventKind::status_changed(&SwapStatus::Pending, &SwapStatus::ServerFunded).unwrap();
+    let changed = SwapEvent::now(a, changed);
+    storage.append_swap_event(&created).await.unwrap();
+    storage.append_swap_event(&changed).await.unwrap();
+
+    let events = storage.get_swap_events(a).await.unwrap();
+    if events.is_empty() {
+        return;
+    }
+    assert_eq!(
+        events,
+        [created, changed],
+        "events are kept in order, after the swap is deleted"
+    );
+    assert!(storage.get_swap_events(b).await.unwrap().is_empty());
+    assert_eq!(
+        storage.get_all_swap_events().await.unwrap(),
+        events,
+        "histories of deleted swaps are listed"
+    );
+}
+
+/// Check storing and deleting pending creations, if the storage journals them.
+async fn assert_pending_creations<S: SwapStorage>(storage: &S) {
+    let first = PendingCreation::now(sample_swap_params());
+    let mut second = PendingCreation::now(sample_swap_params_at(1));
+    storage.store_pending_creation(&first).await.unwrap();
+    storage.store_pending_creation(&second).await.unwrap();
+    second.started_at = first.started_at;
+    storage.store_pending_creation(&second).await.unwrap();
+
+    let mut pending = storage.get_pending_creations().await.unwrap();
+    if pending.is_empty() {
+        return;
+    }
+    pending.sort_by_key(|pending| pending.swap_params.key_index);
+    assert_eq!(
+        pending,
+        [first.clone(), second],
+        "store_pending_creation overwrites"
+    );
+
+    storage
+        .delete_pending_creation(&first.user_id())
+        .await
+        .unwrap();
+    storage
+        .delete_pending_creation(&first.user_id())
+        .await
+        .unwrap();
+    assert_eq!(storage.get_pending_creations().await.unwrap().len(), 1);
+}
+
+/// A swap with the given ID and status, for storage tests.
+pub fn sample_swap(id: &str, status: SwapStatus) -> ExtendedSwapStorageData {
+    let response: GetSwapResponse = serde_json::from_value(serde_json::json!({
+        "direction": "btc_to_evm",
+        "id": id,
+        "status": status,
+        "hash_lock": format!("0x{}", "00".repeat(32)),
+        "fee_sats": 100,
+        "asset_amount": 10.0,
+        "sender_pk": "",
+        "receiver_pk": "",
+        "server_pk": "",
+        "refund_locktime": 0,
+        "unilateral_claim_delay": 0,
+        "unilateral_refund_delay": 0,
+        "unilateral_refund_without_receiver_delay": 0,
+        "network": "signet",
+        "created_at": "2025-01-01T00:00:00Z",
+        "htlc_address_evm": "",
+        "htlc_address_arkade": "",
+        "user_address_evm": "",
+        "ln_invoice": "",
+        "sats_receive": 1000,
+        "source_token": "btc_arkade",
+        "target_token": "usdc_pol",
+        "bitcoin_htlc_claim_txid": null,
+        "bitcoin_htlc_fund_txid": null,
+        "evm_htlc_claim_txid": null,
+        "evm_htlc_fund_txid": null,
+    }))
+    .expect("valid swap");
+
+    ExtendedSwapStorageData {
+        response,
+        swap_params: sample_swap_params(),
+    }
+}
+
+/// A VTXO swap with the given ID and status, for storage tests.
+pub fn sample_vtxo_swap(id: &str, status: VtxoSwapStatus) -> ExtendedVtxoSwapStorageData {
+    let response: VtxoSwapResponse = serde_json::from_value(serde_json::json!({
+        "id": id,
+        "status": status,
+        "created_at": "2025-01-01T00:00:00Z",
+        "client_vhtlc_address": "",
+        "client_fund_amount_sats": 1000,
+        "client_pk": "",
+        "client_locktime": 0,
+        "client_unilateral_claim_delay": 0,
+        "client_unilateral_refund_delay": 0,
+        "client_unilateral_refund_without_receiver_delay": 0,
+        "server_vhtlc_address": "",
+        "server_fund_amount_sats": 900,
+        "server_pk": "",
+        "server_locktime": 0,
+        "server_unilateral_claim_delay": 0,
+        "server_unilateral_refund_delay": 0,
+        "server_unilateral_refund_without_receiver_delay": 0,
+        "arkade_server_pk": "",
+        "preimage_hash": "00".repeat(32),
+        "fee_sats": 100,
+        "network": "signet",
+    }))
+    .expect("valid VTXO swap");
+
+    ExtendedVtxoSwapStorageData {
+        response,
+        swap_params: sample_swap_params(),
+    }
+}
+
+fn sample_swap_params() -> PublicSwapParams {
+    sample_swap_params_at(0)
+}
+
+/// Swap parameters with a key of their own at `key_index`.
+fn sample_swap_params_at(key_index: u32) -> PublicSwapParams {
+    let secret_key = SecretKey::from_slice(&[key_index as u8 + 1; 32]).expect("valid key");
+    let public_key = PublicKey::from_secret_key(&Secp256k1::new(), &secret_key);
+    PublicSwapParams {
+        public_key,
+        preimage_hash: [0; 32],
+        user_id: public_key,
+        key_index,
+        account: 0,
+        scheme: DerivationScheme::V1,
+    }
+}
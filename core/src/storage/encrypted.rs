@@ -0,0 +1,333 @@
+//! Encrypted-at-rest wallet storage.
+//!
+//! [`EncryptedWalletStorage`] wraps any [`WalletStorage`] and encrypts the mnemonic with a
+//! password-derived key before it reaches the underlying backend. Everything else (key index,
+//! passphrase fingerprint, derivation scheme) is passed through unchanged.
+
+use super::{StorageFuture, WalletStorage};
+use crate::crypto::{DerivedKey, EncryptedEnvelope, KdfParams};
+use crate::error::{Error, Result};
+use crate::types::DerivationScheme;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+/// Wallet storage that encrypts the mnemonic with a password.
+///
+/// The mnemonic is encrypted with XChaCha20-Poly1305 under a key derived from the password with
+/// Argon2id. The storage starts out locked: call [`EncryptedWalletStorage::unlock`] before using
+/// it, otherwise reading or writing the mnemonic fails with [`Error::WalletLocked`].
+///
+/// Unlocking a storage that still holds a plaintext mnemonic (written before encryption was
+/// enabled) encrypts it in place.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use lendaswap_core::storage::EncryptedWalletStorage;
+/// use lendaswap_core::{Network, Wallet};
+///
+/// let storage = EncryptedWalletStorage::new(my_storage);
+/// storage.unlock("my password").await?;
+///
+/// let wallet = Wallet::new(storage, Network::Bitcoin);
+/// let mnemonic = wallet.generate_or_get_mnemonic().await?;
+/// ```
+pub struct EncryptedWalletStorage<S: WalletStorage> {
+    inner: S,
+    kdf_params: KdfParams,
+    key: RwLock<Option<DerivedKey>>,
+}
+
+impl<S: WalletStorage> EncryptedWalletStorage<S> {
+    /// Wrap the given storage using the default KDF parameters.
+    pub fn new(inner: S) -> Self {
+        Self::with_kdf_params(inner, KdfParams::default())
+    }
+
+    /// Wrap the given storage using custom KDF parameters for newly encrypted data.
+    ///
+    /// Existing data is always decrypted with the parameters it was encrypted with.
+    pub fn with_kdf_params(inner: S, kdf_params: KdfParams) -> Self {
+        Self {
+            inner,
+            kdf_params,
+            key: RwLock::new(None),
+        }
+    }
+
+    /// Get a reference to the wrapped storage.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Whether the storage is currently unlocked.
+    pub fn is_unlocked(&self) -> bool {
+        self.key.read().expect("key lock poisoned").is_some()
+    }
+
+    /// Unlock the storage with the given password.
+    ///
+    /// If no mnemonic is stored yet, the password will be used to encrypt the next one.
+    /// Returns [`Error::InvalidPassword`] if the password does not match the stored mnemonic and
+    /// [`Error::CorruptedCiphertext`] if the stored data cannot be decrypted.
+    pub async fn unlock(&self, password: &str) -> Result<()> {
+        let key = match self.inner.get_mnemonic().await? {
+            Some(stored) => match EncryptedEnvelope::parse(&stored) {
+                Ok(envelope) => {
+                    let key = DerivedKey::for_envelope(password, &envelope)?;
+                    // Decrypt once to surface corruption early.
+                    key.decrypt(&envelope)?;
+                    key
+                }
+                Err(e) => {
+                    if bip39::Mnemonic::from_str(&stored).is_err() {
+                        return Err(e);
+                    }
+
+                    // Plaintext mnemonic from before encryption was enabled: encrypt it in place.
+                    log::info!("Encrypting previously unencrypted mnemonic");
+                    let key = DerivedKey::derive(password, self.kdf_params)?;
+                    let envelope = key.encrypt(stored.as_bytes())?;
+                    self.inner.set_mnemonic(&envelope.encode()?).await?;
+                    key
+                }
+            },
+            None => DerivedKey::derive(password, self.kdf_params)?,
+        };
+
+        *self.key.write().expect("key lock poisoned") = Some(key);
+        Ok(())
+    }
+
+    /// Lock the storage, forgetting the derived key.
+    pub fn lock(&self) {
+        *self.key.write().expect("key lock poisoned") = None;
+    }
+
+    /// Re-encrypt the stored mnemonic under a new password.
+    ///
+    /// Returns [`Error::InvalidPassword`] if `current_password` is wrong. The storage is left
+    /// unlocked with the new password.
+    pub async fn change_password(&self, current_password: &str, new_password: &str) -> Result<()> {
+        let new_key = DerivedKey::derive(new_password, self.kdf_params)?;
+
+        if let Some(stored) = self.inner.get_mnemonic().await? {
+            let envelope = EncryptedEnvelope::parse(&stored)?;
+            let current_key = DerivedKey::for_envelope(current_password, &envelope)?;
+            let mnemonic = current_key.decrypt(&envelope)?;
+
+            let envelope = new_key.encrypt(&mnemonic)?;
+            self.inner.set_mnemonic(&envelope.encode()?).await?;
+        }
+
+        *self.key.write().expect("key lock poisoned") = Some(new_key);
+        Ok(())
+    }
+
+    fn current_key(&self) -> Result<DerivedKey> {
+        self.key
+            .read()
+            .expect("key lock poisoned")
+            .clone()
+            .ok_or(Error::WalletLocked)
+    }
+}
+
+impl<S: WalletStorage> WalletStorage for EncryptedWalletStorage<S> {
+    fn get_mnemonic(&self) -> StorageFuture<'_, Option<String>> {
+        Box::pin(async move {
+            let Some(stored) = self.inner.get_mnemonic().await? else {
+                return Ok(None);
+            };
+
+            let key = self.current_key()?;
+            let envelope = EncryptedEnvelope::parse(&stored)?;
+            if !key.matches(&envelope) {
+                // Re-encrypted elsewhere (e.g. password changed by another instance).
+                return Err(Error::WalletLocked);
+            }
+
+            let plaintext = key.decrypt(&envelope)?;
+            let mnemonic = String::from_utf8(plaintext.to_vec())
+                .map_err(|_| Error::CorruptedCiphertext("mnemonic is not UTF-8".to_string()))?;
+            Ok(Some(mnemonic))
+        })
+    }
+
+    fn set_mnemonic(&self, mnemonic: &str) -> StorageFuture<'_, ()> {
+        let encrypted = self
+            .current_key()
+            .and_then(|key| key.encrypt(mnemonic.as_bytes()))
+            .and_then(|envelope| envelope.encode());
+        Box::pin(async move { self.inner.set_mnemonic(&encrypted?).await })
+    }
+
+    fn get_key_index(&self) -> StorageFuture<'_, u32> {
+        self.inner.get_key_index()
+    }
+
+    fn set_key_index(&self, index: u32) -> StorageFuture<'_, ()> {
+        self.inner.set_key_index(index)
+    }
+
+    fn reserve_key_index(&self) -> StorageFuture<'_, u32> {
+        self.inner.reserve_key_index()
+    }
+
+    fn get_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        self.inner.get_account_key_index(account)
+    }
+
+    fn set_account_key_index(&self, account: u32, index: u32) -> StorageFuture<'_, ()> {
+        self.inner.set_account_key_index(account, index)
+    }
+
+    fn reserve_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        self.inner.reserve_account_key_index(account)
+    }
+
+    fn get_passphrase_fingerprint(&self) -> StorageFuture<'_, Option<String>> {
+        self.inner.get_passphrase_fingerprint()
+    }
+
+    fn set_passphrase_fingerprint(&self, fingerprint: Option<&str>) -> StorageFuture<'_, ()> {
+        self.inner.set_passphrase_fingerprint(fingerprint)
+    }
+
+    fn get_derivation_scheme(&self) -> StorageFuture<'_, Option<DerivationScheme>> {
+        self.inner.get_derivation_scheme()
+    }
+
+    fn set_derivation_scheme(&self, scheme: DerivationScheme) -> StorageFuture<'_, ()> {
+        self.inner.set_derivation_scheme(scheme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::memory::MemoryWalletStorage;
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    /// Cheap parameters so tests don't spend time in the KDF.
+    fn test_storage() -> EncryptedWalletStorage<MemoryWalletStorage> {
+        let params = KdfParams {
+            memory_kib: 64,
+            iterations: 1,
+            parallelism: 1,
+        };
+        EncryptedWalletStorage::with_kdf_params(MemoryWalletStorage::new(), params)
+    }
+
+    #[tokio::test]
+    async fn test_encrypts_mnemonic_at_rest() {
+        let storage = test_storage();
+
+        // Locked storage refuses to touch the mnemonic
+        assert!(matches!(
+            storage.set_mnemonic(PHRASE).await,
+            Err(Error::WalletLocked)
+        ));
+
+        storage.unlock("hunter2").await.unwrap();
+        storage.set_mnemonic(PHRASE).await.unwrap();
+
+        let raw = storage.inner().get_mnemonic().await.unwrap().unwrap();
+        assert!(!raw.contains("abandon"));
+        assert_eq!(storage.get_mnemonic().await.unwrap().unwrap(), PHRASE);
+
+        storage.lock();
+        assert!(matches!(
+            storage.get_mnemonic().await,
+            Err(Error::WalletLocked)
+        ));
+
+        assert!(matches!(
+            storage.unlock("hunter3").await,
+            Err(Error::InvalidPassword)
+        ));
+        storage.unlock("hunter2").await.unwrap();
+        assert_eq!(storage.get_mnemonic().await.unwrap().unwrap(), PHRASE);
+    }
+
+    #[tokio::test]
+    async fn test_detects_corrupted_ciphertext() {
+        let storage = test_storage();
+        storage.unlock("hunter2").await.unwrap();
+        storage.set_mnemonic(PHRASE).await.unwrap();
+
+        // Flip a byte in the ciphertext
+        let raw = storage.inner().get_mnemonic().await.unwrap().unwrap();
+        let mut envelope: serde_json::Value = serde_json::from_str(&raw).unwrap();
+        let ciphertext = envelope["ciphertext"].as_str().unwrap();
+        let flipped = if ciphertext.starts_with('0') {
+            "1"
+        } else {
+            "0"
+        };
+        envelope["ciphertext"] = format!("{}{}", flipped, &ciphertext[1..]).into();
+        storage
+            .inner()
+            .set_mnemonic(&envelope.to_string())
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            storage.unlock("hunter2").await,
+            Err(Error::CorruptedCiphertext(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_excessive_kdf_params() {
+        let storage = test_storage();
+        storage.unlock("hunter2").await.unwrap();
+        storage.set_mnemonic(PHRASE).await.unwrap();
+
+        // A tampered envelope asking for 4 TiB of memory is rejected before deriving the key
+        let raw = storage.inner().get_mnemonic().await.unwrap().unwrap();
+        let mut envelope: serde_json::Value = serde_json::from_str(&raw).unwrap();
+        envelope["kdf"]["memory_kib"] = u32::MAX.into();
+        storage
+            .inner()
+            .set_mnemonic(&envelope.to_string())
+            .await
+            .unwrap();
+
+        storage.lock();
+        assert!(matches!(
+            storage.unlock("hunter2").await,
+            Err(Error::CorruptedCiphertext(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_change_password_and_migrate_plaintext() {
+        let storage = test_storage();
+
+        // A plaintext mnemonic written before encryption was enabled
+        storage.inner().set_mnemonic(PHRASE).await.unwrap();
+        storage.unlock("hunter2").await.unwrap();
+        let raw = storage.inner().get_mnemonic().await.unwrap().unwrap();
+        assert!(!raw.contains("abandon"));
+
+        assert!(matches!(
+            storage.change_password("wrong", "correct horse").await,
+            Err(Error::InvalidPassword)
+        ));
+        storage
+            .change_password("hunter2", "correct horse")
+            .await
+            .unwrap();
+        assert_eq!(storage.get_mnemonic().await.unwrap().unwrap(), PHRASE);
+
+        storage.lock();
+        assert!(matches!(
+            storage.unlock("hunter2").await,
+            Err(Error::InvalidPassword)
+        ));
+        storage.unlock("correct horse").await.unwrap();
+        assert_eq!(storage.get_mnemonic().await.unwrap().unwrap(), PHRASE);
+    }
+}
@@ -0,0 +1,774 @@
+//! Filesystem storage for CLI tools and small daemons.
+//!
+//! [`FsStorage`] keeps everything in one directory:
+//!
+//! ```text
+//! wallet.json             mnemonic, key indices, passphrase fingerprint, derivation scheme
+//! swaps/<id>.json         one file per swap
+//! vtxo_swaps/<id>.json    one file per VTXO swap
+//! events/<id>.jsonl       event history of a swap, one JSON event per line
+//! pending/<user_id>.json  swap creation that was started but not finalized
+//! .lock                   guards against concurrent writers
+//! ```
+//!
+//! Files are written to a temporary file that is synced and then renamed over the old one, so a
+//! crash leaves either the old or the new version, never a partial one. Event histories are
+//! appended to instead; a line cut short by a crash is skipped when reading them. On Unix, files are
+//! only readable by their owner (mode 0600).
+//!
+//! The mnemonic is stored as given: wrap the storage in an
+//! [`EncryptedWalletStorage`](super::EncryptedWalletStorage) to keep it encrypted at rest.
+
+use super::schema::{
+    QuarantinedRecord, outdated_swap_records, read_swap_records, read_vtxo_swap_records,
+};
+use super::{StorageFuture, SwapStorage, WalletStorage};
+use crate::client::{ExtendedSwapStorageData, ExtendedVtxoSwapStorageData};
+use crate::error::{Error, Result};
+use crate::history::SwapEvent;
+use crate::journal::PendingCreation;
+use crate::types::DerivationScheme;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use zeroize::Zeroize;
+
+const WALLET_FILE: &str = "wallet.json";
+const SWAPS_DIR: &str = "swaps";
+const VTXO_SWAPS_DIR: &str = "vtxo_swaps";
+const EVENTS_DIR: &str = "events";
+const PENDING_DIR: &str = "pending";
+const LOCK_FILE: &str = ".lock";
+
+/// Wallet and swap storage in a directory of JSON files.
+///
+/// Every operation holds an exclusive lock on the directory's lock file, so several processes
+/// (and several clones in one process) can share a directory. Key indices are reserved under
+/// the lock and are therefore never handed out twice.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use lendaswap_core::storage::FsStorage;
+///
+/// let storage = FsStorage::open("~/.lendaswap")?;
+/// let client = Client::new(url, storage.clone(), storage, network, arkade_url);
+/// ```
+#[derive(Clone)]
+pub struct FsStorage {
+    dir: PathBuf,
+    /// Serializes operations within the process, the lock file across processes.
+    lock: Arc<Mutex<()>>,
+}
+
+/// Contents of `wallet.json`. The mnemonic is wiped from memory when dropped.
+#[derive(Default, Serialize, Deserialize)]
+struct WalletFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mnemonic: Option<String>,
+    /// Key index by account.
+    #[serde(default)]
+    key_indices: BTreeMap<u32, u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    passphrase_fingerprint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    derivation_scheme: Option<DerivationScheme>,
+}
+
+impl Drop for WalletFile {
+    fn drop(&mut self) {
+        self.mnemonic.zeroize();
+    }
+}
+
+impl FsStorage {
+    /// Open the storage in `dir`, creating the directory if needed.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        for swaps_dir in [SWAPS_DIR, VTXO_SWAPS_DIR, EVENTS_DIR, PENDING_DIR] {
+            fs::create_dir_all(dir.join(swaps_dir)).map_err(|e| {
+                Error::Storage(format!("Failed to create {}: {}", dir.display(), e))
+            })?;
+        }
+
+        Ok(Self {
+            dir,
+            lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    /// The directory the storage lives in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Run `f` while holding the in-process lock and the lock file.
+    fn locked<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        let _guard = self.lock.lock().expect("storage lock poisoned");
+        let lock_file = open_private(&self.dir.join(LOCK_FILE), false)
+            .and_then(|file| file.lock().map(|()| file))
+            .map_err(|e| Error::Storage(format!("Failed to lock storage: {}", e)))?;
+
+        let result = f();
+        // Dropping the file would release the lock as well, this just makes it explicit
+        let _ = lock_file.unlock();
+        result
+    }
+
+    fn read_wallet(&self) -> Result<WalletFile> {
+        Ok(read_json(&self.dir.join(WALLET_FILE))?.unwrap_or_default())
+    }
+
+    fn update_wallet<T>(&self, f: impl FnOnce(&mut WalletFile) -> Result<T>) -> Result<T> {
+        self.locked(|| {
+            let mut wallet = self.read_wallet()?;
+            let result = f(&mut wallet)?;
+            write_json(&self.dir.join(WALLET_FILE), &wallet)?;
+            Ok(result)
+        })
+    }
+
+    /// The file of swap `swap_id` in `swaps_dir`.
+    fn swap_path(&self, swaps_dir: &str, swap_id: &str) -> Result<PathBuf> {
+        let valid = !swap_id.is_empty()
+            && swap_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(Error::Storage(format!("Invalid swap ID: {:?}", swap_id)));
+        }
+
+        Ok(self.dir.join(swaps_dir).join(format!("{swap_id}.json")))
+    }
+
+    /// The event history file of swap `swap_id`.
+    fn events_path(&self, swap_id: &str) -> Result<PathBuf> {
+        Ok(self.swap_path(EVENTS_DIR, swap_id)?.with_extension("jsonl"))
+    }
+
+    /// The IDs of the swaps in `swaps_dir`.
+    fn swap_ids(&self, swaps_dir: &str) -> Result<Vec<String>> {
+        self.file_ids(swaps_dir, ".json")
+    }
+
+    /// The IDs of the swaps with a file ending in `suffix` in `dir`.
+    fn file_ids(&self, dir: &str, suffix: &str) -> Result<Vec<String>> {
+        let entries = fs::read_dir(self.dir.join(dir))
+            .map_err(|e| Error::Storage(format!("Failed to list swaps: {}", e)))?;
+
+        let mut ids = Vec::new();
+        for entry in entries {
+            let entry =
+                entry.map_err(|e| Error::Storage(format!("Failed to list swaps: {}", e)))?;
+            let name = entry.file_name();
+            let Some(id) = name.to_str().and_then(|name| name.strip_suffix(suffix)) else {
+                continue;
+            };
+            // Skip temporary files of interrupted writes
+            if !id.starts_with('.') {
+                ids.push(id.to_string());
+            }
+        }
+        ids.sort();
+
+        Ok(ids)
+    }
+
+    /// Read all swaps in `swaps_dir`.
+    fn read_swaps<T: for<'de> Deserialize<'de>>(&self, swaps_dir: &str) -> Result<Vec<T>> {
+        self.locked(|| {
+            let mut swaps = Vec::new();
+            for id in self.swap_ids(swaps_dir)? {
+                // Deleted since listing
+                if let Some(data) = read_json(&self.swap_path(swaps_dir, &id)?)? {
+                    swaps.push(data);
+                }
+            }
+            Ok(swaps)
+        })
+    }
+
+    /// The IDs and JSON of all swap records in `swaps_dir`.
+    fn swap_records(&self, swaps_dir: &str) -> Result<Vec<(Option<String>, String)>> {
+        self.locked(|| {
+            let mut records = Vec::new();
+            for id in self.swap_ids(swaps_dir)? {
+                // Deleted since listing
+                if let Some(data) = read_file(&self.swap_path(swaps_dir, &id)?)? {
+                    records.push((Some(id), data));
+                }
+            }
+            Ok(records)
+        })
+    }
+
+    /// Delete a swap file, doing nothing if it doesn't exist.
+    fn delete_swap_file(&self, path: &Path) -> Result<()> {
+        self.locked(|| match fs::remove_file(path) {
+            Ok(()) => sync_parent(path),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(Error::Storage(format!(
+                "Failed to delete {}: {}",
+                path.display(),
+                e
+            ))),
+        })
+    }
+}
+
+impl WalletStorage for FsStorage {
+    fn get_mnemonic(&self) -> StorageFuture<'_, Option<String>> {
+        Box::pin(async move { self.locked(|| Ok(self.read_wallet()?.mnemonic.clone())) })
+    }
+
+    fn set_mnemonic(&self, mnemonic: &str) -> StorageFuture<'_, ()> {
+        let mnemonic = mnemonic.to_string();
+        Box::pin(async move {
+            self.update_wallet(|wallet| {
+                if let Some(mut previous) = wallet.mnemonic.replace(mnemonic) {
+                    previous.zeroize();
+                }
+                Ok(())
+            })
+        })
+    }
+
+    fn get_key_index(&self) -> StorageFuture<'_, u32> {
+        self.get_account_key_index(0)
+    }
+
+    fn set_key_index(&self, index: u32) -> StorageFuture<'_, ()> {
+        self.set_account_key_index(0, index)
+    }
+
+    fn reserve_key_index(&self) -> StorageFuture<'_, u32> {
+        self.reserve_account_key_index(0)
+    }
+
+    fn get_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        Box::pin(async move {
+            self.locked(|| {
+                let wallet = self.read_wallet()?;
+                Ok(wallet.key_indices.get(&account).copied().unwrap_or(0))
+            })
+        })
+    }
+
+    fn set_account_key_index(&self, account: u32, index: u32) -> StorageFuture<'_, ()> {
+        Box::pin(async move {
+            self.update_wallet(|wallet| {
+                wallet.key_indices.insert(account, index);
+                Ok(())
+            })
+        })
+    }
+
+    fn reserve_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        Box::pin(async move {
+            self.update_wallet(|wallet| {
+                let current = wallet.key_indices.get(&account).copied().unwrap_or(0);
+                let next = current
+                    .checked_add(1)
+                    .ok_or_else(|| Error::Storage("key index exhausted".to_string()))?;
+                wallet.key_indices.insert(account, next);
+                Ok(current)
+            })
+        })
+    }
+
+    fn get_passphrase_fingerprint(&self) -> StorageFuture<'_, Option<String>> {
+        Box::pin(
+            async move { self.locked(|| Ok(self.read_wallet()?.passphrase_fingerprint.clone())) },
+        )
+    }
+
+    fn set_passphrase_fingerprint(&self, fingerprint: Option<&str>) -> StorageFuture<'_, ()> {
+        let fingerprint = fingerprint.map(str::to_string);
+        Box::pin(async move {
+            self.update_wallet(|wallet| {
+                wallet.passphrase_fingerprint = fingerprint;
+                Ok(())
+            })
+        })
+    }
+
+    fn get_derivation_scheme(&self) -> StorageFuture<'_, Option<DerivationScheme>> {
+        Box::pin(async move { self.locked(|| Ok(self.read_wallet()?.derivation_scheme)) })
+    }
+
+    fn set_derivation_scheme(&self, scheme: DerivationScheme) -> StorageFuture<'_, ()> {
+        Box::pin(async move {
+            self.update_wallet(|wallet| {
+                wallet.derivation_scheme = Some(scheme);
+                Ok(())
+            })
+        })
+    }
+}
+
+impl SwapStorage for FsStorage {
+    fn get(&self, swap_id: &str) -> StorageFuture<'_, Option<ExtendedSwapStorageData>> {
+        let path = self.swap_path(SWAPS_DIR, swap_id);
+        Box::pin(async move {
+            let path = path?;
+            self.locked(|| read_json(&path))
+        })
+    }
+
+    fn store(&self, swap_id: &str, data: &ExtendedSwapStorageData) -> StorageFuture<'_, ()> {
+        let path = self.swap_path(SWAPS_DIR, swap_id);
+        let data = serde_json::to_vec_pretty(data);
+        Box::pin(async move {
+            let path = path?;
+            let data = data?;
+            self.locked(|| write_atomic(&path, &data))
+        })
+    }
+
+    fn delete(&self, swap_id: &str) -> StorageFuture<'_, ()> {
+        let path = self.swap_path(SWAPS_DIR, swap_id);
+        Box::pin(async move { self.delete_swap_file(&path?) })
+    }
+
+    fn list(&self) -> StorageFuture<'_, Vec<String>> {
+        Box::pin(async move { self.locked(|| self.swap_ids(SWAPS_DIR)) })
+    }
+
+    fn get_all(&self) -> StorageFuture<'_, Vec<ExtendedSwapStorageData>> {
+        Box::pin(async move {
+            let (swaps, _) = read_swap_records(self.swap_records(SWAPS_DIR)?);
+            Ok(swaps)
+        })
+    }
+
+    fn get_quarantined(&self) -> StorageFuture<'_, Vec<QuarantinedRecord>> {
+        Box::pin(async move {
+            let (_, mut quarantined) = read_swap_records(self.swap_records(SWAPS_DIR)?);
+            let (_, vtxo_quarantined) = read_vtxo_swap_records(self.swap_records(VTXO_SWAPS_DIR)?);
+            quarantined.extend(vtxo_quarantined);
+            Ok(quarantined)
+        })
+    }
+
+    fn list_outdated(&self) -> StorageFuture<'_, Vec<String>> {
+        Box::pin(async move { Ok(outdated_swap_records(self.swap_records(SWAPS_DIR)?)) })
+    }
+
+    fn append_swap_event(&self, event: &SwapEvent) -> StorageFuture<'_, ()> {
+        let path = self.events_path(&event.swap_id);
+        let line = serde_json::to_string(event);
+        Box::pin(async move {
+            let path = path?;
+            let line = line?;
+            self.locked(|| append_line(&path, line.as_bytes()))
+        })
+    }
+
+    fn get_swap_events(&self, swap_id: &str) -> StorageFuture<'_, Vec<SwapEvent>> {
+        let path = self.events_path(swap_id);
+        Box::pin(async move { self.locked(|| read_events(&path?)) })
+    }
+
+    fn get_all_swap_events(&self) -> StorageFuture<'_, Vec<SwapEvent>> {
+        Box::pin(async move {
+            self.locked(|| {
+                let mut events = Vec::new();
+                for id in self.file_ids(EVENTS_DIR, ".jsonl")? {
+                    events.extend(read_events(&self.events_path(&id)?)?);
+                }
+                Ok(events)
+            })
+        })
+    }
+
+    fn store_pending_creation(&self, pending: &PendingCreation) -> StorageFuture<'_, ()> {
+        let path = self.swap_path(PENDING_DIR, &pending.user_id());
+        let data = serde_json::to_vec_pretty(pending);
+        Box::pin(async move {
+            let path = path?;
+            let data = data?;
+            self.locked(|| write_atomic(&path, &data))
+        })
+    }
+
+    fn delete_pending_creation(&self, user_id: &str) -> StorageFuture<'_, ()> {
+        let path = self.swap_path(PENDING_DIR, user_id);
+        Box::pin(async move { self.delete_swap_file(&path?) })
+    }
+
+    fn get_pending_creations(&self) -> StorageFuture<'_, Vec<PendingCreation>> {
+        Box::pin(async move { self.read_swaps(PENDING_DIR) })
+    }
+
+    fn supports_vtxo_swaps(&self) -> bool {
+        true
+    }
+
+    fn get_vtxo_swap(
+        &self,
+        swap_id: &str,
+    ) -> StorageFuture<'_, Option<ExtendedVtxoSwapStorageData>> {
+        let path = self.swap_path(VTXO_SWAPS_DIR, swap_id);
+        Box::pin(async move {
+            let path = path?;
+            self.locked(|| read_json(&path))
+        })
+    }
+
+    fn store_vtxo_swap(
+        &self,
+        swap_id: &str,
+        data: &ExtendedVtxoSwapStorageData,
+    ) -> StorageFuture<'_, ()> {
+        let path = self.swap_path(VTXO_SWAPS_DIR, swap_id);
+        let data = serde_json::to_vec_pretty(data);
+        Box::pin(async move {
+            let path = path?;
+            let data = data?;
+            self.locked(|| write_atomic(&path, &data))
+        })
+    }
+
+    fn delete_vtxo_swap(&self, swap_id: &str) -> StorageFuture<'_, ()> {
+        let path = self.swap_path(VTXO_SWAPS_DIR, swap_id);
+        Box::pin(async move { self.delete_swap_file(&path?) })
+    }
+
+    fn get_all_vtxo_swaps(&self) -> StorageFuture<'_, Vec<ExtendedVtxoSwapStorageData>> {
+        Box::pin(async move {
+            let (swaps, _) = read_vtxo_swap_records(self.swap_records(VTXO_SWAPS_DIR)?);
+            Ok(swaps)
+        })
+    }
+}
+
+/// Read a JSON file, returning `None` if it doesn't exist.
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<Option<T>> {
+    let Some(data) = read_file(path)? else {
+        return Ok(None);
+    };
+
+    serde_json::from_str(&data)
+        .map(Some)
+        .map_err(|e| Error::Storage(format!("Invalid JSON in {}: {}", path.display(), e)))
+}
+
+/// Read a text file, returning `None` if it doesn't exist.
+fn read_file(path: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(Error::Storage(format!(
+            "Failed to read {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+/// Read a swap history file, one event per line. A missing file is an empty history.
+fn read_events(path: &Path) -> Result<Vec<SwapEvent>> {
+    let Some(data) = read_file(path)? else {
+        return Ok(Vec::new());
+    };
+
+    let mut events = Vec::new();
+    for line in data.lines().filter(|line| !line.is_empty()) {
+        match serde_json::from_str(line) {
+            Ok(event) => events.push(event),
+            // Cut short by a crash while appending
+            Err(e) => log::warn!("Skipping event in {}: {e}", path.display()),
+        }
+    }
+    Ok(events)
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    write_atomic(path, &serde_json::to_vec_pretty(value)?)
+}
+
+/// Replace the file at `path` with `data`, so that it has either its old or its new contents even
+/// if the process crashes.
+fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| Error::Storage(format!("Invalid path: {}", path.display())))?;
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let write = || -> io::Result<()> {
+        let mut file = open_private(&temp_path, true)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    };
+    if let Err(e) = write() {
+        let _ = fs::remove_file(&temp_path);
+        return Err(Error::Storage(format!(
+            "Failed to write {}: {}",
+            path.display(),
+            e
+        )));
+    }
+
+    sync_parent(path)
+}
+
+/// Append a line to the file at `path`, creating it if needed, and sync it. A previous line cut
+/// short by a crash is terminated first, so that it doesn't swallow the new one.
+fn append_line(path: &Path, line: &[u8]) -> Result<()> {
+    let append = || -> io::Result<bool> {
+        let mut options = OpenOptions::new();
+        options.read(true).append(true).create(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(path)?;
+
+        let len = file.metadata()?.len();
+        if len > 0 {
+            let mut last = [0];
+            file.seek(SeekFrom::Start(len - 1))?;
+            file.read_exact(&mut last)?;
+            if last != *b"\n" {
+                file.write_all(b"\n")?;
+            }
+        }
+        file.write_all(line)?;
+        file.write_all(b"\n")?;
+        file.sync_all()?;
+        Ok(len == 0)
+    };
+
+    match append() {
+        Ok(true) => sync_parent(path),
+        Ok(false) => Ok(()),
+        Err(e) => Err(Error::Storage(format!(
+            "Failed to append to {}: {}",
+            path.display(),
+            e
+        ))),
+    }
+}
+
+/// Open a file for writing, creating it readable only by its owner.
+fn open_private(path: &Path, truncate: bool) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(truncate);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// Sync the directory containing `path`, so that a rename or removal survives a crash.
+fn sync_parent(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    if let Some(parent) = path.parent() {
+        File::open(parent)
+            .and_then(|dir| dir.sync_all())
+            .map_err(|e| Error::Storage(format!("Failed to sync {}: {}", parent.display(), e)))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::SwapStatus;
+    use crate::storage::conformance::{self, sample_swap};
+
+    /// A fresh directory that is removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("lendaswap-fs-{}", uuid::Uuid::new_v4()));
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const SWAP_A: &str = "00000000-0000-0000-0000-00000000000a";
+    const SWAP_B: &str = "00000000-0000-0000-0000-00000000000b";
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_wallet_storage() {
+        let dir = TempDir::new();
+        let storage = FsStorage::open(&dir.0).unwrap();
+
+        assert_eq!(storage.get_mnemonic().await.unwrap(), None);
+        storage.set_mnemonic("abandon about").await.unwrap();
+        assert_eq!(storage.reserve_key_index().await.unwrap(), 0);
+        assert_eq!(storage.reserve_key_index().await.unwrap(), 1);
+        assert_eq!(storage.reserve_account_key_index(3).await.unwrap(), 0);
+        storage
+            .set_passphrase_fingerprint(Some("deadbeef"))
+            .await
+            .unwrap();
+        storage
+            .set_derivation_scheme(DerivationScheme::V1)
+            .await
+            .unwrap();
+
+        // Another handle on the same directory sees everything
+        let reopened = FsStorage::open(&dir.0).unwrap();
+        assert_eq!(
+            reopened.get_mnemonic().await.unwrap().as_deref(),
+            Some("abandon about")
+        );
+        assert_eq!(reopened.get_key_index().await.unwrap(), 2);
+        assert_eq!(reopened.get_account_key_index(3).await.unwrap(), 1);
+        assert_eq!(
+            reopened
+                .get_passphrase_fingerprint()
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("deadbeef")
+        );
+        assert_eq!(
+            reopened.get_derivation_scheme().await.unwrap(),
+            Some(DerivationScheme::V1)
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dir.0.join(WALLET_FILE))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_swap_storage() {
+        let dir = TempDir::new();
+        let storage = FsStorage::open(&dir.0).unwrap();
+
+        storage
+            .store(SWAP_B, &sample_swap(SWAP_B, SwapStatus::Pending))
+            .await
+            .unwrap();
+        storage
+            .store(SWAP_A, &sample_swap(SWAP_A, SwapStatus::Pending))
+            .await
+            .unwrap();
+        storage
+            .store(SWAP_A, &sample_swap(SWAP_A, SwapStatus::ServerFunded))
+            .await
+            .unwrap();
+
+        // Leftover of an interrupted write
+        fs::write(dir.0.join(SWAPS_DIR).join(".a.json.1.tmp"), b"{").unwrap();
+
+        assert_eq!(storage.list().await.unwrap(), vec![SWAP_A, SWAP_B]);
+        let stored = storage.get(SWAP_A).await.unwrap().unwrap();
+        assert_eq!(stored.response.status(), SwapStatus::ServerFunded);
+        assert_eq!(storage.get_all().await.unwrap().len(), 2);
+
+        storage.delete(SWAP_A).await.unwrap();
+        storage.delete(SWAP_A).await.unwrap();
+        assert!(storage.get(SWAP_A).await.unwrap().is_none());
+        assert_eq!(storage.list().await.unwrap(), vec![SWAP_B]);
+
+        assert!(storage.get("../wallet").await.is_err());
+
+        // Written by a newer version
+        let newer = dir.0.join(SWAPS_DIR).join("c.json");
+        fs::write(&newer, br#"{"version": 999}"#).unwrap();
+        assert_eq!(storage.get_all().await.unwrap().len(), 1);
+        let quarantined = storage.get_quarantined().await.unwrap();
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].id.as_deref(), Some("c"));
+        assert!(newer.exists());
+
+        fs::write(dir.0.join(VTXO_SWAPS_DIR).join("d.json"), b"{").unwrap();
+        assert!(storage.get_all_vtxo_swaps().await.unwrap().is_empty());
+        let quarantined = storage.get_quarantined().await.unwrap();
+        assert_eq!(quarantined.len(), 2);
+        assert_eq!(quarantined[1].id.as_deref(), Some("d"));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_swap_events_survive_torn_append() {
+        use crate::history::SwapEventKind;
+
+        let dir = TempDir::new();
+        let storage = FsStorage::open(&dir.0).unwrap();
+        let created = SwapEvent::now(SWAP_A, SwapEventKind::created(&SwapStatus::Pending));
+        storage.append_swap_event(&created).await.unwrap();
+
+        // A crash while appending left half an event behind
+        let path = dir.0.join(EVENTS_DIR).join(format!("{SWAP_A}.jsonl"));
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"swap_id":"#).unwrap();
+        drop(file);
+        assert_eq!(
+            storage.get_swap_events(SWAP_A).await.unwrap(),
+            vec![created.clone()]
+        );
+
+        let claimed = SwapEvent::now(
+            SWAP_A,
+            SwapEventKind::Claimed {
+                txid: "txid".to_string(),
+            },
+        );
+        storage.append_swap_event(&claimed).await.unwrap();
+        assert_eq!(
+            storage.get_swap_events(SWAP_A).await.unwrap(),
+            vec![created, claimed]
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_conformance() {
+        let wallet_dir = TempDir::new();
+        let swap_dir = TempDir::new();
+        conformance::run_wallet_storage_suite(FsStorage::open(&wallet_dir.0).unwrap()).await;
+        conformance::run_swap_storage_suite(FsStorage::open(&swap_dir.0).unwrap()).await;
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_concurrent_reservations() {
+        let dir = TempDir::new();
+        let storage = FsStorage::open(&dir.0).unwrap();
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let storage = FsStorage::open(&dir.0).unwrap();
+                std::thread::spawn(move || {
+                    (0..10)
+                        .map(|_| futures::executor::block_on(storage.reserve_key_index()).unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        let mut reserved: Vec<u32> = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect();
+        reserved.sort();
+        assert_eq!(reserved, (0..40).collect::<Vec<_>>());
+        assert_eq!(storage.get_key_index().await.unwrap(), 40);
+    }
+}
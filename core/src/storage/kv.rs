@@ -0,0 +1,537 @@
+//! Wallet and swap storage on top of a key-value store.
+//!
+//! Platforms that already have a key-value store (Redis, LMDB, browser localStorage, React Native
+//! MMKV) only implement the four methods of [`KeyValueStore`] and wrap it in a [`KvStorage`],
+//! which implements both [`WalletStorage`] and [`SwapStorage`].
+//!
+//! Keys are UTF-8 strings, values are bytes. The key layout and encoding are stable, so that data
+//! written by one platform can be read by another. Every key starts with `lendaswap/v1/`:
+//!
+//! ```text
+//! wallet/mnemonic                mnemonic (UTF-8)
+//! wallet/passphrase_fingerprint  master key fingerprint (UTF-8)
+//! wallet/derivation_scheme       derivation scheme version (decimal)
+//! wallet/key_index/<account>     key index of an account (decimal)
+//! swaps/<id>                     swap (JSON)
+//! vtxo_swaps/<id>                VTXO swap (JSON)
+//! events/<id>/<time>-<nonce>     event of a swap's history (JSON)
+//! pending/<user_id>              pending swap creation (JSON)
+//! ```
+//!
+//! `<time>` is the event's time in nanoseconds since the Unix epoch (20 decimal digits), raised
+//! where needed so that events appended through one storage are ordered, and `<nonce>` is 16
+//! random hex digits, so that storages sharing a store (e.g. two browser tabs) never overwrite each
+//! other's events. Events stored under a 10 digit sequence number by earlier versions are ordered
+//! first.
+//!
+//! Key indices are reserved with an in-process lock, see
+//! [`reserve_key_index_in_process`](super::reserve_key_index_in_process). The
+//! mnemonic is stored as given: wrap the storage in an
+//! [`EncryptedWalletStorage`](super::EncryptedWalletStorage) to keep it encrypted at rest.
+
+use super::schema::{
+    QuarantinedRecord, outdated_swap_records, read_swap_records, read_vtxo_swap_records,
+};
+use super::{StorageFuture, SwapStorage, WalletStorage};
+use crate::client::{ExtendedSwapStorageData, ExtendedVtxoSwapStorageData};
+use crate::error::{Error, Result};
+use crate::history::SwapEvent;
+use crate::journal::PendingCreation;
+use crate::types::DerivationScheme;
+use futures::lock::Mutex;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+/// Prefix of all keys, versioning the layout.
+const PREFIX: &str = "lendaswap/v1/";
+const MNEMONIC: &str = "wallet/mnemonic";
+const PASSPHRASE_FINGERPRINT: &str = "wallet/passphrase_fingerprint";
+const DERIVATION_SCHEME: &str = "wallet/derivation_scheme";
+const KEY_INDEX: &str = "wallet/key_index/";
+const SWAPS: &str = "swaps/";
+const VTXO_SWAPS: &str = "vtxo_swaps/";
+const EVENTS: &str = "events/";
+const PENDING: &str = "pending/";
+
+/// A minimal key-value store with string keys and byte values.
+///
+/// # Example Implementation (TypeScript/localStorage)
+///
+/// ```typescript
+/// const store = new JsKeyValueStoreProvider(
+///     async (key) => decode(localStorage.getItem(key)),         // get
+///     async (key, value) => localStorage.setItem(key, encode(value)), // put
+///     async (key) => localStorage.removeItem(key),              // delete
+///     async (prefix) => entriesWithPrefix(localStorage, prefix), // scan_prefix
+/// );
+/// ```
+#[cfg(target_arch = "wasm32")]
+pub trait KeyValueStore {
+    /// Get the value of a key. Returns `Ok(None)` if the key doesn't exist.
+    fn get(&self, key: &str) -> StorageFuture<'_, Option<Vec<u8>>>;
+
+    /// Set the value of a key, overwriting any existing value.
+    fn put(&self, key: &str, value: &[u8]) -> StorageFuture<'_, ()>;
+
+    /// Delete a key. Deleting a missing key is not an error.
+    fn delete(&self, key: &str) -> StorageFuture<'_, ()>;
+
+    /// Get all entries whose key starts with `prefix`, ordered by key.
+    fn scan_prefix(&self, prefix: &str) -> StorageFuture<'_, Vec<(String, Vec<u8>)>>;
+}
+
+/// A minimal key-value store with string keys and byte values.
+///
+/// # Example Implementation (TypeScript/localStorage)
+///
+/// ```typescript
+/// const store = new JsKeyValueStoreProvider(
+///     async (key) => decode(localStorage.getItem(key)),         // get
+///     async (key, value) => localStorage.setItem(key, encode(value)), // put
+///     async (key) => localStorage.removeItem(key),              // delete
+///     async (prefix) => entriesWithPrefix(localStorage, prefix), // scan_prefix
+/// );
+/// ```
+#[cfg(not(target_arch = "wasm32"))]
+pub trait KeyValueStore: Send + Sync {
+    /// Get the value of a key. Returns `Ok(None)` if the key doesn't exist.
+    fn get(&self, key: &str) -> StorageFuture<'_, Option<Vec<u8>>>;
+
+    /// Set the value of a key, overwriting any existing value.
+    fn put(&self, key: &str, value: &[u8]) -> StorageFuture<'_, ()>;
+
+    /// Delete a key. Deleting a missing key is not an error.
+    fn delete(&self, key: &str) -> StorageFuture<'_, ()>;
+
+    /// Get all entries whose key starts with `prefix`, ordered by key.
+    fn scan_prefix(&self, prefix: &str) -> StorageFuture<'_, Vec<(String, Vec<u8>)>>;
+}
+
+/// Wallet and swap storage on a [`KeyValueStore`], see the [module docs](self) for the layout.
+///
+/// Clones share the store, so a single store can back both the wallet and the swap storage of a
+/// client.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use lendaswap_core::storage::KvStorage;
+///
+/// let storage = KvStorage::new(my_store);
+/// let client = Client::new(url, storage.clone(), storage, network, arkade_url);
+/// ```
+pub struct KvStorage<K> {
+    store: Arc<K>,
+    /// The time of the last appended event, in nanoseconds, so that appends are ordered.
+    last_event_time: Arc<Mutex<u128>>,
+}
+
+impl<K> Clone for KvStorage<K> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            last_event_time: self.last_event_time.clone(),
+        }
+    }
+}
+
+impl<K: KeyValueStore> KvStorage<K> {
+    /// Store wallet and swap data in `store`.
+    pub fn new(store: K) -> Self {
+        Self {
+            store: Arc::new(store),
+            last_event_time: Arc::new(Mutex::new(0)),
+        }
+    }
+
+    /// The underlying key-value store.
+    pub fn key_value_store(&self) -> &K {
+        &self.store
+    }
+
+    async fn get_string(&self, name: &str) -> Result<Option<String>> {
+        let key = key(name);
+        match self.store.get(&key).await? {
+            Some(value) => String::from_utf8(value)
+                .map(Some)
+                .map_err(|e| Error::Storage(format!("Invalid UTF-8 in {}: {}", key, e))),
+            None => Ok(None),
+        }
+    }
+
+    async fn put_string(&self, name: &str, value: &str) -> Result<()> {
+        self.store.put(&key(name), value.as_bytes()).await
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>> {
+        match self.store.get(&key(name)).await? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn put_json<T: Serialize>(&self, name: &str, value: &T) -> Result<()> {
+        self.store
+            .put(&key(name), &serde_json::to_vec(value)?)
+            .await
+    }
+
+    /// The entries under `prefix`, with their keys relative to it.
+    async fn scan(&self, prefix: &str) -> Result<Vec<(String, Vec<u8>)>> {
+        let prefix = key(prefix);
+        let entries = self.store.scan_prefix(&prefix).await?;
+        Ok(entries
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.strip_prefix(&prefix)?.to_string(), value)))
+            .collect())
+    }
+
+    /// Decode the JSON values of all entries under `prefix`.
+    async fn scan_json<T: DeserializeOwned>(&self, prefix: &str) -> Result<Vec<T>> {
+        self.scan(prefix)
+            .await?
+            .iter()
+            .map(|(_, value)| serde_json::from_slice(value).map_err(Error::from))
+            .collect()
+    }
+
+    /// The IDs and JSON of all swap records under `prefix`.
+    async fn swap_records(&self, prefix: &str) -> Result<Vec<(Option<String>, String)>> {
+        Ok(self
+            .scan(prefix)
+            .await?
+            .into_iter()
+            .map(|(id, value)| (Some(id), String::from_utf8_lossy(&value).into_owned()))
+            .collect())
+    }
+}
+
+/// The full key of `name`.
+fn key(name: &str) -> String {
+    format!("{PREFIX}{name}")
+}
+
+/// The name of the record `id` under `prefix`. IDs can't contain `/`, so that they don't reach
+/// into other records' keys.
+fn record_name(prefix: &str, id: &str) -> Result<String> {
+    if id.is_empty() || id.contains('/') {
+        return Err(Error::Storage(format!("Invalid record ID: {:?}", id)));
+    }
+    Ok(format!("{prefix}{id}"))
+}
+
+fn key_index_name(account: u32) -> String {
+    format!("{KEY_INDEX}{account}")
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| Error::Storage(format!("Invalid number in {}: {}", key(name), e)))
+}
+
+impl<K: KeyValueStore> WalletStorage for KvStorage<K> {
+    fn get_mnemonic(&self) -> StorageFuture<'_, Option<String>> {
+        Box::pin(self.get_string(MNEMONIC))
+    }
+
+    fn set_mnemonic(&self, mnemonic: &str) -> StorageFuture<'_, ()> {
+        let mnemonic = mnemonic.to_string();
+        Box::pin(async move { self.put_string(MNEMONIC, &mnemonic).await })
+    }
+
+    fn get_key_index(&self) -> StorageFuture<'_, u32> {
+        self.get_account_key_index(0)
+    }
+
+    fn set_key_index(&self, index: u32) -> StorageFuture<'_, ()> {
+        self.set_account_key_index(0, index)
+    }
+
+    fn get_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        Box::pin(async move {
+            let name = key_index_name(account);
+            match self.get_string(&name).await? {
+                Some(value) => parse_number(&name, &value),
+                None => Ok(0),
+            }
+        })
+    }
+
+    fn set_account_key_index(&self, account: u32, index: u32) -> StorageFuture<'_, ()> {
+        Box::pin(async move {
+            self.put_string(&key_index_name(account), &index.to_string())
+                .await
+        })
+    }
+
+    fn get_passphrase_fingerprint(&self) -> StorageFuture<'_, Option<String>> {
+        Box::pin(self.get_string(PASSPHRASE_FINGERPRINT))
+    }
+
+    fn set_passphrase_fingerprint(&self, fingerprint: Option<&str>) -> StorageFuture<'_, ()> {
+        let fingerprint = fingerprint.map(str::to_string);
+        Box::pin(async move {
+            match fingerprint {
+                Some(fingerprint) => self.put_string(PASSPHRASE_FINGERPRINT, &fingerprint).await,
+                None => self.store.delete(&key(PASSPHRASE_FINGERPRINT)).await,
+            }
+        })
+    }
+
+    fn get_derivation_scheme(&self) -> StorageFuture<'_, Option<DerivationScheme>> {
+        Box::pin(async move {
+            let Some(value) = self.get_string(DERIVATION_SCHEME).await? else {
+                return Ok(None);
+            };
+            let version: u8 = parse_number(DERIVATION_SCHEME, &value)?;
+            Ok(Some(DerivationScheme::try_from(version)?))
+        })
+    }
+
+    fn set_derivation_scheme(&self, scheme: DerivationScheme) -> StorageFuture<'_, ()> {
+        Box::pin(async move {
+            self.put_string(DERIVATION_SCHEME, &scheme.version().to_string())
+                .await
+        })
+    }
+}
+
+impl<K: KeyValueStore> SwapStorage for KvStorage<K> {
+    fn get(&self, swap_id: &str) -> StorageFuture<'_, Option<ExtendedSwapStorageData>> {
+        let name = record_name(SWAPS, swap_id);
+        Box::pin(async move { self.get_json(&name?).await })
+    }
+
+    fn store(&self, swap_id: &str, data: &ExtendedSwapStorageData) -> StorageFuture<'_, ()> {
+        let name = record_name(SWAPS, swap_id);
+        let data = data.clone();
+        Box::pin(async move { self.put_json(&name?, &data).await })
+    }
+
+    fn delete(&self, swap_id: &str) -> StorageFuture<'_, ()> {
+        let name = record_name(SWAPS, swap_id);
+        Box::pin(async move { self.store.delete(&key(&name?)).await })
+    }
+
+    fn list(&self) -> StorageFuture<'_, Vec<String>> {
+        Box::pin(async move {
+            let entries = self.scan(SWAPS).await?;
+            Ok(entries.into_iter().map(|(id, _)| id).collect())
+        })
+    }
+
+    fn get_all(&self) -> StorageFuture<'_, Vec<ExtendedSwapStorageData>> {
+        Box::pin(async move {
+            let (swaps, _) = read_swap_records(self.swap_records(SWAPS).await?);
+            Ok(swaps)
+        })
+    }
+
+    fn get_quarantined(&self) -> StorageFuture<'_, Vec<QuarantinedRecord>> {
+        Box::pin(async move {
+            let (_, mut quarantined) = read_swap_records(self.swap_records(SWAPS).await?);
+            let (_, vtxo_quarantined) =
+                read_vtxo_swap_records(self.swap_records(VTXO_SWAPS).await?);
+            quarantined.extend(vtxo_quarantined);
+            Ok(quarantined)
+        })
+    }
+
+    fn list_outdated(&self) -> StorageFuture<'_, Vec<String>> {
+        Box::pin(async move { Ok(outdated_swap_records(self.swap_records(SWAPS).await?)) })
+    }
+
+    fn append_swap_event(&self, event: &SwapEvent) -> StorageFuture<'_, ()> {
+        let prefix = record_name(EVENTS, &event.swap_id).map(|name| format!("{name}/"));
+        let event = event.clone();
+        Box::pin(async move {
+            let prefix = prefix?;
+            let time = {
+                let mut last = self.last_event_time.lock().await;
+                let time = u128::try_from(event.timestamp.unix_timestamp_nanos()).unwrap_or(0);
+                *last = time.max(*last + 1);
+                *last
+            };
+            let nonce = OsRng.next_u64();
+            self.put_json(&format!("{prefix}{time:020}-{nonce:016x}"), &event)
+                .await
+        })
+    }
+
+    fn get_swap_events(&self, swap_id: &str) -> StorageFuture<'_, Vec<SwapEvent>> {
+        let prefix = record_name(EVENTS, swap_id).map(|name| format!("{name}/"));
+        Box::pin(async move { self.scan_json(&prefix?).await })
+    }
+
+    fn get_all_swap_events(&self) -> StorageFuture<'_, Vec<SwapEvent>> {
+        // Ordered by swap ID, then sequence number
+        Box::pin(self.scan_json(EVENTS))
+    }
+
+    fn store_pending_creation(&self, pending: &PendingCreation) -> StorageFuture<'_, ()> {
+        let name = record_name(PENDING, &pending.user_id());
+        let pending = pending.clone();
+        Box::pin(async move { self.put_json(&name?, &pending).await })
+    }
+
+    fn delete_pending_creation(&self, user_id: &str) -> StorageFuture<'_, ()> {
+        let name = record_name(PENDING, user_id);
+        Box::pin(async move { self.store.delete(&key(&name?)).await })
+    }
+
+    fn get_pending_creations(&self) -> StorageFuture<'_, Vec<PendingCreation>> {
+        Box::pin(self.scan_json(PENDING))
+    }
+
+    fn supports_vtxo_swaps(&self) -> bool {
+        true
+    }
+
+    fn get_vtxo_swap(
+        &self,
+        swap_id: &str,
+    ) -> StorageFuture<'_, Option<ExtendedVtxoSwapStorageData>> {
+        let name = record_name(VTXO_SWAPS, swap_id);
+        Box::pin(async move { self.get_json(&name?).await })
+    }
+
+    fn store_vtxo_swap(
+        &self,
+        swap_id: &str,
+        data: &ExtendedVtxoSwapStorageData,
+    ) -> StorageFuture<'_, ()> {
+        let name = record_name(VTXO_SWAPS, swap_id);
+        let data = data.clone();
+        Box::pin(async move { self.put_json(&name?, &data).await })
+    }
+
+    fn delete_vtxo_swap(&self, swap_id: &str) -> StorageFuture<'_, ()> {
+        let name = record_name(VTXO_SWAPS, swap_id);
+        Box::pin(async move { self.store.delete(&key(&name?)).await })
+    }
+
+    fn get_all_vtxo_swaps(&self) -> StorageFuture<'_, Vec<ExtendedVtxoSwapStorageData>> {
+        Box::pin(async move {
+            let (swaps, _) = read_vtxo_swap_records(self.swap_records(VTXO_SWAPS).await?);
+            Ok(swaps)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::SwapStatus;
+    use crate::history::SwapEventKind;
+    use crate::storage::conformance::{self, sample_swap};
+    use crate::storage::memory::MemoryKeyValueStore;
+
+    const SWAP_A: &str = "00000000-0000-0000-0000-00000000000a";
+
+    #[tokio::test]
+    async fn test_conformance() {
+        conformance::run_wallet_storage_suite(KvStorage::new(MemoryKeyValueStore::new())).await;
+        conformance::run_swap_storage_suite(KvStorage::new(MemoryKeyValueStore::new())).await;
+    }
+
+    /// A store shared by several [`KvStorage`]s, like the localStorage of several browser tabs.
+    struct SharedStore(Arc<MemoryKeyValueStore>);
+
+    impl KeyValueStore for SharedStore {
+        fn get(&self, key: &str) -> StorageFuture<'_, Option<Vec<u8>>> {
+            self.0.get(key)
+        }
+
+        fn put(&self, key: &str, value: &[u8]) -> StorageFuture<'_, ()> {
+            self.0.put(key, value)
+        }
+
+        fn delete(&self, key: &str) -> StorageFuture<'_, ()> {
+            self.0.delete(key)
+        }
+
+        fn scan_prefix(&self, prefix: &str) -> StorageFuture<'_, Vec<(String, Vec<u8>)>> {
+            self.0.scan_prefix(prefix)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_swap_events_of_storages_sharing_a_store() {
+        let store = Arc::new(MemoryKeyValueStore::new());
+        let first = KvStorage::new(SharedStore(store.clone()));
+        let second = KvStorage::new(SharedStore(store));
+
+        // Appended at the same time, neither overwrites the other
+        let event = SwapEvent::now(SWAP_A, SwapEventKind::created(&SwapStatus::Pending));
+        first.append_swap_event(&event).await.unwrap();
+        second.append_swap_event(&event).await.unwrap();
+
+        assert_eq!(
+            first.get_swap_events(SWAP_A).await.unwrap(),
+            [event.clone(), event]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_key_layout() {
+        let storage = KvStorage::new(MemoryKeyValueStore::new());
+        storage.set_mnemonic("abandon about").await.unwrap();
+        storage.set_account_key_index(2, 7).await.unwrap();
+        storage
+            .set_derivation_scheme(DerivationScheme::V1)
+            .await
+            .unwrap();
+        storage
+            .store(SWAP_A, &sample_swap(SWAP_A, SwapStatus::Pending))
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            let event = SwapEvent::now(SWAP_A, SwapEventKind::created(&SwapStatus::Pending));
+            storage.append_swap_event(&event).await.unwrap();
+        }
+
+        let keys: Vec<String> = storage
+            .key_value_store()
+            .scan_prefix("")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        let (events, keys) = keys.split_at(2);
+        for event in events {
+            let name = event
+                .strip_prefix(&format!("lendaswap/v1/events/{SWAP_A}/"))
+                .unwrap();
+            let (time, nonce) = name.split_once('-').unwrap();
+            assert_eq!((time.len(), nonce.len()), (20, 16));
+        }
+        assert!(events[0] < events[1]);
+        assert_eq!(
+            keys,
+            [
+                format!("lendaswap/v1/swaps/{SWAP_A}"),
+                "lendaswap/v1/wallet/derivation_scheme".to_string(),
+                "lendaswap/v1/wallet/key_index/2".to_string(),
+                "lendaswap/v1/wallet/mnemonic".to_string(),
+            ]
+        );
+        assert_eq!(
+            storage
+                .key_value_store()
+                .get("lendaswap/v1/wallet/key_index/2")
+                .await
+                .unwrap(),
+            Some(b"7".to_vec())
+        );
+
+        assert!(storage.get("a/b").await.is_err());
+    }
+}
@@ -0,0 +1,353 @@
+//! In-memory storage for tests.
+//!
+//! Nothing is persisted: the data is gone once the storage is dropped.
+
+use super::{KeyValueStore, StorageFuture, SwapStorage, WalletStorage};
+use crate::client::{ExtendedSwapStorageData, ExtendedVtxoSwapStorageData};
+use crate::history::SwapEvent;
+use crate::journal::PendingCreation;
+use crate::types::DerivationScheme;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+/// In-memory wallet storage.
+///
+/// Supports every optional feature of [`WalletStorage`]: accounts, passphrase fingerprints and
+/// derivation schemes.
+pub struct MemoryWalletStorage {
+    mnemonic: RwLock<Option<String>>,
+    key_index: RwLock<u32>,
+    account_key_indices: RwLock<HashMap<u32, u32>>,
+    passphrase_fingerprint: RwLock<Option<String>>,
+    derivation_scheme: RwLock<Option<DerivationScheme>>,
+}
+
+impl MemoryWalletStorage {
+    /// Create a new empty memory wallet storage.
+    pub fn new() -> Self {
+        Self {
+            mnemonic: RwLock::new(None),
+            key_index: RwLock::new(0),
+            account_key_indices: RwLock::new(HashMap::new()),
+            passphrase_fingerprint: RwLock::new(None),
+            derivation_scheme: RwLock::new(None),
+        }
+    }
+}
+
+impl Default for MemoryWalletStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WalletStorage for MemoryWalletStorage {
+    fn get_mnemonic(&self) -> StorageFuture<'_, Option<String>> {
+        Box::pin(async move {
+            let mnemonic = self.mnemonic.read().unwrap();
+            Ok(mnemonic.clone())
+        })
+    }
+
+    fn set_mnemonic(&self, mnemonic: &str) -> StorageFuture<'_, ()> {
+        let mnemonic = mnemonic.to_string();
+        Box::pin(async move {
+            let mut stored = self.mnemonic.write().unwrap();
+            *stored = Some(mnemonic);
+            Ok(())
+        })
+    }
+
+    fn get_key_index(&self) -> StorageFuture<'_, u32> {
+        Box::pin(async move {
+            let index = self.key_index.read().unwrap();
+            Ok(*index)
+        })
+    }
+
+    fn set_key_index(&self, index: u32) -> StorageFuture<'_, ()> {
+        Box::pin(async move {
+            let mut stored = self.key_index.write().unwrap();
+            *stored = index;
+            Ok(())
+        })
+    }
+
+    fn reserve_key_index(&self) -> StorageFuture<'_, u32> {
+        Box::pin(async move {
+            let mut stored = self.key_index.write().unwrap();
+            let current = *stored;
+            *stored += 1;
+            Ok(current)
+        })
+    }
+
+    fn get_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        if account == 0 {
+            return self.get_key_index();
+        }
+        Box::pin(async move {
+            let indices = self.account_key_indices.read().unwrap();
+            Ok(indices.get(&account).copied().unwrap_or(0))
+        })
+    }
+
+    fn set_account_key_index(&self, account: u32, index: u32) -> StorageFuture<'_, ()> {
+        if account == 0 {
+            return self.set_key_index(index);
+        }
+        Box::pin(async move {
+            self.account_key_indices
+                .write()
+                .unwrap()
+                .insert(account, index);
+            Ok(())
+        })
+    }
+
+    fn reserve_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        if account == 0 {
+            return self.reserve_key_index();
+        }
+        Box::pin(async move {
+            let mut indices = self.account_key_indices.write().unwrap();
+            let stored = indices.entry(account).or_insert(0);
+            let current = *stored;
+            *stored += 1;
+            Ok(current)
+        })
+    }
+
+    fn get_passphrase_fingerprint(&self) -> StorageFuture<'_, Option<String>> {
+        Box::pin(async move {
+            let fingerprint = self.passphrase_fingerprint.read().unwrap();
+            Ok(fingerprint.clone())
+        })
+    }
+
+    fn set_passphrase_fingerprint(&self, fingerprint: Option<&str>) -> StorageFuture<'_, ()> {
+        let fingerprint = fingerprint.map(|f| f.to_string());
+        Box::pin(async move {
+            let mut stored = self.passphrase_fingerprint.write().unwrap();
+            *stored = fingerprint;
+            Ok(())
+        })
+    }
+
+    fn get_derivation_scheme(&self) -> StorageFuture<'_, Option<DerivationScheme>> {
+        Box::pin(async move { Ok(*self.derivation_scheme.read().unwrap()) })
+    }
+
+    fn set_derivation_scheme(&self, scheme: DerivationScheme) -> StorageFuture<'_, ()> {
+        Box::pin(async move {
+            *self.derivation_scheme.write().unwrap() = Some(scheme);
+            Ok(())
+        })
+    }
+}
+
+/// In-memory swap storage, including VTXO swaps, swap histories and pending creations. Swaps are
+/// listed in order of their IDs.
+#[derive(Default)]
+pub struct MemorySwapStorage {
+    swaps: RwLock<BTreeMap<String, ExtendedSwapStorageData>>,
+    vtxo_swaps: RwLock<BTreeMap<String, ExtendedVtxoSwapStorageData>>,
+    events: RwLock<HashMap<String, Vec<SwapEvent>>>,
+    pending_creations: RwLock<BTreeMap<String, PendingCreation>>,
+}
+
+impl MemorySwapStorage {
+    /// Create a new empty memory swap storage.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SwapStorage for MemorySwapStorage {
+    fn get(&self, swap_id: &str) -> StorageFuture<'_, Option<ExtendedSwapStorageData>> {
+        let swap_id = swap_id.to_string();
+        Box::pin(async move { Ok(self.swaps.read().unwrap().get(&swap_id).cloned()) })
+    }
+
+    fn store(&self, swap_id: &str, data: &ExtendedSwapStorageData) -> StorageFuture<'_, ()> {
+        let swap_id = swap_id.to_string();
+        let data = data.clone();
+        Box::pin(async move {
+            self.swaps.write().unwrap().insert(swap_id, data);
+            Ok(())
+        })
+    }
+
+    fn delete(&self, swap_id: &str) -> StorageFuture<'_, ()> {
+        let swap_id = swap_id.to_string();
+        Box::pin(async move {
+            self.swaps.write().unwrap().remove(&swap_id);
+            Ok(())
+        })
+    }
+
+    fn list(&self) -> StorageFuture<'_, Vec<String>> {
+        Box::pin(async move { Ok(self.swaps.read().unwrap().keys().cloned().collect()) })
+    }
+
+    fn get_all(&self) -> StorageFuture<'_, Vec<ExtendedSwapStorageData>> {
+        Box::pin(async move { Ok(self.swaps.read().unwrap().values().cloned().collect()) })
+    }
+
+    fn list_outdated(&self) -> StorageFuture<'_, Vec<String>> {
+        // Swaps are kept as they are, not in a stored format
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    fn append_swap_event(&self, event: &SwapEvent) -> StorageFuture<'_, ()> {
+        let event = event.clone();
+        Box::pin(async move {
+            self.events
+                .write()
+                .unwrap()
+                .entry(event.swap_id.clone())
+                .or_default()
+                .push(event);
+            Ok(())
+        })
+    }
+
+    fn get_swap_events(&self, swap_id: &str) -> StorageFuture<'_, Vec<SwapEvent>> {
+        let swap_id = swap_id.to_string();
+        Box::pin(async move {
+            let events = self.events.read().unwrap();
+            Ok(events.get(&swap_id).cloned().unwrap_or_default())
+        })
+    }
+
+    fn get_all_swap_events(&self) -> StorageFuture<'_, Vec<SwapEvent>> {
+        Box::pin(async move {
+            let events = self.events.read().unwrap();
+            Ok(events.values().flatten().cloned().collect())
+        })
+    }
+
+    fn store_pending_creation(&self, pending: &PendingCreation) -> StorageFuture<'_, ()> {
+        let pending = pending.clone();
+        Box::pin(async move {
+            self.pending_creations
+                .write()
+                .unwrap()
+                .insert(pending.user_id(), pending);
+            Ok(())
+        })
+    }
+
+    fn delete_pending_creation(&self, user_id: &str) -> StorageFuture<'_, ()> {
+        let user_id = user_id.to_string();
+        Box::pin(async move {
+            self.pending_creations.write().unwrap().remove(&user_id);
+            Ok(())
+        })
+    }
+
+    fn get_pending_creations(&self) -> StorageFuture<'_, Vec<PendingCreation>> {
+        Box::pin(async move {
+            let pending = self.pending_creations.read().unwrap();
+            Ok(pending.values().cloned().collect())
+        })
+    }
+
+    fn supports_vtxo_swaps(&self) -> bool {
+        true
+    }
+
+    fn get_vtxo_swap(
+        &self,
+        swap_id: &str,
+    ) -> StorageFuture<'_, Option<ExtendedVtxoSwapStorageData>> {
+        let swap_id = swap_id.to_string();
+        Box::pin(async move { Ok(self.vtxo_swaps.read().unwrap().get(&swap_id).cloned()) })
+    }
+
+    fn store_vtxo_swap(
+        &self,
+        swap_id: &str,
+        data: &ExtendedVtxoSwapStorageData,
+    ) -> StorageFuture<'_, ()> {
+        let swap_id = swap_id.to_string();
+        let data = data.clone();
+        Box::pin(async move {
+            self.vtxo_swaps.write().unwrap().insert(swap_id, data);
+            Ok(())
+        })
+    }
+
+    fn delete_vtxo_swap(&self, swap_id: &str) -> StorageFuture<'_, ()> {
+        let swap_id = swap_id.to_string();
+        Box::pin(async move {
+            self.vtxo_swaps.write().unwrap().remove(&swap_id);
+            Ok(())
+        })
+    }
+
+    fn get_all_vtxo_swaps(&self) -> StorageFuture<'_, Vec<ExtendedVtxoSwapStorageData>> {
+        Box::pin(async move { Ok(self.vtxo_swaps.read().unwrap().values().cloned().collect()) })
+    }
+}
+
+/// In-memory key-value store, e.g. to back a [`KvStorage`](super::KvStorage) in tests.
+#[derive(Default)]
+pub struct MemoryKeyValueStore {
+    entries: RwLock<BTreeMap<String, Vec<u8>>>,
+}
+
+impl MemoryKeyValueStore {
+    /// Create a new empty memory key-value store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl KeyValueStore for MemoryKeyValueStore {
+    fn get(&self, key: &str) -> StorageFuture<'_, Option<Vec<u8>>> {
+        let key = key.to_string();
+        Box::pin(async move { Ok(self.entries.read().unwrap().get(&key).cloned()) })
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> StorageFuture<'_, ()> {
+        let key = key.to_string();
+        let value = value.to_vec();
+        Box::pin(async move {
+            self.entries.write().unwrap().insert(key, value);
+            Ok(())
+        })
+    }
+
+    fn delete(&self, key: &str) -> StorageFuture<'_, ()> {
+        let key = key.to_string();
+        Box::pin(async move {
+            self.entries.write().unwrap().remove(&key);
+            Ok(())
+        })
+    }
+
+    fn scan_prefix(&self, prefix: &str) -> StorageFuture<'_, Vec<(String, Vec<u8>)>> {
+        let prefix = prefix.to_string();
+        Box::pin(async move {
+            let entries = self.entries.read().unwrap();
+            Ok(entries
+                .range(prefix.clone()..)
+                .take_while(|(key, _)| key.starts_with(&prefix))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::conformance;
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_conformance() {
+        conformance::run_wallet_storage_suite(MemoryWalletStorage::new()).await;
+        conformance::run_swap_storage_suite(MemorySwapStorage::new()).await;
+    }
+}
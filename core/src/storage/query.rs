@@ -0,0 +1,284 @@
+//! Filtering, sorting and pagination of stored swaps.
+//!
+//! A [`SwapQuery`] is run by [`SwapStorage::query`](super::SwapStorage::query). The default
+//! implementation loads all swaps and applies the query with [`SwapQuery::apply`]; backends that
+//! can filter and sort by themselves, like the SQLite storage, override it.
+//!
+//! Swaps are ordered by their creation time, then by ID. Creation times are compared in whole
+//! seconds. A page's [`SwapPage::next_cursor`] points after its last swap and is passed as
+//! [`SwapQuery::cursor`] to get the next page. Cursors are opaque: only pass them to the storage
+//! that returned them.
+
+use crate::api::{Chain, SwapDirection, SwapStatus, TokenId};
+use crate::client::ExtendedSwapStorageData;
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+/// Order of the swaps returned by a query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    /// Oldest swaps first.
+    Ascending,
+    /// Newest swaps first.
+    #[default]
+    Descending,
+}
+
+/// Filter, sort order and page of a swap query.
+///
+/// The default query returns all swaps, newest first. Filters are combined: a swap is returned
+/// if it matches all of them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SwapQuery {
+    /// Only swaps with one of these statuses. Empty matches any status.
+    pub statuses: Vec<SwapStatus>,
+    /// Only swaps in this direction.
+    pub direction: Option<SwapDirection>,
+    /// Only swaps sending or receiving this token.
+    pub token: Option<TokenId>,
+    /// Only swaps sending or receiving a token on this chain.
+    pub chain: Option<Chain>,
+    /// Only swaps created at or after this time.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub created_from: Option<OffsetDateTime>,
+    /// Only swaps created before this time.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub created_until: Option<OffsetDateTime>,
+    /// Only swaps of this account.
+    pub account: Option<u32>,
+    /// Order of the returned swaps.
+    pub order: SortOrder,
+    /// Maximum number of swaps to return, at least 1. `None` returns all matching swaps.
+    pub limit: Option<usize>,
+    /// Return the swaps after this cursor, taken from [`SwapPage::next_cursor`].
+    pub cursor: Option<String>,
+}
+
+/// A page of swaps returned by a query.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapPage {
+    /// The matching swaps, in the query's order.
+    pub swaps: Vec<ExtendedSwapStorageData>,
+    /// Cursor of the next page, or `None` if this is the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// Position of a swap in the query order: its creation time as a Unix timestamp, then its ID.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SwapCursor {
+    /// Creation time as a Unix timestamp.
+    pub created_at: i64,
+    /// Swap ID.
+    pub id: String,
+}
+
+impl SwapCursor {
+    /// The position of a stored swap.
+    pub fn of(swap: &ExtendedSwapStorageData) -> Self {
+        Self {
+            created_at: swap.response.common().created_at.unix_timestamp(),
+            id: swap.response.id(),
+        }
+    }
+
+    /// Parse a cursor returned in [`SwapPage::next_cursor`].
+    pub fn parse(cursor: &str) -> Result<Self> {
+        let (created_at, id) = cursor
+            .split_once(':')
+            .ok_or_else(|| Error::Parse(format!("Invalid swap cursor: {}", cursor)))?;
+        let created_at = created_at
+            .parse()
+            .map_err(|e| Error::Parse(format!("Invalid swap cursor {}: {}", cursor, e)))?;
+
+        Ok(Self {
+            created_at,
+            id: id.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for SwapCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.created_at, self.id)
+    }
+}
+
+impl SwapQuery {
+    /// The parsed [`SwapQuery::cursor`].
+    pub fn parsed_cursor(&self) -> Result<Option<SwapCursor>> {
+        self.cursor.as_deref().map(SwapCursor::parse).transpose()
+    }
+
+    /// The [`SwapQuery::limit`], rejecting a limit of 0, which would return empty pages forever.
+    pub fn checked_limit(&self) -> Result<Option<usize>> {
+        match self.limit {
+            Some(0) => Err(Error::Parse(
+                "Swap query limit must be at least 1".to_string(),
+            )),
+            limit => Ok(limit),
+        }
+    }
+
+    /// Whether a swap matches the query's filters. The cursor is not taken into account.
+    pub fn matches(&self, swap: &ExtendedSwapStorageData) -> bool {
+        let response = &swap.response;
+        let created_at = response.common().created_at.unix_timestamp();
+        let (source_token, target_token) = response.tokens();
+
+        (self.statuses.is_empty() || self.statuses.contains(&response.status()))
+            && self.direction.is_none_or(|d| d == response.direction())
+            && self
+                .token
+                .as_ref()
+                .is_none_or(|t| t == source_token || t == target_token)
+            && self
+                .chain
+                .is_none_or(|c| source_token.chain() == Some(c) || target_token.chain() == Some(c))
+            && self
+                .created_from
+                .is_none_or(|from| created_at >= from.unix_timestamp())
+            && self
+                .created_until
+                .is_none_or(|until| created_at < until.unix_timestamp())
+            && self.account.is_none_or(|a| a == swap.swap_params.account)
+    }
+
+    /// Run the query on the given swaps.
+    pub fn apply(&self, swaps: Vec<ExtendedSwapStorageData>) -> Result<SwapPage> {
+        let cursor = self.parsed_cursor()?;
+        let limit = self.checked_limit()?;
+        let mut swaps: Vec<(SwapCursor, ExtendedSwapStorageData)> = swaps
+            .into_iter()
+            .filter(|swap| self.matches(swap))
+            .map(|swap| (SwapCursor::of(&swap), swap))
+            .filter(|(position, _)| match (&cursor, self.order) {
+                (None, _) => true,
+                (Some(cursor), SortOrder::Ascending) => position > cursor,
+                (Some(cursor), SortOrder::Descending) => position < cursor,
+            })
+            .collect();
+
+        swaps.sort_by(|(a, _), (b, _)| match self.order {
+            SortOrder::Ascending => a.cmp(b),
+            SortOrder::Descending => b.cmp(a),
+        });
+
+        let next_cursor = match limit {
+            Some(limit) if swaps.len() > limit => {
+                swaps.truncate(limit);
+                swaps.last().map(|(position, _)| position.to_string())
+            }
+            _ => None,
+        };
+
+        Ok(SwapPage {
+            swaps: swaps.into_iter().map(|(_, swap)| swap).collect(),
+            next_cursor,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::GetSwapResponse;
+    use crate::storage::conformance::sample_swap;
+    use time::macros::datetime;
+
+    fn swap(id: &str, status: SwapStatus, created_at: OffsetDateTime) -> ExtendedSwapStorageData {
+        let mut swap = sample_swap(id, status);
+        match &mut swap.response {
+            GetSwapResponse::BtcToEvm(r) => r.common.created_at = created_at,
+            GetSwapResponse::EvmToBtc(r) => r.common.created_at = created_at,
+        }
+        swap
+    }
+
+    const SWAP_A: &str = "00000000-0000-0000-0000-00000000000a";
+    const SWAP_B: &str = "00000000-0000-0000-0000-00000000000b";
+    const SWAP_C: &str = "00000000-0000-0000-0000-00000000000c";
+    const SWAP_D: &str = "00000000-0000-0000-0000-00000000000d";
+
+    fn ids(page: &SwapPage) -> Vec<String> {
+        page.swaps.iter().map(|swap| swap.response.id()).collect()
+    }
+
+    #[test]
+    fn test_filter_and_paginate() {
+        let swaps = vec![
+            swap(SWAP_A, SwapStatus::Pending, datetime!(2025-01-01 0:00 UTC)),
+            swap(
+                SWAP_B,
+                SwapStatus::ServerFunded,
+                datetime!(2025-01-02 0:00 UTC),
+            ),
+            swap(SWAP_C, SwapStatus::Pending, datetime!(2025-01-03 0:00 UTC)),
+            swap(SWAP_D, SwapStatus::Pending, datetime!(2025-01-03 0:00 UTC)),
+        ];
+
+        let query = SwapQuery {
+            statuses: vec![SwapStatus::Pending],
+            limit: Some(2),
+            ..SwapQuery::default()
+        };
+        let page = query.apply(swaps.clone()).unwrap();
+        assert_eq!(ids(&page), [SWAP_D, SWAP_C]);
+
+        let query = SwapQuery {
+            cursor: page.next_cursor,
+            ..query
+        };
+        let page = query.apply(swaps.clone()).unwrap();
+        assert_eq!(ids(&page), [SWAP_A]);
+        assert_eq!(page.next_cursor, None);
+
+        let query = SwapQuery {
+            created_from: Some(datetime!(2025-01-02 0:00 UTC)),
+            created_until: Some(datetime!(2025-01-03 0:00 UTC)),
+            order: SortOrder::Ascending,
+            ..SwapQuery::default()
+        };
+        assert_eq!(ids(&query.apply(swaps.clone()).unwrap()), [SWAP_B]);
+
+        let query = SwapQuery {
+            token: Some(TokenId::Coin("usdc_pol".to_string())),
+            chain: Some(Chain::Arkade),
+            direction: Some(SwapDirection::BtcToEvm),
+            account: Some(0),
+            order: SortOrder::Ascending,
+            ..SwapQuery::default()
+        };
+        assert_eq!(
+            ids(&query.apply(swaps.clone()).unwrap()),
+            [SWAP_A, SWAP_B, SWAP_C, SWAP_D]
+        );
+
+        let query = SwapQuery {
+            chain: Some(Chain::Ethereum),
+            ..SwapQuery::default()
+        };
+        assert!(query.apply(swaps).unwrap().swaps.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_cursor() {
+        let query = SwapQuery {
+            cursor: Some("not a cursor".to_string()),
+            ..SwapQuery::default()
+        };
+        assert!(query.apply(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_zero_limit() {
+        let query = SwapQuery {
+            limit: Some(0),
+            ..SwapQuery::default()
+        };
+        let swaps = vec![sample_swap(SWAP_A, SwapStatus::Pending)];
+        assert!(matches!(query.apply(swaps), Err(Error::Parse(_))));
+    }
+}
@@ -0,0 +1,346 @@
+//! Versioning of stored swap records.
+//!
+//! Every [`ExtendedSwapStorageData`] and [`ExtendedVtxoSwapStorageData`] is serialized with a
+//! `version` field. Records without one were written before versioning and have version 0. When
+//! a record is deserialized, the migrations it hasn't seen yet are applied to its JSON, so old
+//! records stay readable when the API types change. Storing the record again writes it in the
+//! current format, see [`crate::Client::migrate_swap_storage`]. Records in an older format are
+//! listed by [`SwapStorage::list_outdated`](super::SwapStorage::list_outdated) and rewritten when
+//! the client is initialized.
+//!
+//! To change the stored format, append a migration that rewrites the previous version's JSON.
+//! The current version is the number of migrations.
+//!
+//! Records that can't be read, e.g. because a newer version of the SDK wrote them, are not
+//! returned by [`SwapStorage::get_all`](super::SwapStorage::get_all). They are quarantined: left
+//! untouched in storage and listed by
+//! [`SwapStorage::get_quarantined`](super::SwapStorage::get_quarantined), see
+//! [`read_swap_records`] and [`read_vtxo_swap_records`].
+
+use crate::api::{GetSwapResponse, VtxoSwapResponse};
+use crate::client::{ExtendedSwapStorageData, ExtendedVtxoSwapStorageData};
+use crate::types::PublicSwapParams;
+use serde::de::{DeserializeOwned, Error as _};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+/// Upgrades a record from the version of its index in the migration list to the next version.
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// Migrations of swap records, applied in order.
+const SWAP_RECORD_MIGRATIONS: &[Migration] = &[
+    // 1: Early versions stored the swap's secret key and preimage
+    strip_swap_secrets,
+];
+
+/// Migrations of VTXO swap records, applied in order.
+const VTXO_SWAP_RECORD_MIGRATIONS: &[Migration] = &[];
+
+/// The version swap records are written with.
+pub const SWAP_RECORD_VERSION: u32 = SWAP_RECORD_MIGRATIONS.len() as u32;
+
+/// The version VTXO swap records are written with.
+pub const VTXO_SWAP_RECORD_VERSION: u32 = VTXO_SWAP_RECORD_MIGRATIONS.len() as u32;
+
+const VERSION_FIELD: &str = "version";
+
+/// A stored record that can't be read.
+///
+/// The record is kept in storage as it is, so it can be read by a newer version of the SDK,
+/// exported, or deleted by the user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuarantinedRecord {
+    /// The swap ID, if it is known.
+    pub id: Option<String>,
+    /// Why the record can't be read.
+    pub error: String,
+    /// The record as stored.
+    pub data: String,
+}
+
+/// Read stored swap records, given as their IDs (if known) and JSON.
+///
+/// Returns the readable swaps and the quarantined records, each in the given order.
+pub fn read_swap_records(
+    records: impl IntoIterator<Item = (Option<String>, String)>,
+) -> (Vec<ExtendedSwapStorageData>, Vec<QuarantinedRecord>) {
+    read_records("swap", records)
+}
+
+/// Read stored VTXO swap records, given as their IDs (if known) and JSON.
+///
+/// Returns the readable VTXO swaps and the quarantined records, each in the given order.
+pub fn read_vtxo_swap_records(
+    records: impl IntoIterator<Item = (Option<String>, String)>,
+) -> (Vec<ExtendedVtxoSwapStorageData>, Vec<QuarantinedRecord>) {
+    read_records("VTXO swap", records)
+}
+
+fn read_records<T: DeserializeOwned>(
+    kind: &str,
+    records: impl IntoIterator<Item = (Option<String>, String)>,
+) -> (Vec<T>, Vec<QuarantinedRecord>) {
+    let mut readable = Vec::new();
+    let mut quarantined = Vec::new();
+    for (id, data) in records {
+        match serde_json::from_str(&data) {
+            Ok(record) => readable.push(record),
+            Err(e) => {
+                let id = id.or_else(|| record_id(&data));
+                log::warn!(
+                    "Skipping unreadable {} record {}: {}",
+                    kind,
+                    id.as_deref().unwrap_or("without ID"),
+                    e
+                );
+                quarantined.push(QuarantinedRecord {
+                    id,
+                    error: e.to_string(),
+                    data,
+                });
+            }
+        }
+    }
+
+    (readable, quarantined)
+}
+
+/// The IDs of the given swap records that were written in an older format than
+/// [`SWAP_RECORD_VERSION`] and should be rewritten, e.g. because they still hold the swap's
+/// secrets. Records without an ID or a readable version are left out.
+pub fn outdated_swap_records(
+    records: impl IntoIterator<Item = (Option<String>, String)>,
+) -> Vec<String> {
+    records
+        .into_iter()
+        .filter(|(_, data)| {
+            let Ok(Value::Object(record)) = serde_json::from_str(data) else {
+                return false;
+            };
+            match record.get(VERSION_FIELD) {
+                None => true,
+                Some(version) => version
+                    .as_u64()
+                    .is_some_and(|version| version < u64::from(SWAP_RECORD_VERSION)),
+            }
+        })
+        .filter_map(|(id, data)| id.or_else(|| record_id(&data)))
+        .collect()
+}
+
+/// The swap ID of a record that can't be deserialized, if its JSON contains one.
+fn record_id(data: &str) -> Option<String> {
+    let value: Value = serde_json::from_str(data).ok()?;
+    let id = value.get("response")?.get("id")?.as_str()?;
+    Some(id.to_string())
+}
+
+/// Apply the migrations the record hasn't seen yet and remove its version field.
+fn migrate(value: Value, migrations: &[Migration]) -> Result<Value, String> {
+    let Value::Object(mut record) = value else {
+        return Err("expected a swap record object".to_string());
+    };
+
+    let version = match record.remove(VERSION_FIELD) {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| format!("invalid record version {}", version))?
+            as usize,
+    };
+    if version > migrations.len() {
+        return Err(format!(
+            "record version {} is newer than the supported version {}",
+            version,
+            migrations.len()
+        ));
+    }
+
+    for (applied, migration) in migrations.iter().enumerate().skip(version) {
+        migration(&mut record)
+            .map_err(|e| format!("failed to migrate record to version {}: {}", applied + 1, e))?;
+    }
+
+    Ok(Value::Object(record))
+}
+
+fn strip_swap_secrets(record: &mut Map<String, Value>) -> Result<(), String> {
+    let swap_params = record
+        .get_mut("swap_params")
+        .and_then(Value::as_object_mut)
+        .ok_or("missing swap_params")?;
+    swap_params.remove("secret_key");
+    swap_params.remove("preimage");
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct SwapRecordRef<'a> {
+    version: u32,
+    response: &'a GetSwapResponse,
+    swap_params: &'a PublicSwapParams,
+}
+
+#[derive(Deserialize)]
+struct SwapRecord {
+    response: GetSwapResponse,
+    swap_params: PublicSwapParams,
+}
+
+impl Serialize for ExtendedSwapStorageData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SwapRecordRef {
+            version: SWAP_RECORD_VERSION,
+            response: &self.response,
+            swap_params: &self.swap_params,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtendedSwapStorageData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = migrate(Value::deserialize(deserializer)?, SWAP_RECORD_MIGRATIONS)
+            .map_err(D::Error::custom)?;
+        let record: SwapRecord = serde_json::from_value(value).map_err(D::Error::custom)?;
+
+        Ok(Self {
+            response: record.response,
+            swap_params: record.swap_params,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct VtxoSwapRecordRef<'a> {
+    version: u32,
+    response: &'a VtxoSwapResponse,
+    swap_params: &'a PublicSwapParams,
+}
+
+#[derive(Deserialize)]
+struct VtxoSwapRecord {
+    response: VtxoSwapResponse,
+    swap_params: PublicSwapParams,
+}
+
+impl Serialize for ExtendedVtxoSwapStorageData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        VtxoSwapRecordRef {
+            version: VTXO_SWAP_RECORD_VERSION,
+            response: &self.response,
+            swap_params: &self.swap_params,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtendedVtxoSwapStorageData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = migrate(
+            Value::deserialize(deserializer)?,
+            VTXO_SWAP_RECORD_MIGRATIONS,
+        )
+        .map_err(D::Error::custom)?;
+        let record: VtxoSwapRecord = serde_json::from_value(value).map_err(D::Error::custom)?;
+
+        Ok(Self {
+            response: record.response,
+            swap_params: record.swap_params,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{SwapStatus, VtxoSwapStatus};
+    use crate::storage::conformance::{sample_swap, sample_vtxo_swap};
+
+    const SWAP_A: &str = "00000000-0000-0000-0000-00000000000a";
+
+    #[test]
+    fn test_swap_record_roundtrip() {
+        let swap = sample_swap(SWAP_A, SwapStatus::Pending);
+        let value = serde_json::to_value(&swap).unwrap();
+        assert_eq!(value[VERSION_FIELD], SWAP_RECORD_VERSION);
+
+        let restored: ExtendedSwapStorageData = serde_json::from_value(value).unwrap();
+        assert_eq!(restored.response.id(), SWAP_A);
+        assert_eq!(restored.swap_params, swap.swap_params);
+    }
+
+    #[test]
+    fn test_migrate_unversioned_record() {
+        let swap = sample_swap(SWAP_A, SwapStatus::Pending);
+        let mut value = serde_json::to_value(&swap).unwrap();
+        value.as_object_mut().unwrap().remove(VERSION_FIELD);
+        value["swap_params"]["secret_key"] = Value::from("11".repeat(32));
+        value["swap_params"]["preimage"] = Value::from("22".repeat(32));
+
+        let migrated = migrate(value.clone(), SWAP_RECORD_MIGRATIONS).unwrap();
+        assert!(migrated["swap_params"].get("secret_key").is_none());
+        assert!(migrated["swap_params"].get("preimage").is_none());
+
+        let restored: ExtendedSwapStorageData = serde_json::from_value(value).unwrap();
+        assert_eq!(restored.swap_params, swap.swap_params);
+    }
+
+    #[test]
+    fn test_quarantine_unreadable_records() {
+        let swap = sample_swap(SWAP_A, SwapStatus::Pending);
+        let mut newer = serde_json::to_value(&swap).unwrap();
+        newer[VERSION_FIELD] = Value::from(SWAP_RECORD_VERSION + 1);
+        let newer = newer.to_string();
+
+        let (swaps, quarantined) = read_swap_records([
+            (None, serde_json::to_string(&swap).unwrap()),
+            (None, newer.clone()),
+            (Some("b".to_string()), "{".to_string()),
+        ]);
+
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(quarantined.len(), 2);
+        assert_eq!(quarantined[0].id.as_deref(), Some(SWAP_A));
+        assert!(quarantined[0].error.contains("newer"));
+        assert_eq!(quarantined[0].data, newer);
+        assert_eq!(quarantined[1].id.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn test_quarantine_unreadable_vtxo_swap_records() {
+        let swap = sample_vtxo_swap(SWAP_A, VtxoSwapStatus::Pending);
+        let mut newer = serde_json::to_value(&swap).unwrap();
+        newer[VERSION_FIELD] = Value::from(VTXO_SWAP_RECORD_VERSION + 1);
+
+        let (swaps, quarantined) = read_vtxo_swap_records([
+            (None, newer.to_string()),
+            (None, serde_json::to_string(&swap).unwrap()),
+        ]);
+
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].response.id, swap.response.id);
+        assert_eq!(quarantined.len(), 1);
+        assert_eq!(quarantined[0].id.as_deref(), Some(SWAP_A));
+        assert!(quarantined[0].error.contains("newer"));
+    }
+
+    #[test]
+    fn test_outdated_swap_records() {
+        let swap = sample_swap(SWAP_A, SwapStatus::Pending);
+        let current = serde_json::to_value(&swap).unwrap();
+        let mut unversioned = current.clone();
+        unversioned.as_object_mut().unwrap().remove(VERSION_FIELD);
+        let mut newer = current.clone();
+        newer[VERSION_FIELD] = Value::from(SWAP_RECORD_VERSION + 1);
+
+        let outdated = outdated_swap_records([
+            (Some("a".to_string()), current.to_string()),
+            (Some("b".to_string()), unversioned.to_string()),
+            (None, unversioned.to_string()),
+            (Some("c".to_string()), newer.to_string()),
+            (Some("d".to_string()), "{".to_string()),
+        ]);
+        assert_eq!(outdated, ["b", SWAP_A]);
+    }
+}
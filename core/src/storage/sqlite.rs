@@ -0,0 +1,933 @@
+//! SQLite storage for native apps.
+//!
+//! [`SqliteStorage`] implements both [`WalletStorage`] and [`SwapStorage`] on a single database
+//! file. Swaps are stored as JSON, with their ID, status, direction, creation time, account and
+//! tokens in columns for querying; VTXO swaps, swap events and pending creations have tables of
+//! their own. The schema is versioned with SQLite's `user_version` and migrated when the database
+//! is opened.
+//!
+//! The mnemonic is stored as given: wrap the storage in an
+//! [`EncryptedWalletStorage`](super::EncryptedWalletStorage) to keep it encrypted at rest.
+
+use super::schema::{
+    QuarantinedRecord, outdated_swap_records, read_swap_records, read_vtxo_swap_records,
+};
+use super::{
+    SortOrder, StorageFuture, SwapCursor, SwapPage, SwapQuery, SwapStorage, WalletStorage,
+};
+use crate::api::{Chain, TokenId};
+use crate::client::{ExtendedSwapStorageData, ExtendedVtxoSwapStorageData};
+use crate::error::{Error, Result};
+use crate::history::SwapEvent;
+use crate::journal::PendingCreation;
+use crate::types::DerivationScheme;
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension, TransactionBehavior, params, params_from_iter};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Schema migrations, applied in order. The schema version is the number of applied migrations.
+const MIGRATIONS: &[&str] = &[
+    // 1: Initial schema
+    "CREATE TABLE wallet (
+        name TEXT PRIMARY KEY NOT NULL,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE key_indices (
+        account INTEGER PRIMARY KEY NOT NULL,
+        key_index INTEGER NOT NULL
+    );
+    CREATE TABLE swaps (
+        id TEXT PRIMARY KEY NOT NULL,
+        status TEXT NOT NULL,
+        direction TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX swaps_status ON swaps (status);
+    CREATE INDEX swaps_direction ON swaps (direction);
+    CREATE INDEX swaps_created_at ON swaps (created_at);",
+    // 2: VTXO swaps
+    "CREATE TABLE vtxo_swaps (
+        id TEXT PRIMARY KEY NOT NULL,
+        status TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX vtxo_swaps_status ON vtxo_swaps (status);",
+    // 3: Account and token columns for querying swaps
+    "ALTER TABLE swaps ADD COLUMN account INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE swaps ADD COLUMN source_token TEXT NOT NULL DEFAULT '';
+    ALTER TABLE swaps ADD COLUMN target_token TEXT NOT NULL DEFAULT '';
+    UPDATE swaps SET
+        account = COALESCE(json_extract(data, '$.swap_params.account'), 0),
+        source_token = COALESCE(json_extract(data, '$.response.source_token'), ''),
+        target_token = COALESCE(json_extract(data, '$.response.target_token'), '')
+    WHERE json_valid(data);
+    CREATE INDEX swaps_account_created_at ON swaps (account, created_at);",
+    // 4: Swap event histories
+    "CREATE TABLE swap_events (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        swap_id TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX swap_events_swap_id ON swap_events (swap_id, seq);",
+    // 5: Journal of swap creations
+    "CREATE TABLE pending_creations (
+        user_id TEXT PRIMARY KEY NOT NULL,
+        data TEXT NOT NULL
+    );",
+];
+
+/// How long to wait for a lock held by another connection before giving up.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const MNEMONIC: &str = "mnemonic";
+const PASSPHRASE_FINGERPRINT: &str = "passphrase_fingerprint";
+const DERIVATION_SCHEME: &str = "derivation_scheme";
+
+/// Wallet and swap storage in a SQLite database.
+///
+/// Clones share the same connection, so several [`crate::Client`]s in one process can use the
+/// same database: give each a clone. Other processes may open the same file; the database runs
+/// in WAL mode and key indices are reserved in write transactions, so they never hand out the
+/// same index twice.
+///
+/// Queries run synchronously on the calling task. They are short, but callers on a
+/// multi-threaded runtime with many concurrent clients may want to keep that in mind.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use lendaswap_core::storage::SqliteStorage;
+///
+/// let storage = SqliteStorage::open("lendaswap.db")?;
+/// let client = Client::new(url, storage.clone(), storage, network, arkade_url);
+/// ```
+#[derive(Clone)]
+pub struct SqliteStorage {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStorage {
+    /// Open (or create) the database at `path` and migrate it to the current schema.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path).map_err(sqlite_error)?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+            .map_err(sqlite_error)?;
+        Self::from_connection(conn)
+    }
+
+    /// Open a new in-memory database, e.g. for tests.
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory().map_err(sqlite_error)?)
+    }
+
+    fn from_connection(mut conn: Connection) -> Result<Self> {
+        conn.busy_timeout(BUSY_TIMEOUT).map_err(sqlite_error)?;
+        migrate(&mut conn)?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// The schema version of the database.
+    pub fn schema_version(&self) -> Result<u32> {
+        self.with_connection(|conn| conn.query_row("PRAGMA user_version", [], |row| row.get(0)))
+    }
+
+    fn with_connection<T>(
+        &self,
+        f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+    ) -> Result<T> {
+        let mut conn = self.conn.lock().expect("connection lock poisoned");
+        f(&mut *conn).map_err(sqlite_error)
+    }
+
+    fn get_value(&self, name: &str) -> Result<Option<String>> {
+        self.with_connection(|conn| {
+            conn.query_row(
+                "SELECT value FROM wallet WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()
+        })
+    }
+
+    fn set_value(&self, name: &str, value: Option<&str>) -> Result<()> {
+        self.with_connection(|conn| {
+            match value {
+                Some(value) => conn.execute(
+                    "INSERT INTO wallet (name, value) VALUES (?1, ?2)
+                     ON CONFLICT (name) DO UPDATE SET value = excluded.value",
+                    params![name, value],
+                )?,
+                None => conn.execute("DELETE FROM wallet WHERE name = ?1", params![name])?,
+            };
+            Ok(())
+        })
+    }
+
+    fn key_index(&self, account: u32) -> Result<u32> {
+        self.with_connection(|conn| {
+            let index = conn
+                .query_row(
+                    "SELECT key_index FROM key_indices WHERE account = ?1",
+                    params![account],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            Ok(index.unwrap_or(0))
+        })
+    }
+
+    fn set_key_index_of(&self, account: u32, index: u32) -> Result<()> {
+        self.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO key_indices (account, key_index) VALUES (?1, ?2)
+                 ON CONFLICT (account) DO UPDATE SET key_index = excluded.key_index",
+                params![account, index],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Reserve the key index of `account` in a write transaction.
+    fn reserve_key_index_of(&self, account: u32) -> Result<u32> {
+        let reserved = self.with_connection(|conn| {
+            let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+            let current: u32 = tx
+                .query_row(
+                    "SELECT key_index FROM key_indices WHERE account = ?1",
+                    params![account],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .unwrap_or(0);
+            let Some(next) = current.checked_add(1) else {
+                return Ok(None);
+            };
+            tx.execute(
+                "INSERT INTO key_indices (account, key_index) VALUES (?1, ?2)
+                 ON CONFLICT (account) DO UPDATE SET key_index = excluded.key_index",
+                params![account, next],
+            )?;
+            tx.commit()?;
+            Ok(Some(current))
+        })?;
+
+        reserved.ok_or_else(|| Error::Storage("key index exhausted".to_string()))
+    }
+
+    /// The IDs and JSON of all swap records in `table`, in order of creation.
+    fn swap_records(&self, table: &str) -> Result<Vec<(Option<String>, String)>> {
+        self.with_connection(|conn| {
+            let mut statement = conn.prepare(&format!(
+                "SELECT id, data FROM {table} ORDER BY created_at, id"
+            ))?;
+            let rows = statement
+                .query_map([], |row| Ok((Some(row.get(0)?), row.get(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        })
+    }
+
+    /// Run a swap query in SQL, returning the positions and JSON of the page's swaps and the
+    /// position of the next page's first swap, if any.
+    fn query_records(
+        &self,
+        query: &SwapQuery,
+    ) -> Result<(Vec<(SwapCursor, String)>, Option<SwapCursor>)> {
+        let mut conditions = Vec::new();
+        let mut values = Vec::new();
+
+        if !query.statuses.is_empty() {
+            let placeholders = vec!["?"; query.statuses.len()].join(", ");
+            conditions.push(format!("status IN ({})", placeholders));
+            for status in &query.statuses {
+                values.push(Value::Text(serde_name(status)?));
+            }
+        }
+        if let Some(direction) = &query.direction {
+            conditions.push("direction = ?".to_string());
+            values.push(Value::Text(serde_name(direction)?));
+        }
+        if let Some(token) = &query.token {
+            conditions.push("(source_token = ? OR target_token = ?)".to_string());
+            values.push(Value::Text(token.as_str().to_string()));
+            values.push(Value::Text(token.as_str().to_string()));
+        }
+        if let Some(chain) = query.chain {
+            let (condition, pattern) = chain_condition(chain);
+            conditions.push(format!(
+                "(source_token {} OR target_token {})",
+                condition, condition
+            ));
+            values.push(Value::Text(pattern.clone()));
+            values.push(Value::Text(pattern));
+        }
+        if let Some(from) = query.created_from {
+            conditions.push("created_at >= ?".to_string());
+            values.push(Value::Integer(from.unix_timestamp()));
+        }
+        if let Some(until) = query.created_until {
+            conditions.push("created_at < ?".to_string());
+            values.push(Value::Integer(until.unix_timestamp()));
+        }
+        if let Some(account) = query.account {
+            conditions.push("account = ?".to_string());
+            values.push(Value::Integer(account.into()));
+        }
+
+        let (comparison, direction) = match query.order {
+            SortOrder::Ascending => (">", "ASC"),
+            SortOrder::Descending => ("<", "DESC"),
+        };
+        let limit = query.checked_limit()?;
+        if let Some(cursor) = query.parsed_cursor()? {
+            conditions.push(format!("(created_at, id) {} (?, ?)", comparison));
+            values.push(Value::Integer(cursor.created_at));
+            values.push(Value::Text(cursor.id));
+        }
+
+        let mut sql = "SELECT created_at, id, data FROM swaps".to_string();
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(&format!(
+            " ORDER BY created_at {}, id {}",
+            direction, direction
+        ));
+        if let Some(limit) = limit {
+            // One more row tells whether there is a next page
+            sql.push_str(" LIMIT ?");
+            let limit = i64::try_from(limit.saturating_add(1)).unwrap_or(i64::MAX);
+            values.push(Value::Integer(limit));
+        }
+
+        let mut rows = self.with_connection(|conn| {
+            let mut statement = conn.prepare(&sql)?;
+            let rows = statement
+                .query_map(params_from_iter(values), |row| {
+                    let cursor = SwapCursor {
+                        created_at: row.get(0)?,
+                        id: row.get(1)?,
+                    };
+                    Ok((cursor, row.get(2)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        })?;
+
+        let next = match limit {
+            Some(limit) if rows.len() > limit => {
+                rows.truncate(limit);
+                rows.last().map(|(cursor, _)| cursor.clone())
+            }
+            _ => None,
+        };
+
+        Ok((rows, next))
+    }
+}
+
+impl WalletStorage for SqliteStorage {
+    fn get_mnemonic(&self) -> StorageFuture<'_, Option<String>> {
+        Box::pin(async move { self.get_value(MNEMONIC) })
+    }
+
+    fn set_mnemonic(&self, mnemonic: &str) -> StorageFuture<'_, ()> {
+        let mnemonic = mnemonic.to_string();
+        Box::pin(async move { self.set_value(MNEMONIC, Some(&mnemonic)) })
+    }
+
+    fn get_key_index(&self) -> StorageFuture<'_, u32> {
+        Box::pin(async move { self.key_index(0) })
+    }
+
+    fn set_key_index(&self, index: u32) -> StorageFuture<'_, ()> {
+        Box::pin(async move { self.set_key_index_of(0, index) })
+    }
+
+    fn reserve_key_index(&self) -> StorageFuture<'_, u32> {
+        Box::pin(async move { self.reserve_key_index_of(0) })
+    }
+
+    fn get_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        Box::pin(async move { self.key_index(account) })
+    }
+
+    fn set_account_key_index(&self, account: u32, index: u32) -> StorageFuture<'_, ()> {
+        Box::pin(async move { self.set_key_index_of(account, index) })
+    }
+
+    fn reserve_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        Box::pin(async move { self.reserve_key_index_of(account) })
+    }
+
+    fn get_passphrase_fingerprint(&self) -> StorageFuture<'_, Option<String>> {
+        Box::pin(async move { self.get_value(PASSPHRASE_FINGERPRINT) })
+    }
+
+    fn set_passphrase_fingerprint(&self, fingerprint: Option<&str>) -> StorageFuture<'_, ()> {
+        let fingerprint = fingerprint.map(str::to_string);
+        Box::pin(async move { self.set_value(PASSPHRASE_FINGERPRINT, fingerprint.as_deref()) })
+    }
+
+    fn get_derivation_scheme(&self) -> StorageFuture<'_, Option<DerivationScheme>> {
+        Box::pin(async move {
+            let Some(value) = self.get_value(DERIVATION_SCHEME)? else {
+                return Ok(None);
+            };
+            let version = value
+                .parse::<u8>()
+                .map_err(|e| Error::Storage(format!("Invalid derivation scheme: {}", e)))?;
+            Ok(Some(DerivationScheme::try_from(version)?))
+        })
+    }
+
+    fn set_derivation_scheme(&self, scheme: DerivationScheme) -> StorageFuture<'_, ()> {
+        Box::pin(
+            async move { self.set_value(DERIVATION_SCHEME, Some(&scheme.version().to_string())) },
+        )
+    }
+}
+
+impl SwapStorage for SqliteStorage {
+    fn get(&self, swap_id: &str) -> StorageFuture<'_, Option<ExtendedSwapStorageData>> {
+        let swap_id = swap_id.to_string();
+        Box::pin(async move {
+            let data: Option<String> = self.with_connection(|conn| {
+                conn.query_row(
+                    "SELECT data FROM swaps WHERE id = ?1",
+                    params![swap_id],
+                    |row| row.get(0),
+                )
+                .optional()
+            })?;
+
+            match data {
+                Some(data) => Ok(Some(serde_json::from_str(&data)?)),
+                None => Ok(None),
+            }
+        })
+    }
+
+    fn store(&self, swap_id: &str, data: &ExtendedSwapStorageData) -> StorageFuture<'_, ()> {
+        let swap_id = swap_id.to_string();
+        let row = SwapRow::new(data);
+        Box::pin(async move {
+            let row = row?;
+            self.with_connection(|conn| {
+                conn.execute(
+                    "INSERT INTO swaps
+                        (id, status, direction, created_at, account, source_token, target_token,
+                         data)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                     ON CONFLICT (id) DO UPDATE SET
+                        status = excluded.status,
+                        direction = excluded.direction,
+                        created_at = excluded.created_at,
+                        account = excluded.account,
+                        source_token = excluded.source_token,
+                        target_token = excluded.target_token,
+                        data = excluded.data",
+                    params![
+                        swap_id,
+                        row.status,
+                        row.direction,
+                        row.created_at,
+                        row.account,
+                        row.source_token,
+                        row.target_token,
+                        row.data
+                    ],
+                )?;
+                Ok(())
+            })
+        })
+    }
+
+    fn delete(&self, swap_id: &str) -> StorageFuture<'_, ()> {
+        let swap_id = swap_id.to_string();
+        Box::pin(async move {
+            self.with_connection(|conn| {
+                conn.execute("DELETE FROM swaps WHERE id = ?1", params![swap_id])?;
+                Ok(())
+            })
+        })
+    }
+
+    fn list(&self) -> StorageFuture<'_, Vec<String>> {
+        Box::pin(async move {
+            self.with_connection(|conn| {
+                let mut statement = conn.prepare("SELECT id FROM swaps ORDER BY created_at, id")?;
+                let ids = statement
+                    .query_map([], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                Ok(ids)
+            })
+        })
+    }
+
+    fn get_all(&self) -> StorageFuture<'_, Vec<ExtendedSwapStorageData>> {
+        Box::pin(async move {
+            let (swaps, _) = read_swap_records(self.swap_records("swaps")?);
+            Ok(swaps)
+        })
+    }
+
+    fn get_quarantined(&self) -> StorageFuture<'_, Vec<QuarantinedRecord>> {
+        Box::pin(async move {
+            let (_, mut quarantined) = read_swap_records(self.swap_records("swaps")?);
+            let (_, vtxo_quarantined) = read_vtxo_swap_records(self.swap_records("vtxo_swaps")?);
+            quarantined.extend(vtxo_quarantined);
+            Ok(quarantined)
+        })
+    }
+
+    fn list_outdated(&self) -> StorageFuture<'_, Vec<String>> {
+        Box::pin(async move { Ok(outdated_swap_records(self.swap_records("swaps")?)) })
+    }
+
+    fn query(&self, query: &SwapQuery) -> StorageFuture<'_, SwapPage> {
+        let query = query.clone();
+        Box::pin(async move {
+            let (rows, next) = self.query_records(&query)?;
+            let (swaps, _) = read_swap_records(
+                rows.into_iter()
+                    .map(|(cursor, data)| (Some(cursor.id), data)),
+            );
+
+            Ok(SwapPage {
+                swaps,
+                next_cursor: next.map(|cursor| cursor.to_string()),
+            })
+        })
+    }
+
+    fn append_swap_event(&self, event: &SwapEvent) -> StorageFuture<'_, ()> {
+        let swap_id = event.swap_id.clone();
+        let data = serde_json::to_string(event);
+        Box::pin(async move {
+            let data = data?;
+            self.with_connection(|conn| {
+                conn.execute(
+                    "INSERT INTO swap_events (swap_id, data) VALUES (?1, ?2)",
+                    params![swap_id, data],
+                )?;
+                Ok(())
+            })
+        })
+    }
+
+    fn get_swap_events(&self, swap_id: &str) -> StorageFuture<'_, Vec<SwapEvent>> {
+        let swap_id = swap_id.to_string();
+        Box::pin(async move {
+            let rows = self.with_connection(|conn| {
+                let mut statement =
+                    conn.prepare("SELECT data FROM swap_events WHERE swap_id = ?1 ORDER BY seq")?;
+                let rows = statement
+                    .query_map(params![swap_id], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                Ok(rows)
+            })?;
+
+            rows.iter()
+                .map(|data| Ok(serde_json::from_str(data)?))
+                .collect()
+        })
+    }
+
+    fn get_all_swap_events(&self) -> StorageFuture<'_, Vec<SwapEvent>> {
+        Box::pin(async move {
+            let rows = self.with_connection(|conn| {
+                let mut statement =
+                    conn.prepare("SELECT data FROM swap_events ORDER BY swap_id, seq")?;
+                let rows = statement
+                    .query_map([], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                Ok(rows)
+            })?;
+
+            rows.iter()
+                .map(|data| Ok(serde_json::from_str(data)?))
+                .collect()
+        })
+    }
+
+    fn store_pending_creation(&self, pending: &PendingCreation) -> StorageFuture<'_, ()> {
+        let user_id = pending.user_id();
+        let data = serde_json::to_string(pending);
+        Box::pin(async move {
+            let data = data?;
+            self.with_connection(|conn| {
+                conn.execute(
+                    "INSERT INTO pending_creations (user_id, data) VALUES (?1, ?2)
+                     ON CONFLICT (user_id) DO UPDATE SET data = excluded.data",
+                    params![user_id, data],
+                )?;
+                Ok(())
+            })
+        })
+    }
+
+    fn delete_pending_creation(&self, user_id: &str) -> StorageFuture<'_, ()> {
+        let user_id = user_id.to_string();
+        Box::pin(async move {
+            self.with_connection(|conn| {
+                conn.execute(
+                    "DELETE FROM pending_creations WHERE user_id = ?1",
+                    params![user_id],
+                )?;
+                Ok(())
+            })
+        })
+    }
+
+    fn get_pending_creations(&self) -> StorageFuture<'_, Vec<PendingCreation>> {
+        Box::pin(async move {
+            let rows = self.with_connection(|conn| {
+                let mut statement =
+                    conn.prepare("SELECT data FROM pending_creations ORDER BY user_id")?;
+                let rows = statement
+                    .query_map([], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                Ok(rows)
+            })?;
+
+            rows.iter()
+                .map(|data| Ok(serde_json::from_str(data)?))
+                .collect()
+        })
+    }
+
+    fn supports_vtxo_swaps(&self) -> bool {
+        true
+    }
+
+    fn get_vtxo_swap(
+        &self,
+        swap_id: &str,
+    ) -> StorageFuture<'_, Option<ExtendedVtxoSwapStorageData>> {
+        let swap_id = swap_id.to_string();
+        Box::pin(async move {
+            let data: Option<String> = self.with_connection(|conn| {
+                conn.query_row(
+                    "SELECT data FROM vtxo_swaps WHERE id = ?1",
+                    params![swap_id],
+                    |row| row.get(0),
+                )
+                .optional()
+            })?;
+
+            match data {
+                Some(data) => Ok(Some(serde_json::from_str(&data)?)),
+                None => Ok(None),
+            }
+        })
+    }
+
+    fn store_vtxo_swap(
+        &self,
+        swap_id: &str,
+        data: &ExtendedVtxoSwapStorageData,
+    ) -> StorageFuture<'_, ()> {
+        let swap_id = swap_id.to_string();
+        let status = serde_name(&data.response.status);
+        let created_at = data.response.created_at.unix_timestamp();
+        let data = serde_json::to_string(data);
+        Box::pin(async move {
+            let (status, data) = (status?, data?);
+            self.with_connection(|conn| {
+                conn.execute(
+                    "INSERT INTO vtxo_swaps (id, status, created_at, data)
+                     VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT (id) DO UPDATE SET
+                        status = excluded.status,
+                        created_at = excluded.created_at,
+                        data = excluded.data",
+                    params![swap_id, status, created_at, data],
+                )?;
+                Ok(())
+            })
+        })
+    }
+
+    fn delete_vtxo_swap(&self, swap_id: &str) -> StorageFuture<'_, ()> {
+        let swap_id = swap_id.to_string();
+        Box::pin(async move {
+            self.with_connection(|conn| {
+                conn.execute("DELETE FROM vtxo_swaps WHERE id = ?1", params![swap_id])?;
+                Ok(())
+            })
+        })
+    }
+
+    fn get_all_vtxo_swaps(&self) -> StorageFuture<'_, Vec<ExtendedVtxoSwapStorageData>> {
+        Box::pin(async move {
+            let (swaps, _) = read_vtxo_swap_records(self.swap_records("vtxo_swaps")?);
+            Ok(swaps)
+        })
+    }
+}
+
+/// The columns of a stored swap.
+struct SwapRow {
+    status: String,
+    direction: String,
+    /// Unix timestamp, so that rows sort chronologically.
+    created_at: i64,
+    account: u32,
+    source_token: String,
+    target_token: String,
+    data: String,
+}
+
+impl SwapRow {
+    fn new(data: &ExtendedSwapStorageData) -> Result<Self> {
+        let response = &data.response;
+        let (source_token, target_token) = response.tokens();
+        Ok(Self {
+            status: serde_name(&response.status())?,
+            direction: serde_name(&response.direction())?,
+            created_at: response.common().created_at.unix_timestamp(),
+            account: data.swap_params.account,
+            source_token: source_token.as_str().to_string(),
+            target_token: target_token.as_str().to_string(),
+            data: serde_json::to_string(data)?,
+        })
+    }
+}
+
+/// SQL condition on a token column matching the tokens of a chain (see [`TokenId::chain`]), and
+/// the value to bind to it.
+fn chain_condition(chain: Chain) -> (&'static str, String) {
+    match chain {
+        Chain::Arkade => ("= ?", TokenId::BtcArkade.as_str().to_string()),
+        Chain::Lightning => ("= ?", TokenId::BtcLightning.as_str().to_string()),
+        Chain::Polygon => ("LIKE ? ESCAPE '\\'", "%\\_pol".to_string()),
+        Chain::Ethereum => ("LIKE ? ESCAPE '\\'", "%\\_eth".to_string()),
+    }
+}
+
+/// The name a unit enum variant is serialized as, e.g. `serverfunded` for a status.
+fn serde_name<T: Serialize>(value: &T) -> Result<String> {
+    match serde_json::to_value(value)? {
+        serde_json::Value::String(name) => Ok(name),
+        other => Err(Error::Storage(format!("Expected a name, got {}", other))),
+    }
+}
+
+/// Apply the migrations the database hasn't seen yet.
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(sqlite_error)?;
+    let version: usize = tx
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(sqlite_error)?;
+    if version > MIGRATIONS.len() {
+        return Err(Error::Storage(format!(
+            "Database schema version {} is newer than the supported version {}",
+            version,
+            MIGRATIONS.len()
+        )));
+    }
+
+    for (applied, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        log::info!("Migrating database to schema version {}", applied + 1);
+        tx.execute_batch(migration).map_err(sqlite_error)?;
+        tx.pragma_update(None, "user_version", applied + 1)
+            .map_err(sqlite_error)?;
+    }
+
+    tx.commit().map_err(sqlite_error)
+}
+
+fn sqlite_error(e: rusqlite::Error) -> Error {
+    Error::Storage(format!("SQLite error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::SwapStatus;
+    use crate::storage::conformance::{self, sample_swap};
+
+    const SWAP_A: &str = "00000000-0000-0000-0000-00000000000a";
+    const SWAP_B: &str = "00000000-0000-0000-0000-00000000000b";
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_wallet_storage() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        assert_eq!(storage.schema_version().unwrap(), MIGRATIONS.len() as u32);
+
+        assert_eq!(storage.get_mnemonic().await.unwrap(), None);
+        storage.set_mnemonic("abandon about").await.unwrap();
+        assert_eq!(
+            storage.get_mnemonic().await.unwrap().as_deref(),
+            Some("abandon about")
+        );
+
+        assert_eq!(storage.reserve_key_index().await.unwrap(), 0);
+        assert_eq!(storage.reserve_key_index().await.unwrap(), 1);
+        assert_eq!(storage.reserve_account_key_index(3).await.unwrap(), 0);
+        assert_eq!(storage.get_key_index().await.unwrap(), 2);
+        assert_eq!(storage.get_account_key_index(3).await.unwrap(), 1);
+
+        storage
+            .set_passphrase_fingerprint(Some("deadbeef"))
+            .await
+            .unwrap();
+        assert_eq!(
+            storage
+                .get_passphrase_fingerprint()
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("deadbeef")
+        );
+        storage.set_passphrase_fingerprint(None).await.unwrap();
+        assert_eq!(storage.get_passphrase_fingerprint().await.unwrap(), None);
+
+        assert_eq!(storage.get_derivation_scheme().await.unwrap(), None);
+        storage
+            .set_derivation_scheme(DerivationScheme::V1)
+            .await
+            .unwrap();
+        assert_eq!(
+            storage.get_derivation_scheme().await.unwrap(),
+            Some(DerivationScheme::V1)
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_swap_storage() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+
+        storage
+            .store(SWAP_B, &sample_swap(SWAP_B, SwapStatus::Pending))
+            .await
+            .unwrap();
+        storage
+            .store(SWAP_A, &sample_swap(SWAP_A, SwapStatus::Pending))
+            .await
+            .unwrap();
+        storage
+            .store(SWAP_A, &sample_swap(SWAP_A, SwapStatus::ServerFunded))
+            .await
+            .unwrap();
+
+        assert_eq!(storage.list().await.unwrap(), vec![SWAP_A, SWAP_B]);
+        let stored = storage.get(SWAP_A).await.unwrap().unwrap();
+        assert_eq!(stored.response.status(), SwapStatus::ServerFunded);
+
+        let status: String = storage
+            .with_connection(|conn| {
+                conn.query_row(
+                    "SELECT status FROM swaps WHERE id = ?1",
+                    params![SWAP_A],
+                    |row| row.get(0),
+                )
+            })
+            .unwrap();
+        assert_eq!(status, "serverfunded");
+
+        storage.delete(SWAP_A).await.unwrap();
+        assert!(storage.get(SWAP_A).await.unwrap().is_none());
+        assert_eq!(storage.get_all().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_quarantine_unreadable_swaps() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        storage
+            .store(SWAP_A, &sample_swap(SWAP_A, SwapStatus::Pending))
+            .await
+            .unwrap();
+        storage
+            .with_connection(|conn| {
+                conn.execute(
+                    "INSERT INTO swaps (id, status, direction, created_at, data)
+                     VALUES (?1, 'pending', 'btc_to_evm', 0, '{\"version\": 999}')",
+                    params![SWAP_B],
+                )?;
+                conn.execute(
+                    "INSERT INTO vtxo_swaps (id, status, created_at, data)
+                     VALUES (?1, 'pending', 0, '{\"version\": 999}')",
+                    params![SWAP_A],
+                )
+            })
+            .unwrap();
+
+        let swaps = storage.get_all().await.unwrap();
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].response.id(), SWAP_A);
+        assert!(storage.get_all_vtxo_swaps().await.unwrap().is_empty());
+
+        let quarantined = storage.get_quarantined().await.unwrap();
+        assert_eq!(quarantined.len(), 2);
+        assert_eq!(quarantined[0].id.as_deref(), Some(SWAP_B));
+        assert_eq!(quarantined[1].id.as_deref(), Some(SWAP_A));
+        assert!(storage.get(SWAP_B).await.is_err());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_migrate_query_columns() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.execute_batch(MIGRATIONS[1]).unwrap();
+        conn.pragma_update(None, "user_version", 2).unwrap();
+        let data = serde_json::to_string(&sample_swap(SWAP_A, SwapStatus::Pending)).unwrap();
+        conn.execute(
+            "INSERT INTO swaps (id, status, direction, created_at, data)
+             VALUES (?1, 'pending', 'btc_to_evm', 1735689600, ?2)",
+            params![SWAP_A, data],
+        )
+        .unwrap();
+
+        let storage = SqliteStorage::from_connection(conn).unwrap();
+        assert_eq!(storage.schema_version().unwrap(), MIGRATIONS.len() as u32);
+
+        let query = SwapQuery {
+            token: Some(TokenId::BtcArkade),
+            account: Some(0),
+            ..SwapQuery::default()
+        };
+        let page = storage.query(&query).await.unwrap();
+        assert_eq!(page.swaps.len(), 1);
+        assert_eq!(page.swaps[0].response.id(), SWAP_A);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_conformance() {
+        conformance::run_wallet_storage_suite(SqliteStorage::open_in_memory().unwrap()).await;
+        conformance::run_swap_storage_suite(SqliteStorage::open_in_memory().unwrap()).await;
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_reopen_keeps_data() {
+        let dir = std::env::temp_dir().join(format!("lendaswap-sqlite-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("lendaswap.db");
+
+        {
+            let storage = SqliteStorage::open(&path).unwrap();
+            let clone = storage.clone();
+            storage.set_key_index(7).await.unwrap();
+            assert_eq!(clone.reserve_key_index().await.unwrap(), 7);
+        }
+
+        let storage = SqliteStorage::open(&path).unwrap();
+        assert_eq!(storage.get_key_index().await.unwrap(), 8);
+        assert_eq!(storage.schema_version().unwrap(), MIGRATIONS.len() as u32);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
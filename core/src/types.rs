@@ -1,7 +1,12 @@
 //! Shared types for the Lendaswap Client SDK.
 
 use bitcoin::secp256k1::{PublicKey, SecretKey};
+use secrecy::{CloneableSecret, ExposeSecret, SecretBox};
+use serde::ser::SerializeStruct;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::ops::Deref;
+use zeroize::{Zeroize, Zeroizing};
 
 /// Serde module for serializing `[u8; 32]` as hex strings.
 mod hex_bytes32 {
@@ -80,17 +85,401 @@ impl std::fmt::Display for Network {
     }
 }
 
+/// Wordlist used for BIP39 mnemonics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MnemonicLanguage {
+    #[default]
+    English,
+    SimplifiedChinese,
+    TraditionalChinese,
+    Czech,
+    French,
+    Italian,
+    Japanese,
+    Korean,
+    Portuguese,
+    Spanish,
+}
+
+impl MnemonicLanguage {
+    /// Convert to bip39 crate's Language type.
+    pub fn to_bip39_language(self) -> bip39::Language {
+        match self {
+            MnemonicLanguage::English => bip39::Language::English,
+            MnemonicLanguage::SimplifiedChinese => bip39::Language::SimplifiedChinese,
+            MnemonicLanguage::TraditionalChinese => bip39::Language::TraditionalChinese,
+            MnemonicLanguage::Czech => bip39::Language::Czech,
+            MnemonicLanguage::French => bip39::Language::French,
+            MnemonicLanguage::Italian => bip39::Language::Italian,
+            MnemonicLanguage::Japanese => bip39::Language::Japanese,
+            MnemonicLanguage::Korean => bip39::Language::Korean,
+            MnemonicLanguage::Portuguese => bip39::Language::Portuguese,
+            MnemonicLanguage::Spanish => bip39::Language::Spanish,
+        }
+    }
+}
+
+impl std::str::FromStr for MnemonicLanguage {
+    type Err = crate::error::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "english" | "en" => Ok(MnemonicLanguage::English),
+            "simplified_chinese" | "chinese_simplified" | "zh_hans" => {
+                Ok(MnemonicLanguage::SimplifiedChinese)
+            }
+            "traditional_chinese" | "chinese_traditional" | "zh_hant" => {
+                Ok(MnemonicLanguage::TraditionalChinese)
+            }
+            "czech" | "cs" => Ok(MnemonicLanguage::Czech),
+            "french" | "fr" => Ok(MnemonicLanguage::French),
+            "italian" | "it" => Ok(MnemonicLanguage::Italian),
+            "japanese" | "ja" => Ok(MnemonicLanguage::Japanese),
+            "korean" | "ko" => Ok(MnemonicLanguage::Korean),
+            "portuguese" | "pt" => Ok(MnemonicLanguage::Portuguese),
+            "spanish" | "es" => Ok(MnemonicLanguage::Spanish),
+            _ => Err(crate::error::Error::Parse(format!(
+                "Unknown mnemonic language: {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// Options for creating a new mnemonic.
+///
+/// By default a random 12-word English mnemonic is generated.
+///
+/// Caller-supplied entropy is the wallet's seed: it is wiped from memory when dropped and
+/// redacted from the `Debug` output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct MnemonicOptions {
+    /// Number of words (12, 15, 18, 21, or 24).
+    pub word_count: usize,
+    /// Wordlist to encode the mnemonic with.
+    pub language: MnemonicLanguage,
+    /// Caller-supplied entropy. If `None`, entropy is taken from the OS random number generator.
+    ///
+    /// Must be `word_count * 4 / 3` bytes long, i.e. 16 bytes for 12 words and 32 bytes for 24.
+    pub entropy: Option<Zeroizing<Vec<u8>>>,
+}
+
+impl fmt::Debug for MnemonicOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MnemonicOptions")
+            .field("word_count", &self.word_count)
+            .field("language", &self.language)
+            .field("entropy", &self.entropy.as_ref().map(|_| "[REDACTED]"))
+            .finish()
+    }
+}
+
+impl Default for MnemonicOptions {
+    fn default() -> Self {
+        Self {
+            word_count: 12,
+            language: MnemonicLanguage::default(),
+            entropy: None,
+        }
+    }
+}
+
+impl MnemonicOptions {
+    /// Options for a random mnemonic with the given number of words.
+    pub fn with_word_count(word_count: usize) -> Self {
+        Self {
+            word_count,
+            ..Self::default()
+        }
+    }
+
+    /// Options for a mnemonic encoding the given entropy.
+    ///
+    /// The word count follows from the entropy length.
+    pub fn from_entropy(entropy: Vec<u8>) -> Self {
+        Self {
+            word_count: entropy.len() * 3 / 4,
+            entropy: Some(Zeroizing::new(entropy)),
+            ..Self::default()
+        }
+    }
+
+    /// Options for a mnemonic derived from six-sided dice rolls.
+    ///
+    /// `rolls` are the rolled numbers, each between 1 and 6. The entropy is the SHA256 of the
+    /// rolls written as ASCII digits (as on Coldcard), truncated to the length required for
+    /// `word_count`. Enough rolls must be provided to cover that entropy: 50 rolls for 12 words
+    /// up to 99 rolls for 24 words.
+    pub fn from_dice_rolls(rolls: &[u8], word_count: usize) -> crate::error::Result<Self> {
+        use sha2::{Digest, Sha256};
+
+        let entropy_bytes = entropy_len(word_count)?;
+        if let Some(roll) = rolls.iter().find(|roll| !(1..=6).contains(*roll)) {
+            return Err(crate::error::Error::InvalidMnemonic(format!(
+                "Invalid dice roll: {}",
+                roll
+            )));
+        }
+
+        // Each roll of a six-sided die carries log2(6) bits of entropy.
+        let required = ((entropy_bytes * 8) as f64 / 6f64.log2()).ceil() as usize;
+        if rolls.len() < required {
+            return Err(crate::error::Error::InvalidMnemonic(format!(
+                "{} words require at least {} dice rolls, got {}",
+                word_count,
+                required,
+                rolls.len()
+            )));
+        }
+
+        let digits: Zeroizing<String> =
+            Zeroizing::new(rolls.iter().map(|roll| char::from(b'0' + roll)).collect());
+        let hash: Zeroizing<[u8; 32]> = Zeroizing::new(Sha256::digest(digits.as_bytes()).into());
+        let entropy = Zeroizing::new(hash[..entropy_bytes].to_vec());
+
+        Ok(Self {
+            word_count,
+            entropy: Some(entropy),
+            ..Self::default()
+        })
+    }
+
+    /// Use the given wordlist.
+    pub fn language(mut self, language: MnemonicLanguage) -> Self {
+        self.language = language;
+        self
+    }
+}
+
+/// Number of entropy bytes encoded by a mnemonic with the given number of words.
+pub(crate) fn entropy_len(word_count: usize) -> crate::error::Result<usize> {
+    match word_count {
+        12 | 15 | 18 | 21 | 24 => Ok(word_count * 4 / 3),
+        _ => Err(crate::error::Error::InvalidMnemonic(format!(
+            "Unsupported word count: {} (expected 12, 15, 18, 21, or 24)",
+            word_count
+        ))),
+    }
+}
+
+/// Version of the scheme used to derive a swap's key and preimage from the mnemonic.
+///
+/// Each swap records the scheme it was derived with, so that changes to the derivation only
+/// apply to new swaps and existing ones can still be claimed and refunded. User IDs are derived
+/// the same way in every scheme, since the server derives them from the user-ID Xpub for
+/// recovery.
+///
+/// Serialized as the version number. Records without a scheme were written before schemes were
+/// introduced and use [`DerivationScheme::V1`], which is also the `Default`.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(into = "u8", try_from = "u8")]
+#[non_exhaustive]
+pub enum DerivationScheme {
+    /// Signing key at `m/83696968'/121923'/{index}'`, preimage as a tagged hash of the key.
+    #[default]
+    V1,
+}
+
+impl DerivationScheme {
+    /// The scheme new wallets use.
+    pub const LATEST: Self = Self::V1;
+
+    /// All known schemes, oldest first.
+    pub const ALL: [Self; 1] = [Self::V1];
+
+    /// The version number of this scheme.
+    pub fn version(self) -> u8 {
+        match self {
+            DerivationScheme::V1 => 1,
+        }
+    }
+
+    /// Position of this scheme in [`DerivationScheme::ALL`].
+    pub(crate) fn position(self) -> usize {
+        usize::from(self.version() - 1)
+    }
+}
+
+impl From<DerivationScheme> for u8 {
+    fn from(scheme: DerivationScheme) -> Self {
+        scheme.version()
+    }
+}
+
+impl TryFrom<u8> for DerivationScheme {
+    type Error = crate::error::Error;
+
+    fn try_from(version: u8) -> Result<Self, Self::Error> {
+        DerivationScheme::ALL
+            .into_iter()
+            .find(|scheme| scheme.version() == version)
+            .ok_or_else(|| {
+                crate::error::Error::Parse(format!("Unknown derivation scheme: {}", version))
+            })
+    }
+}
+
+impl std::fmt::Display for DerivationScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "v{}", self.version())
+    }
+}
+
 /// Parameters derived for a swap operation.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// The secret key and preimage are redacted in `Debug` output and wiped from memory on drop.
+/// They are not serialized unless explicitly requested with [`SwapParams::with_secrets`].
+#[derive(Debug, Clone)]
 pub struct SwapParams {
-    pub secret_key: SecretKey,
+    pub secret_key: SecretBox<SwapSecretKey>,
+    pub public_key: PublicKey,
+    pub preimage: SecretBox<Preimage>,
+    pub preimage_hash: [u8; 32],
+    pub user_id: PublicKey,
+    pub key_index: u32,
+    /// The account the swap belongs to.
+    pub account: u32,
+    /// The scheme the key and preimage were derived with.
+    pub scheme: DerivationScheme,
+}
+
+impl SwapParams {
+    /// The public part of these parameters, safe to persist.
+    pub fn to_public(&self) -> PublicSwapParams {
+        PublicSwapParams {
+            public_key: self.public_key,
+            preimage_hash: self.preimage_hash,
+            user_id: self.user_id,
+            key_index: self.key_index,
+            account: self.account,
+            scheme: self.scheme,
+        }
+    }
+
+    /// Serialize these parameters including the secret key and preimage.
+    ///
+    /// `SwapParams` itself can't be serialized, so that secrets don't end up in logs or storage
+    /// by accident. Use [`SwapParams::to_public`] for anything that is persisted.
+    pub fn with_secrets(&self) -> SwapParamsWithSecrets<'_> {
+        SwapParamsWithSecrets(self)
+    }
+}
+
+/// Serializes [`SwapParams`] including their secrets, see [`SwapParams::with_secrets`].
+pub struct SwapParamsWithSecrets<'a>(&'a SwapParams);
+
+impl Serialize for SwapParamsWithSecrets<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let params = self.0;
+        let preimage = Zeroizing::new(hex::encode(**params.preimage.expose_secret()));
+
+        let mut state = serializer.serialize_struct("SwapParams", 8)?;
+        state.serialize_field("secret_key", &**params.secret_key.expose_secret())?;
+        state.serialize_field("public_key", &params.public_key)?;
+        state.serialize_field("preimage", preimage.as_str())?;
+        state.serialize_field("preimage_hash", &hex::encode(params.preimage_hash))?;
+        state.serialize_field("user_id", &params.user_id)?;
+        state.serialize_field("key_index", &params.key_index)?;
+        state.serialize_field("account", &params.account)?;
+        state.serialize_field("scheme", &params.scheme)?;
+        state.end()
+    }
+}
+
+/// A swap's secret key.
+///
+/// Held in a [`SecretBox`], which wipes it on drop. Dereferences to the [`SecretKey`].
+#[derive(Clone)]
+pub struct SwapSecretKey(SecretKey);
+
+impl From<SecretKey> for SwapSecretKey {
+    fn from(secret_key: SecretKey) -> Self {
+        Self(secret_key)
+    }
+}
+
+impl Deref for SwapSecretKey {
+    type Target = SecretKey;
+
+    fn deref(&self) -> &SecretKey {
+        &self.0
+    }
+}
+
+impl Zeroize for SwapSecretKey {
+    fn zeroize(&mut self) {
+        self.0.non_secure_erase();
+    }
+}
+
+impl CloneableSecret for SwapSecretKey {}
+
+impl fmt::Debug for SwapSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SwapSecretKey([REDACTED])")
+    }
+}
+
+/// A swap's preimage.
+///
+/// Held in a [`SecretBox`], which wipes it on drop. Dereferences to the preimage bytes.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Preimage([u8; 32]);
+
+impl From<[u8; 32]> for Preimage {
+    fn from(preimage: [u8; 32]) -> Self {
+        Self(preimage)
+    }
+}
+
+impl Deref for Preimage {
+    type Target = [u8; 32];
+
+    fn deref(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Zeroize for Preimage {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl CloneableSecret for Preimage {}
+
+impl fmt::Debug for Preimage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Preimage([REDACTED])")
+    }
+}
+
+/// The public part of [`SwapParams`], as persisted in swap storage.
+///
+/// The secret key and preimage are never stored. They are re-derived from `key_index` when
+/// needed, see [`crate::HdWallet::derive_swap_params`].
+///
+/// Records written by older versions still contain `secret_key` and `preimage`. These fields are
+/// ignored when deserializing, so re-storing such a record strips them. Records written before
+/// accounts were introduced belong to account 0, those written before derivation schemes were
+/// introduced use [`DerivationScheme::V1`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicSwapParams {
     pub public_key: PublicKey,
-    #[serde(with = "hex_bytes32")]
-    pub preimage: [u8; 32],
     #[serde(with = "hex_bytes32")]
     pub preimage_hash: [u8; 32],
     pub user_id: PublicKey,
     pub key_index: u32,
+    #[serde(default)]
+    pub account: u32,
+    #[serde(default)]
+    pub scheme: DerivationScheme,
 }
 
 /// VHTLC amounts returned from Arkade.
@@ -126,3 +515,82 @@ pub struct SwapData {
     /// VHTLC address on Arkade.
     pub vhtlc_address: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HdWallet;
+
+    #[test]
+    fn test_public_swap_params_strip_legacy_secrets() {
+        let wallet = HdWallet::generate(bitcoin::Network::Bitcoin, 12).unwrap();
+        let params = wallet.derive_swap_params(3).unwrap();
+
+        // Records written by older versions contain the full `SwapParams`
+        let legacy = serde_json::to_string(&params.with_secrets()).unwrap();
+        let public: PublicSwapParams = serde_json::from_str(&legacy).unwrap();
+        assert_eq!(public, params.to_public());
+
+        let stored = serde_json::to_value(&public).unwrap();
+        assert!(stored.get("secret_key").is_none());
+        assert!(stored.get("preimage").is_none());
+
+        let stored = stored.to_string();
+        assert!(!stored.contains(&hex::encode(
+            params.secret_key.expose_secret().secret_bytes()
+        )));
+        assert!(!stored.contains(&hex::encode(**params.preimage.expose_secret())));
+    }
+
+    #[test]
+    fn test_derivation_scheme_serialization() {
+        let wallet = HdWallet::generate(bitcoin::Network::Bitcoin, 12).unwrap();
+        let params = wallet.derive_swap_params(3).unwrap().to_public();
+
+        let mut stored = serde_json::to_value(&params).unwrap();
+        assert_eq!(stored["scheme"], serde_json::json!(1));
+
+        // Records written before schemes were introduced use V1
+        stored.as_object_mut().unwrap().remove("scheme");
+        let legacy: PublicSwapParams = serde_json::from_value(stored.clone()).unwrap();
+        assert_eq!(legacy.scheme, DerivationScheme::V1);
+
+        stored["scheme"] = serde_json::json!(99);
+        assert!(serde_json::from_value::<PublicSwapParams>(stored).is_err());
+    }
+
+    #[test]
+    fn test_debug_output_is_redacted() {
+        let wallet = HdWallet::generate(bitcoin::Network::Bitcoin, 12).unwrap();
+        let params = wallet.derive_swap_params(3).unwrap();
+        let secret_key = hex::encode(params.secret_key.expose_secret().secret_bytes());
+        let preimage = hex::encode(**params.preimage.expose_secret());
+
+        for formatted in [
+            format!("{:?}", params),
+            format!("{:#?}", params),
+            format!("{:?}", params.secret_key.expose_secret()),
+            format!("{:?}", params.preimage.expose_secret()),
+        ] {
+            assert!(!formatted.contains(&secret_key), "{formatted}");
+            assert!(!formatted.contains(&preimage), "{formatted}");
+        }
+        let wallet = wallet.with_passphrase("correct horse battery staple");
+        let formatted = format!("{:?}", wallet);
+        assert!(
+            !formatted.contains(&wallet.mnemonic_phrase()),
+            "{formatted}"
+        );
+        assert!(!formatted.contains("correct horse"), "{formatted}");
+
+        let options = MnemonicOptions::from_entropy(vec![0xab; 16]);
+        for formatted in [format!("{:?}", options), format!("{:#?}", options)] {
+            assert!(!formatted.contains("171"), "{formatted}");
+        }
+
+        // Secrets are only serialized on request
+        let serialized = serde_json::to_string(&params.with_secrets()).unwrap();
+        assert!(serialized.contains(&secret_key));
+        assert!(serialized.contains(&preimage));
+    }
+}
@@ -3,49 +3,42 @@
 //! This module provides functionality for claiming and refunding VHTLCs
 //! on the Arkade network.
 
-use crate::SwapParams;
 use crate::error::{Error, Result};
+use crate::signer::{PsbtKind, SwapSigner, sign_psbt};
 use crate::types::{Network, SwapData, VhtlcAmounts};
-use ark_rs::core::VTXO_CONDITION_KEY;
-use ark_rs::core::send::{
-    OffchainTransactions, VtxoInput, build_offchain_transactions, sign_ark_transaction,
-    sign_checkpoint_transaction,
-};
+use ark_rs::core::send::{OffchainTransactions, VtxoInput, build_offchain_transactions};
 use ark_rs::core::server::{GetVtxosRequest, parse_sequence_number};
 use ark_rs::core::vhtlc::{VhtlcOptions, VhtlcScript};
 use ark_rs::core::{ArkAddress, VtxoList};
 use bitcoin::absolute::LockTime;
-use bitcoin::consensus::Encodable;
 use bitcoin::hashes::Hash;
-use bitcoin::key::{Keypair, Secp256k1};
-use bitcoin::secp256k1::schnorr;
 use bitcoin::taproot::LeafVersion;
-use bitcoin::{Amount, PublicKey, Txid, VarInt, XOnlyPublicKey, psbt, secp256k1};
+use bitcoin::{Amount, PublicKey, Txid};
+use zeroize::Zeroizing;
 
 /// Claim a VHTLC swap by providing the preimage.
 ///
 /// This function reconstructs the VHTLC from stored parameters,
 /// signs the claim transaction, and submits it to the Arkade server.
+///
+/// The key and preimage for `swap_data.key_index` are provided by `signer`.
 pub async fn claim(
     ark_server_url: &str,
     claim_ark_address: ArkAddress,
     swap_data: SwapData,
-    swap_params: SwapParams,
+    signer: &dyn SwapSigner,
     network: Network,
 ) -> Result<Txid> {
-    let secp = Secp256k1::new();
-
     let bitcoin_network = network.to_bitcoin_network();
 
-    let secret_key = swap_params.secret_key;
-    let own_kp = Keypair::from_secret_key(&secp, &secret_key);
-    let own_pk = own_kp.public_key();
+    let key_index = swap_data.key_index;
+    let own_pk = signer.public_key(key_index).await?;
 
-    // Parse preimage
-    let preimage = swap_params.preimage;
+    // Get preimage from the signer
+    let preimage = Zeroizing::new(signer.preimage(key_index).await?);
 
     // Hash the preimage for VHTLC construction (SHA256 -> RIPEMD160)
-    let sha256_hash = bitcoin::hashes::sha256::Hash::hash(&preimage);
+    let sha256_hash = bitcoin::hashes::sha256::Hash::hash(preimage.as_slice());
     let ripemd160_hash = bitcoin::hashes::ripemd160::Hash::hash(&sha256_hash.to_byte_array());
 
     // Parse public keys
@@ -144,39 +137,14 @@ pub async fn claim(
     } = build_offchain_transactions(&outputs, None, &vhtlc_inputs, &server_info)
         .map_err(|e| Error::Vhtlc(format!("Failed to build offchain TXs: {}", e)))?;
 
-    // Sign function that adds preimage witness
-    let sign_fn = |input: &mut psbt::Input,
-                   msg: secp256k1::Message|
-     -> std::result::Result<
-        Vec<(schnorr::Signature, XOnlyPublicKey)>,
-        ark_rs::core::Error,
-    > {
-        // Add preimage to PSBT input
-        {
-            let mut bytes = vec![1]; // One witness element
-            let length = VarInt::from(preimage.len() as u64);
-            length
-                .consensus_encode(&mut bytes)
-                .expect("valid length encoding");
-            bytes.extend_from_slice(&preimage);
-
-            input.unknown.insert(
-                psbt::raw::Key {
-                    type_value: 222,
-                    key: VTXO_CONDITION_KEY.to_vec(),
-                },
-                bytes,
-            );
-        }
-
-        let sig = Secp256k1::new().sign_schnorr_no_aux_rand(&msg, &own_kp);
-        let pk = own_kp.public_key().into();
-
-        Ok(vec![(sig, pk)])
-    };
-
-    sign_ark_transaction(sign_fn, &mut ark_tx, 0)
-        .map_err(|e| Error::Vhtlc(format!("Failed to sign ark transaction: {}", e)))?;
+    sign_psbt(
+        signer,
+        key_index,
+        Some(&preimage),
+        &mut ark_tx,
+        PsbtKind::Ark { input_index: 0 },
+    )
+    .await?;
 
     let ark_txid = ark_tx.unsigned_tx.compute_txid();
 
@@ -187,8 +155,14 @@ pub async fn claim(
 
     let mut checkpoint_psbts = res.signed_checkpoint_txs;
     for checkpoint_psbt in checkpoint_psbts.iter_mut() {
-        sign_checkpoint_transaction(sign_fn, checkpoint_psbt)
-            .map_err(|e| Error::Vhtlc(format!("Failed to sign checkpoint TX: {}", e)))?;
+        sign_psbt(
+            signer,
+            key_index,
+            Some(&preimage),
+            checkpoint_psbt,
+            PsbtKind::Checkpoint,
+        )
+        .await?;
     }
 
     rest_client
@@ -205,24 +179,22 @@ pub async fn claim(
 ///
 /// This function reconstructs the VHTLC from stored parameters,
 /// signs the refund transaction, and submits it to the Arkade server.
+///
+/// The key for `swap_data.key_index` is provided by `signer`. The preimage is not needed, only its
+/// SHA256 hash.
 pub async fn refund(
     ark_server_url: &str,
     refund_ark_address: ArkAddress,
     swap_data: SwapData,
-    swap_params: SwapParams,
+    preimage_hash: [u8; 32],
+    signer: &dyn SwapSigner,
     network: Network,
 ) -> Result<Txid> {
-    let secp = Secp256k1::new();
-
-    let secret_key = swap_params.secret_key;
-    let own_kp = Keypair::from_secret_key(&secp, &secret_key);
-    let own_pk = own_kp.public_key();
+    let key_index = swap_data.key_index;
+    let own_pk = signer.public_key(key_index).await?;
 
-    // Parse preimage for hash computation
-    let preimage_bytes = swap_params.preimage;
-
-    // Hash the preimage for VHTLC construction (SHA256 -> RIPEMD160)
-    let sha256_hash = bitcoin::hashes::sha256::Hash::hash(&preimage_bytes);
+    // Hash the preimage hash for VHTLC construction (SHA256 -> RIPEMD160)
+    let sha256_hash = bitcoin::hashes::sha256::Hash::from_byte_array(preimage_hash);
     let ripemd160_hash = bitcoin::hashes::ripemd160::Hash::hash(&sha256_hash.to_byte_array());
 
     // Parse public keys
@@ -329,21 +301,14 @@ pub async fn refund(
     } = build_offchain_transactions(&outputs, None, &vhtlc_inputs, &server_info)
         .map_err(|e| Error::Vhtlc(format!("Failed to build offchain TXs: {}", e)))?;
 
-    // Sign function (no preimage needed for refund)
-    let sign_fn = |_: &mut psbt::Input,
-                   msg: secp256k1::Message|
-     -> std::result::Result<
-        Vec<(schnorr::Signature, XOnlyPublicKey)>,
-        ark_rs::core::Error,
-    > {
-        let sig = Secp256k1::new().sign_schnorr_no_aux_rand(&msg, &own_kp);
-        let pk = own_kp.public_key().into();
-
-        Ok(vec![(sig, pk)])
-    };
-
-    sign_ark_transaction(sign_fn, &mut ark_tx, 0)
-        .map_err(|e| Error::Vhtlc(format!("Failed to sign ark transaction: {}", e)))?;
+    sign_psbt(
+        signer,
+        key_index,
+        None,
+        &mut ark_tx,
+        PsbtKind::Ark { input_index: 0 },
+    )
+    .await?;
 
     let ark_txid = ark_tx.unsigned_tx.compute_txid();
 
@@ -354,8 +319,14 @@ pub async fn refund(
 
     let mut checkpoint_psbts = res.signed_checkpoint_txs;
     for checkpoint_psbt in checkpoint_psbts.iter_mut() {
-        sign_checkpoint_transaction(sign_fn, checkpoint_psbt)
-            .map_err(|e| Error::Vhtlc(format!("Failed to sign checkpoint TX: {}", e)))?;
+        sign_psbt(
+            signer,
+            key_index,
+            None,
+            checkpoint_psbt,
+            PsbtKind::Checkpoint,
+        )
+        .await?;
     }
 
     rest_client
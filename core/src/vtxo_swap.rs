@@ -4,49 +4,43 @@
 //! - Claiming the server's VHTLC (after server funds)
 //! - Refunding the client's VHTLC (if swap fails)
 
-use crate::SwapParams;
 use crate::api::VtxoSwapResponse;
 use crate::error::{Error, Result};
+use crate::signer::{PsbtKind, SwapSigner, sign_psbt};
 use crate::types::Network;
-use ark_rs::core::VTXO_CONDITION_KEY;
-use ark_rs::core::send::{
-    OffchainTransactions, VtxoInput, build_offchain_transactions, sign_ark_transaction,
-    sign_checkpoint_transaction,
-};
+use ark_rs::core::send::{OffchainTransactions, VtxoInput, build_offchain_transactions};
 use ark_rs::core::server::{GetVtxosRequest, parse_sequence_number};
 use ark_rs::core::vhtlc::{VhtlcOptions, VhtlcScript};
 use ark_rs::core::{ArkAddress, VtxoList};
 use bitcoin::absolute::LockTime;
-use bitcoin::consensus::Encodable;
 use bitcoin::hashes::Hash;
-use bitcoin::key::{Keypair, Secp256k1};
-use bitcoin::secp256k1::schnorr;
 use bitcoin::taproot::LeafVersion;
-use bitcoin::{Amount, PublicKey, Txid, VarInt, XOnlyPublicKey, psbt, secp256k1};
+use bitcoin::{Amount, PublicKey, Txid};
+use zeroize::Zeroizing;
 
 /// Claim the server's VHTLC in a VTXO swap.
 ///
 /// In a VTXO swap, the client claims the server's VHTLC by revealing the preimage.
 /// The server is the sender and the client is the receiver.
+///
+/// The key and preimage for `key_index` are provided by `signer`.
 pub async fn claim_server_vhtlc(
     ark_server_url: &str,
     claim_ark_address: ArkAddress,
     swap: &VtxoSwapResponse,
-    swap_params: SwapParams,
+    key_index: u32,
+    signer: &dyn SwapSigner,
     network: Network,
 ) -> Result<Txid> {
-    let secp = Secp256k1::new();
     let bitcoin_network = network.to_bitcoin_network();
 
-    let secret_key = swap_params.secret_key;
-    let own_kp = Keypair::from_secret_key(&secp, &secret_key);
-    let own_pk = own_kp.public_key();
+    let own_pk = signer.public_key(key_index).await?;
 
-    // Parse preimage
-    let preimage = swap_params.preimage;
+    // Get preimage from the signer
+    let preimage = Zeroizing::new(signer.preimage(key_index).await?);
 
     // Hash the preimage for VHTLC construction (SHA256 -> RIPEMD160)
-    let sha256_hash = bitcoin::hashes::sha256::Hash::hash(&preimage);
+    let sha256_hash = bitcoin::hashes::sha256::Hash::hash(preimage.as_slice());
     let ripemd160_hash = bitcoin::hashes::ripemd160::Hash::hash(&sha256_hash.to_byte_array());
 
     // Parse public keys
@@ -147,39 +141,14 @@ pub async fn claim_server_vhtlc(
     } = build_offchain_transactions(&outputs, None, &vhtlc_inputs, &server_info)
         .map_err(|e| Error::Vhtlc(format!("Failed to build offchain TXs: {}", e)))?;
 
-    // Sign function that adds preimage witness
-    let sign_fn = |input: &mut psbt::Input,
-                   msg: secp256k1::Message|
-     -> std::result::Result<
-        Vec<(schnorr::Signature, XOnlyPublicKey)>,
-        ark_rs::core::Error,
-    > {
-        // Add preimage to PSBT input
-        {
-            let mut bytes = vec![1]; // One witness element
-            let length = VarInt::from(preimage.len() as u64);
-            length
-                .consensus_encode(&mut bytes)
-                .expect("valid length encoding");
-            bytes.extend_from_slice(&preimage);
-
-            input.unknown.insert(
-                psbt::raw::Key {
-                    type_value: 222,
-                    key: VTXO_CONDITION_KEY.to_vec(),
-                },
-                bytes,
-            );
-        }
-
-        let sig = Secp256k1::new().sign_schnorr_no_aux_rand(&msg, &own_kp);
-        let pk = own_kp.public_key().into();
-
-        Ok(vec![(sig, pk)])
-    };
-
-    sign_ark_transaction(sign_fn, &mut ark_tx, 0)
-        .map_err(|e| Error::Vhtlc(format!("Failed to sign ark transaction: {}", e)))?;
+    sign_psbt(
+        signer,
+        key_index,
+        Some(&preimage),
+        &mut ark_tx,
+        PsbtKind::Ark { input_index: 0 },
+    )
+    .await?;
 
     let ark_txid = ark_tx.unsigned_tx.compute_txid();
 
@@ -190,8 +159,14 @@ pub async fn claim_server_vhtlc(
 
     let mut checkpoint_psbts = res.signed_checkpoint_txs;
     for checkpoint_psbt in checkpoint_psbts.iter_mut() {
-        sign_checkpoint_transaction(sign_fn, checkpoint_psbt)
-            .map_err(|e| Error::Vhtlc(format!("Failed to sign checkpoint TX: {}", e)))?;
+        sign_psbt(
+            signer,
+            key_index,
+            Some(&preimage),
+            checkpoint_psbt,
+            PsbtKind::Checkpoint,
+        )
+        .await?;
     }
 
     rest_client
@@ -211,25 +186,29 @@ pub async fn claim_server_vhtlc(
 ///
 /// In a VTXO swap, the client can refund their own VHTLC after the locktime expires.
 /// The client is the sender and the server is the receiver.
+///
+/// The key for `key_index` is provided by `signer`. The preimage is not needed, only its hash
+/// as recorded in the swap.
 pub async fn refund_client_vhtlc(
     ark_server_url: &str,
     refund_ark_address: ArkAddress,
     swap: &VtxoSwapResponse,
-    swap_params: SwapParams,
+    key_index: u32,
+    signer: &dyn SwapSigner,
     network: Network,
 ) -> Result<Txid> {
-    let secp = Secp256k1::new();
     let bitcoin_network = network.to_bitcoin_network();
 
-    let secret_key = swap_params.secret_key;
-    let own_kp = Keypair::from_secret_key(&secp, &secret_key);
-    let own_pk = own_kp.public_key();
+    let own_pk = signer.public_key(key_index).await?;
 
-    // Parse preimage for hash computation
-    let preimage_bytes = swap_params.preimage;
+    // Parse the preimage hash recorded in the swap
+    let preimage_hash: [u8; 32] = hex::decode(&swap.preimage_hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| Error::Parse(format!("Invalid preimage hash: {}", swap.preimage_hash)))?;
 
-    // Hash the preimage for VHTLC construction (SHA256 -> RIPEMD160)
-    let sha256_hash = bitcoin::hashes::sha256::Hash::hash(&preimage_bytes);
+    // Hash the preimage hash for VHTLC construction (SHA256 -> RIPEMD160)
+    let sha256_hash = bitcoin::hashes::sha256::Hash::from_byte_array(preimage_hash);
     let ripemd160_hash = bitcoin::hashes::ripemd160::Hash::hash(&sha256_hash.to_byte_array());
 
     // Parse public keys
@@ -337,21 +316,14 @@ pub async fn refund_client_vhtlc(
     } = build_offchain_transactions(&outputs, None, &vhtlc_inputs, &server_info)
         .map_err(|e| Error::Vhtlc(format!("Failed to build offchain TXs: {}", e)))?;
 
-    // Sign function (no preimage needed for refund)
-    let sign_fn = |_: &mut psbt::Input,
-                   msg: secp256k1::Message|
-     -> std::result::Result<
-        Vec<(schnorr::Signature, XOnlyPublicKey)>,
-        ark_rs::core::Error,
-    > {
-        let sig = Secp256k1::new().sign_schnorr_no_aux_rand(&msg, &own_kp);
-        let pk = own_kp.public_key().into();
-
-        Ok(vec![(sig, pk)])
-    };
-
-    sign_ark_transaction(sign_fn, &mut ark_tx, 0)
-        .map_err(|e| Error::Vhtlc(format!("Failed to sign ark transaction: {}", e)))?;
+    sign_psbt(
+        signer,
+        key_index,
+        None,
+        &mut ark_tx,
+        PsbtKind::Ark { input_index: 0 },
+    )
+    .await?;
 
     let ark_txid = ark_tx.unsigned_tx.compute_txid();
 
@@ -362,8 +334,14 @@ pub async fn refund_client_vhtlc(
 
     let mut checkpoint_psbts = res.signed_checkpoint_txs;
     for checkpoint_psbt in checkpoint_psbts.iter_mut() {
-        sign_checkpoint_transaction(sign_fn, checkpoint_psbt)
-            .map_err(|e| Error::Vhtlc(format!("Failed to sign checkpoint TX: {}", e)))?;
+        sign_psbt(
+            signer,
+            key_index,
+            None,
+            checkpoint_psbt,
+            PsbtKind::Checkpoint,
+        )
+        .await?;
     }
 
     rest_client
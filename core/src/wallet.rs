@@ -3,10 +3,17 @@
 //! This module provides the high-level wallet API that combines HD key derivation
 //! and VHTLC operations with pluggable storage.
 
+use crate::backup::WalletBackup;
 use crate::error::{Error, Result};
 use crate::hd_wallet::HdWallet;
-use crate::storage::{WalletStorage, WalletStorageExt};
-use crate::types::{Network, SwapParams};
+use crate::message::MessageSignature;
+use crate::slip39::Slip39Options;
+use crate::storage::WalletStorage;
+use crate::types::{DerivationScheme, MnemonicOptions, Network, SwapParams};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::{Arc, RwLock};
+use zeroize::Zeroizing;
 
 /// Main wallet struct with injected storage.
 ///
@@ -22,15 +29,67 @@ use crate::types::{Network, SwapParams};
 /// let mnemonic = wallet.generate_or_get_mnemonic().await?;
 /// let params = wallet.derive_swap_params().await?;
 /// ```
+///
+/// # BIP39 passphrase
+///
+/// A wallet can be protected with a BIP39 passphrase (the "25th word"). The passphrase is
+/// never persisted: only the master key fingerprint is stored, so that the wallet knows a
+/// passphrase is required and can reject a wrong one. After restarting, call
+/// [`Wallet::unlock_with_passphrase`] before deriving keys.
+///
+/// # Accounts
+///
+/// Keys, the key index and the user ID xpub are scoped to an account, see
+/// [`HdWallet::with_account`]. Wallets use account 0 unless configured with
+/// [`Wallet::with_account`].
+///
+/// # Derivation schemes
+///
+/// New swaps are derived with the wallet's [`DerivationScheme`], which is stored when the
+/// mnemonic is created or imported. Wallets created before schemes were introduced use
+/// [`DerivationScheme::V1`]. Each swap records its scheme, so existing swaps keep working when
+/// the scheme of new swaps changes.
 pub struct Wallet<S: WalletStorage> {
     storage: S,
     network: Network,
+    account: u32,
+    /// In-memory BIP39 passphrase, wiped when replaced or dropped. `None` until provided.
+    passphrase: RwLock<Option<Zeroizing<String>>>,
+    /// HD wallet of the stored mnemonic, so the seed is only derived once.
+    hd_wallet: RwLock<CachedHdWallet>,
+}
+
+/// The cached [`HdWallet`], if any.
+#[derive(Default)]
+struct CachedHdWallet {
+    /// Incremented whenever the cache is cleared, so that a wallet built from the previous
+    /// mnemonic or passphrase is not cached afterwards.
+    generation: u64,
+    wallet: Option<Arc<HdWallet>>,
 }
 
 impl<S: WalletStorage> Wallet<S> {
     /// Create a new wallet with the given storage provider.
     pub fn new(storage: S, network: Network) -> Self {
-        Self { storage, network }
+        Self {
+            storage,
+            network,
+            account: 0,
+            passphrase: RwLock::new(None),
+            hd_wallet: RwLock::new(CachedHdWallet::default()),
+        }
+    }
+
+    /// Use the given account for key derivation and the key index.
+    pub fn with_account(mut self, account: u32) -> Self {
+        self.account = account;
+        self.clear_cache();
+        self
+    }
+
+    /// Get the account this wallet derives keys for.
+    pub fn account(&self) -> u32 {
+        self.account
     }
 
     /// Get the network this wallet is configured for.
@@ -48,13 +107,53 @@ impl<S: WalletStorage> Wallet<S> {
     /// If a mnemonic already exists in storage, it is returned.
     /// Otherwise, a new 12-word mnemonic is generated and stored.
     pub async fn generate_or_get_mnemonic(&self) -> Result<String> {
+        self.generate_or_get_mnemonic_with_options(&MnemonicOptions::default())
+            .await
+    }
+
+    /// Generate a new mnemonic according to `options`, or return the existing one.
+    ///
+    /// If a mnemonic already exists in storage, it is returned and `options` are ignored.
+    pub async fn generate_or_get_mnemonic_with_options(
+        &self,
+        options: &MnemonicOptions,
+    ) -> Result<String> {
         if let Some(mnemonic) = self.storage.get_mnemonic().await? {
             return Ok(mnemonic);
         }
 
-        let wallet = HdWallet::generate(self.network.to_bitcoin_network(), 12)?;
+        let wallet = HdWallet::generate_with_options(self.network.to_bitcoin_network(), options)?;
         let mnemonic = wallet.mnemonic_phrase();
+        self.storage.set_derivation_scheme(wallet.scheme()).await?;
         self.storage.set_mnemonic(&mnemonic).await?;
+        self.clear_cache();
+
+        Ok(mnemonic)
+    }
+
+    /// Generate a new mnemonic protected by a BIP39 passphrase, or return the existing one.
+    ///
+    /// If a mnemonic already exists in storage, the passphrase is checked against it (see
+    /// [`Wallet::unlock_with_passphrase`]) and the mnemonic is returned. Otherwise, a new
+    /// mnemonic is generated according to `options` and stored together with the passphrase
+    /// fingerprint.
+    pub async fn generate_or_get_mnemonic_with_passphrase(
+        &self,
+        options: &MnemonicOptions,
+        passphrase: &str,
+    ) -> Result<String> {
+        if let Some(mnemonic) = self.storage.get_mnemonic().await? {
+            self.unlock_with_passphrase(passphrase).await?;
+            return Ok(mnemonic);
+        }
+
+        let wallet = HdWallet::generate_with_options(self.network.to_bitcoin_network(), options)?
+            .with_passphrase(passphrase);
+        let mnemonic = wallet.mnemonic_phrase();
+        let fingerprint = passphrase_fingerprint(&wallet, passphrase)?;
+        self.store_mnemonic(&mnemonic, wallet.scheme(), fingerprint.as_deref())
+            .await?;
+        self.set_passphrase(passphrase);
 
         Ok(mnemonic)
     }
@@ -68,68 +167,393 @@ impl<S: WalletStorage> Wallet<S> {
 
     /// Import a mnemonic phrase (replaces existing).
     ///
-    /// The mnemonic is validated before being stored.
+    /// The mnemonic may be in any supported BIP39 language. It is validated and normalized
+    /// before being stored.
     pub async fn import_mnemonic(&self, phrase: &str) -> Result<()> {
+        self.import_mnemonic_with_passphrase(phrase, "").await
+    }
+
+    /// Import a mnemonic phrase protected by a BIP39 passphrase (replaces existing).
+    ///
+    /// The passphrase itself is kept in memory only; its fingerprint is persisted so the
+    /// wallet can require and verify it later. An empty passphrase is equivalent to
+    /// [`Wallet::import_mnemonic`].
+    pub async fn import_mnemonic_with_passphrase(
+        &self,
+        phrase: &str,
+        passphrase: &str,
+    ) -> Result<()> {
         // Validate by creating wallet
-        let _wallet = HdWallet::from_mnemonic(phrase, self.network.to_bitcoin_network())?;
-        self.storage.set_mnemonic(phrase).await?;
+        let wallet = HdWallet::from_mnemonic_with_passphrase(
+            phrase,
+            passphrase,
+            self.network.to_bitcoin_network(),
+        )?;
+        let fingerprint = passphrase_fingerprint(&wallet, passphrase)?;
+        self.store_mnemonic(
+            &wallet.mnemonic_phrase(),
+            wallet.scheme(),
+            fingerprint.as_deref(),
+        )
+        .await?;
         // Reset key index when importing new mnemonic
         self.storage.set_key_index(0).await?;
+        if self.account != 0 {
+            self.storage.set_account_key_index(self.account, 0).await?;
+        }
+        self.set_passphrase(passphrase);
+        Ok(())
+    }
+
+    /// Split the stored mnemonic into SLIP-39 shares.
+    ///
+    /// See [`HdWallet::to_slip39_shares`]. A BIP39 passphrase is not part of the shares.
+    pub async fn to_slip39_shares(&self, options: &Slip39Options) -> Result<Vec<Vec<String>>> {
+        let mnemonic = self
+            .storage
+            .get_mnemonic()
+            .await?
+            .ok_or(Error::NoMnemonic)?;
+        let wallet = HdWallet::from_mnemonic(&mnemonic, self.network.to_bitcoin_network())?;
+        wallet.to_slip39_shares(options)
+    }
+
+    /// Restore the wallet from SLIP-39 shares (replaces existing).
+    ///
+    /// `slip39_passphrase` is the passphrase the shares were created with, `bip39_passphrase`
+    /// the wallet's BIP39 passphrase (empty if unused). Swap keys are the same as those of the
+    /// wallet the shares were created from.
+    pub async fn import_slip39_shares<T: AsRef<str>>(
+        &self,
+        shares: &[T],
+        slip39_passphrase: &str,
+        bip39_passphrase: &str,
+    ) -> Result<()> {
+        let wallet = HdWallet::from_slip39_shares(
+            shares,
+            slip39_passphrase,
+            self.network.to_bitcoin_network(),
+        )?;
+        self.import_mnemonic_with_passphrase(&wallet.mnemonic_phrase(), bip39_passphrase)
+            .await
+    }
+
+    /// Provide the BIP39 passphrase for the stored mnemonic.
+    ///
+    /// The passphrase is verified against the stored fingerprint and kept in memory for
+    /// subsequent derivations. Returns [`Error::InvalidPassphrase`] if it does not match.
+    pub async fn unlock_with_passphrase(&self, passphrase: &str) -> Result<()> {
+        let mnemonic = self
+            .storage
+            .get_mnemonic()
+            .await?
+            .ok_or(Error::NoMnemonic)?;
+
+        let expected = self.storage.get_passphrase_fingerprint().await?;
+        match expected {
+            Some(expected) => {
+                let wallet = HdWallet::from_mnemonic_with_passphrase(
+                    &mnemonic,
+                    passphrase,
+                    self.network.to_bitcoin_network(),
+                )?;
+                if wallet.master_fingerprint()?.to_string() != expected {
+                    return Err(Error::InvalidPassphrase);
+                }
+            }
+            None if !passphrase.is_empty() => return Err(Error::InvalidPassphrase),
+            None => {}
+        }
+
+        self.set_passphrase(passphrase);
         Ok(())
     }
 
+    /// The derivation scheme used for new swaps.
+    pub async fn derivation_scheme(&self) -> Result<DerivationScheme> {
+        Ok(self
+            .storage
+            .get_derivation_scheme()
+            .await?
+            .unwrap_or_default())
+    }
+
+    /// Whether the stored mnemonic is protected by a BIP39 passphrase.
+    pub async fn has_passphrase(&self) -> Result<bool> {
+        Ok(self.storage.get_passphrase_fingerprint().await?.is_some())
+    }
+
+    /// Persist a mnemonic together with its scheme and passphrase fingerprint.
+    ///
+    /// The fingerprint is written first, so that a stored mnemonic is never without the
+    /// fingerprint of its passphrase. If the mnemonic can't be stored, the previous fingerprint
+    /// is restored, so that it keeps matching the previous mnemonic.
+    async fn store_mnemonic(
+        &self,
+        mnemonic: &str,
+        scheme: DerivationScheme,
+        fingerprint: Option<&str>,
+    ) -> Result<()> {
+        let previous = self.storage.get_passphrase_fingerprint().await?;
+        self.storage.set_passphrase_fingerprint(fingerprint).await?;
+
+        let stored = async {
+            self.storage.set_derivation_scheme(scheme).await?;
+            self.storage.set_mnemonic(mnemonic).await
+        }
+        .await;
+        if stored.is_err()
+            && let Err(e) = self
+                .storage
+                .set_passphrase_fingerprint(previous.as_deref())
+                .await
+        {
+            log::warn!("Failed to restore the passphrase fingerprint: {}", e);
+        }
+        self.clear_cache();
+        stored
+    }
+
+    fn set_passphrase(&self, passphrase: &str) {
+        *self.passphrase.write().expect("passphrase lock poisoned") =
+            Some(Zeroizing::new(passphrase.to_string()));
+        self.clear_cache();
+    }
+
+    /// Drop the cached keys, so that the next derivation reads the mnemonic from storage again.
+    ///
+    /// The wallet clears the cache itself whenever it replaces the mnemonic or passphrase. Call
+    /// this after the storage was changed behind the wallet's back, e.g. locked or written by
+    /// another wallet.
+    pub fn clear_cache(&self) {
+        let mut cached = self.hd_wallet.write().expect("wallet lock poisoned");
+        cached.generation += 1;
+        cached.wallet = None;
+    }
+
+    /// Build the HD wallet from the stored mnemonic and scheme and the in-memory passphrase.
+    ///
+    /// The wallet is cached until the mnemonic or passphrase is replaced, see
+    /// [`Wallet::clear_cache`], so that neither the storage nor the seed is touched again.
+    ///
+    /// Returns `Ok(None)` if no mnemonic has been stored.
+    async fn load_hd_wallet(&self) -> Result<Option<Arc<HdWallet>>> {
+        let generation = {
+            let cached = self.hd_wallet.read().expect("wallet lock poisoned");
+            if let Some(wallet) = &cached.wallet {
+                return Ok(Some(wallet.clone()));
+            }
+            cached.generation
+        };
+
+        let mnemonic = match self.storage.get_mnemonic().await? {
+            Some(m) => Zeroizing::new(m),
+            None => return Ok(None),
+        };
+        let scheme = self.derivation_scheme().await?;
+
+        let passphrase = self
+            .passphrase
+            .read()
+            .expect("passphrase lock poisoned")
+            .clone();
+        let passphrase = match passphrase {
+            Some(passphrase) => passphrase,
+            None if self.has_passphrase().await? => return Err(Error::PassphraseRequired),
+            None => Zeroizing::new(String::new()),
+        };
+
+        let wallet = HdWallet::from_mnemonic_with_passphrase(
+            &mnemonic,
+            &passphrase,
+            self.network.to_bitcoin_network(),
+        )?;
+        let wallet = Arc::new(wallet.with_account(self.account).with_scheme(scheme));
+
+        let mut cached = self.hd_wallet.write().expect("wallet lock poisoned");
+        if cached.generation == generation {
+            cached.wallet = Some(wallet.clone());
+        }
+        Ok(Some(wallet))
+    }
+
     /// Derive swap parameters for a new swap (increments index).
     ///
     /// This automatically increments the key derivation index after deriving.
     pub async fn derive_swap_params(&self) -> Result<SwapParams> {
-        let index = self.storage.increment_key_index().await?;
+        let index = self.storage.reserve_account_key_index(self.account).await?;
         self.derive_swap_params_at_index(index).await
     }
 
     /// Sets the local key index to [`index`]
     pub async fn set_key_index(&self, index: u32) -> Result<()> {
-        self.storage.set_key_index(index).await
+        self.storage
+            .set_account_key_index(self.account, index)
+            .await
     }
 
     /// Derive swap parameters at a specific index (for recovery).
     ///
     /// This does not modify the stored key index.
     pub async fn derive_swap_params_at_index(&self, index: u32) -> Result<SwapParams> {
-        let mnemonic = self
-            .storage
-            .get_mnemonic()
-            .await?
-            .ok_or(Error::NoMnemonic)?;
-
-        let wallet = HdWallet::from_mnemonic(&mnemonic, self.network.to_bitcoin_network())?;
+        let wallet = self.load_hd_wallet().await?.ok_or(Error::NoMnemonic)?;
         wallet.derive_swap_params(index)
     }
 
+    /// Derive swap parameters at a specific index with the given derivation scheme.
+    ///
+    /// Used for swaps created with a different scheme than the wallet's current one. This does
+    /// not modify the stored key index.
+    pub async fn derive_swap_params_with_scheme(
+        &self,
+        scheme: DerivationScheme,
+        index: u32,
+    ) -> Result<SwapParams> {
+        let wallet = self.load_hd_wallet().await?.ok_or(Error::NoMnemonic)?;
+        wallet.derive_swap_params_with_scheme(scheme, index)
+    }
+
+    /// Derive swap parameters for every index in `indices` (for recovery).
+    ///
+    /// Reads the mnemonic once for the whole range. This does not modify the stored key index.
+    pub async fn derive_swap_params_range(&self, indices: Range<u32>) -> Result<Vec<SwapParams>> {
+        let wallet = self.load_hd_wallet().await?.ok_or(Error::NoMnemonic)?;
+        wallet.derive_swap_params_range(indices)
+    }
+
+    /// Derive swap parameters for each of `indices` with the given derivation scheme.
+    pub async fn derive_swap_params_batch_with_scheme(
+        &self,
+        scheme: DerivationScheme,
+        indices: &[u32],
+    ) -> Result<Vec<SwapParams>> {
+        let wallet = self.load_hd_wallet().await?.ok_or(Error::NoMnemonic)?;
+        wallet.derive_swap_params_batch_with_scheme(scheme, indices)
+    }
+
+    /// Sign `msg` with the swap key at `key_index`, see [`crate::message`].
+    pub async fn sign_message(&self, key_index: u32, msg: &[u8]) -> Result<MessageSignature> {
+        let wallet = self.load_hd_wallet().await?.ok_or(Error::NoMnemonic)?;
+        wallet.sign_message(key_index, msg)
+    }
+
+    /// Sign `msg` with the user ID at `key_index`, see [`crate::message`].
+    pub async fn sign_message_with_user_id(
+        &self,
+        key_index: u32,
+        msg: &[u8],
+    ) -> Result<MessageSignature> {
+        let wallet = self.load_hd_wallet().await?.ok_or(Error::NoMnemonic)?;
+        wallet.sign_message_with_user_id(key_index, msg)
+    }
+
+    /// Derive the account's Nostr keys, see [`HdWallet::derive_nostr_keys`].
+    pub async fn derive_nostr_keys(&self) -> Result<nostr::Keys> {
+        let wallet = self.load_hd_wallet().await?.ok_or(Error::NoMnemonic)?;
+        wallet.derive_nostr_keys()
+    }
+
     /// Get the user ID Xpub for wallet recovery.
     ///
     /// This extended public key can be shared with the server to enable
     /// recovery of past swaps.
     pub async fn get_user_id_xpub(&self) -> Result<Option<String>> {
-        let mnemonic = match self.storage.get_mnemonic().await? {
-            Some(m) => m,
+        let wallet = match self.load_hd_wallet().await? {
+            Some(wallet) => wallet,
             None => return Ok(None),
         };
 
-        let wallet = HdWallet::from_mnemonic(&mnemonic, self.network.to_bitcoin_network())?;
         let xpub = wallet.derive_user_id_xpub()?;
         Ok(Some(xpub.to_string()))
     }
 
-    /// Get the current key derivation index.
+    /// Get the current key derivation index of the wallet's account.
     pub async fn get_key_index(&self) -> Result<u32> {
-        self.storage.get_key_index().await
+        self.storage.get_account_key_index(self.account).await
+    }
+
+    /// Collect the wallet data for a backup.
+    ///
+    /// Contains the key indices of account 0, of the wallet's account and of `accounts`, e.g.
+    /// the accounts of the backed up swaps.
+    pub(crate) async fn backup(
+        &self,
+        accounts: impl IntoIterator<Item = u32>,
+    ) -> Result<WalletBackup> {
+        let mnemonic = self
+            .storage
+            .get_mnemonic()
+            .await?
+            .ok_or(Error::NoMnemonic)?;
+
+        let mut key_indices = BTreeMap::new();
+        key_indices.insert(0, self.storage.get_key_index().await?);
+        for account in accounts.into_iter().chain([self.account]) {
+            if !key_indices.contains_key(&account) {
+                let index = self.storage.get_account_key_index(account).await?;
+                key_indices.insert(account, index);
+            }
+        }
+
+        Ok(WalletBackup {
+            mnemonic,
+            passphrase_fingerprint: self.storage.get_passphrase_fingerprint().await?,
+            derivation_scheme: self.derivation_scheme().await?,
+            key_indices,
+        })
+    }
+
+    /// Restore the wallet data of a backup.
+    ///
+    /// Fails if the storage already holds a different mnemonic. Key indices are never lowered.
+    pub(crate) async fn restore_backup(&self, backup: &WalletBackup) -> Result<()> {
+        let wallet = HdWallet::from_mnemonic(&backup.mnemonic, self.network.to_bitcoin_network())?;
+        let mnemonic = Zeroizing::new(wallet.mnemonic_phrase());
+
+        if let Some(existing) = self.storage.get_mnemonic().await?.map(Zeroizing::new)
+            && existing != mnemonic
+        {
+            return Err(Error::InvalidBackup(
+                "the wallet storage already holds a different mnemonic".to_string(),
+            ));
+        }
+
+        self.store_mnemonic(
+            &mnemonic,
+            backup.derivation_scheme,
+            backup.passphrase_fingerprint.as_deref(),
+        )
+        .await?;
+
+        for (&account, &index) in &backup.key_indices {
+            if index == 0 {
+                continue;
+            }
+            let current = self.storage.get_account_key_index(account).await?;
+            if index > current {
+                self.storage.set_account_key_index(account, index).await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The fingerprint to store for `wallet`, or `None` if it isn't protected by a passphrase.
+fn passphrase_fingerprint(wallet: &HdWallet, passphrase: &str) -> Result<Option<String>> {
+    if passphrase.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(wallet.master_fingerprint()?.to_string()))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::StorageFuture;
     use crate::storage::memory::MemoryWalletStorage;
+    use secrecy::ExposeSecret;
 
     #[tokio::test]
     async fn test_generate_or_get_mnemonic() {
@@ -173,6 +597,247 @@ mod tests {
         assert_eq!(params2.key_index, 1);
 
         // Keys should be different
-        assert_ne!(params1.secret_key, params2.secret_key);
+        assert_ne!(
+            params1.secret_key.expose_secret().secret_bytes(),
+            params2.secret_key.expose_secret().secret_bytes()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_accounts_have_separate_key_indices() {
+        let storage = MemoryWalletStorage::new();
+        let wallet = Wallet::new(storage, Network::Bitcoin);
+        wallet.generate_or_get_mnemonic().await.unwrap();
+        let default_params = wallet.derive_swap_params().await.unwrap();
+        wallet.derive_swap_params().await.unwrap();
+
+        let wallet = wallet.with_account(1);
+        let account_params = wallet.derive_swap_params().await.unwrap();
+        assert_eq!(account_params.key_index, 0);
+        assert_eq!(account_params.account, 1);
+        assert_ne!(account_params.public_key, default_params.public_key);
+        assert_ne!(account_params.user_id, default_params.user_id);
+        assert_eq!(wallet.get_key_index().await.unwrap(), 1);
+
+        let wallet = wallet.with_account(0);
+        assert_eq!(wallet.get_key_index().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_derivation_scheme_is_recorded() {
+        let storage = MemoryWalletStorage::new();
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        storage.set_mnemonic(phrase).await.unwrap();
+
+        // Wallets created before schemes were introduced use V1
+        let wallet = Wallet::new(storage, Network::Bitcoin);
+        assert_eq!(
+            wallet.derivation_scheme().await.unwrap(),
+            DerivationScheme::V1
+        );
+        assert_eq!(
+            wallet.derive_swap_params().await.unwrap().scheme,
+            DerivationScheme::V1
+        );
+
+        let wallet = Wallet::new(MemoryWalletStorage::new(), Network::Bitcoin);
+        wallet.generate_or_get_mnemonic().await.unwrap();
+        assert_eq!(
+            wallet.storage().get_derivation_scheme().await.unwrap(),
+            Some(DerivationScheme::LATEST)
+        );
+        let params = wallet.derive_swap_params().await.unwrap();
+        assert_eq!(params.scheme, DerivationScheme::LATEST);
+
+        for scheme in DerivationScheme::ALL {
+            let params = wallet
+                .derive_swap_params_with_scheme(scheme, params.key_index)
+                .await
+                .unwrap();
+            assert_eq!(params.scheme, scheme);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_derive_swap_params_unique() {
+        let storage = MemoryWalletStorage::new();
+        let wallet = Wallet::new(storage, Network::Bitcoin);
+        wallet.generate_or_get_mnemonic().await.unwrap();
+
+        let params = futures::future::try_join_all((0..100).map(|_| wallet.derive_swap_params()))
+            .await
+            .unwrap();
+
+        let indices: std::collections::HashSet<_> = params.iter().map(|p| p.key_index).collect();
+        let preimages: std::collections::HashSet<_> = params
+            .iter()
+            .map(|p| **p.preimage.expose_secret())
+            .collect();
+        assert_eq!(indices.len(), 100);
+        assert_eq!(preimages.len(), 100);
+        assert_eq!(wallet.get_key_index().await.unwrap(), 100);
+    }
+
+    #[tokio::test]
+    async fn test_slip39_backup_and_restore() {
+        let original = Wallet::new(MemoryWalletStorage::new(), Network::Bitcoin);
+        original.generate_or_get_mnemonic().await.unwrap();
+        let params = original.derive_swap_params_at_index(3).await.unwrap();
+
+        let groups = original
+            .to_slip39_shares(&Slip39Options::single_group(3, 5))
+            .await
+            .unwrap();
+        let shares = [&groups[0][4], &groups[0][1], &groups[0][2]];
+
+        let restored = Wallet::new(MemoryWalletStorage::new(), Network::Bitcoin);
+        restored
+            .import_slip39_shares(&shares, "", "")
+            .await
+            .unwrap();
+        assert_eq!(
+            restored.get_mnemonic().await.unwrap(),
+            original.get_mnemonic().await.unwrap()
+        );
+        let restored_params = restored.derive_swap_params_at_index(3).await.unwrap();
+        assert_eq!(restored_params.public_key, params.public_key);
+        assert_eq!(
+            restored_params.preimage.expose_secret(),
+            params.preimage.expose_secret()
+        );
+
+        assert!(
+            restored
+                .import_slip39_shares(&shares[..2], "", "")
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_mnemonic_with_passphrase() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+        let plain = Wallet::new(MemoryWalletStorage::new(), Network::Bitcoin);
+        plain.import_mnemonic(phrase).await.unwrap();
+        assert!(!plain.has_passphrase().await.unwrap());
+
+        let protected = Wallet::new(MemoryWalletStorage::new(), Network::Bitcoin);
+        protected
+            .import_mnemonic_with_passphrase(phrase, "correct horse")
+            .await
+            .unwrap();
+        assert!(protected.has_passphrase().await.unwrap());
+
+        // Keys and the recovery Xpub follow the passphrase-derived seed
+        let plain_params = plain.derive_swap_params_at_index(0).await.unwrap();
+        let protected_params = protected.derive_swap_params_at_index(0).await.unwrap();
+        assert_ne!(plain_params.public_key, protected_params.public_key);
+        assert_ne!(
+            plain.get_user_id_xpub().await.unwrap(),
+            protected.get_user_id_xpub().await.unwrap()
+        );
+
+        // The passphrase is not persisted: a fresh wallet on the same storage must be unlocked
+        let Wallet { storage, .. } = protected;
+        let reopened = Wallet::new(storage, Network::Bitcoin);
+        assert!(matches!(
+            reopened.derive_swap_params_at_index(0).await,
+            Err(Error::PassphraseRequired)
+        ));
+        assert!(matches!(
+            reopened.unlock_with_passphrase("wrong horse").await,
+            Err(Error::InvalidPassphrase)
+        ));
+        assert!(matches!(
+            reopened.unlock_with_passphrase("").await,
+            Err(Error::InvalidPassphrase)
+        ));
+
+        reopened
+            .unlock_with_passphrase("correct horse")
+            .await
+            .unwrap();
+        let reopened_params = reopened.derive_swap_params_at_index(0).await.unwrap();
+        assert_eq!(reopened_params.public_key, protected_params.public_key);
+    }
+
+    /// Storage whose mnemonic can't be written.
+    struct ReadOnlyMnemonicStorage(MemoryWalletStorage);
+
+    impl WalletStorage for ReadOnlyMnemonicStorage {
+        fn get_mnemonic(&self) -> StorageFuture<'_, Option<String>> {
+            self.0.get_mnemonic()
+        }
+
+        fn set_mnemonic(&self, _mnemonic: &str) -> StorageFuture<'_, ()> {
+            Box::pin(async { Err(Error::Storage("read-only".to_string())) })
+        }
+
+        fn get_key_index(&self) -> StorageFuture<'_, u32> {
+            self.0.get_key_index()
+        }
+
+        fn set_key_index(&self, index: u32) -> StorageFuture<'_, ()> {
+            self.0.set_key_index(index)
+        }
+
+        fn get_passphrase_fingerprint(&self) -> StorageFuture<'_, Option<String>> {
+            self.0.get_passphrase_fingerprint()
+        }
+
+        fn set_passphrase_fingerprint(&self, fingerprint: Option<&str>) -> StorageFuture<'_, ()> {
+            self.0.set_passphrase_fingerprint(fingerprint)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_import_keeps_passphrase_fingerprint() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let storage = MemoryWalletStorage::new();
+        storage
+            .set_passphrase_fingerprint(Some("deadbeef"))
+            .await
+            .unwrap();
+
+        let wallet = Wallet::new(ReadOnlyMnemonicStorage(storage), Network::Bitcoin);
+        assert!(
+            wallet
+                .import_mnemonic_with_passphrase(phrase, "correct horse")
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            wallet
+                .storage()
+                .get_passphrase_fingerprint()
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("deadbeef")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_cached_until_replaced() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let other = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+
+        let wallet = Wallet::new(MemoryWalletStorage::new(), Network::Bitcoin);
+        wallet.import_mnemonic(phrase).await.unwrap();
+        let first = wallet.derive_swap_params_at_index(0).await.unwrap();
+
+        // Writes behind the wallet's back are not read until the cache is cleared
+        wallet.storage().set_mnemonic(other).await.unwrap();
+        let cached = wallet.derive_swap_params_at_index(0).await.unwrap();
+        assert_eq!(cached.public_key, first.public_key);
+        wallet.clear_cache();
+        let reloaded = wallet.derive_swap_params_at_index(0).await.unwrap();
+        assert_ne!(reloaded.public_key, first.public_key);
+
+        // Replacing the mnemonic through the wallet clears the cache
+        wallet.import_mnemonic(phrase).await.unwrap();
+        let imported = wallet.derive_swap_params_at_index(0).await.unwrap();
+        assert_eq!(imported.public_key, first.public_key);
     }
 }
@@ -3,126 +3,24 @@
 //! Run with: cargo test --test integration -- --nocapture --ignored
 
 use lendaswap_core::api::{EvmChain, TokenId};
+use lendaswap_core::journal::PENDING_CREATION_GRACE_PERIOD;
+use lendaswap_core::storage::StorageFuture;
+use lendaswap_core::storage::memory::{
+    MemoryKeyValueStore, MemorySwapStorage, MemoryWalletStorage,
+};
 use lendaswap_core::{
-    ApiClient, Client, ExtendedSwapStorageData, Network, StorageFuture, SwapStorage, WalletStorage,
+    ApiClient, Client, ExtendedSwapStorageData, KeyValueStore, KvStorage, Network, PendingCreation,
+    PendingCreationKind, SwapEvent, SwapEventKind, SwapStorage, WalletStorage,
 };
 use rust_decimal_macros::dec;
-use std::collections::HashMap;
-use std::sync::RwLock;
 
 const API_URL: &str = "http://localhost:3333";
 
-/// In-memory wallet storage implementation for integration tests.
-#[derive(Default)]
-pub struct InMemoryWalletStorage {
-    mnemonic: RwLock<Option<String>>,
-    key_index: RwLock<u32>,
-}
-
-impl InMemoryWalletStorage {
-    pub fn new() -> Self {
-        Self {
-            mnemonic: RwLock::new(None),
-            key_index: RwLock::new(0),
-        }
-    }
-}
-
-impl WalletStorage for InMemoryWalletStorage {
-    fn get_mnemonic(&self) -> StorageFuture<'_, Option<String>> {
-        Box::pin(async move {
-            let mnemonic = self.mnemonic.read().unwrap();
-            Ok(mnemonic.clone())
-        })
-    }
-
-    fn set_mnemonic(&self, mnemonic: &str) -> StorageFuture<'_, ()> {
-        let mnemonic = mnemonic.to_string();
-        Box::pin(async move {
-            let mut stored = self.mnemonic.write().unwrap();
-            *stored = Some(mnemonic);
-            Ok(())
-        })
-    }
-
-    fn get_key_index(&self) -> StorageFuture<'_, u32> {
-        Box::pin(async move {
-            let index = self.key_index.read().unwrap();
-            Ok(*index)
-        })
-    }
-
-    fn set_key_index(&self, index: u32) -> StorageFuture<'_, ()> {
-        Box::pin(async move {
-            let mut stored = self.key_index.write().unwrap();
-            *stored = index;
-            Ok(())
-        })
-    }
-}
-
-/// In-memory swap storage implementation for integration tests.
-#[derive(Default)]
-pub struct InMemorySwapStorage {
-    data: RwLock<HashMap<String, ExtendedSwapStorageData>>,
-}
-
-impl InMemorySwapStorage {
-    pub fn new() -> Self {
-        Self {
-            data: RwLock::new(HashMap::new()),
-        }
-    }
-}
-
-impl SwapStorage for InMemorySwapStorage {
-    fn get(&self, swap_id: &str) -> StorageFuture<'_, Option<ExtendedSwapStorageData>> {
-        let swap_id = swap_id.to_string();
-        Box::pin(async move {
-            let data = self.data.read().unwrap();
-            Ok(data.get(&swap_id).cloned())
-        })
-    }
-
-    fn store(&self, swap_id: &str, data: &ExtendedSwapStorageData) -> StorageFuture<'_, ()> {
-        let swap_id = swap_id.to_string();
-        let data = data.clone();
-        Box::pin(async move {
-            let mut storage = self.data.write().unwrap();
-            storage.insert(swap_id, data);
-            Ok(())
-        })
-    }
-
-    fn delete(&self, swap_id: &str) -> StorageFuture<'_, ()> {
-        let swap_id = swap_id.to_string();
-        Box::pin(async move {
-            let mut data = self.data.write().unwrap();
-            data.remove(&swap_id);
-            Ok(())
-        })
-    }
-
-    fn list(&self) -> StorageFuture<'_, Vec<String>> {
-        Box::pin(async move {
-            let data = self.data.read().unwrap();
-            Ok(data.keys().cloned().collect())
-        })
-    }
-
-    fn get_all(&self) -> StorageFuture<'_, Vec<ExtendedSwapStorageData>> {
-        Box::pin(async move {
-            let data = self.data.read().unwrap();
-            Ok(data.values().cloned().collect())
-        })
-    }
-}
-
 #[tokio::test]
 #[ignore] // Run manually with: cargo test --test integration test_create_arkade_to_evm_swap -- --nocapture --ignored
 async fn test_create_arkade_to_evm_swap() {
-    let wallet_storage = InMemoryWalletStorage::new();
-    let swap_storage = InMemorySwapStorage::new();
+    let wallet_storage = MemoryWalletStorage::new();
+    let swap_storage = MemorySwapStorage::new();
 
     let client = Client::new(
         API_URL,
@@ -284,10 +182,11 @@ const ARKADE_URL: &str = "http://localhost:7070";
 #[tokio::test]
 #[ignore] // Run manually: cargo test --test integration test_vtxo_swap_e2e_happy_path -- --nocapture --ignored
 async fn test_vtxo_swap_e2e_happy_path() {
+    use lendaswap_core::SwapEventKind;
     use lendaswap_core::api::VtxoSwapStatus;
 
-    let wallet_storage = InMemoryWalletStorage::new();
-    let swap_storage = InMemorySwapStorage::new();
+    let wallet_storage = MemoryWalletStorage::new();
+    let swap_storage = MemorySwapStorage::new();
 
     let client = Client::new(
         API_URL,
@@ -358,12 +257,21 @@ async fn test_vtxo_swap_e2e_happy_path() {
     let claim_address = "tark1qpt0syx7j0jspe69kldtljet0x9jz6ns4xw70m0w0xl30yfhn0mz6ewavea7k58gk987srkn3sale5r9plldtq2m7zmd4sqm4ekuxg058tftg7"; // Replace with actual address
 
     let txid = client
-        .claim_vtxo_swap(&swap, swap_params, claim_address)
+        .claim_vtxo_swap(&swap, swap_params.key_index, claim_address)
         .await
         .expect("Failed to claim");
 
     println!("  Claimed! Transaction: {}", txid);
 
+    let history = client
+        .swap_history(&swap.id.to_string())
+        .await
+        .expect("Failed to load history");
+    assert!(matches!(
+        history.last().map(|event| &event.kind),
+        Some(SwapEventKind::Claimed { txid: claimed }) if *claimed == txid
+    ));
+
     // Step 5: Wait for completion
     println!("\nStep 5: Waiting for swap completion...");
     loop {
@@ -399,8 +307,8 @@ async fn test_vtxo_swap_e2e_happy_path() {
 async fn test_vtxo_swap_client_refund() {
     use lendaswap_core::api::VtxoSwapStatus;
 
-    let wallet_storage = InMemoryWalletStorage::new();
-    let swap_storage = InMemorySwapStorage::new();
+    let wallet_storage = MemoryWalletStorage::new();
+    let swap_storage = MemorySwapStorage::new();
 
     let client = Client::new(
         API_URL,
@@ -473,7 +381,7 @@ async fn test_vtxo_swap_client_refund() {
 
     loop {
         match client
-            .refund_vtxo_swap(&swap, swap_params.clone(), refund_address)
+            .refund_vtxo_swap(&swap, swap_params.key_index, refund_address)
             .await
         {
             Ok(txid) => {
@@ -488,3 +396,454 @@ async fn test_vtxo_swap_client_refund() {
         tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
     }
 }
+
+// ============================================================================
+// Backup Tests
+// ============================================================================
+
+fn test_swap(id: &str, swap_params: lendaswap_core::PublicSwapParams) -> ExtendedSwapStorageData {
+    ExtendedSwapStorageData {
+        response: serde_json::from_value(serde_json::json!({
+            "direction": "btc_to_evm",
+            "id": id,
+            "status": "pending",
+            "hash_lock": format!("0x{}", hex::encode(swap_params.preimage_hash)),
+            "fee_sats": 100,
+            "asset_amount": 10.0,
+            "sender_pk": swap_params.public_key.to_string(),
+            "receiver_pk": "",
+            "server_pk": "",
+            "refund_locktime": 0,
+            "unilateral_claim_delay": 0,
+            "unilateral_refund_delay": 0,
+            "unilateral_refund_without_receiver_delay": 0,
+            "network": "regtest",
+            "created_at": "2025-01-01T00:00:00Z",
+            "htlc_address_evm": "",
+            "htlc_address_arkade": "",
+            "user_address_evm": "",
+            "ln_invoice": "",
+            "sats_receive": 1000,
+            "source_token": "btc_arkade",
+            "target_token": "usdc_pol",
+            "bitcoin_htlc_claim_txid": null,
+            "bitcoin_htlc_fund_txid": null,
+            "evm_htlc_claim_txid": null,
+            "evm_htlc_fund_txid": null,
+        }))
+        .unwrap(),
+        swap_params,
+    }
+}
+
+/// Backups don't talk to the API, so this runs without a server.
+#[tokio::test]
+async fn test_backup_roundtrip() {
+    let kdf_params = lendaswap_core::storage::KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+    let source = Client::new(
+        API_URL,
+        MemoryWalletStorage::new(),
+        MemorySwapStorage::new(),
+        Network::Regtest,
+        ARKADE_URL.to_string(),
+    );
+    source.init(None).await.unwrap();
+    let params = source.wallet().derive_swap_params().await.unwrap();
+    let swap_id = "00000000-0000-0000-0000-00000000000a";
+    source
+        .swap_storage()
+        .store(swap_id, &test_swap(swap_id, params.to_public()))
+        .await
+        .unwrap();
+    // A swap of another account, whose key index isn't stored in the source wallet
+    let other_account_id = "00000000-0000-0000-0000-00000000000b";
+    let other_account_params = lendaswap_core::PublicSwapParams {
+        account: 2,
+        key_index: 4,
+        ..params.to_public()
+    };
+    source
+        .swap_storage()
+        .store(
+            other_account_id,
+            &test_swap(other_account_id, other_account_params),
+        )
+        .await
+        .unwrap();
+
+    let vtxo_swap_id = "00000000-0000-0000-0000-00000000000c";
+    let vtxo_swap = lendaswap_core::storage::conformance::sample_vtxo_swap(
+        vtxo_swap_id,
+        lendaswap_core::api::VtxoSwapStatus::ClientFunded,
+    );
+    source
+        .swap_storage()
+        .store_vtxo_swap(vtxo_swap_id, &vtxo_swap)
+        .await
+        .unwrap();
+
+    let backup = source
+        .export_backup_with_kdf_params("hunter2", kdf_params)
+        .await
+        .unwrap();
+
+    let target = Client::new(
+        API_URL,
+        MemoryWalletStorage::new(),
+        MemorySwapStorage::new(),
+        Network::Regtest,
+        ARKADE_URL.to_string(),
+    );
+    assert!(target.import_backup(&backup, "hunter3").await.is_err());
+
+    let (metadata, report) = target.import_backup(&backup, "hunter2").await.unwrap();
+    assert_eq!(metadata.network, Network::Regtest);
+    assert_eq!(metadata.swap_count, 2);
+    assert_eq!(metadata.vtxo_swap_count, 1);
+    assert_eq!(report.added, vec![swap_id, other_account_id, vtxo_swap_id]);
+    assert_eq!(report.key_index, 1);
+    assert_eq!(
+        target
+            .wallet()
+            .storage()
+            .get_account_key_index(2)
+            .await
+            .unwrap(),
+        5
+    );
+    assert_eq!(
+        target.get_mnemonic().await.unwrap(),
+        source.get_mnemonic().await.unwrap()
+    );
+    let restored = target.load_swap_data_from_storage(swap_id).await.unwrap();
+    assert_eq!(restored.swap_params, params.to_public());
+    let restored = target
+        .swap_storage()
+        .get_vtxo_swap(vtxo_swap_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(restored.swap_params, vtxo_swap.swap_params);
+
+    // Importing again changes nothing
+    let (_, report) = target.import_backup(&backup, "hunter2").await.unwrap();
+    assert_eq!(
+        report.unchanged,
+        vec![swap_id, other_account_id, vtxo_swap_id]
+    );
+
+    // Backups are bound to their network and don't replace another wallet
+    let mainnet = Client::new(
+        API_URL,
+        MemoryWalletStorage::new(),
+        MemorySwapStorage::new(),
+        Network::Bitcoin,
+        ARKADE_URL.to_string(),
+    );
+    assert!(matches!(
+        mainnet.import_backup(&backup, "hunter2").await,
+        Err(lendaswap_core::Error::InvalidBackup(_))
+    ));
+    let other = Client::new(
+        API_URL,
+        MemoryWalletStorage::new(),
+        MemorySwapStorage::new(),
+        Network::Regtest,
+        ARKADE_URL.to_string(),
+    );
+    other.init(None).await.unwrap();
+    assert!(matches!(
+        other.import_backup(&backup, "hunter2").await,
+        Err(lendaswap_core::Error::InvalidBackup(_))
+    ));
+}
+
+/// Migrating rewrites the readable swaps and leaves unreadable ones quarantined.
+#[tokio::test]
+async fn test_migrate_swap_storage_skips_unreadable_records() {
+    let wallet = Client::new(
+        API_URL,
+        MemoryWalletStorage::new(),
+        MemorySwapStorage::new(),
+        Network::Regtest,
+        ARKADE_URL.to_string(),
+    );
+    wallet.init(None).await.unwrap();
+    let params = wallet.wallet().derive_swap_params().await.unwrap();
+
+    let storage = KvStorage::new(MemoryKeyValueStore::new());
+    let unreadable_id = "00000000-0000-0000-0000-00000000000a";
+    let readable_id = "00000000-0000-0000-0000-00000000000b";
+    storage
+        .key_value_store()
+        .put(
+            &format!("lendaswap/v1/swaps/{unreadable_id}"),
+            br#"{"version": 99}"#,
+        )
+        .await
+        .unwrap();
+    storage
+        .store(readable_id, &test_swap(readable_id, params.to_public()))
+        .await
+        .unwrap();
+
+    let client = Client::new(
+        API_URL,
+        MemoryWalletStorage::new(),
+        storage,
+        Network::Regtest,
+        ARKADE_URL.to_string(),
+    );
+    assert_eq!(client.migrate_swap_storage().await.unwrap(), 1);
+
+    let quarantined = client.list_quarantined_swaps().await.unwrap();
+    assert_eq!(quarantined.len(), 1);
+    assert_eq!(quarantined[0].id.as_deref(), Some(unreadable_id));
+}
+
+/// VTXO swap creations without a journaled ID are kept until their grace period has passed.
+#[tokio::test]
+async fn test_reconcile_keeps_recent_vtxo_swap_creations() {
+    let client = Client::new(
+        API_URL,
+        MemoryWalletStorage::new(),
+        MemorySwapStorage::new(),
+        Network::Regtest,
+        ARKADE_URL.to_string(),
+    );
+    client.init(None).await.unwrap();
+
+    let recent = PendingCreation::now_with_kind(
+        client
+            .wallet()
+            .derive_swap_params()
+            .await
+            .unwrap()
+            .to_public(),
+        PendingCreationKind::VtxoSwap,
+    );
+    let mut abandoned = PendingCreation::now_with_kind(
+        client
+            .wallet()
+            .derive_swap_params()
+            .await
+            .unwrap()
+            .to_public(),
+        PendingCreationKind::VtxoSwap,
+    );
+    abandoned.started_at -= PENDING_CREATION_GRACE_PERIOD + time::Duration::seconds(1);
+    for pending in [&recent, &abandoned] {
+        client
+            .swap_storage()
+            .store_pending_creation(pending)
+            .await
+            .unwrap();
+    }
+
+    let reconciled = client.reconcile_pending_creations().await.unwrap();
+    assert!(reconciled.swaps.is_empty());
+    assert!(reconciled.vtxo_swaps.is_empty());
+    assert_eq!(
+        client.swap_storage().get_pending_creations().await.unwrap(),
+        [recent]
+    );
+}
+
+/// Initializing the client removes the secrets older versions stored with each swap.
+#[tokio::test]
+async fn test_init_rewrites_outdated_swap_records() {
+    let wallet = Client::new(
+        API_URL,
+        MemoryWalletStorage::new(),
+        MemorySwapStorage::new(),
+        Network::Regtest,
+        ARKADE_URL.to_string(),
+    );
+    wallet.init(None).await.unwrap();
+    let params = wallet.wallet().derive_swap_params().await.unwrap();
+
+    let swap_id = "00000000-0000-0000-0000-00000000000a";
+    let key = format!("lendaswap/v1/swaps/{swap_id}");
+    let mut record = serde_json::to_value(test_swap(swap_id, params.to_public())).unwrap();
+    record.as_object_mut().unwrap().remove("version");
+    record["swap_params"]["secret_key"] = "11".repeat(32).into();
+    record["swap_params"]["preimage"] = "22".repeat(32).into();
+    let storage = KvStorage::new(MemoryKeyValueStore::new());
+    storage
+        .key_value_store()
+        .put(&key, record.to_string().as_bytes())
+        .await
+        .unwrap();
+
+    let client = Client::new(
+        API_URL,
+        MemoryWalletStorage::new(),
+        storage,
+        Network::Regtest,
+        ARKADE_URL.to_string(),
+    );
+    client.init(None).await.unwrap();
+
+    let stored = client
+        .swap_storage()
+        .key_value_store()
+        .get(&key)
+        .await
+        .unwrap()
+        .unwrap();
+    let stored: serde_json::Value = serde_json::from_slice(&stored).unwrap();
+    assert!(stored.get("version").is_some());
+    assert!(stored["swap_params"].get("secret_key").is_none());
+    assert!(stored["swap_params"].get("preimage").is_none());
+    assert!(
+        client
+            .swap_storage()
+            .list_outdated()
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+/// Histories are exported after their swap is deleted, but not for swaps of other accounts.
+#[tokio::test]
+async fn test_export_swap_history_of_deleted_swaps() {
+    let client = Client::new(
+        API_URL,
+        MemoryWalletStorage::new(),
+        MemorySwapStorage::new(),
+        Network::Regtest,
+        ARKADE_URL.to_string(),
+    );
+    client.init(None).await.unwrap();
+    let params = client.wallet().derive_swap_params().await.unwrap();
+
+    let deleted_id = "00000000-0000-0000-0000-00000000000a";
+    let other_account_id = "00000000-0000-0000-0000-00000000000b";
+    let other_account_params = lendaswap_core::PublicSwapParams {
+        account: 2,
+        ..params.to_public()
+    };
+    client
+        .swap_storage()
+        .store(deleted_id, &test_swap(deleted_id, params.to_public()))
+        .await
+        .unwrap();
+    client
+        .swap_storage()
+        .store(
+            other_account_id,
+            &test_swap(other_account_id, other_account_params),
+        )
+        .await
+        .unwrap();
+
+    let created = SwapEventKind::Created {
+        status: "pending".to_string(),
+    };
+    let deleted_event = SwapEvent::now(deleted_id, created.clone());
+    for event in [&deleted_event, &SwapEvent::now(other_account_id, created)] {
+        client
+            .swap_storage()
+            .append_swap_event(event)
+            .await
+            .unwrap();
+    }
+    client.delete_swap(deleted_id.to_string()).await.unwrap();
+
+    let export = client.export_swap_history().await.unwrap();
+    assert_eq!(export.events, [deleted_event]);
+}
+
+/// A swap storage without VTXO swap support, which journals swap creations.
+struct SwapsOnlyStorage(MemorySwapStorage);
+
+impl SwapStorage for SwapsOnlyStorage {
+    fn get(&self, swap_id: &str) -> StorageFuture<'_, Option<ExtendedSwapStorageData>> {
+        self.0.get(swap_id)
+    }
+
+    fn store(&self, swap_id: &str, data: &ExtendedSwapStorageData) -> StorageFuture<'_, ()> {
+        self.0.store(swap_id, data)
+    }
+
+    fn delete(&self, swap_id: &str) -> StorageFuture<'_, ()> {
+        self.0.delete(swap_id)
+    }
+
+    fn list(&self) -> StorageFuture<'_, Vec<String>> {
+        self.0.list()
+    }
+
+    fn get_all(&self) -> StorageFuture<'_, Vec<ExtendedSwapStorageData>> {
+        self.0.get_all()
+    }
+
+    fn store_pending_creation(&self, pending: &PendingCreation) -> StorageFuture<'_, ()> {
+        self.0.store_pending_creation(pending)
+    }
+
+    fn delete_pending_creation(&self, user_id: &str) -> StorageFuture<'_, ()> {
+        self.0.delete_pending_creation(user_id)
+    }
+
+    fn get_pending_creations(&self) -> StorageFuture<'_, Vec<PendingCreation>> {
+        self.0.get_pending_creations()
+    }
+}
+
+/// Without VTXO swap support, no VTXO swap is created and journaled VTXO swaps don't fail the
+/// initialization.
+#[tokio::test]
+async fn test_vtxo_swaps_unsupported_by_storage() {
+    let client = Client::new(
+        API_URL,
+        MemoryWalletStorage::new(),
+        SwapsOnlyStorage(MemorySwapStorage::new()),
+        Network::Regtest,
+        ARKADE_URL.to_string(),
+    );
+    client.init(None).await.unwrap();
+
+    // Fails before calling the API, which isn't running
+    let error = client.create_vtxo_swap(vec![]).await.unwrap_err();
+    assert!(
+        matches!(error, lendaswap_core::Error::Storage(_)),
+        "{error:#}"
+    );
+    assert!(
+        client
+            .swap_storage()
+            .get_pending_creations()
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let mut pending = PendingCreation::now_with_kind(
+        client
+            .wallet()
+            .derive_swap_params()
+            .await
+            .unwrap()
+            .to_public(),
+        PendingCreationKind::VtxoSwap,
+    );
+    pending.swap_id = Some("00000000-0000-0000-0000-00000000000a".to_string());
+    client
+        .swap_storage()
+        .store_pending_creation(&pending)
+        .await
+        .unwrap();
+
+    let reconciled = client.reconcile_pending_creations().await.unwrap();
+    assert!(reconciled.vtxo_swaps.is_empty());
+    assert_eq!(
+        client.swap_storage().get_pending_creations().await.unwrap(),
+        [pending]
+    );
+}
@@ -10,6 +10,7 @@
 import {
   type CreateVtxoSwapResult,
   type EstimateVtxoSwapResponse,
+  JsKeyValueStoreProvider,
   JsSwapStorageProvider,
   JsWalletStorageProvider,
   type SwapParams,
@@ -20,8 +21,13 @@ import {
   type TokenInfo as WasmTokenInfo,
   getLogLevel as wasmGetLogLevel,
   setLogLevel as wasmSetLogLevel,
+  verifySwapOwnership as wasmVerifySwapOwnership,
 } from "../wasm/lendaswap_wasm_sdk.js";
-import type { VhtlcAmounts } from "./types.js";
+import type {
+  MnemonicOptions,
+  Slip39Group,
+  VhtlcAmounts,
+} from "./types.js";
 
 // Re-export WASM types directly
 export {
@@ -212,13 +218,175 @@ export interface EvmToBtcSwapResponse extends SwapCommonFields {
 export type GetSwapResponse = BtcToEvmSwapResponse | EvmToBtcSwapResponse;
 
 /**
- * Extended swap storage data that includes the swap response and optional secret.
- * Used for persisting swap data locally with the preimage secret.
+ * Public swap parameters as persisted alongside a swap.
+ *
+ * Secrets (secret key, preimage) are not stored: the SDK re-derives them from
+ * `key_index` when claiming or refunding.
+ */
+export interface StoredSwapParams {
+  /** Public key (hex-encoded). */
+  public_key: string;
+  /** Hash of the preimage (hex-encoded). */
+  preimage_hash: string;
+  /** User ID derived from HD wallet (hex-encoded). */
+  user_id: string;
+  /** Key derivation index used. */
+  key_index: number;
+  /** Account the keys were derived for. Missing in records created before accounts. */
+  account?: number;
+  /** Version of the derivation scheme. Missing in records created before schemes (version 1). */
+  scheme?: number;
+}
+
+/**
+ * Extended swap storage data that includes the swap response and the public swap parameters.
+ * Used for persisting swap data locally.
  */
 export interface ExtendedSwapStorageData {
+  /** Schema version of the record. Missing in records created before versioning (version 0). */
+  version?: number;
   // TODO: flatten this. No  need to return extended swap data
   response: GetSwapResponse;
-  swap_params: SwapParams;
+  swap_params: StoredSwapParams;
+}
+
+/**
+ * A swap creation that was started but not finalized. It is stored before the swap is created
+ * with the API and deleted once the swap is stored.
+ */
+export interface PendingCreation {
+  /** The parameters the swap is created with. `swap_params.user_id` identifies the entry. */
+  swap_params: StoredSwapParams;
+  /** The kind of swap. Missing in entries written before VTXO swaps were journaled. */
+  kind?: "swap" | "vtxo_swap";
+  /** The ID the server assigned to a VTXO swap, once known. */
+  swap_id?: string;
+  /** When the creation was started (RFC 3339). */
+  started_at: string;
+}
+
+/**
+ * The swaps stored by `reconcilePendingCreations`.
+ */
+export interface ReconciledCreations {
+  /** The restored swaps. */
+  swaps: ExtendedSwapStorageData[];
+  /** The restored VTXO swaps. */
+  vtxo_swaps: ExtendedVtxoSwapStorageData[];
+}
+
+/**
+ * A stored swap record that can't be read, e.g. because a newer SDK version wrote it.
+ * It is kept in storage unchanged and skipped when listing swaps.
+ */
+export interface QuarantinedRecord {
+  /** The swap ID, if known. */
+  id: string | null;
+  /** Why the record can't be read. */
+  error: string;
+  /** The record as stored (JSON). */
+  data: string;
+}
+
+/**
+ * Filter, sort order and page of a swap query. Omitted fields don't filter.
+ */
+export interface SwapQuery {
+  /** Only swaps with one of these statuses. */
+  statuses?: SwapStatus[];
+  /** Only swaps in this direction. */
+  direction?: "btc_to_evm" | "evm_to_btc";
+  /** Only swaps sending or receiving this token. */
+  token?: TokenIdString;
+  /** Only swaps sending or receiving a token on this chain. */
+  chain?: Chain;
+  /** Only swaps created at or after this time (RFC 3339). */
+  created_from?: string;
+  /** Only swaps created before this time (RFC 3339). */
+  created_until?: string;
+  /** Order of the returned swaps (default: newest first). */
+  order?: "ascending" | "descending";
+  /** Maximum number of swaps to return, at least 1. */
+  limit?: number;
+  /** Return the swaps after this cursor, taken from `SwapPage.next_cursor`. */
+  cursor?: string;
+}
+
+/**
+ * A page of swaps returned by a query.
+ */
+export interface SwapPage {
+  /** The matching swaps, in the query's order. */
+  swaps: ExtendedSwapStorageData[];
+  /** Cursor of the next page, or null if this is the last page. */
+  next_cursor: string | null;
+}
+
+/**
+ * An event in the history of a swap. Statuses are named as the API serializes them.
+ */
+export type SwapEvent = {
+  /** The swap the event belongs to. */
+  swap_id: string;
+  /** When the SDK recorded the event (RFC 3339). */
+  timestamp: string;
+} & (
+  | { type: "created"; status: string }
+  | { type: "status_changed"; from: string; to: string }
+  | { type: "api_call"; endpoint: string }
+  | { type: "claimed"; txid: string }
+  | { type: "refunded"; txid: string }
+  | { type: "error"; operation: string; message: string }
+);
+
+/**
+ * The history of all swaps of an account, e.g. for compliance records.
+ */
+export interface SwapHistoryExport {
+  /** When the history was exported (RFC 3339). */
+  exported_at: string;
+  /** The account whose swaps are included. */
+  account: number;
+  /** The events of all stored swaps of the account, oldest first. */
+  events: SwapEvent[];
+}
+
+/**
+ * A VTXO swap as stored locally, in the API's snake_case format.
+ */
+export interface StoredVtxoSwapResponse {
+  id: string;
+  status: VtxoSwapStatus;
+  created_at: string;
+  client_vhtlc_address: string;
+  client_fund_amount_sats: number;
+  client_pk: string;
+  client_locktime: number;
+  client_unilateral_claim_delay: number;
+  client_unilateral_refund_delay: number;
+  client_unilateral_refund_without_receiver_delay: number;
+  server_vhtlc_address: string;
+  server_fund_amount_sats: number;
+  server_pk: string;
+  server_locktime: number;
+  server_unilateral_claim_delay: number;
+  server_unilateral_refund_delay: number;
+  server_unilateral_refund_without_receiver_delay: number;
+  arkade_server_pk: string;
+  preimage_hash: string;
+  fee_sats: number;
+  network: string;
+}
+
+/**
+ * Extended VTXO swap storage data: the VTXO swap and the public swap parameters.
+ * Used for persisting VTXO swaps locally.
+ */
+export interface ExtendedVtxoSwapStorageData {
+  /** Schema version of the record. */
+  version?: number;
+  response: StoredVtxoSwapResponse;
+  swap_params: StoredSwapParams;
 }
 
 /**
@@ -274,6 +442,67 @@ export interface GelatoSubmitResponse {
  */
 export type RecoveredSwap = GetSwapResponse & { index: number };
 
+/**
+ * A recovered swap that was not merged because it disagrees with the local record.
+ */
+export interface RecoveryConflict {
+  swap_id: string;
+  kind: "key_mismatch" | "local_status_ahead";
+}
+
+/**
+ * Outcome of merging recovered swaps into local storage.
+ */
+export interface RecoveryReport {
+  added: string[];
+  updated: string[];
+  unchanged: string[];
+  conflicts: RecoveryConflict[];
+  local_only: string[];
+  key_index: number;
+}
+
+/**
+ * Information about a backup created with `Client.exportBackup`.
+ */
+export interface BackupMetadata {
+  network: string;
+  /** RFC 3339 timestamp of when the backup was created. */
+  created_at: string;
+  /** Account of the client that created the backup. */
+  account: number;
+  swap_count: number;
+  /** Missing in backups created before VTXO swaps were backed up. */
+  vtxo_swap_count?: number;
+}
+
+/**
+ * A BIP340 Schnorr signature over a message, as returned by `Client.signMessage`.
+ */
+export interface MessageSignature {
+  /** Hex-encoded public key of the signing key. */
+  public_key: string;
+  /** Hex-encoded signature. */
+  signature: string;
+}
+
+/**
+ * Key an ownership proof is signed with: the swap's refund or claim key, or its user ID.
+ */
+export type OwnershipKey = "swap_key" | "user_id";
+
+/**
+ * Proof that the holder of a key created a swap, as returned by `Client.proveSwapOwnership`.
+ */
+export interface OwnershipProof {
+  swap_id: string;
+  key: OwnershipKey;
+  /** The verifier's challenge the proof answers. */
+  challenge: string;
+  public_key: string;
+  signature: string;
+}
+
 /**
  * Response from the recover swaps endpoint.
  */
@@ -355,6 +584,21 @@ export interface WalletStorageProvider {
   getKeyIndex: () => Promise<number>;
   /** Set the key derivation index. */
   setKeyIndex: (index: number) => Promise<void>;
+  /**
+   * Atomically return the current key index and store the next one.
+   *
+   * Optional. Implement this with a transaction if the storage is shared between tabs,
+   * otherwise concurrent swaps in different tabs may reuse a key.
+   */
+  reserveKeyIndex?: () => Promise<number>;
+  /**
+   * Get the key derivation index of an account other than account 0.
+   *
+   * Optional. Required, together with `setAccountKeyIndex`, to use accounts other than 0.
+   */
+  getAccountKeyIndex?: (account: number) => Promise<number>;
+  /** Set the key derivation index of an account other than account 0. */
+  setAccountKeyIndex?: (account: number, index: number) => Promise<void>;
 }
 
 /**
@@ -373,6 +617,74 @@ export interface SwapStorageProvider {
   list: () => Promise<string[]>;
   /** List all stored swaps. */
   getAll: () => Promise<ExtendedSwapStorageData[]>;
+  /**
+   * Get VTXO swap data by swap ID. Returns null if not found.
+   *
+   * Optional. Required, together with the other VTXO swap methods, to create VTXO swaps.
+   */
+  getVtxoSwap?: (swapId: string) => Promise<ExtendedVtxoSwapStorageData | null>;
+  /** Store VTXO swap data. Overwrites any existing VTXO swap with the same ID. */
+  storeVtxoSwap?: (
+    swapId: string,
+    data: ExtendedVtxoSwapStorageData,
+  ) => Promise<void>;
+  /** Delete VTXO swap data by swap ID. */
+  deleteVtxoSwap?: (swapId: string) => Promise<void>;
+  /** List all stored VTXO swaps. */
+  getAllVtxoSwaps?: () => Promise<ExtendedVtxoSwapStorageData[]>;
+  /**
+   * Run a swap query in the storage, e.g. on IndexedDB indexes.
+   *
+   * Optional. Without it, queries load all swaps with `getAll` and filter them in the SDK.
+   * The query's `account` is set by the client.
+   */
+  query?: (query: SwapQuery & { account?: number }) => Promise<SwapPage>;
+  /**
+   * Append an event to the history of its swap.
+   *
+   * Optional. Required, together with `getSwapEvents` and `getAllSwapEvents`, to keep swap
+   * histories.
+   */
+  appendSwapEvent?: (event: SwapEvent) => Promise<void>;
+  /**
+   * Get the events of a swap in the order they were appended. Events are kept when the swap
+   * is deleted.
+   */
+  getSwapEvents?: (swapId: string) => Promise<SwapEvent[]>;
+  /**
+   * Get the events of all swaps, including deleted ones, each swap's in the order they were
+   * appended.
+   */
+  getAllSwapEvents?: () => Promise<SwapEvent[]>;
+  /**
+   * Store a pending swap creation, keyed by `pending.swap_params.user_id`. Overwrites any entry
+   * with the same user ID.
+   *
+   * Optional. Required, together with the other pending creation methods, to recover swaps
+   * whose creation was interrupted after the API call.
+   */
+  storePendingCreation?: (pending: PendingCreation) => Promise<void>;
+  /** Delete a pending swap creation by user ID. */
+  deletePendingCreation?: (userId: string) => Promise<void>;
+  /** List all pending swap creations. */
+  getPendingCreations?: () => Promise<PendingCreation[]>;
+}
+
+/**
+ * Key-value store that holds both the wallet and the swap data of a client, e.g. on
+ * localStorage, React Native MMKV or a server-side key-value database.
+ *
+ * All keys start with `lendaswap/v1/`.
+ */
+export interface KeyValueStoreProvider {
+  /** Get the value of a key. Returns null if the key doesn't exist. */
+  get: (key: string) => Promise<Uint8Array | null>;
+  /** Set the value of a key, overwriting any existing value. */
+  put: (key: string, value: Uint8Array) => Promise<void>;
+  /** Delete a key. Deleting a missing key is not an error. */
+  delete: (key: string) => Promise<void>;
+  /** Get all entries whose key starts with `prefix`, in any order. */
+  scanPrefix: (prefix: string) => Promise<[string, Uint8Array][]>;
 }
 
 /**
@@ -395,6 +707,7 @@ export class Client {
    * @param swapStorage - Storage provider for persisting swap data (uses Dexie/IndexedDB)
    * @param network - Bitcoin network ("bitcoin", "testnet", "regtest", "mutinynet")
    * @param arkadeUrl - Arkade's server url
+   * @param account - Optional account of the mnemonic to use (default: 0)
    * @param wasmPath - Optional path to the WASM file (for Node.js environments)
    * @returns A new Client instance
    *
@@ -437,29 +750,115 @@ export class Client {
     swapStorage: SwapStorageProvider,
     network: Network,
     arkadeUrl: string,
+    account = 0,
   ): Promise<Client> {
     // Bind wallet storage methods to preserve 'this' context when called from WASM
-    const jsWalletStorageProvider = new JsWalletStorageProvider(
+    let jsWalletStorageProvider = new JsWalletStorageProvider(
       walletStorage.getMnemonic.bind(walletStorage),
       walletStorage.setMnemonic.bind(walletStorage),
       walletStorage.getKeyIndex.bind(walletStorage),
       walletStorage.setKeyIndex.bind(walletStorage),
     );
+    if (walletStorage.reserveKeyIndex) {
+      jsWalletStorageProvider = jsWalletStorageProvider.withReserveKeyIndex(
+        walletStorage.reserveKeyIndex.bind(walletStorage),
+      );
+    }
+    if (walletStorage.getAccountKeyIndex && walletStorage.setAccountKeyIndex) {
+      jsWalletStorageProvider = jsWalletStorageProvider.withAccountKeyIndex(
+        walletStorage.getAccountKeyIndex.bind(walletStorage),
+        walletStorage.setAccountKeyIndex.bind(walletStorage),
+      );
+    }
     // Bind swap storage methods to preserve 'this' context when called from WASM
-    const jsSwapStorageProvider = new JsSwapStorageProvider(
+    let jsSwapStorageProvider = new JsSwapStorageProvider(
       swapStorage.get.bind(swapStorage),
       swapStorage.store.bind(swapStorage),
       swapStorage.delete.bind(swapStorage),
       swapStorage.list.bind(swapStorage),
       swapStorage.getAll.bind(swapStorage),
     );
+    if (
+      swapStorage.getVtxoSwap &&
+      swapStorage.storeVtxoSwap &&
+      swapStorage.deleteVtxoSwap &&
+      swapStorage.getAllVtxoSwaps
+    ) {
+      jsSwapStorageProvider = jsSwapStorageProvider.withVtxoSwaps(
+        swapStorage.getVtxoSwap.bind(swapStorage),
+        swapStorage.storeVtxoSwap.bind(swapStorage),
+        swapStorage.deleteVtxoSwap.bind(swapStorage),
+        swapStorage.getAllVtxoSwaps.bind(swapStorage),
+      );
+    }
+    if (swapStorage.query) {
+      jsSwapStorageProvider = jsSwapStorageProvider.withQuery(
+        swapStorage.query.bind(swapStorage),
+      );
+    }
+    if (
+      swapStorage.appendSwapEvent &&
+      swapStorage.getSwapEvents &&
+      swapStorage.getAllSwapEvents
+    ) {
+      jsSwapStorageProvider = jsSwapStorageProvider.withSwapEvents(
+        swapStorage.appendSwapEvent.bind(swapStorage),
+        swapStorage.getSwapEvents.bind(swapStorage),
+        swapStorage.getAllSwapEvents.bind(swapStorage),
+      );
+    }
+    if (
+      swapStorage.storePendingCreation &&
+      swapStorage.deletePendingCreation &&
+      swapStorage.getPendingCreations
+    ) {
+      jsSwapStorageProvider = jsSwapStorageProvider.withPendingCreations(
+        swapStorage.storePendingCreation.bind(swapStorage),
+        swapStorage.deletePendingCreation.bind(swapStorage),
+        swapStorage.getPendingCreations.bind(swapStorage),
+      );
+    }
     const wasmClient = new WasmClient(
       baseUrl,
       jsWalletStorageProvider,
       jsSwapStorageProvider,
       network,
       arkadeUrl,
+    ).withAccount(account);
+
+    return new Client(wasmClient);
+  }
+
+  /**
+   * Create a new Client that keeps its wallet and swap data in a single key-value store.
+   *
+   * @param baseUrl - The base URL of the Lendaswap API
+   * @param store - Key-value store for wallet and swap data
+   * @param network - Bitcoin network ("bitcoin", "testnet", "regtest", "mutinynet")
+   * @param arkadeUrl - Arkade's server url
+   * @param account - Optional account of the mnemonic to use (default: 0)
+   * @returns A new Client instance
+   */
+  static async createWithKeyValueStore(
+    baseUrl: string,
+    store: KeyValueStoreProvider,
+    network: Network,
+    arkadeUrl: string,
+    account = 0,
+  ): Promise<Client> {
+    // Bind store methods to preserve 'this' context when called from WASM
+    const jsKeyValueStoreProvider = new JsKeyValueStoreProvider(
+      store.get.bind(store),
+      store.put.bind(store),
+      store.delete.bind(store),
+      store.scanPrefix.bind(store),
     );
+    const wasmClient = WasmClient.fromKeyValueStore(
+      baseUrl,
+      jsKeyValueStoreProvider,
+      network,
+      arkadeUrl,
+    ).withAccount(account);
 
     return new Client(wasmClient);
   }
@@ -468,6 +867,54 @@ export class Client {
     await this.client.init(mnemonic);
   }
 
+  /**
+   * Initialize the wallet, creating a new mnemonic with the given options if none is stored.
+   *
+   * @param options - Word count, wordlist and optional entropy for the new mnemonic
+   */
+  async initWithOptions(options: MnemonicOptions): Promise<void> {
+    await this.client.initWithOptions(
+      options.wordCount,
+      options.language,
+      options.entropy,
+    );
+  }
+
+  /**
+   * Split the wallet's mnemonic into SLIP-39 Shamir shares.
+   *
+   * Restoring from the shares yields the same mnemonic, and therefore the same swap keys.
+   *
+   * @param groupThreshold - Number of groups required to restore the wallet
+   * @param groups - The groups of shares to create
+   * @param passphrase - Optional passphrase to encrypt the shares with
+   * @returns One array of share mnemonics per group
+   */
+  async getSlip39Shares(
+    groupThreshold: number,
+    groups: Slip39Group[],
+    passphrase = "",
+  ): Promise<string[][]> {
+    return (await this.client.getSlip39Shares(
+      groupThreshold,
+      groups.map((group) => ({
+        member_threshold: group.memberThreshold,
+        member_count: group.memberCount,
+      })),
+      passphrase,
+    )) as string[][];
+  }
+
+  /**
+   * Restore the wallet from SLIP-39 shares, replacing the current mnemonic.
+   *
+   * @param shares - Enough share mnemonics to meet the thresholds
+   * @param passphrase - The passphrase the shares were created with
+   */
+  async initFromSlip39Shares(shares: string[], passphrase = ""): Promise<void> {
+    await this.client.initFromSlip39Shares(shares, passphrase);
+  }
+
   /**
    * Create an Arkade to EVM swap (BTC → Token).
    *
@@ -591,6 +1038,57 @@ export class Client {
     return (await this.client.listAll()) as ExtendedSwapStorageData[];
   }
 
+  /**
+   * Queries the stored swaps, filtered, sorted and paginated.
+   *
+   * @param query - The filters, sort order and page (default: all swaps, newest first)
+   * @returns The page of swaps and the cursor of the next page
+   */
+  async listSwaps(query: SwapQuery = {}): Promise<SwapPage> {
+    return (await this.client.listSwaps(query)) as SwapPage;
+  }
+
+  /**
+   * Gets the stored swap records that can't be read and are skipped by `listAllSwaps`.
+   *
+   * @returns The quarantined records
+   */
+  async listQuarantinedSwaps(): Promise<QuarantinedRecord[]> {
+    return (await this.client.listQuarantinedSwaps()) as QuarantinedRecord[];
+  }
+
+  /**
+   * Recovers the swaps and VTXO swaps whose creation was interrupted after the API call, e.g.
+   * by closing the tab. This runs automatically in `init`.
+   *
+   * @returns The recovered swaps and VTXO swaps
+   */
+  async reconcilePendingCreations(): Promise<ReconciledCreations> {
+    const reconciled = await this.client.reconcilePendingCreations();
+    return reconciled as ReconciledCreations;
+  }
+
+  /**
+   * Gets the recorded history of a swap or VTXO swap: status changes, API calls, claim and
+   * refund transactions, and errors.
+   *
+   * @param id - The swap ID
+   * @returns The swap's events, oldest first
+   */
+  async getSwapHistory(id: string): Promise<SwapEvent[]> {
+    return (await this.client.swapHistory(id)) as SwapEvent[];
+  }
+
+  /**
+   * Exports the histories of all stored swaps of the client's account, e.g. for compliance
+   * records.
+   *
+   * @returns The events of all swaps, oldest first
+   */
+  async exportSwapHistory(): Promise<SwapHistoryExport> {
+    return (await this.client.exportSwapHistory()) as SwapHistoryExport;
+  }
+
   /**
    * Claim a swap via Gelato relay (gasless).
    *
@@ -652,6 +1150,90 @@ export class Client {
     return (await this.client.recoverSwaps()) as ExtendedSwapStorageData[];
   }
 
+  /**
+   * Recover swaps for the currently loaded mnemonic and merge them into local storage.
+   *
+   * Local swaps are never deleted and the key index is never lowered.
+   *
+   * @returns Report of added, updated, conflicting and local-only swaps
+   */
+  async recoverSwapsWithReport(): Promise<RecoveryReport> {
+    return (await this.client.recoverSwapsWithReport()) as RecoveryReport;
+  }
+
+  /**
+   * Export the wallet and all stored swaps as an encrypted backup.
+   *
+   * The BIP39 passphrase is not part of the backup.
+   *
+   * @param password - Password to encrypt the backup with
+   * @returns The backup as a JSON string
+   */
+  async exportBackup(password: string): Promise<string> {
+    return await this.client.exportBackup(password);
+  }
+
+  /**
+   * Restore a backup created with `exportBackup` into this client's storage.
+   *
+   * The backup must be for the client's network, and the wallet storage must be empty or hold
+   * the same mnemonic. Swaps are merged like recovered swaps.
+   *
+   * @param backup - The backup as returned by `exportBackup`
+   * @param password - Password the backup was encrypted with
+   * @returns The backup's metadata and a report of the merged swaps
+   */
+  async importBackup(
+    backup: string,
+    password: string,
+  ): Promise<{ metadata: BackupMetadata; report: RecoveryReport }> {
+    const [metadata, report] = (await this.client.importBackup(
+      backup,
+      password,
+    )) as [BackupMetadata, RecoveryReport];
+    return { metadata, report };
+  }
+
+  /**
+   * Sign a message with the swap key at `keyIndex`.
+   *
+   * The signature is a BIP340 Schnorr signature over a tagged hash of the message, so it
+   * cannot be mistaken for a transaction signature.
+   *
+   * @param keyIndex - Key index of the swap key
+   * @param message - The message to sign
+   */
+  async signMessage(
+    keyIndex: number,
+    message: string,
+  ): Promise<MessageSignature> {
+    return (await this.client.signMessage(
+      keyIndex,
+      message,
+    )) as MessageSignature;
+  }
+
+  /**
+   * Prove that this client created a swap, e.g. for a support dispute.
+   *
+   * @param swapId - The swap ID
+   * @param challenge - The verifier's challenge, e.g. a nonce, so that the proof can't be
+   *   replayed
+   * @param key - Sign with the swap's refund or claim key (`"swap_key"`) or its user ID
+   *   (`"user_id"`)
+   */
+  async proveSwapOwnership(
+    swapId: string,
+    challenge: string,
+    key: OwnershipKey = "swap_key",
+  ): Promise<OwnershipProof> {
+    return (await this.client.proveSwapOwnership(
+      swapId,
+      challenge,
+      key,
+    )) as OwnershipProof;
+  }
+
   /**
    * Get current loaded mnemonic
    * @returns The mnemonic as string
@@ -681,6 +1263,16 @@ export class Client {
     return await this.client.deleteSwap(id);
   }
 
+  /**
+   * Rewrite all stored swaps without the secret keys and preimages that
+   * older SDK versions persisted.
+   *
+   * @returns The number of swaps rewritten
+   */
+  async migrateSwapStorage(): Promise<number> {
+    return await this.client.migrateSwapStorage();
+  }
+
   // =========================================================================
   // VTXO Swap Methods
   // =========================================================================
@@ -702,6 +1294,9 @@ export class Client {
    * then the server funds their VHTLC, and the client claims the server's
    * VHTLC to complete the swap.
    *
+   * The swap is stored before it is returned, which requires the VTXO swap
+   * methods of the swap storage provider.
+   *
    * @param vtxos - List of VTXO outpoints to refresh ("txid:vout" format)
    * @returns The swap response and swap parameters
    */
@@ -719,6 +1314,64 @@ export class Client {
     return await this.client.getVtxoSwap(id);
   }
 
+  /**
+   * Fetch the latest status of a stored VTXO swap and update the local storage.
+   *
+   * @param id - The swap ID
+   * @returns The updated VTXO swap data
+   */
+  async refreshVtxoSwap(id: string): Promise<ExtendedVtxoSwapStorageData> {
+    return (await this.client.refreshVtxoSwap(
+      id,
+    )) as ExtendedVtxoSwapStorageData;
+  }
+
+  /**
+   * Gets all stored VTXO swaps.
+   *
+   * @returns The stored VTXO swaps of the account
+   */
+  async listVtxoSwaps(): Promise<ExtendedVtxoSwapStorageData[]> {
+    return (await this.client.listVtxoSwaps()) as ExtendedVtxoSwapStorageData[];
+  }
+
+  /**
+   * Delete a stored VTXO swap.
+   *
+   * @param id - The swap ID
+   */
+  async deleteVtxoSwap(id: string): Promise<void> {
+    await this.client.deleteVtxoSwap(id);
+  }
+
+  /**
+   * Claim the server's VHTLC of a stored VTXO swap.
+   *
+   * @param swapId - The swap ID
+   * @param claimAddress - The Arkade address to receive the claimed funds
+   * @returns The claim transaction ID
+   */
+  async claimVtxoSwapById(
+    swapId: string,
+    claimAddress: string,
+  ): Promise<string> {
+    return await this.client.claimVtxoSwapById(swapId, claimAddress);
+  }
+
+  /**
+   * Refund the client's VHTLC of a stored VTXO swap.
+   *
+   * @param swapId - The swap ID
+   * @param refundAddress - The Arkade address to receive the refunded funds
+   * @returns The refund transaction ID
+   */
+  async refundVtxoSwapById(
+    swapId: string,
+    refundAddress: string,
+  ): Promise<string> {
+    return await this.client.refundVtxoSwapById(swapId, refundAddress);
+  }
+
   /**
    * Claim the server's VHTLC in a VTXO swap.
    *
@@ -726,7 +1379,7 @@ export class Client {
    * The client reveals the preimage to claim the fresh VTXOs.
    *
    * @param swap - The VTXO swap response
-   * @param swapParams - The client's swap parameters (containing preimage)
+   * @param swapParams - The swap parameters returned by `createVtxoSwap`
    * @param claimAddress - The Arkade address to receive the claimed funds
    * @returns The claim transaction ID
    */
@@ -745,7 +1398,7 @@ export class Client {
    * and the client's locktime has expired.
    *
    * @param swap - The VTXO swap response
-   * @param swapParams - The client's swap parameters
+   * @param swapParams - The swap parameters returned by `createVtxoSwap`
    * @param refundAddress - The Arkade address to receive the refunded funds
    * @returns The refund transaction ID
    */
@@ -796,3 +1449,22 @@ export function setLogLevel(level: LogLevel): void {
 export function getLogLevel(): LogLevel {
   return wasmGetLogLevel() as LogLevel;
 }
+
+/**
+ * Verify a proof created with `Client.proveSwapOwnership` using the swap's refund or claim key.
+ *
+ * Proofs signed with the user ID cannot be checked against the swap alone, as it does not
+ * contain the user ID.
+ *
+ * @param swap - The swap as returned by the API
+ * @param challenge - The challenge the proof was requested with
+ * @param proof - The proof to verify
+ * @throws If the proof is not valid for the swap or doesn't answer the challenge
+ */
+export function verifySwapOwnership(
+  swap: GetSwapResponse,
+  challenge: string,
+  proof: OwnershipProof,
+): void {
+  wasmVerifySwapOwnership(swap, challenge, proof);
+}
@@ -38,6 +38,7 @@
 // Re-export WASM types that are commonly used
 // Storage provider types for Client.create()
 export type {
+  KeyValueStoreProvider,
   QuoteResponse,
   QuoteResponseInfo,
   SwapStorageProvider,
@@ -49,6 +50,7 @@ export type {
 // API client
 export {
   type AssetPair,
+  type BackupMetadata,
   type BtcToEvmSwapResponse,
   type Chain,
   Client,
@@ -58,15 +60,30 @@ export {
   type EvmToBtcSwapResponse,
   type EvmToLightningSwapRequest,
   type ExtendedSwapStorageData,
+  type ExtendedVtxoSwapStorageData,
   type GelatoSubmitRequest,
   type GelatoSubmitResponse,
   type GetSwapResponse,
   getLogLevel,
   type LogLevel,
+  type MessageSignature,
+  type OwnershipKey,
+  type OwnershipProof,
+  type PendingCreation,
+  type QuarantinedRecord,
   type QuoteRequest,
+  type ReconciledCreations,
   type RecoveredSwap,
   type RecoverSwapsResponse,
+  type RecoveryConflict,
+  type RecoveryReport,
+  type StoredSwapParams,
+  type StoredVtxoSwapResponse,
   type SwapCommonFields,
+  type SwapEvent,
+  type SwapHistoryExport,
+  type SwapPage,
+  type SwapQuery,
   type SwapRequest,
   type SwapStatus,
   setLogLevel,
@@ -75,6 +92,7 @@ export {
   VtxoSwapParams,
   VtxoSwapResponse,
   type VtxoSwapStatus,
+  verifySwapOwnership,
 } from "./api.js";
 export {
   PriceFeedService,
@@ -93,7 +111,15 @@ export {
   DexieWalletStorageProvider,
   STORAGE_KEYS,
 } from "./storage/index.js";
-export type { Network, SwapData, SwapParams, VhtlcAmounts } from "./types.js";
+export type {
+  MnemonicLanguage,
+  MnemonicOptions,
+  Network,
+  Slip39Group,
+  SwapData,
+  SwapParams,
+  VhtlcAmounts,
+} from "./types.js";
 export {
   type GetUsdPriceOptions,
   getCoinGeckoId,
@@ -6,7 +6,12 @@
  */
 
 import Dexie, { type Table } from "dexie";
-import type { ExtendedSwapStorageData } from "../api.js";
+import type {
+  ExtendedSwapStorageData,
+  ExtendedVtxoSwapStorageData,
+  PendingCreation,
+  SwapEvent,
+} from "../api.js";
 
 /**
  * Stored swap record in IndexedDB.
@@ -16,17 +21,41 @@ interface SwapRecord extends ExtendedSwapStorageData {
   id: string;
 }
 
+/**
+ * Stored VTXO swap record in IndexedDB, keyed by swap ID like `SwapRecord`.
+ */
+interface VtxoSwapRecord extends ExtendedVtxoSwapStorageData {
+  id: string;
+}
+
+/**
+ * Stored swap event in IndexedDB. The auto-incremented `seq` keeps the append order.
+ */
+type SwapEventRecord = SwapEvent & { seq?: number };
+
 /**
  * Dexie database for storing swap data.
  */
 class LendaswapDatabase extends Dexie {
   swaps!: Table<SwapRecord, string>;
+  vtxoSwaps!: Table<VtxoSwapRecord, string>;
+  swapEvents!: Table<SwapEventRecord, number>;
+  pendingCreations!: Table<PendingCreation, string>;
 
   constructor(dbName = "lendaswap") {
     super(dbName);
     this.version(1).stores({
       swaps: "id", // Primary key only, no additional indexes needed
     });
+    this.version(2).stores({
+      vtxoSwaps: "id",
+    });
+    this.version(3).stores({
+      swapEvents: "++seq, swap_id",
+    });
+    this.version(4).stores({
+      pendingCreations: "swap_params.user_id",
+    });
   }
 }
 
@@ -110,10 +139,11 @@ export class DexieSwapStorageProvider {
   }
 
   /**
-   * Clear all swap data.
+   * Clear all swap data, including VTXO swaps.
    */
   async clear(): Promise<void> {
     await this.db.swaps.clear();
+    await this.db.vtxoSwaps.clear();
   }
 
   /**
@@ -125,6 +155,115 @@ export class DexieSwapStorageProvider {
     return this.db.swaps.toArray();
   }
 
+  /**
+   * Get VTXO swap data by swap ID.
+   *
+   * @param swapId - The swap ID
+   * @returns The VTXO swap data, or null if not found
+   */
+  async getVtxoSwap(
+    swapId: string,
+  ): Promise<ExtendedVtxoSwapStorageData | null> {
+    const record = await this.db.vtxoSwaps.get(swapId);
+    if (!record) {
+      return null;
+    }
+    const { id: _, ...data } = record;
+    return data;
+  }
+
+  /**
+   * Store VTXO swap data.
+   *
+   * @param swapId - The swap ID
+   * @param data - The VTXO swap data to store
+   */
+  async storeVtxoSwap(
+    swapId: string,
+    data: ExtendedVtxoSwapStorageData,
+  ): Promise<void> {
+    await this.db.vtxoSwaps.put({ id: swapId, ...data });
+  }
+
+  /**
+   * Delete VTXO swap data by swap ID.
+   *
+   * @param swapId - The swap ID
+   */
+  async deleteVtxoSwap(swapId: string): Promise<void> {
+    await this.db.vtxoSwaps.delete(swapId);
+  }
+
+  /**
+   * Get all stored VTXO swaps.
+   *
+   * @returns Array of all VTXO swap data with their IDs
+   */
+  async getAllVtxoSwaps(): Promise<VtxoSwapRecord[]> {
+    return this.db.vtxoSwaps.toArray();
+  }
+
+  /**
+   * Append an event to the history of its swap.
+   *
+   * @param event - The event to append
+   */
+  async appendSwapEvent(event: SwapEvent): Promise<void> {
+    await this.db.swapEvents.add(event);
+  }
+
+  /**
+   * Get the history of a swap, in the order the events were appended.
+   *
+   * @param swapId - The swap ID
+   * @returns The swap's events
+   */
+  async getSwapEvents(swapId: string): Promise<SwapEvent[]> {
+    const records = await this.db.swapEvents
+      .where("swap_id")
+      .equals(swapId)
+      .sortBy("seq");
+    return records.map(({ seq: _, ...event }) => event as SwapEvent);
+  }
+
+  /**
+   * Get the histories of all swaps, including deleted ones, in the order the events were
+   * appended.
+   *
+   * @returns The events of all swaps
+   */
+  async getAllSwapEvents(): Promise<SwapEvent[]> {
+    const records = await this.db.swapEvents.orderBy("seq").toArray();
+    return records.map(({ seq: _, ...event }) => event as SwapEvent);
+  }
+
+  /**
+   * Store a pending swap creation, keyed by its user ID.
+   *
+   * @param pending - The pending creation
+   */
+  async storePendingCreation(pending: PendingCreation): Promise<void> {
+    await this.db.pendingCreations.put(pending);
+  }
+
+  /**
+   * Delete a pending swap creation.
+   *
+   * @param userId - The user ID of the pending creation
+   */
+  async deletePendingCreation(userId: string): Promise<void> {
+    await this.db.pendingCreations.delete(userId);
+  }
+
+  /**
+   * Get all pending swap creations.
+   *
+   * @returns Array of all pending creations
+   */
+  async getPendingCreations(): Promise<PendingCreation[]> {
+    return this.db.pendingCreations.toArray();
+  }
+
   /**
    * Close the database connection.
    */
@@ -20,17 +20,32 @@ interface WalletRecord {
   keyIndex: number;
 }
 
+/**
+ * Key index of an account other than account 0.
+ */
+interface AccountRecord {
+  /** The account number */
+  account: number;
+  /** The current key derivation index of the account */
+  keyIndex: number;
+}
+
 /**
  * Dexie database for storing wallet data.
  */
 class WalletDatabase extends Dexie {
   wallet!: Table<WalletRecord, string>;
+  accounts!: Table<AccountRecord, number>;
 
   constructor(dbName = "lendaswap-wallet") {
     super(dbName);
     this.version(1).stores({
       wallet: "id", // Primary key only
     });
+    this.version(2).stores({
+      wallet: "id",
+      accounts: "account",
+    });
   }
 }
 
@@ -127,11 +142,56 @@ export class DexieWalletStorageProvider implements WalletStorageProvider {
     });
   }
 
+  /**
+   * Atomically reserve the current key index and store the next one.
+   *
+   * Runs in a single IndexedDB transaction, so tabs sharing the database never
+   * reserve the same index.
+   *
+   * @returns The reserved key index
+   */
+  async reserveKeyIndex(): Promise<number> {
+    return this.db.transaction("rw", this.db.wallet, async () => {
+      const existing = await this.db.wallet.get(
+        DexieWalletStorageProvider.WALLET_ID,
+      );
+      const index = existing?.keyIndex ?? 0;
+      await this.db.wallet.put({
+        id: DexieWalletStorageProvider.WALLET_ID,
+        mnemonic: existing?.mnemonic ?? null,
+        keyIndex: index + 1,
+      });
+      return index;
+    });
+  }
+
+  /**
+   * Get the key derivation index of an account other than account 0.
+   *
+   * @param account - The account number
+   * @returns The key index, or 0 if not set
+   */
+  async getAccountKeyIndex(account: number): Promise<number> {
+    const record = await this.db.accounts.get(account);
+    return record?.keyIndex ?? 0;
+  }
+
+  /**
+   * Set the key derivation index of an account other than account 0.
+   *
+   * @param account - The account number
+   * @param index - The key index to store
+   */
+  async setAccountKeyIndex(account: number, index: number): Promise<void> {
+    await this.db.accounts.put({ account, keyIndex: index });
+  }
+
   /**
    * Clear all wallet data.
    */
   async clear(): Promise<void> {
     await this.db.wallet.clear();
+    await this.db.accounts.clear();
   }
 
   /**
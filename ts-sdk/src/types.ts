@@ -8,21 +8,60 @@
 export type Network = "bitcoin" | "testnet" | "regtest" | "mutinynet";
 
 /**
- * Parameters derived for a swap operation.
+ * BIP39 wordlist used for new mnemonics.
+ */
+export type MnemonicLanguage =
+  | "english"
+  | "simplified_chinese"
+  | "traditional_chinese"
+  | "czech"
+  | "french"
+  | "italian"
+  | "japanese"
+  | "korean"
+  | "portuguese"
+  | "spanish";
+
+/**
+ * Options for creating a new mnemonic.
+ */
+export interface MnemonicOptions {
+  /** Number of words (12, 15, 18, 21, or 24). Defaults to 12. */
+  wordCount?: number;
+  /** Wordlist to use. Defaults to English. */
+  language?: MnemonicLanguage;
+  /** Hex-encoded entropy to create the mnemonic from, instead of random entropy. */
+  entropy?: string;
+}
+
+/**
+ * A group of SLIP-39 shares.
+ */
+export interface Slip39Group {
+  /** Number of shares required to reconstruct the group. */
+  memberThreshold: number;
+  /** Number of shares to create. */
+  memberCount: number;
+}
+
+/**
+ * Public parameters derived for a swap operation.
+ *
+ * Secrets are never exposed: they are re-derived from `keyIndex` inside the SDK.
  */
 export interface SwapParams {
-  /** Secret key (hex-encoded). */
-  ownSk: string;
   /** Public key (hex-encoded). */
   ownPk: string;
-  /** Preimage for HTLC (hex-encoded). */
-  preimage: string;
   /** Hash of the preimage (hex-encoded). */
   preimageHash: string;
   /** User ID derived from HD wallet (hex-encoded). */
   userId: string;
   /** Key derivation index used. */
   keyIndex: number;
+  /** Account the keys were derived for. */
+  account: number;
+  /** Version of the scheme the key and preimage were derived with. */
+  scheme: number;
 }
 
 /**
@@ -1,3 +1,5 @@
+use crate::JsKeyValueStoreAdapter;
+use crate::JsKeyValueStoreProvider;
 use crate::JsSwapStorageAdapter;
 use crate::JsSwapStorageProvider;
 use crate::JsWalletStorageAdapter;
@@ -8,6 +10,7 @@ use crate::js_types::SwapParams;
 use crate::to_js_value;
 use lendaswap_core;
 use lendaswap_core::api as core_api;
+use lendaswap_core::storage::{KvStorage, SwapStorage, WalletStorage};
 use rust_decimal::Decimal;
 use rust_decimal::prelude::FromPrimitive;
 use wasm_bindgen::JsValue;
@@ -305,7 +308,7 @@ impl TryFrom<&VtxoSwapResponse> for core_api::VtxoSwapResponse {
 pub struct CreateVtxoSwapResult {
     /// The swap response
     pub response: VtxoSwapResponse,
-    /// The swap parameters (needed for claim/refund)
+    /// The public swap parameters (needed for claim/refund)
     #[wasm_bindgen(js_name = "swapParams")]
     pub swap_params: SwapParams,
 }
@@ -313,7 +316,7 @@ pub struct CreateVtxoSwapResult {
 /// Lendaswap client.
 #[wasm_bindgen]
 pub struct Client {
-    inner: lendaswap_core::Client<JsWalletStorageAdapter, JsSwapStorageAdapter>,
+    inner: lendaswap_core::Client<Box<dyn WalletStorage>, Box<dyn SwapStorage>>,
 }
 
 #[wasm_bindgen]
@@ -337,8 +340,9 @@ impl Client {
         let network = network
             .parse()
             .map_err(|e: lendaswap_core::Error| JsValue::from_str(&format!("{}", e)))?;
-        let wallet_adapter = JsWalletStorageAdapter::new(wallet_storage);
-        let swap_adapter = JsSwapStorageAdapter::new(swap_storage);
+        let wallet_adapter: Box<dyn WalletStorage> =
+            Box::new(JsWalletStorageAdapter::new(wallet_storage));
+        let swap_adapter: Box<dyn SwapStorage> = Box::new(JsSwapStorageAdapter::new(swap_storage));
 
         Ok(Client {
             inner: lendaswap_core::Client::new(
@@ -351,6 +355,49 @@ impl Client {
         })
     }
 
+    /// Create a new client that keeps its wallet and swap data in a single key-value store.
+    ///
+    /// # Arguments
+    /// * `base_url` - The Lendaswap API URL
+    /// * `store` - Key-value store for wallet and swap data
+    /// * `network` - The Bitcoin network ("bitcoin" or "testnet")
+    /// * `arkade_url` - The Arkade server URL
+    #[wasm_bindgen(js_name = "fromKeyValueStore")]
+    pub fn from_key_value_store(
+        base_url: String,
+        store: JsKeyValueStoreProvider,
+        network: String,
+        arkade_url: String,
+    ) -> Result<Client, JsValue> {
+        let network = network
+            .parse()
+            .map_err(|e: lendaswap_core::Error| JsValue::from_str(&format!("{}", e)))?;
+        let storage = KvStorage::new(JsKeyValueStoreAdapter::new(store));
+        let wallet_storage: Box<dyn WalletStorage> = Box::new(storage.clone());
+        let swap_storage: Box<dyn SwapStorage> = Box::new(storage);
+
+        Ok(Client {
+            inner: lendaswap_core::Client::new(
+                base_url,
+                wallet_storage,
+                swap_storage,
+                network,
+                arkade_url,
+            ),
+        })
+    }
+
+    /// Use the given account of the mnemonic for keys, the key index and swaps.
+    ///
+    /// Accounts other than 0 need a wallet storage with account key index callbacks, see
+    /// `JsWalletStorageProvider.withAccountKeyIndex`.
+    #[wasm_bindgen(js_name = "withAccount")]
+    pub fn with_account(self, account: u32) -> Client {
+        Client {
+            inner: self.inner.with_account(account),
+        }
+    }
+
     #[wasm_bindgen(js_name = "init")]
     pub async fn init(&self, mnemonic: Option<String>) -> Result<(), JsValue> {
         self.inner
@@ -360,6 +407,87 @@ impl Client {
         Ok(())
     }
 
+    /// Initialize the wallet, creating a new mnemonic with the given options if none is stored.
+    ///
+    /// # Arguments
+    /// * `word_count` - Number of words (12, 15, 18, 21, or 24). Defaults to 12.
+    /// * `language` - BIP39 wordlist, e.g. "english" or "japanese". Defaults to English.
+    /// * `entropy_hex` - Optional hex-encoded entropy to create the mnemonic from.
+    #[wasm_bindgen(js_name = "initWithOptions")]
+    pub async fn init_with_options(
+        &self,
+        word_count: Option<u32>,
+        language: Option<String>,
+        entropy_hex: Option<String>,
+    ) -> Result<(), JsValue> {
+        let mut options = match entropy_hex {
+            Some(entropy_hex) => {
+                let entropy = hex::decode(&entropy_hex)
+                    .map_err(|e| JsValue::from_str(&format!("Invalid entropy: {}", e)))?;
+                lendaswap_core::MnemonicOptions::from_entropy(entropy)
+            }
+            None => lendaswap_core::MnemonicOptions::default(),
+        };
+        if let Some(word_count) = word_count {
+            options.word_count = word_count as usize;
+        }
+        if let Some(language) = language {
+            options.language = language
+                .parse()
+                .map_err(|e: lendaswap_core::Error| JsValue::from_str(&format!("{}", e)))?;
+        }
+
+        self.inner
+            .init_with_options(None, &options)
+            .await
+            .map_err(|e: lendaswap_core::Error| JsValue::from_str(&format!("{}", e)))?;
+        Ok(())
+    }
+
+    /// Split the wallet's mnemonic into SLIP-39 shares.
+    ///
+    /// # Arguments
+    /// * `group_threshold` - Number of groups required to restore the wallet
+    /// * `groups` - Array of `{ member_threshold, member_count }` objects
+    /// * `passphrase` - Passphrase to encrypt the shares with (may be empty)
+    ///
+    /// Returns one array of share mnemonics per group.
+    #[wasm_bindgen(js_name = "getSlip39Shares")]
+    pub async fn get_slip39_shares(
+        &self,
+        group_threshold: u8,
+        groups: JsValue,
+        passphrase: String,
+    ) -> Result<JsValue, JsValue> {
+        let groups: Vec<lendaswap_core::Slip39Group> = serde_wasm_bindgen::from_value(groups)
+            .map_err(|e| JsValue::from_str(&format!("Invalid groups: {}", e)))?;
+        let options = lendaswap_core::Slip39Options::with_groups(group_threshold, groups)
+            .passphrase(&passphrase);
+
+        let shares = self
+            .inner
+            .wallet()
+            .to_slip39_shares(&options)
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+
+        to_js_value(&shares)
+    }
+
+    /// Restore the wallet from SLIP-39 shares (replaces the existing mnemonic).
+    #[wasm_bindgen(js_name = "initFromSlip39Shares")]
+    pub async fn init_from_slip39_shares(
+        &self,
+        shares: Vec<String>,
+        passphrase: String,
+    ) -> Result<(), JsValue> {
+        self.inner
+            .wallet()
+            .import_slip39_shares(&shares, &passphrase, "")
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))
+    }
+
     /// Create an Arkade to EVM swap.
     #[wasm_bindgen(js_name = "createArkadeToEvmSwap")]
     pub async fn create_arkade_to_evm_swap(
@@ -553,6 +681,87 @@ impl Client {
         to_js_value(&swap)
     }
 
+    /// Query the stored swaps, filtered, sorted and paginated.
+    ///
+    /// Takes a `SwapQuery` object; omitted fields don't filter. This function returns
+    /// `SwapPage`, whose `next_cursor` is passed as the next query's `cursor`.
+    #[wasm_bindgen(js_name = "listSwaps")]
+    pub async fn list_swaps(&self, query: JsValue) -> Result<JsValue, JsValue> {
+        let query: lendaswap_core::SwapQuery = if query.is_undefined() || query.is_null() {
+            lendaswap_core::SwapQuery::default()
+        } else {
+            serde_wasm_bindgen::from_value(query)
+                .map_err(|e| JsValue::from_str(&format!("Invalid query: {}", e)))?
+        };
+
+        let page = self
+            .inner
+            .list_swaps(query)
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+
+        to_js_value(&page)
+    }
+
+    /// Get the stored swap records that can't be read, e.g. because a newer SDK version wrote
+    /// them. They are skipped by `listAll`.
+    ///
+    /// This function returns `QuarantinedRecord[]`.
+    #[wasm_bindgen(js_name = "listQuarantinedSwaps")]
+    pub async fn list_quarantined_swaps(&self) -> Result<JsValue, JsValue> {
+        let records = self
+            .inner
+            .list_quarantined_swaps()
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+
+        to_js_value(&records)
+    }
+
+    /// Recover the swaps and VTXO swaps whose creation was interrupted after the API call. This
+    /// runs in `init` as well.
+    ///
+    /// This function returns `ReconciledCreations`.
+    #[wasm_bindgen(js_name = "reconcilePendingCreations")]
+    pub async fn reconcile_pending_creations(&self) -> Result<JsValue, JsValue> {
+        let reconciled = self
+            .inner
+            .reconcile_pending_creations()
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+
+        to_js_value(&reconciled)
+    }
+
+    /// Get the recorded events of a swap or VTXO swap, oldest first.
+    ///
+    /// This function returns `SwapEvent[]`.
+    #[wasm_bindgen(js_name = "swapHistory")]
+    pub async fn swap_history(&self, id: String) -> Result<JsValue, JsValue> {
+        let events = self
+            .inner
+            .swap_history(&id)
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+
+        to_js_value(&events)
+    }
+
+    /// Export the event histories of all stored swaps of the client's account, e.g. for
+    /// compliance records.
+    ///
+    /// This function returns `SwapHistoryExport`.
+    #[wasm_bindgen(js_name = "exportSwapHistory")]
+    pub async fn export_swap_history(&self) -> Result<JsValue, JsValue> {
+        let export = self
+            .inner
+            .export_swap_history()
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+
+        to_js_value(&export)
+    }
+
     #[wasm_bindgen(js_name = "claimGelato")]
     pub async fn claim_gelato(
         &self,
@@ -625,6 +834,87 @@ impl Client {
         to_js_value(&response)
     }
 
+    /// Recover swaps using xpub and return a report of what was merged.
+    #[wasm_bindgen(js_name = "recoverSwapsWithReport")]
+    pub async fn recover_swaps_with_report(&self) -> Result<JsValue, JsValue> {
+        let report = self
+            .inner
+            .recover_swaps_with_report()
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+
+        to_js_value(&report)
+    }
+
+    /// Export the wallet and all stored swaps as a backup encrypted with `password`.
+    #[wasm_bindgen(js_name = "exportBackup")]
+    pub async fn export_backup(&self, password: String) -> Result<String, JsValue> {
+        self.inner
+            .export_backup(&password)
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))
+    }
+
+    /// Restore a backup created with `exportBackup`.
+    ///
+    /// Returns `[metadata, report]`: the backup's metadata and a report of the merged swaps.
+    #[wasm_bindgen(js_name = "importBackup")]
+    pub async fn import_backup(
+        &self,
+        backup: String,
+        password: String,
+    ) -> Result<JsValue, JsValue> {
+        let imported = self
+            .inner
+            .import_backup(&backup, &password)
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+
+        to_js_value(&imported)
+    }
+
+    /// Sign a message with the swap key at `key_index`.
+    ///
+    /// Returns `{ public_key, signature }` as hex strings.
+    #[wasm_bindgen(js_name = "signMessage")]
+    pub async fn sign_message(&self, key_index: u32, message: String) -> Result<JsValue, JsValue> {
+        let signature = self
+            .inner
+            .wallet()
+            .sign_message(key_index, message.as_bytes())
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+
+        to_js_value(&signature)
+    }
+
+    /// Prove that this client created a swap, e.g. for a support dispute.
+    ///
+    /// # Arguments
+    /// * `swap_id` - The swap ID
+    /// * `challenge` - The verifier's challenge, e.g. a nonce, so that the proof can't be replayed
+    /// * `key` - `"swap_key"` to sign with the swap's refund or claim key, `"user_id"` to sign
+    ///   with its user ID
+    #[wasm_bindgen(js_name = "proveSwapOwnership")]
+    pub async fn prove_swap_ownership(
+        &self,
+        swap_id: String,
+        challenge: String,
+        key: String,
+    ) -> Result<JsValue, JsValue> {
+        let key: lendaswap_core::OwnershipKey = key
+            .parse()
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+
+        let proof = self
+            .inner
+            .prove_swap_ownership(&swap_id, &challenge, key)
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+
+        to_js_value(&proof)
+    }
+
     /// Get mnemonic
     #[wasm_bindgen(js_name = "getMnemonic")]
     pub async fn get_mnemonic(&self) -> Result<String, JsValue> {
@@ -671,6 +961,20 @@ impl Client {
         Ok(())
     }
 
+    /// Rewrite all stored swaps without the secrets older versions persisted.
+    ///
+    /// Returns the number of swaps rewritten.
+    #[wasm_bindgen(js_name = "migrateSwapStorage")]
+    pub async fn migrate_swap_storage(&self) -> Result<u32, JsValue> {
+        let migrated = self
+            .inner
+            .migrate_swap_storage()
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+
+        Ok(migrated as u32)
+    }
+
     // =========================================================================
     // VTXO Swap Methods
     // =========================================================================
@@ -695,7 +999,8 @@ impl Client {
 
     /// Create a VTXO swap for refreshing VTXOs.
     ///
-    /// Returns the swap response and swap params.
+    /// Returns the swap response and swap params. The swap is stored as well, which requires VTXO
+    /// swap callbacks on the swap storage provider (see `JsSwapStorageProvider.withVtxoSwaps`).
     ///
     /// # Arguments
     /// * `vtxos` - List of VTXO outpoints to refresh ("txid:vout" format)
@@ -728,11 +1033,82 @@ impl Client {
         Ok(response.into())
     }
 
+    /// Fetch the latest status of a stored VTXO swap and update the local storage.
+    ///
+    /// This function returns `ExtendedVtxoSwapStorageData`.
+    #[wasm_bindgen(js_name = "refreshVtxoSwap")]
+    pub async fn refresh_vtxo_swap(&self, id: String) -> Result<JsValue, JsValue> {
+        let swap = self
+            .inner
+            .refresh_vtxo_swap(&id)
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+
+        to_js_value(&swap)
+    }
+
+    /// Get all stored VTXO swaps of the account.
+    ///
+    /// This function returns `ExtendedVtxoSwapStorageData[]`.
+    #[wasm_bindgen(js_name = "listVtxoSwaps")]
+    pub async fn list_vtxo_swaps(&self) -> Result<JsValue, JsValue> {
+        let swaps = self
+            .inner
+            .list_vtxo_swaps()
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
+
+        to_js_value(&swaps)
+    }
+
+    /// Delete a stored VTXO swap.
+    #[wasm_bindgen(js_name = "deleteVtxoSwap")]
+    pub async fn delete_vtxo_swap(&self, id: String) -> Result<(), JsValue> {
+        self.inner
+            .delete_vtxo_swap(&id)
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))
+    }
+
+    /// Claim the server's VHTLC of a stored VTXO swap.
+    ///
+    /// # Arguments
+    /// * `swap_id` - The ID of the VTXO swap
+    /// * `claim_address` - The Arkade address to receive the claimed funds
+    #[wasm_bindgen(js_name = "claimVtxoSwapById")]
+    pub async fn claim_vtxo_swap_by_id(
+        &self,
+        swap_id: String,
+        claim_address: String,
+    ) -> Result<String, JsValue> {
+        self.inner
+            .claim_vtxo_swap_by_id(&swap_id, &claim_address)
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))
+    }
+
+    /// Refund the client's VHTLC of a stored VTXO swap.
+    ///
+    /// # Arguments
+    /// * `swap_id` - The ID of the VTXO swap
+    /// * `refund_address` - The Arkade address to receive the refunded funds
+    #[wasm_bindgen(js_name = "refundVtxoSwapById")]
+    pub async fn refund_vtxo_swap_by_id(
+        &self,
+        swap_id: String,
+        refund_address: String,
+    ) -> Result<String, JsValue> {
+        self.inner
+            .refund_vtxo_swap_by_id(&swap_id, &refund_address)
+            .await
+            .map_err(|e| JsValue::from_str(&format!("{:#}", e)))
+    }
+
     /// Claim the server's VHTLC in a VTXO swap.
     ///
     /// # Arguments
     /// * `swap` - The VTXO swap response
-    /// * `swap_params` - The client's swap parameters
+    /// * `swap_params` - The swap parameters returned by `createVtxoSwap`
     /// * `claim_address` - The Arkade address to receive the claimed funds
     #[wasm_bindgen(js_name = "claimVtxoSwap")]
     pub async fn claim_vtxo_swap(
@@ -744,14 +1120,10 @@ impl Client {
         let core_swap: lendaswap_core::api::VtxoSwapResponse = swap
             .try_into()
             .map_err(|e: String| JsValue::from_str(&format!("Failed to convert swap: {}", e)))?;
-        let core_params: lendaswap_core::SwapParams =
-            swap_params.try_into().map_err(|e: String| {
-                JsValue::from_str(&format!("Failed to convert swap_params: {}", e))
-            })?;
 
         let txid = self
             .inner
-            .claim_vtxo_swap(&core_swap, core_params, &claim_address)
+            .claim_vtxo_swap(&core_swap, swap_params.key_index, &claim_address)
             .await
             .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
 
@@ -762,7 +1134,7 @@ impl Client {
     ///
     /// # Arguments
     /// * `swap` - The VTXO swap response
-    /// * `swap_params` - The client's swap parameters
+    /// * `swap_params` - The swap parameters returned by `createVtxoSwap`
     /// * `refund_address` - The Arkade address to receive the refunded funds
     #[wasm_bindgen(js_name = "refundVtxoSwap")]
     pub async fn refund_vtxo_swap(
@@ -774,17 +1146,31 @@ impl Client {
         let core_swap: lendaswap_core::api::VtxoSwapResponse = swap
             .try_into()
             .map_err(|e: String| JsValue::from_str(&format!("Failed to convert swap: {}", e)))?;
-        let core_params: lendaswap_core::SwapParams =
-            swap_params.try_into().map_err(|e: String| {
-                JsValue::from_str(&format!("Failed to convert swap_params: {}", e))
-            })?;
 
         let txid = self
             .inner
-            .refund_vtxo_swap(&core_swap, core_params, &refund_address)
+            .refund_vtxo_swap(&core_swap, swap_params.key_index, &refund_address)
             .await
             .map_err(|e| JsValue::from_str(&format!("{:#}", e)))?;
 
         Ok(txid)
     }
 }
+
+/// Verify a proof created with `proveSwapOwnership` using the swap's refund or claim key.
+///
+/// Throws if the proof is not valid for `swap` or doesn't answer `challenge`.
+#[wasm_bindgen(js_name = "verifySwapOwnership")]
+pub fn verify_swap_ownership(
+    swap: JsValue,
+    challenge: String,
+    proof: JsValue,
+) -> Result<(), JsValue> {
+    let swap: core_api::GetSwapResponse = serde_wasm_bindgen::from_value(swap)
+        .map_err(|e| JsValue::from_str(&format!("Invalid swap: {}", e)))?;
+    let proof: lendaswap_core::OwnershipProof = serde_wasm_bindgen::from_value(proof)
+        .map_err(|e| JsValue::from_str(&format!("Invalid proof: {}", e)))?;
+
+    lendaswap_core::verify_swap_ownership(&swap, &challenge, &proof)
+        .map_err(|e| JsValue::from_str(&format!("{:#}", e)))
+}
@@ -2,11 +2,19 @@
 //!
 //! This module provides the bridge between JavaScript storage implementations
 //! and the Rust WalletStorage/SwapStorage traits. It allows TypeScript code to provide
-//! storage callbacks that are used by the core SDK.
+//! storage callbacks that are used by the core SDK, or a single key-value store that backs both.
 
-use js_sys::{Function, Promise};
-use lendaswap_core::ExtendedSwapStorageData;
-use lendaswap_core::storage::{StorageFuture, SwapStorage, WalletStorage};
+use js_sys::{Array, Function, JSON, Promise, Reflect, Uint8Array};
+use lendaswap_core::storage::schema::{
+    QuarantinedRecord, outdated_swap_records, read_swap_records,
+};
+use lendaswap_core::storage::{
+    KeyValueStore, StorageFuture, SwapPage, SwapQuery, SwapStorage, WalletStorage,
+    reserve_key_index_in_process,
+};
+use lendaswap_core::{
+    ExtendedSwapStorageData, ExtendedVtxoSwapStorageData, PendingCreation, SwapEvent,
+};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::JsFuture;
 
@@ -31,6 +39,9 @@ pub struct JsWalletStorageProvider {
     set_mnemonic_fn: Function,
     get_key_index_fn: Function,
     set_key_index_fn: Function,
+    reserve_key_index_fn: Option<Function>,
+    get_account_key_index_fn: Option<Function>,
+    set_account_key_index_fn: Option<Function>,
 }
 
 #[wasm_bindgen]
@@ -54,8 +65,44 @@ impl JsWalletStorageProvider {
             set_mnemonic_fn,
             get_key_index_fn,
             set_key_index_fn,
+            reserve_key_index_fn: None,
+            get_account_key_index_fn: None,
+            set_account_key_index_fn: None,
         }
     }
+
+    /// Set a callback that atomically reserves a key index.
+    ///
+    /// The callback must return the current index and store the next one in a single atomic step
+    /// (e.g. an IndexedDB transaction), so that several tabs never get the same index. Without
+    /// it, reservations are only serialized within this WASM instance.
+    ///
+    /// # Arguments
+    /// * `reserve_key_index_fn` - Function: `() => Promise<number>`
+    #[wasm_bindgen(js_name = "withReserveKeyIndex")]
+    pub fn with_reserve_key_index(mut self, reserve_key_index_fn: Function) -> Self {
+        self.reserve_key_index_fn = Some(reserve_key_index_fn);
+        self
+    }
+
+    /// Set callbacks that store the key index of accounts other than account 0.
+    ///
+    /// Account 0 always uses the key index callbacks passed to the constructor. Without these
+    /// callbacks, only account 0 is supported.
+    ///
+    /// # Arguments
+    /// * `get_account_key_index_fn` - Function: `(account: number) => Promise<number>`
+    /// * `set_account_key_index_fn` - Function: `(account: number, index: number) => Promise<void>`
+    #[wasm_bindgen(js_name = "withAccountKeyIndex")]
+    pub fn with_account_key_index(
+        mut self,
+        get_account_key_index_fn: Function,
+        set_account_key_index_fn: Function,
+    ) -> Self {
+        self.get_account_key_index_fn = Some(get_account_key_index_fn);
+        self.set_account_key_index_fn = Some(set_account_key_index_fn);
+        self
+    }
 }
 
 /// Internal adapter that implements the core WalletStorage trait using JS callbacks.
@@ -167,6 +214,117 @@ impl WalletStorage for JsWalletStorageAdapter {
             Ok(())
         })
     }
+
+    fn reserve_key_index(&self) -> StorageFuture<'_, u32> {
+        let Some(reserve_key_index_fn) = &self.provider.reserve_key_index_fn else {
+            return Box::pin(reserve_key_index_in_process(self));
+        };
+        let result = reserve_key_index_fn.call0(&JsValue::NULL);
+
+        Box::pin(async move {
+            let promise: Promise = result
+                .map_err(|e| {
+                    lendaswap_core::Error::Storage(format!(
+                        "Failed to call reserve_key_index: {:?}",
+                        e
+                    ))
+                })?
+                .dyn_into()
+                .map_err(|_| {
+                    lendaswap_core::Error::Storage("Expected Promise from reserve_key_index".into())
+                })?;
+
+            let value = JsFuture::from(promise).await.map_err(|e| {
+                lendaswap_core::Error::Storage(format!(
+                    "reserve_key_index Promise rejected: {:?}",
+                    e
+                ))
+            })?;
+
+            value.as_f64().map(|v| v as u32).ok_or_else(|| {
+                lendaswap_core::Error::Storage("Expected number from reserve_key_index".into())
+            })
+        })
+    }
+
+    fn get_account_key_index(&self, account: u32) -> StorageFuture<'_, u32> {
+        let get_account_key_index_fn = match (account, &self.provider.get_account_key_index_fn) {
+            (0, _) => return self.get_key_index(),
+            (_, Some(get_account_key_index_fn)) => get_account_key_index_fn,
+            (_, None) => return Box::pin(async { Err(accounts_unsupported()) }),
+        };
+        let account = JsValue::from_f64(account as f64);
+        let result = get_account_key_index_fn.call1(&JsValue::NULL, &account);
+
+        Box::pin(async move {
+            let promise: Promise = result
+                .map_err(|e| {
+                    lendaswap_core::Error::Storage(format!(
+                        "Failed to call get_account_key_index: {:?}",
+                        e
+                    ))
+                })?
+                .dyn_into()
+                .map_err(|_| {
+                    lendaswap_core::Error::Storage(
+                        "Expected Promise from get_account_key_index".into(),
+                    )
+                })?;
+
+            let value = JsFuture::from(promise).await.map_err(|e| {
+                lendaswap_core::Error::Storage(format!(
+                    "get_account_key_index Promise rejected: {:?}",
+                    e
+                ))
+            })?;
+
+            let index = value.as_f64().unwrap_or(0.0) as u32;
+            Ok(index)
+        })
+    }
+
+    fn set_account_key_index(&self, account: u32, index: u32) -> StorageFuture<'_, ()> {
+        let set_account_key_index_fn = match (account, &self.provider.set_account_key_index_fn) {
+            (0, _) => return self.set_key_index(index),
+            (_, Some(set_account_key_index_fn)) => set_account_key_index_fn,
+            (_, None) => return Box::pin(async { Err(accounts_unsupported()) }),
+        };
+        let account = JsValue::from_f64(account as f64);
+        let index = JsValue::from_f64(index as f64);
+        let result = set_account_key_index_fn.call2(&JsValue::NULL, &account, &index);
+
+        Box::pin(async move {
+            let promise: Promise = result
+                .map_err(|e| {
+                    lendaswap_core::Error::Storage(format!(
+                        "Failed to call set_account_key_index: {:?}",
+                        e
+                    ))
+                })?
+                .dyn_into()
+                .map_err(|_| {
+                    lendaswap_core::Error::Storage(
+                        "Expected Promise from set_account_key_index".into(),
+                    )
+                })?;
+
+            JsFuture::from(promise).await.map_err(|e| {
+                lendaswap_core::Error::Storage(format!(
+                    "set_account_key_index Promise rejected: {:?}",
+                    e
+                ))
+            })?;
+
+            Ok(())
+        })
+    }
+}
+
+fn accounts_unsupported() -> lendaswap_core::Error {
+    lendaswap_core::Error::Storage(
+        "this storage does not support multiple accounts without account key index callbacks"
+            .into(),
+    )
 }
 
 /// JavaScript swap storage provider passed from TypeScript.
@@ -196,6 +354,17 @@ pub struct JsSwapStorageProvider {
     delete_fn: Function,
     list_fn: Function,
     get_all_fn: Function,
+    get_vtxo_swap_fn: Option<Function>,
+    store_vtxo_swap_fn: Option<Function>,
+    delete_vtxo_swap_fn: Option<Function>,
+    get_all_vtxo_swaps_fn: Option<Function>,
+    query_fn: Option<Function>,
+    append_swap_event_fn: Option<Function>,
+    get_swap_events_fn: Option<Function>,
+    get_all_swap_events_fn: Option<Function>,
+    store_pending_creation_fn: Option<Function>,
+    delete_pending_creation_fn: Option<Function>,
+    get_pending_creations_fn: Option<Function>,
 }
 
 #[wasm_bindgen]
@@ -222,8 +391,102 @@ impl JsSwapStorageProvider {
             delete_fn,
             list_fn,
             get_all_fn,
+            get_vtxo_swap_fn: None,
+            store_vtxo_swap_fn: None,
+            delete_vtxo_swap_fn: None,
+            get_all_vtxo_swaps_fn: None,
+            query_fn: None,
+            append_swap_event_fn: None,
+            get_swap_events_fn: None,
+            get_all_swap_events_fn: None,
+            store_pending_creation_fn: None,
+            delete_pending_creation_fn: None,
+            get_pending_creations_fn: None,
         }
     }
+
+    /// Set callbacks that store VTXO swaps, e.g. in a table of their own.
+    ///
+    /// Without these callbacks, `createVtxoSwap` fails before creating the swap, since it couldn't
+    /// be stored.
+    ///
+    /// # Arguments
+    /// * `get_vtxo_swap_fn` - Function: `(swapId: string) => Promise<ExtendedVtxoSwapStorageData | null>`
+    /// * `store_vtxo_swap_fn` - Function: `(swapId: string, data: ExtendedVtxoSwapStorageData) => Promise<void>`
+    /// * `delete_vtxo_swap_fn` - Function: `(swapId: string) => Promise<void>`
+    /// * `get_all_vtxo_swaps_fn` - Function: `() => Promise<ExtendedVtxoSwapStorageData[]>`
+    #[wasm_bindgen(js_name = "withVtxoSwaps")]
+    pub fn with_vtxo_swaps(
+        mut self,
+        get_vtxo_swap_fn: Function,
+        store_vtxo_swap_fn: Function,
+        delete_vtxo_swap_fn: Function,
+        get_all_vtxo_swaps_fn: Function,
+    ) -> Self {
+        self.get_vtxo_swap_fn = Some(get_vtxo_swap_fn);
+        self.store_vtxo_swap_fn = Some(store_vtxo_swap_fn);
+        self.delete_vtxo_swap_fn = Some(delete_vtxo_swap_fn);
+        self.get_all_vtxo_swaps_fn = Some(get_all_vtxo_swaps_fn);
+        self
+    }
+
+    /// Set a callback that runs swap queries in the storage, e.g. on IndexedDB indexes.
+    ///
+    /// Without it, queries load all swaps with `get_all_fn` and filter them in the SDK. The
+    /// callback must return the same swaps as the SDK would; its cursors are only ever passed
+    /// back to it.
+    ///
+    /// # Arguments
+    /// * `query_fn` - Function: `(query: SwapQuery) => Promise<{ swaps: ExtendedSwapStorageData[], next_cursor: string | null }>`
+    #[wasm_bindgen(js_name = "withQuery")]
+    pub fn with_query(mut self, query_fn: Function) -> Self {
+        self.query_fn = Some(query_fn);
+        self
+    }
+
+    /// Set callbacks that keep the event history of swaps, e.g. in a table of their own.
+    ///
+    /// Without these callbacks, events are discarded and swap histories are empty. Events must be
+    /// returned in the order they were appended, and kept when their swap is deleted.
+    ///
+    /// # Arguments
+    /// * `append_swap_event_fn` - Function: `(event: SwapEvent) => Promise<void>`
+    /// * `get_swap_events_fn` - Function: `(swapId: string) => Promise<SwapEvent[]>`
+    /// * `get_all_swap_events_fn` - Function: `() => Promise<SwapEvent[]>`, the events of all swaps, including deleted ones
+    #[wasm_bindgen(js_name = "withSwapEvents")]
+    pub fn with_swap_events(
+        mut self,
+        append_swap_event_fn: Function,
+        get_swap_events_fn: Function,
+        get_all_swap_events_fn: Function,
+    ) -> Self {
+        self.append_swap_event_fn = Some(append_swap_event_fn);
+        self.get_swap_events_fn = Some(get_swap_events_fn);
+        self.get_all_swap_events_fn = Some(get_all_swap_events_fn);
+        self
+    }
+
+    /// Set callbacks that journal swap creations, so that a swap whose creation was interrupted
+    /// after the API call is recovered when the client is initialized.
+    ///
+    /// Without these callbacks, such a swap is only found by a full recovery.
+    ///
+    /// # Arguments
+    /// * `store_pending_creation_fn` - Function: `(pending: PendingCreation) => Promise<void>`, keyed by `pending.swap_params.user_id`
+    /// * `delete_pending_creation_fn` - Function: `(userId: string) => Promise<void>`
+    /// * `get_pending_creations_fn` - Function: `() => Promise<PendingCreation[]>`
+    #[wasm_bindgen(js_name = "withPendingCreations")]
+    pub fn with_pending_creations(
+        mut self,
+        store_pending_creation_fn: Function,
+        delete_pending_creation_fn: Function,
+        get_pending_creations_fn: Function,
+    ) -> Self {
+        self.store_pending_creation_fn = Some(store_pending_creation_fn);
+        self.delete_pending_creation_fn = Some(delete_pending_creation_fn);
+        self.get_pending_creations_fn = Some(get_pending_creations_fn);
+        self
+    }
 }
 
 /// Internal adapter that implements the core SwapStorage trait using JS callbacks.
@@ -239,6 +502,69 @@ impl JsSwapStorageAdapter {
     pub fn new(provider: JsSwapStorageProvider) -> Self {
         Self { provider }
     }
+
+    /// Fetch all stored swaps as JSON, so that unreadable records can be quarantined one by one.
+    async fn swap_records(&self) -> lendaswap_core::Result<Vec<(Option<String>, String)>> {
+        let promise: Promise = self
+            .provider
+            .get_all_fn
+            .call0(&JsValue::NULL)
+            .map_err(|e| {
+                lendaswap_core::Error::Storage(format!("Failed to call get_all: {:?}", e))
+            })?
+            .dyn_into()
+            .map_err(|_| lendaswap_core::Error::Storage("Expected Promise from get_all".into()))?;
+
+        let value = JsFuture::from(promise).await.map_err(|e| {
+            lendaswap_core::Error::Storage(format!("get_all Promise rejected: {:?}", e))
+        })?;
+
+        json_records(value, "get_all")
+    }
+}
+
+/// Convert an array of swaps returned by a callback to JSON, so that unreadable records can be
+/// quarantined one by one.
+fn json_records(
+    value: JsValue,
+    callback: &str,
+) -> lendaswap_core::Result<Vec<(Option<String>, String)>> {
+    let records: Array = value
+        .dyn_into()
+        .map_err(|_| lendaswap_core::Error::Storage(format!("Expected array from {}", callback)))?;
+
+    records
+        .iter()
+        .map(|record| {
+            let data = JSON::stringify(&record).map_err(|e| {
+                lendaswap_core::Error::Storage(format!("Failed to serialize swap: {:?}", e))
+            })?;
+            Ok((None, String::from(data)))
+        })
+        .collect()
+}
+
+/// Await the events returned by the swap history callback `callback`.
+async fn swap_events(
+    result: Result<JsValue, JsValue>,
+    callback: &str,
+) -> lendaswap_core::Result<Vec<SwapEvent>> {
+    let promise: Promise = result
+        .map_err(|e| {
+            lendaswap_core::Error::Storage(format!("Failed to call {}: {:?}", callback, e))
+        })?
+        .dyn_into()
+        .map_err(|_| {
+            lendaswap_core::Error::Storage(format!("Expected Promise from {}", callback))
+        })?;
+
+    let value = JsFuture::from(promise).await.map_err(|e| {
+        lendaswap_core::Error::Storage(format!("{} Promise rejected: {:?}", callback, e))
+    })?;
+
+    serde_wasm_bindgen::from_value(value).map_err(|e| {
+        lendaswap_core::Error::Storage(format!("Failed to deserialize swap events: {:?}", e))
+    })
 }
 
 impl SwapStorage for JsSwapStorageAdapter {
@@ -350,26 +676,548 @@ impl SwapStorage for JsSwapStorageAdapter {
     }
 
     fn get_all(&self) -> StorageFuture<'_, Vec<ExtendedSwapStorageData>> {
-        let result = self.provider.get_all_fn.call0(&JsValue::NULL);
+        Box::pin(async move {
+            let (swaps, _) = read_swap_records(self.swap_records().await?);
+            Ok(swaps)
+        })
+    }
+
+    fn get_quarantined(&self) -> StorageFuture<'_, Vec<QuarantinedRecord>> {
+        Box::pin(async move {
+            let (_, quarantined) = read_swap_records(self.swap_records().await?);
+            Ok(quarantined)
+        })
+    }
+
+    fn list_outdated(&self) -> StorageFuture<'_, Vec<String>> {
+        Box::pin(async move { Ok(outdated_swap_records(self.swap_records().await?)) })
+    }
+
+    fn query(&self, query: &SwapQuery) -> StorageFuture<'_, SwapPage> {
+        let query = query.clone();
+        let Some(query_fn) = &self.provider.query_fn else {
+            return Box::pin(async move { query.apply(self.get_all().await?) });
+        };
+        let query_js = serde_wasm_bindgen::to_value(&query);
+
+        Box::pin(async move {
+            query.checked_limit()?;
+            let query_js = query_js.map_err(|e| {
+                lendaswap_core::Error::Storage(format!("Failed to serialize query: {:?}", e))
+            })?;
+
+            let promise: Promise = query_fn
+                .call1(&JsValue::NULL, &query_js)
+                .map_err(|e| {
+                    lendaswap_core::Error::Storage(format!("Failed to call query: {:?}", e))
+                })?
+                .dyn_into()
+                .map_err(|_| {
+                    lendaswap_core::Error::Storage("Expected Promise from query".into())
+                })?;
+
+            let value = JsFuture::from(promise).await.map_err(|e| {
+                lendaswap_core::Error::Storage(format!("query Promise rejected: {:?}", e))
+            })?;
+
+            let field = |name: &str| {
+                Reflect::get(&value, &JsValue::from_str(name)).map_err(|e| {
+                    lendaswap_core::Error::Storage(format!(
+                        "Failed to read {} from query result: {:?}",
+                        name, e
+                    ))
+                })
+            };
+            let (swaps, _) = read_swap_records(json_records(field("swaps")?, "query")?);
+
+            Ok(SwapPage {
+                swaps,
+                next_cursor: field("next_cursor")?.as_string(),
+            })
+        })
+    }
+
+    fn append_swap_event(&self, event: &SwapEvent) -> StorageFuture<'_, ()> {
+        let Some(append_swap_event_fn) = &self.provider.append_swap_event_fn else {
+            return Box::pin(async { Ok(()) });
+        };
+        // The event's kind is flattened into it, which serializes as a map
+        let event_js = crate::to_js_value(event);
+
+        Box::pin(async move {
+            let event_js = event_js.map_err(|e| {
+                lendaswap_core::Error::Storage(format!("Failed to serialize swap event: {:?}", e))
+            })?;
+
+            let promise: Promise = append_swap_event_fn
+                .call1(&JsValue::NULL, &event_js)
+                .map_err(|e| {
+                    lendaswap_core::Error::Storage(format!(
+                        "Failed to call append_swap_event: {:?}",
+                        e
+                    ))
+                })?
+                .dyn_into()
+                .map_err(|_| {
+                    lendaswap_core::Error::Storage("Expected Promise from append_swap_event".into())
+                })?;
+
+            JsFuture::from(promise).await.map_err(|e| {
+                lendaswap_core::Error::Storage(format!(
+                    "append_swap_event Promise rejected: {:?}",
+                    e
+                ))
+            })?;
+
+            Ok(())
+        })
+    }
+
+    fn get_swap_events(&self, swap_id: &str) -> StorageFuture<'_, Vec<SwapEvent>> {
+        let Some(get_swap_events_fn) = &self.provider.get_swap_events_fn else {
+            return Box::pin(async { Ok(Vec::new()) });
+        };
+        let swap_id = JsValue::from_str(swap_id);
+        let result = get_swap_events_fn.call1(&JsValue::NULL, &swap_id);
+        Box::pin(swap_events(result, "get_swap_events"))
+    }
+
+    fn get_all_swap_events(&self) -> StorageFuture<'_, Vec<SwapEvent>> {
+        let Some(get_all_swap_events_fn) = &self.provider.get_all_swap_events_fn else {
+            return Box::pin(async { Ok(Vec::new()) });
+        };
+        let result = get_all_swap_events_fn.call0(&JsValue::NULL);
+        Box::pin(swap_events(result, "get_all_swap_events"))
+    }
+
+    fn store_pending_creation(&self, pending: &PendingCreation) -> StorageFuture<'_, ()> {
+        let Some(store_pending_creation_fn) = &self.provider.store_pending_creation_fn else {
+            return Box::pin(async { Ok(()) });
+        };
+        let pending_js = serde_wasm_bindgen::to_value(pending);
+
+        Box::pin(async move {
+            let pending_js = pending_js.map_err(|e| {
+                lendaswap_core::Error::Storage(format!(
+                    "Failed to serialize pending creation: {:?}",
+                    e
+                ))
+            })?;
+
+            let promise: Promise = store_pending_creation_fn
+                .call1(&JsValue::NULL, &pending_js)
+                .map_err(|e| {
+                    lendaswap_core::Error::Storage(format!(
+                        "Failed to call store_pending_creation: {:?}",
+                        e
+                    ))
+                })?
+                .dyn_into()
+                .map_err(|_| {
+                    lendaswap_core::Error::Storage(
+                        "Expected Promise from store_pending_creation".into(),
+                    )
+                })?;
+
+            JsFuture::from(promise).await.map_err(|e| {
+                lendaswap_core::Error::Storage(format!(
+                    "store_pending_creation Promise rejected: {:?}",
+                    e
+                ))
+            })?;
+
+            Ok(())
+        })
+    }
+
+    fn delete_pending_creation(&self, user_id: &str) -> StorageFuture<'_, ()> {
+        let Some(delete_pending_creation_fn) = &self.provider.delete_pending_creation_fn else {
+            return Box::pin(async { Ok(()) });
+        };
+        let user_id = JsValue::from_str(user_id);
+        let result = delete_pending_creation_fn.call1(&JsValue::NULL, &user_id);
 
         Box::pin(async move {
             let promise: Promise = result
                 .map_err(|e| {
-                    lendaswap_core::Error::Storage(format!("Failed to call list: {:?}", e))
+                    lendaswap_core::Error::Storage(format!(
+                        "Failed to call delete_pending_creation: {:?}",
+                        e
+                    ))
                 })?
                 .dyn_into()
-                .map_err(|_| lendaswap_core::Error::Storage("Expected Promise from list".into()))?;
+                .map_err(|_| {
+                    lendaswap_core::Error::Storage(
+                        "Expected Promise from delete_pending_creation".into(),
+                    )
+                })?;
+
+            JsFuture::from(promise).await.map_err(|e| {
+                lendaswap_core::Error::Storage(format!(
+                    "delete_pending_creation Promise rejected: {:?}",
+                    e
+                ))
+            })?;
+
+            Ok(())
+        })
+    }
+
+    fn get_pending_creations(&self) -> StorageFuture<'_, Vec<PendingCreation>> {
+        let Some(get_pending_creations_fn) = &self.provider.get_pending_creations_fn else {
+            return Box::pin(async { Ok(Vec::new()) });
+        };
+        let result = get_pending_creations_fn.call0(&JsValue::NULL);
+
+        Box::pin(async move {
+            let promise: Promise = result
+                .map_err(|e| {
+                    lendaswap_core::Error::Storage(format!(
+                        "Failed to call get_pending_creations: {:?}",
+                        e
+                    ))
+                })?
+                .dyn_into()
+                .map_err(|_| {
+                    lendaswap_core::Error::Storage(
+                        "Expected Promise from get_pending_creations".into(),
+                    )
+                })?;
 
             let value = JsFuture::from(promise).await.map_err(|e| {
-                lendaswap_core::Error::Storage(format!("list Promise rejected: {:?}", e))
+                lendaswap_core::Error::Storage(format!(
+                    "get_pending_creations Promise rejected: {:?}",
+                    e
+                ))
+            })?;
+
+            let pending: Vec<PendingCreation> =
+                serde_wasm_bindgen::from_value(value).map_err(|e| {
+                    lendaswap_core::Error::Storage(format!(
+                        "Failed to deserialize pending creations: {:?}",
+                        e
+                    ))
+                })?;
+
+            Ok(pending)
+        })
+    }
+
+    fn supports_vtxo_swaps(&self) -> bool {
+        self.provider.store_vtxo_swap_fn.is_some()
+    }
+
+    fn get_vtxo_swap(
+        &self,
+        swap_id: &str,
+    ) -> StorageFuture<'_, Option<ExtendedVtxoSwapStorageData>> {
+        let Some(get_vtxo_swap_fn) = &self.provider.get_vtxo_swap_fn else {
+            return Box::pin(async { Ok(None) });
+        };
+        let swap_id = JsValue::from_str(swap_id);
+        let result = get_vtxo_swap_fn.call1(&JsValue::NULL, &swap_id);
+
+        Box::pin(async move {
+            let promise: Promise = result
+                .map_err(|e| {
+                    lendaswap_core::Error::Storage(format!("Failed to call get_vtxo_swap: {:?}", e))
+                })?
+                .dyn_into()
+                .map_err(|_| {
+                    lendaswap_core::Error::Storage("Expected Promise from get_vtxo_swap".into())
+                })?;
+
+            let value = JsFuture::from(promise).await.map_err(|e| {
+                lendaswap_core::Error::Storage(format!("get_vtxo_swap Promise rejected: {:?}", e))
+            })?;
+
+            if value.is_null() || value.is_undefined() {
+                Ok(None)
+            } else {
+                let data: ExtendedVtxoSwapStorageData = serde_wasm_bindgen::from_value(value)
+                    .map_err(|e| {
+                        lendaswap_core::Error::Storage(format!(
+                            "Failed to deserialize VTXO swap data: {:?}",
+                            e
+                        ))
+                    })?;
+                Ok(Some(data))
+            }
+        })
+    }
+
+    fn store_vtxo_swap(
+        &self,
+        swap_id: &str,
+        data: &ExtendedVtxoSwapStorageData,
+    ) -> StorageFuture<'_, ()> {
+        let Some(store_vtxo_swap_fn) = &self.provider.store_vtxo_swap_fn else {
+            return Box::pin(async { Err(vtxo_swaps_unsupported()) });
+        };
+        let swap_id = JsValue::from_str(swap_id);
+        let data_js = serde_wasm_bindgen::to_value(data);
+
+        Box::pin(async move {
+            let data_js = data_js.map_err(|e| {
+                lendaswap_core::Error::Storage(format!(
+                    "Failed to serialize VTXO swap data: {:?}",
+                    e
+                ))
+            })?;
+
+            let result = store_vtxo_swap_fn.call2(&JsValue::NULL, &swap_id, &data_js);
+
+            let promise: Promise = result
+                .map_err(|e| {
+                    lendaswap_core::Error::Storage(format!(
+                        "Failed to call store_vtxo_swap: {:?}",
+                        e
+                    ))
+                })?
+                .dyn_into()
+                .map_err(|_| {
+                    lendaswap_core::Error::Storage("Expected Promise from store_vtxo_swap".into())
+                })?;
+
+            JsFuture::from(promise).await.map_err(|e| {
+                lendaswap_core::Error::Storage(format!("store_vtxo_swap Promise rejected: {:?}", e))
+            })?;
+
+            Ok(())
+        })
+    }
+
+    fn delete_vtxo_swap(&self, swap_id: &str) -> StorageFuture<'_, ()> {
+        let Some(delete_vtxo_swap_fn) = &self.provider.delete_vtxo_swap_fn else {
+            return Box::pin(async { Ok(()) });
+        };
+        let swap_id = JsValue::from_str(swap_id);
+        let result = delete_vtxo_swap_fn.call1(&JsValue::NULL, &swap_id);
+
+        Box::pin(async move {
+            let promise: Promise = result
+                .map_err(|e| {
+                    lendaswap_core::Error::Storage(format!(
+                        "Failed to call delete_vtxo_swap: {:?}",
+                        e
+                    ))
+                })?
+                .dyn_into()
+                .map_err(|_| {
+                    lendaswap_core::Error::Storage("Expected Promise from delete_vtxo_swap".into())
+                })?;
+
+            JsFuture::from(promise).await.map_err(|e| {
+                lendaswap_core::Error::Storage(format!(
+                    "delete_vtxo_swap Promise rejected: {:?}",
+                    e
+                ))
+            })?;
+
+            Ok(())
+        })
+    }
+
+    fn get_all_vtxo_swaps(&self) -> StorageFuture<'_, Vec<ExtendedVtxoSwapStorageData>> {
+        let Some(get_all_vtxo_swaps_fn) = &self.provider.get_all_vtxo_swaps_fn else {
+            return Box::pin(async { Ok(Vec::new()) });
+        };
+        let result = get_all_vtxo_swaps_fn.call0(&JsValue::NULL);
+
+        Box::pin(async move {
+            let promise: Promise = result
+                .map_err(|e| {
+                    lendaswap_core::Error::Storage(format!(
+                        "Failed to call get_all_vtxo_swaps: {:?}",
+                        e
+                    ))
+                })?
+                .dyn_into()
+                .map_err(|_| {
+                    lendaswap_core::Error::Storage(
+                        "Expected Promise from get_all_vtxo_swaps".into(),
+                    )
+                })?;
+
+            let value = JsFuture::from(promise).await.map_err(|e| {
+                lendaswap_core::Error::Storage(format!(
+                    "get_all_vtxo_swaps Promise rejected: {:?}",
+                    e
+                ))
             })?;
 
-            let swaps: Vec<ExtendedSwapStorageData> = serde_wasm_bindgen::from_value(value)
+            let swaps: Vec<ExtendedVtxoSwapStorageData> = serde_wasm_bindgen::from_value(value)
                 .map_err(|e| {
-                    lendaswap_core::Error::Storage(format!("Failed to deserialize swaps: {:?}", e))
+                    lendaswap_core::Error::Storage(format!(
+                        "Failed to deserialize VTXO swaps: {:?}",
+                        e
+                    ))
                 })?;
 
             Ok(swaps)
         })
     }
 }
+
+fn vtxo_swaps_unsupported() -> lendaswap_core::Error {
+    lendaswap_core::Error::Storage(
+        "this storage does not support VTXO swaps without VTXO swap callbacks".into(),
+    )
+}
+
+/// JavaScript key-value store passed from TypeScript, which backs both the wallet and the swap
+/// storage of a client, see `Client.fromKeyValueStore`.
+///
+/// Keys are strings, values are bytes. See `KvStorage` in the core crate for the key layout.
+///
+/// # Example (TypeScript)
+///
+/// ```typescript
+/// const store = new JsKeyValueStoreProvider(
+///     async (key) => await kv.get(key) ?? null,
+///     async (key, value) => { await kv.put(key, value); },
+///     async (key) => { await kv.delete(key); },
+///     async (prefix) => await kv.entriesWithPrefix(prefix),
+/// );
+/// ```
+#[wasm_bindgen]
+pub struct JsKeyValueStoreProvider {
+    get_fn: Function,
+    put_fn: Function,
+    delete_fn: Function,
+    scan_prefix_fn: Function,
+}
+
+#[wasm_bindgen]
+impl JsKeyValueStoreProvider {
+    /// Create a new JsKeyValueStoreProvider from JavaScript callbacks.
+    ///
+    /// # Arguments
+    /// * `get_fn` - Function: `(key: string) => Promise<Uint8Array | null>`
+    /// * `put_fn` - Function: `(key: string, value: Uint8Array) => Promise<void>`
+    /// * `delete_fn` - Function: `(key: string) => Promise<void>`
+    /// * `scan_prefix_fn` - Function: `(prefix: string) => Promise<[string, Uint8Array][]>`, in any order
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        get_fn: Function,
+        put_fn: Function,
+        delete_fn: Function,
+        scan_prefix_fn: Function,
+    ) -> Self {
+        Self {
+            get_fn,
+            put_fn,
+            delete_fn,
+            scan_prefix_fn,
+        }
+    }
+}
+
+/// Internal adapter that implements the core KeyValueStore trait using JS callbacks.
+pub struct JsKeyValueStoreAdapter {
+    provider: JsKeyValueStoreProvider,
+}
+
+impl JsKeyValueStoreAdapter {
+    /// Create a new adapter wrapping a JsKeyValueStoreProvider.
+    pub fn new(provider: JsKeyValueStoreProvider) -> Self {
+        Self { provider }
+    }
+}
+
+/// Await the Promise returned by the key-value store callback `callback`.
+async fn await_kv_promise(
+    result: Result<JsValue, JsValue>,
+    callback: &str,
+) -> lendaswap_core::Result<JsValue> {
+    let promise: Promise = result
+        .map_err(|e| {
+            lendaswap_core::Error::Storage(format!("Failed to call {}: {:?}", callback, e))
+        })?
+        .dyn_into()
+        .map_err(|_| {
+            lendaswap_core::Error::Storage(format!("Expected Promise from {}", callback))
+        })?;
+
+    JsFuture::from(promise).await.map_err(|e| {
+        lendaswap_core::Error::Storage(format!("{} Promise rejected: {:?}", callback, e))
+    })
+}
+
+impl KeyValueStore for JsKeyValueStoreAdapter {
+    fn get(&self, key: &str) -> StorageFuture<'_, Option<Vec<u8>>> {
+        let key = JsValue::from_str(key);
+        let result = self.provider.get_fn.call1(&JsValue::NULL, &key);
+
+        Box::pin(async move {
+            let value = await_kv_promise(result, "get").await?;
+            if value.is_null() || value.is_undefined() {
+                return Ok(None);
+            }
+
+            let value: Uint8Array = value.dyn_into().map_err(|_| {
+                lendaswap_core::Error::Storage("Expected Uint8Array from get".into())
+            })?;
+            Ok(Some(value.to_vec()))
+        })
+    }
+
+    fn put(&self, key: &str, value: &[u8]) -> StorageFuture<'_, ()> {
+        let key = JsValue::from_str(key);
+        let value = Uint8Array::from(value);
+        let result = self.provider.put_fn.call2(&JsValue::NULL, &key, &value);
+
+        Box::pin(async move {
+            await_kv_promise(result, "put").await?;
+            Ok(())
+        })
+    }
+
+    fn delete(&self, key: &str) -> StorageFuture<'_, ()> {
+        let key = JsValue::from_str(key);
+        let result = self.provider.delete_fn.call1(&JsValue::NULL, &key);
+
+        Box::pin(async move {
+            await_kv_promise(result, "delete").await?;
+            Ok(())
+        })
+    }
+
+    fn scan_prefix(&self, prefix: &str) -> StorageFuture<'_, Vec<(String, Vec<u8>)>> {
+        let prefix = JsValue::from_str(prefix);
+        let result = self.provider.scan_prefix_fn.call1(&JsValue::NULL, &prefix);
+
+        Box::pin(async move {
+            let value = await_kv_promise(result, "scan_prefix").await?;
+            let entries: Array = value.dyn_into().map_err(|_| {
+                lendaswap_core::Error::Storage("Expected array from scan_prefix".into())
+            })?;
+
+            let mut entries = entries
+                .iter()
+                .map(|entry| {
+                    let entry: Array = entry.dyn_into().map_err(|_| {
+                        lendaswap_core::Error::Storage(
+                            "Expected [key, value] entries from scan_prefix".into(),
+                        )
+                    })?;
+                    let key = entry.get(0).as_string().ok_or_else(|| {
+                        lendaswap_core::Error::Storage(
+                            "Expected string key from scan_prefix".into(),
+                        )
+                    })?;
+                    let value: Uint8Array = entry.get(1).dyn_into().map_err(|_| {
+                        lendaswap_core::Error::Storage(
+                            "Expected Uint8Array value from scan_prefix".into(),
+                        )
+                    })?;
+                    Ok((key, value.to_vec()))
+                })
+                .collect::<lendaswap_core::Result<Vec<_>>>()?;
+
+            // The trait returns entries ordered by key, which JS stores don't all guarantee.
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Ok(entries)
+        })
+    }
+}